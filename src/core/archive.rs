@@ -0,0 +1,195 @@
+//! Reading source files straight out of `.tar`, `.tar.gz` and `.zip` archives
+//!
+//! Entries are streamed one at a time and never written to disk, so a CI job
+//! can index a downloaded source archive without unpacking it.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::error::{Error, Result};
+
+/// Archive layouts that can be read, chosen by file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the archive's extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Ok(ArchiveFormat::Zip)
+        } else {
+            Err(Error::Config(format!(
+                "unsupported archive {:?}: expected .tar, .tar.gz, .tgz or .zip",
+                path
+            )))
+        }
+    }
+
+    /// The archive's file name without the format's extension, e.g.
+    /// `project` for `project.tar.gz`
+    pub fn stem(self, path: &Path) -> String {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unnamed");
+        let extensions: &[&str] = match self {
+            ArchiveFormat::Tar => &[".tar"],
+            ArchiveFormat::TarGz => &[".tar.gz", ".tgz"],
+            ArchiveFormat::Zip => &[".zip"],
+        };
+        extensions
+            .iter()
+            .find_map(|ext| {
+                let cut = name.len().checked_sub(ext.len())?;
+                name.get(cut..)?
+                    .eq_ignore_ascii_case(ext)
+                    .then(|| name[..cut].to_string())
+            })
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+/// Call `visit` with the path, size and contents of each regular file in
+/// the archive
+///
+/// Paths are relative to the archive root, with `.` and `..` components
+/// dropped. Directories, links and entries with an empty path are skipped.
+pub fn for_each_file<F>(archive_path: &Path, mut visit: F) -> Result<()>
+where
+    F: FnMut(&Path, u64, &mut dyn Read) -> Result<()>,
+{
+    let format = ArchiveFormat::from_path(archive_path)?;
+    let file = File::open(archive_path).map_err(|e| Error::io(archive_path, e))?;
+    let reader = BufReader::new(file);
+
+    match format {
+        ArchiveFormat::Tar => visit_tar(archive_path, tar::Archive::new(reader), &mut visit),
+        ArchiveFormat::TarGz => visit_tar(
+            archive_path,
+            tar::Archive::new(GzDecoder::new(reader)),
+            &mut visit,
+        ),
+        ArchiveFormat::Zip => visit_zip(archive_path, reader, &mut visit),
+    }
+}
+
+fn visit_tar<R, F>(archive_path: &Path, mut archive: tar::Archive<R>, visit: &mut F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(&Path, u64, &mut dyn Read) -> Result<()>,
+{
+    let io_error = |e| Error::io(archive_path, e);
+    for entry in archive.entries().map_err(io_error)? {
+        let mut entry = entry.map_err(io_error)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = normalize(&entry.path().map_err(io_error)?);
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let size = entry.size();
+        visit(&path, size, &mut entry)?;
+    }
+    Ok(())
+}
+
+fn visit_zip<R, F>(archive_path: &Path, reader: R, visit: &mut F) -> Result<()>
+where
+    R: Read + io::Seek,
+    F: FnMut(&Path, u64, &mut dyn Read) -> Result<()>,
+{
+    let zip_error = |e: zip::result::ZipError| Error::io(archive_path, io::Error::other(e));
+    let mut archive = zip::ZipArchive::new(reader).map_err(zip_error)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(zip_error)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name().map(|p| normalize(&p)) else {
+            continue;
+        };
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let size = entry.size();
+        visit(&path, size, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// Keep only the normal components of an entry path, so `./src/a.go` is
+/// stored as `src/a.go`
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_format_from_path() {
+        let format = |name: &str| ArchiveFormat::from_path(Path::new(name)).ok();
+        assert_eq!(format("src.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("src.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("SRC.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("src.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(format("src.rar"), None);
+
+        assert_eq!(
+            ArchiveFormat::TarGz.stem(Path::new("/ci/project.tar.gz")),
+            "project"
+        );
+        assert_eq!(ArchiveFormat::Zip.stem(Path::new("project.ZIP")), "project");
+    }
+
+    #[test]
+    fn test_for_each_file_in_zip() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("src.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("pkg/", options).unwrap();
+        writer.start_file("pkg/util.go", options).unwrap();
+        writer.write_all(b"package pkg").unwrap();
+        writer.finish().unwrap();
+
+        let mut files = Vec::new();
+        for_each_file(&archive_path, |path, size, reader| {
+            let mut contents = String::new();
+            reader.read_to_string(&mut contents).unwrap();
+            files.push((path.to_path_buf(), size, contents));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            files,
+            vec![(PathBuf::from("pkg/util.go"), 11, "package pkg".to_string())]
+        );
+    }
+}
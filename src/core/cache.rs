@@ -0,0 +1,190 @@
+//! In-memory LRU cache of query results
+//!
+//! Entries are keyed by project, the project's graph generation and a query
+//! signature. Re-parsing a project bumps its generation, so results cached
+//! before the parse are never returned afterwards; they age out of the LRU.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// Number of results kept when no capacity is given
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    project_id: i64,
+    generation: i64,
+    signature: String,
+}
+
+#[derive(Debug, Default)]
+struct CacheEntries {
+    values: HashMap<CacheKey, serde_json::Value>,
+    /// Least recently used first
+    order: VecDeque<CacheKey>,
+}
+
+/// A bounded cache of serialized query results
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    entries: Mutex<CacheEntries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl QueryCache {
+    /// Create a cache holding up to `capacity` results; 0 disables caching
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(CacheEntries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the cached result for a query, or run `compute` and cache it
+    pub fn get_or_insert_with<T, F>(
+        &self,
+        project_id: i64,
+        generation: i64,
+        signature: &str,
+        compute: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T>,
+    {
+        let key = CacheKey {
+            project_id,
+            generation,
+            signature: signature.to_string(),
+        };
+
+        if let Some(value) = self.lookup(&key) {
+            if let Ok(result) = serde_json::from_value(value) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(result);
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = compute()?;
+        if self.capacity > 0 {
+            if let Ok(value) = serde_json::to_value(&result) {
+                self.insert(key, value);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Number of queries answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of queries that had to run against the database
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().values.len()
+    }
+
+    /// Whether the cache holds no results
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        let mut entries = self.entries.lock().unwrap();
+        let value = entries.values.get(key)?.clone();
+        entries.order.retain(|k| k != key);
+        entries.order.push_back(key.clone());
+        Some(value)
+    }
+
+    fn insert(&self, key: CacheKey, value: serde_json::Value) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.values.insert(key.clone(), value).is_some() {
+            entries.order.retain(|k| k != &key);
+        }
+        entries.order.push_back(key);
+
+        while entries.values.len() > self.capacity {
+            match entries.order.pop_front() {
+                Some(oldest) => {
+                    entries.values.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_and_generation_miss() {
+        let cache = QueryCache::new(8);
+
+        let first: Vec<String> = cache
+            .get_or_insert_with(1, 0, "symbols:Foo", || Ok(vec!["Foo".to_string()]))
+            .unwrap();
+        let second: Vec<String> = cache
+            .get_or_insert_with(1, 0, "symbols:Foo", || panic!("should be cached"))
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        let _: Vec<String> = cache
+            .get_or_insert_with(1, 1, "symbols:Foo", || Ok(vec![]))
+            .unwrap();
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = QueryCache::new(2);
+        for signature in ["a", "b"] {
+            let _: u32 = cache.get_or_insert_with(1, 0, signature, || Ok(1)).unwrap();
+        }
+        // Touch "a" so "b" becomes the oldest entry
+        let _: u32 = cache.get_or_insert_with(1, 0, "a", || Ok(1)).unwrap();
+        let _: u32 = cache.get_or_insert_with(1, 0, "c", || Ok(1)).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        let misses = cache.misses();
+        let _: u32 = cache.get_or_insert_with(1, 0, "a", || Ok(1)).unwrap();
+        assert_eq!(cache.misses(), misses);
+        let _: u32 = cache.get_or_insert_with(1, 0, "b", || Ok(1)).unwrap();
+        assert_eq!(cache.misses(), misses + 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let cache = QueryCache::new(0);
+        for _ in 0..2 {
+            let _: u32 = cache.get_or_insert_with(1, 0, "a", || Ok(1)).unwrap();
+        }
+        assert_eq!(cache.misses(), 2);
+        assert!(cache.is_empty());
+    }
+}
@@ -1,7 +1,13 @@
 //! Configuration management for CodeGraph
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::parser::ColumnEncoding;
+use crate::error::{Error, Result};
+#[cfg(feature = "service")]
+use crate::storage::sqlite::{OpenOptions, Synchronous};
 
 /// Main configuration for the CodeGraph service
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +20,10 @@ pub struct Config {
 
     /// Logging configuration
     pub logging: LoggingConfig,
+
+    /// Parsing configuration
+    #[serde(default)]
+    pub parse: ParseConfig,
 }
 
 impl Default for Config {
@@ -22,6 +32,7 @@ impl Default for Config {
             server: ServerConfig::default(),
             database: DatabaseConfig::default(),
             logging: LoggingConfig::default(),
+            parse: ParseConfig::default(),
         }
     }
 }
@@ -40,6 +51,14 @@ pub struct ServerConfig {
 
     /// Allowed origins for CORS
     pub cors_origins: Vec<String>,
+
+    /// Parse jobs allowed to run at once; further requests get 429
+    #[serde(default = "default_max_parse_jobs")]
+    pub max_parse_jobs: usize,
+}
+
+fn default_max_parse_jobs() -> usize {
+    2
 }
 
 impl Default for ServerConfig {
@@ -49,6 +68,7 @@ impl Default for ServerConfig {
             port: 8080,
             cors_enabled: true,
             cors_origins: vec!["*".to_string()],
+            max_parse_jobs: default_max_parse_jobs(),
         }
     }
 }
@@ -61,6 +81,28 @@ pub struct DatabaseConfig {
 
     /// Connection pool size
     pub pool_size: u32,
+
+    /// Milliseconds to wait for a lock held by another process before a
+    /// write fails with "database is locked"
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+
+    /// Use write-ahead logging so queries do not block parse writes
+    #[serde(default = "default_wal")]
+    pub wal: bool,
+
+    /// SQLite `synchronous` level: off, normal, full or extra
+    #[cfg(feature = "service")]
+    #[serde(default)]
+    pub synchronous: Synchronous,
+}
+
+fn default_wal() -> bool {
+    true
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    5000
 }
 
 impl Default for DatabaseConfig {
@@ -68,6 +110,22 @@ impl Default for DatabaseConfig {
         Self {
             path: PathBuf::from("codegraph.db"),
             pool_size: 4,
+            busy_timeout_ms: default_busy_timeout_ms(),
+            wal: default_wal(),
+            #[cfg(feature = "service")]
+            synchronous: Synchronous::default(),
+        }
+    }
+}
+
+#[cfg(feature = "service")]
+impl DatabaseConfig {
+    /// Connection settings for opening the configured database
+    pub fn open_options(&self) -> OpenOptions {
+        OpenOptions {
+            busy_timeout: std::time::Duration::from_millis(self.busy_timeout_ms),
+            wal: self.wal,
+            synchronous: self.synchronous,
         }
     }
 }
@@ -91,22 +149,264 @@ impl Default for LoggingConfig {
     }
 }
 
+impl LoggingConfig {
+    /// Filter directives for the configured level, applied to CodeGraph's
+    /// own logs and the HTTP request traces
+    pub fn filter_directives(&self) -> Result<String> {
+        self.level
+            .parse::<tracing::level_filters::LevelFilter>()
+            .map_err(|_| Error::Config(format!("Invalid log level: {}", self.level)))?;
+        Ok(format!("codegraph={0},tower_http={0}", self.level))
+    }
+}
+
+/// Parsing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseConfig {
+    /// Files larger than this many bytes are skipped
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Files tree-sitter takes longer than this to parse are skipped;
+    /// 0 disables the limit
+    #[serde(default = "default_parse_timeout_ms")]
+    pub parse_timeout_ms: u64,
+
+    /// Files whose syntax tree has more nodes than this are skipped before
+    /// extraction; 0 disables the limit
+    #[serde(default = "default_max_syntax_nodes")]
+    pub max_syntax_nodes: usize,
+
+    /// Files whose syntax tree nests deeper than this are skipped before
+    /// extraction, which recurses once per level; 0 disables the limit
+    #[serde(default = "default_max_syntax_depth")]
+    pub max_syntax_depth: usize,
+
+    /// Follow symbolic links while collecting files
+    #[serde(default)]
+    pub follow_symlinks: bool,
+
+    /// Deepest directory level walked below a root; the root's own files
+    /// are at depth 1
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+
+    /// Skip files marked as generated code, e.g. by Go's
+    /// `// Code generated ... DO NOT EDIT.`
+    #[serde(default)]
+    pub skip_generated: bool,
+
+    /// Collect files on several threads, which also honors `.gitignore`;
+    /// faster on network filesystems
+    #[serde(default)]
+    pub parallel_walk: bool,
+
+    /// Extra file extensions mapped to language IDs, e.g. `".jav" = "java"`
+    #[serde(default)]
+    pub extra_extensions: BTreeMap<String, String>,
+
+    /// Language dialects to opt into, e.g. `go = "generics"`
+    #[serde(default)]
+    pub dialects: BTreeMap<String, String>,
+
+    /// Store file paths relative to the project root, so the database can
+    /// be moved to another machine
+    #[serde(default)]
+    pub relative_paths: bool,
+
+    /// Only parse files matching one of these globs, relative to the root
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Skip files matching any of these globs, relative to the root
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Unit node columns are stored in: `byte`, `char` or `utf16`
+    #[serde(default)]
+    pub column_encoding: ColumnEncoding,
+
+    /// Encoding for files that have no byte order mark and are not valid
+    /// UTF-8, e.g. `windows-1252`; unset decodes them as lossy UTF-8
+    #[serde(default)]
+    pub fallback_encoding: Option<String>,
+}
+
+fn default_max_file_bytes() -> u64 {
+    2 * 1024 * 1024
+}
+
+fn default_max_depth() -> usize {
+    50
+}
+
+fn default_parse_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_syntax_nodes() -> usize {
+    2_000_000
+}
+
+fn default_max_syntax_depth() -> usize {
+    500
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: default_max_file_bytes(),
+            parse_timeout_ms: default_parse_timeout_ms(),
+            max_syntax_nodes: default_max_syntax_nodes(),
+            max_syntax_depth: default_max_syntax_depth(),
+            follow_symlinks: false,
+            max_depth: default_max_depth(),
+            skip_generated: false,
+            parallel_walk: false,
+            extra_extensions: BTreeMap::new(),
+            dialects: BTreeMap::new(),
+            relative_paths: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            column_encoding: ColumnEncoding::default(),
+            fallback_encoding: None,
+        }
+    }
+}
+
+/// Settings given on the command line
+///
+/// These take precedence over environment variables, the config file and
+/// the defaults, in that order.
+#[derive(Debug, Clone, Default)]
+pub struct CliOverrides {
+    /// Path to a TOML config file
+    pub config_file: Option<PathBuf>,
+
+    /// Host to bind or connect to
+    pub host: Option<String>,
+
+    /// Server port
+    pub port: Option<u16>,
+
+    /// Path to SQLite database file
+    pub database: Option<PathBuf>,
+
+    /// Log level, e.g. from `--verbose`
+    pub log_level: Option<String>,
+}
+
 impl Config {
+    /// Load configuration, layering defaults < file < `CODEGRAPH_*` env < CLI
+    pub fn load(cli: &CliOverrides) -> Result<Self> {
+        Self::load_with_env(cli, |key| std::env::var(key).ok())
+    }
+
+    fn load_with_env(cli: &CliOverrides, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut config = match &cli.config_file {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        if let Some(host) = env("CODEGRAPH_HOST") {
+            config.server.host = host;
+        }
+        if let Some(port) = env("CODEGRAPH_PORT") {
+            config.server.port = port.parse().map_err(|_| {
+                Error::Config(format!("CODEGRAPH_PORT is not a valid port: {}", port))
+            })?;
+        }
+        if let Some(database) = env("CODEGRAPH_DATABASE") {
+            config.database.path = PathBuf::from(database);
+        }
+        if let Some(level) = env("CODEGRAPH_LOG_LEVEL") {
+            config.logging.level = level;
+        }
+        if let Some(format) = env("CODEGRAPH_LOG_FORMAT") {
+            config.logging.format = format;
+        }
+
+        if let Some(host) = &cli.host {
+            config.server.host = host.clone();
+        }
+        if let Some(port) = cli.port {
+            config.server.port = port;
+        }
+        if let Some(database) = &cli.database {
+            config.database.path = database.clone();
+        }
+        if let Some(level) = &cli.log_level {
+            config.logging.level = level.clone();
+        }
+        config.database.path = expand_path_with_env(&config.database.path, &env);
+
+        Ok(config)
+    }
+
     /// Load configuration from a TOML file
-    pub fn from_file(path: &std::path::Path) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let config: Config = toml::from_str(&content).map_err(|e| Error::Config(e.to_string()))?;
         Ok(config)
     }
 
     /// Save configuration to a TOML file
-    pub fn to_file(&self, path: &std::path::Path) -> anyhow::Result<()> {
-        let content = toml::to_string_pretty(self)?;
-        std::fs::write(path, content)?;
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).map_err(|e| Error::Config(e.to_string()))?;
+        std::fs::write(path, content).map_err(|e| Error::io(path, e))?;
         Ok(())
     }
 }
 
+/// Expand a leading `~` to the home directory and `$VAR` or `${VAR}` to the
+/// variable's value
+///
+/// Unset variables and paths that are not valid UTF-8 are left as they are.
+pub fn expand_path(path: &Path) -> PathBuf {
+    expand_path_with_env(path, |key| std::env::var(key).ok())
+}
+
+fn expand_path_with_env(path: &Path, env: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(std::path::is_separator) {
+            if let Some(home) = env("HOME").or_else(|| env("USERPROFILE")) {
+                expanded.push_str(&home);
+                rest = after;
+            }
+        }
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => braced
+                .find('}')
+                .map_or(("", after), |end| (&braced[..end], &braced[end + 1..])),
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        // A `$` without a name, or an unset variable, is kept as written
+        match Some(name).filter(|n| !n.is_empty()).and_then(&env) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[dollar..rest.len() - remainder.len()]),
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,6 +422,8 @@ mod tests {
         assert_eq!(config.database.pool_size, 4);
         assert_eq!(config.logging.level, "info");
         assert_eq!(config.logging.format, "pretty");
+        assert_eq!(config.parse.max_file_bytes, 2 * 1024 * 1024);
+        assert!(!config.parse.follow_symlinks);
     }
 
     #[test]
@@ -138,6 +440,10 @@ mod tests {
         let config = DatabaseConfig::default();
         assert_eq!(config.path, PathBuf::from("codegraph.db"));
         assert_eq!(config.pool_size, 4);
+        assert_eq!(config.busy_timeout_ms, 5000);
+        assert!(config.wal);
+        #[cfg(feature = "service")]
+        assert_eq!(config.synchronous, Synchronous::Full);
     }
 
     #[test]
@@ -170,15 +476,23 @@ mod tests {
                 port: 9090,
                 cors_enabled: false,
                 cors_origins: vec!["https://example.com".to_string()],
+                max_parse_jobs: 4,
             },
             database: DatabaseConfig {
                 path: PathBuf::from("/tmp/test.db"),
                 pool_size: 8,
+                ..DatabaseConfig::default()
             },
             logging: LoggingConfig {
                 level: "debug".to_string(),
                 format: "json".to_string(),
             },
+            parse: ParseConfig {
+                max_file_bytes: 1024,
+                follow_symlinks: true,
+                extra_extensions: BTreeMap::from([(".jav".to_string(), "java".to_string())]),
+                ..ParseConfig::default()
+            },
         };
 
         config.to_file(&config_path).unwrap();
@@ -190,11 +504,173 @@ mod tests {
         assert_eq!(loaded.database.pool_size, 8);
         assert_eq!(loaded.logging.level, "debug");
         assert_eq!(loaded.logging.format, "json");
+        assert_eq!(loaded.parse.max_file_bytes, 1024);
+        assert!(loaded.parse.follow_symlinks);
+        assert_eq!(loaded.parse.extra_extensions[".jav"], "java");
+    }
+
+    #[test]
+    fn test_config_without_parse_section() {
+        let toml_str = r#"
+            [server]
+            host = "127.0.0.1"
+            port = 8080
+            cors_enabled = true
+            cors_origins = ["*"]
+
+            [database]
+            path = "codegraph.db"
+            pool_size = 4
+
+            [logging]
+            level = "info"
+            format = "pretty"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.parse.max_file_bytes,
+            ParseConfig::default().max_file_bytes
+        );
+    }
+
+    #[test]
+    fn test_config_with_partial_parse_section() {
+        let toml_str = r#"
+            [server]
+            host = "127.0.0.1"
+            port = 8080
+            cors_enabled = true
+            cors_origins = ["*"]
+
+            [database]
+            path = "codegraph.db"
+            pool_size = 4
+
+            [logging]
+            level = "info"
+            format = "pretty"
+
+            [parse]
+            skip_generated = true
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.parse.skip_generated);
+        assert_eq!(
+            config.parse.max_file_bytes,
+            ParseConfig::default().max_file_bytes
+        );
+    }
+
+    #[test]
+    fn test_load_layers_env_and_cli_over_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let mut file_config = Config::default();
+        file_config.server.host = "file-host".to_string();
+        file_config.server.port = 7000;
+        file_config.database.path = PathBuf::from("file.db");
+        file_config.to_file(&config_path).unwrap();
+
+        let env = |key: &str| match key {
+            "CODEGRAPH_PORT" => Some("7001".to_string()),
+            "CODEGRAPH_DATABASE" => Some("env.db".to_string()),
+            _ => None,
+        };
+
+        // Env overrides the file
+        let cli = CliOverrides {
+            config_file: Some(config_path.clone()),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, env).unwrap();
+        assert_eq!(config.server.host, "file-host");
+        assert_eq!(config.server.port, 7001);
+        assert_eq!(config.database.path, PathBuf::from("env.db"));
+
+        // CLI overrides the env
+        let cli = CliOverrides {
+            config_file: Some(config_path),
+            port: Some(7002),
+            database: Some(PathBuf::from("cli.db")),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, env).unwrap();
+        assert_eq!(config.server.port, 7002);
+        assert_eq!(config.database.path, PathBuf::from("cli.db"));
+    }
+
+    #[test]
+    fn test_env_log_level_sets_filter() {
+        let env = |key: &str| match key {
+            "CODEGRAPH_LOG_LEVEL" => Some("debug".to_string()),
+            "CODEGRAPH_LOG_FORMAT" => Some("json".to_string()),
+            _ => None,
+        };
+
+        let config = Config::load_with_env(&CliOverrides::default(), env).unwrap();
+        assert_eq!(config.logging.format, "json");
+        assert_eq!(
+            config.logging.filter_directives().unwrap(),
+            "codegraph=debug,tower_http=debug"
+        );
+
+        // A CLI level, such as a quiet parse, wins over the env
+        let cli = CliOverrides {
+            log_level: Some("warn".to_string()),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, env).unwrap();
+        assert_eq!(
+            config.logging.filter_directives().unwrap(),
+            "codegraph=warn,tower_http=warn"
+        );
+
+        let config = Config::load_with_env(&CliOverrides::default(), |key: &str| {
+            (key == "CODEGRAPH_LOG_LEVEL").then(|| "loud".to_string())
+        })
+        .unwrap();
+        assert!(config.logging.filter_directives().is_err());
+    }
+
+    #[test]
+    fn test_load_uses_file_database_unless_flag_given() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let mut file_config = Config::default();
+        file_config.database.path = PathBuf::from("from-file.db");
+        file_config.to_file(&config_path).unwrap();
+
+        let no_env = |_: &str| None;
+        let cli = CliOverrides {
+            config_file: Some(config_path.clone()),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, no_env).unwrap();
+        assert_eq!(config.database.path, PathBuf::from("from-file.db"));
+
+        let cli = CliOverrides {
+            config_file: Some(config_path),
+            database: Some(PathBuf::from("flag.db")),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, no_env).unwrap();
+        assert_eq!(config.database.path, PathBuf::from("flag.db"));
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_env_port() {
+        let env = |key: &str| (key == "CODEGRAPH_PORT").then(|| "not-a-port".to_string());
+        let result = Config::load_with_env(&CliOverrides::default(), env);
+        assert!(matches!(result, Err(Error::Config(_))));
     }
 
     #[test]
     fn test_config_from_file_not_found() {
-        let result = Config::from_file(std::path::Path::new("/nonexistent/path/config.toml"));
+        let result = Config::from_file(Path::new("/nonexistent/path/config.toml"));
         assert!(result.is_err());
     }
 
@@ -208,9 +684,59 @@ mod tests {
                 "http://localhost:3000".to_string(),
                 "https://app.example.com".to_string(),
             ],
+            ..ServerConfig::default()
         };
 
         assert_eq!(config.cors_origins.len(), 2);
-        assert!(config.cors_origins.contains(&"http://localhost:3000".to_string()));
+        assert!(config
+            .cors_origins
+            .contains(&"http://localhost:3000".to_string()));
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let env = |key: &str| match key {
+            "HOME" => Some("/home/dev".to_string()),
+            "DATA_DIR" => Some("/var/data".to_string()),
+            _ => None,
+        };
+        let expand = |path: &str| expand_path_with_env(Path::new(path), env);
+
+        assert_eq!(expand("~/x.db"), PathBuf::from("/home/dev/x.db"));
+        assert_eq!(expand("~"), PathBuf::from("/home/dev"));
+        assert_eq!(expand("$HOME/x.db"), PathBuf::from("/home/dev/x.db"));
+        assert_eq!(
+            expand("${DATA_DIR}/graph.db"),
+            PathBuf::from("/var/data/graph.db")
+        );
+        assert_eq!(
+            expand("$DATA_DIR-old.db"),
+            PathBuf::from("/var/data-old.db")
+        );
+
+        // Only a leading `~` is expanded, and unknown names are kept
+        assert_eq!(expand("~user/x.db"), PathBuf::from("~user/x.db"));
+        assert_eq!(expand("a~/x.db"), PathBuf::from("a~/x.db"));
+        assert_eq!(expand("$MISSING/x.db"), PathBuf::from("$MISSING/x.db"));
+        assert_eq!(expand("${MISSING/x.db"), PathBuf::from("${MISSING/x.db"));
+        assert_eq!(expand("cost$.db"), PathBuf::from("cost$.db"));
+        assert_eq!(expand(":memory:"), PathBuf::from(":memory:"));
+    }
+
+    #[test]
+    fn test_load_expands_database_path() {
+        let env = |key: &str| match key {
+            "HOME" => Some("/home/dev".to_string()),
+            _ => None,
+        };
+        let cli = CliOverrides {
+            database: Some(PathBuf::from("~/code/graph.db")),
+            ..Default::default()
+        };
+        let config = Config::load_with_env(&cli, env).unwrap();
+        assert_eq!(
+            config.database.path,
+            PathBuf::from("/home/dev/code/graph.db")
+        );
     }
 }
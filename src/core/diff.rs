@@ -0,0 +1,303 @@
+//! Graph snapshots and diffs between two parses of a project
+//!
+//! A snapshot is a portable JSON export of a project's symbols and edges.
+//! Symbols are identified by node type and qualified name, and edges by
+//! the identities of their endpoints, so a snapshot taken before a change
+//! can be compared against the graph after re-parsing.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::storage::Database;
+
+/// Node types that mark occurrences rather than declarations
+const OCCURRENCE_TYPES: [&str; 2] = ["call", "reference"];
+
+/// A declared symbol in a snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotSymbol {
+    pub node_type: String,
+    pub name: String,
+    pub qualified_name: Option<String>,
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub attributes: Option<String>,
+}
+
+impl SnapshotSymbol {
+    /// Identity used to match the symbol across snapshots
+    pub fn key(&self) -> String {
+        node_key(&self.node_type, self.qualified_name.as_deref(), &self.name)
+    }
+
+    /// Whether the symbol's declaration differs, ignoring where it starts.
+    /// Edits elsewhere in a file shift line numbers without changing a
+    /// symbol, so only the file, length and attributes are compared.
+    fn differs_from(&self, other: &SnapshotSymbol) -> bool {
+        self.file != other.file
+            || self.end_line.abs_diff(self.start_line) != other.end_line.abs_diff(other.start_line)
+            || self.attributes != other.attributes
+    }
+}
+
+/// An edge in a snapshot, keyed by its endpoints' identities
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SnapshotEdge {
+    pub source: String,
+    pub edge_type: String,
+    pub target: String,
+}
+
+/// A JSON export of a project's graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub project: String,
+    pub symbols: Vec<SnapshotSymbol>,
+    pub edges: Vec<SnapshotEdge>,
+}
+
+/// Counts of what changed between two snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffSummary {
+    pub symbols_added: usize,
+    pub symbols_removed: usize,
+    pub symbols_changed: usize,
+    pub edges_added: usize,
+    pub edges_removed: usize,
+}
+
+/// Differences between a baseline snapshot and the current graph
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphDiff {
+    pub summary: DiffSummary,
+    pub added_symbols: Vec<SnapshotSymbol>,
+    pub removed_symbols: Vec<SnapshotSymbol>,
+    /// Current versions of symbols present in both snapshots that changed
+    pub changed_symbols: Vec<SnapshotSymbol>,
+    pub added_edges: Vec<SnapshotEdge>,
+    pub removed_edges: Vec<SnapshotEdge>,
+}
+
+fn node_key(node_type: &str, qualified_name: Option<&str>, name: &str) -> String {
+    format!("{}:{}", node_type, qualified_name.unwrap_or(name))
+}
+
+impl GraphSnapshot {
+    /// Export the current graph of a project
+    pub fn from_database(db: &Database, project_id: i64) -> Result<Self> {
+        let project = db
+            .get_project_status(project_id)?
+            .ok_or_else(|| Error::ProjectNotFound(project_id.to_string()))?;
+
+        let nodes = db.get_project_nodes(project_id)?;
+        let mut keys = HashMap::with_capacity(nodes.len());
+        let mut symbols = Vec::new();
+
+        for (file, node) in nodes {
+            keys.insert(
+                node.id,
+                node_key(&node.node_type, node.qualified_name.as_deref(), &node.name),
+            );
+            if OCCURRENCE_TYPES.contains(&node.node_type.as_str()) {
+                continue;
+            }
+            symbols.push(SnapshotSymbol {
+                node_type: node.node_type,
+                name: node.name,
+                qualified_name: node.qualified_name,
+                file,
+                start_line: node.start_line,
+                end_line: node.end_line,
+                attributes: node.attributes,
+            });
+        }
+
+        // Cross-project edges have an endpoint outside this project; skip them
+        let edges: BTreeSet<SnapshotEdge> = db
+            .get_project_edges(project_id)?
+            .into_iter()
+            .filter_map(|e| {
+                Some(SnapshotEdge {
+                    source: keys.get(&e.source_id)?.clone(),
+                    edge_type: e.edge_type,
+                    target: keys.get(&e.target_id)?.clone(),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            project: project.name,
+            symbols,
+            edges: edges.into_iter().collect(),
+        })
+    }
+
+    /// Read a snapshot previously written with `export json`
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        serde_json::from_str(&content).map_err(|e| Error::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Compare this baseline against a newer snapshot
+    pub fn diff(&self, current: &GraphSnapshot) -> GraphDiff {
+        let before = symbols_by_key(&self.symbols);
+        let after = symbols_by_key(&current.symbols);
+
+        let mut diff = GraphDiff::default();
+
+        for (key, symbol) in &after {
+            match before.get(key) {
+                None => diff.added_symbols.push((*symbol).clone()),
+                Some(old) if symbol.differs_from(old) => {
+                    diff.changed_symbols.push((*symbol).clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, symbol) in &before {
+            if !after.contains_key(key) {
+                diff.removed_symbols.push((*symbol).clone());
+            }
+        }
+
+        let edges_before: BTreeSet<&SnapshotEdge> = self.edges.iter().collect();
+        let edges_after: BTreeSet<&SnapshotEdge> = current.edges.iter().collect();
+        diff.added_edges = edges_after
+            .difference(&edges_before)
+            .copied()
+            .cloned()
+            .collect();
+        diff.removed_edges = edges_before
+            .difference(&edges_after)
+            .copied()
+            .cloned()
+            .collect();
+
+        diff.summary = DiffSummary {
+            symbols_added: diff.added_symbols.len(),
+            symbols_removed: diff.removed_symbols.len(),
+            symbols_changed: diff.changed_symbols.len(),
+            edges_added: diff.added_edges.len(),
+            edges_removed: diff.removed_edges.len(),
+        };
+        diff
+    }
+}
+
+/// Index symbols by identity; when several share one (e.g. overloads) the
+/// first declaration wins
+fn symbols_by_key(symbols: &[SnapshotSymbol]) -> BTreeMap<String, &SnapshotSymbol> {
+    let mut map = BTreeMap::new();
+    for symbol in symbols {
+        map.entry(symbol.key()).or_insert(symbol);
+    }
+    map
+}
+
+/// Compare a project's current graph against a snapshot file
+pub fn diff_against(db: &Database, project_id: i64, baseline: &Path) -> Result<GraphDiff> {
+    let current = GraphSnapshot::from_database(db, project_id)?;
+    Ok(GraphSnapshot::load(baseline)?.diff(&current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(node_type: &str, name: &str, start_line: u32, end_line: u32) -> SnapshotSymbol {
+        SnapshotSymbol {
+            node_type: node_type.to_string(),
+            name: name.to_string(),
+            qualified_name: Some(format!("app.{}", name)),
+            file: "/src/app.go".to_string(),
+            start_line,
+            end_line,
+            attributes: None,
+        }
+    }
+
+    fn edge(source: &str, edge_type: &str, target: &str) -> SnapshotEdge {
+        SnapshotEdge {
+            source: source.to_string(),
+            edge_type: edge_type.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff_added_removed_and_changed() {
+        let baseline = GraphSnapshot {
+            project: "app".to_string(),
+            symbols: vec![
+                symbol("function", "main", 3, 5),
+                symbol("function", "old", 7, 8),
+                symbol("function", "grow", 10, 11),
+            ],
+            edges: vec![edge("function:app.main", "calls", "call:old")],
+        };
+        let current = GraphSnapshot {
+            project: "app".to_string(),
+            symbols: vec![
+                // Shifted down by one line but otherwise unchanged
+                symbol("function", "main", 4, 6),
+                symbol("function", "grow", 11, 15),
+                symbol("function", "fresh", 17, 18),
+            ],
+            edges: vec![edge("function:app.main", "calls", "call:fresh")],
+        };
+
+        let diff = baseline.diff(&current);
+        assert_eq!(
+            diff.summary,
+            DiffSummary {
+                symbols_added: 1,
+                symbols_removed: 1,
+                symbols_changed: 1,
+                edges_added: 1,
+                edges_removed: 1,
+            }
+        );
+        assert_eq!(diff.added_symbols[0].name, "fresh");
+        assert_eq!(diff.removed_symbols[0].name, "old");
+        assert_eq!(diff.changed_symbols[0].name, "grow");
+        assert_eq!(diff.added_edges[0].target, "call:fresh");
+        assert_eq!(diff.removed_edges[0].target, "call:old");
+    }
+
+    #[test]
+    fn test_diff_identical_snapshots_is_empty() {
+        let snapshot = GraphSnapshot {
+            project: "app".to_string(),
+            symbols: vec![symbol("function", "main", 3, 5)],
+            edges: vec![edge("function:app.main", "calls", "call:helper")],
+        };
+
+        let diff = snapshot.diff(&snapshot.clone());
+        assert_eq!(diff.summary, DiffSummary::default());
+    }
+
+    #[test]
+    fn test_diff_inverted_span_does_not_underflow() {
+        // Snapshots are read from JSON, so a span may come in inverted
+        let baseline = GraphSnapshot {
+            project: "app".to_string(),
+            symbols: vec![symbol("function", "main", 5, 3)],
+            edges: Vec::new(),
+        };
+        let current = GraphSnapshot {
+            project: "app".to_string(),
+            symbols: vec![symbol("function", "main", 3, 6)],
+            edges: Vec::new(),
+        };
+
+        let diff = baseline.diff(&current);
+        assert_eq!(diff.summary.symbols_changed, 1);
+    }
+}
@@ -0,0 +1,153 @@
+//! Git integration for incremental parsing
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Error, Result};
+
+/// Files changed in the working tree relative to a git ref
+#[derive(Debug, Clone, Default)]
+pub struct GitChanges {
+    /// Added or modified files
+    pub changed: Vec<PathBuf>,
+    /// Deleted files
+    pub deleted: Vec<PathBuf>,
+}
+
+/// List files under `root` that changed since `git_ref`
+///
+/// Untracked files that aren't ignored count as changed. Returns `Ok(None)`
+/// if `root` is not inside a git work tree. Paths are joined onto `root` so
+/// they compare equal to paths from file collection.
+pub fn changed_files_since(root: &Path, git_ref: &str) -> Result<Option<GitChanges>> {
+    let inside = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .map_err(|e| Error::io(root, e))?;
+    if !inside.status.success() {
+        return Ok(None);
+    }
+
+    // -z keeps paths verbatim instead of C-quoting non-ASCII names
+    let diff = git_output(
+        root,
+        &[
+            "diff",
+            "-z",
+            "--name-status",
+            "--relative",
+            "--no-renames",
+            git_ref,
+        ],
+    )?;
+    let mut changes = parse_name_status(root, &diff);
+
+    let untracked = git_output(root, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+    changes.changed.extend(
+        untracked
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(|p| root.join(p)),
+    );
+
+    Ok(Some(changes))
+}
+
+/// Run a git command in `root` and return its stdout
+fn git_output(root: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .map_err(|e| Error::io(root, e))?;
+    if !output.status.success() {
+        return Err(Error::Git(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `git diff -z --name-status` output, where each status and path
+/// is terminated by a NUL
+fn parse_name_status(root: &Path, output: &str) -> GitChanges {
+    let mut changes = GitChanges::default();
+
+    let mut fields = output.split('\0');
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        let path = root.join(path);
+        if status.starts_with('D') {
+            changes.deleted.push(path);
+        } else {
+            changes.changed.push(path);
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_status() {
+        let root = Path::new("/repo");
+        let output = "M\0src/A.java\0A\0src/B.java\0D\0src/C.java\0";
+
+        let changes = parse_name_status(root, output);
+        assert_eq!(
+            changes.changed,
+            vec![
+                PathBuf::from("/repo/src/A.java"),
+                PathBuf::from("/repo/src/B.java")
+            ]
+        );
+        assert_eq!(changes.deleted, vec![PathBuf::from("/repo/src/C.java")]);
+    }
+
+    #[test]
+    fn test_changed_files_since_non_ascii_paths() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", "user.email=test@example.com", "-c", "user.name=Test"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        std::fs::write(root.join("été.go"), "package main").unwrap();
+        std::fs::write(root.join("über.go"), "package main").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        std::fs::write(root.join("été.go"), "package main\n\nfunc main() {}").unwrap();
+        std::fs::remove_file(root.join("über.go")).unwrap();
+        std::fs::write(root.join("ñandú.go"), "package main").unwrap();
+
+        let changes = changed_files_since(root, "HEAD").unwrap().unwrap();
+        assert_eq!(
+            changes.changed,
+            vec![root.join("été.go"), root.join("ñandú.go")]
+        );
+        assert_eq!(changes.deleted, vec![root.join("über.go")]);
+    }
+
+    #[test]
+    fn test_changed_files_since_not_a_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let changes = changed_files_since(temp_dir.path(), "HEAD").unwrap();
+        assert!(changes.is_none());
+    }
+}
@@ -1,50 +1,170 @@
 //! Graph builder for constructing code graphs
 
-use std::path::Path;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use serde::Serialize;
 use tracing::debug;
 
-use crate::core::parser::FileGraphData;
+use crate::core::parser::{is_test_file, FileGraphData};
+use crate::error::Result;
 use crate::storage::models::{EdgeRecord, FileRecord, NodeRecord, ProjectRecord};
 use crate::storage::Database;
 
+/// Outcome of [`GraphBuilder::rebuild_cross_references`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReferenceRebuild {
+    /// `references` edges removed before resolving again
+    pub cleared: usize,
+    /// References, calls and field accesses linked
+    pub resolved: usize,
+    /// References still without a target
+    pub unresolved: usize,
+}
+
+/// Outcome of [`GraphBuilder::remove_file_and_relink`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileRemoval {
+    /// The removed file's path as stored in the database
+    pub path: String,
+    /// References, calls and field accesses linked after the removal
+    pub relinked: usize,
+}
+
+/// A Go module rooted at one of the project's roots
+struct GoModule {
+    /// Module path from the `module` directive
+    path: String,
+    /// Directory holding the `go.mod`
+    dir: PathBuf,
+}
+
+/// The module path declared by the `module` directive of a `go.mod`
+fn go_module_path(go_mod: &str) -> Option<String> {
+    go_mod.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("module")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let path = rest.split("//").next()?.trim().trim_matches('"');
+        (!path.is_empty()).then(|| path.to_string())
+    })
+}
+
 /// Builder for constructing and storing code graphs
-pub struct GraphBuilder {
-    db: Database,
+///
+/// Owns its database by default, but also works over a borrowed one, such
+/// as the server's shared connection.
+pub struct GraphBuilder<D = Database> {
+    db: D,
+    path_root: Option<PathBuf>,
+    /// Roots of the project being built, recorded by
+    /// [`create_or_get_project`](Self::create_or_get_project) and
+    /// [`add_project_roots`](Self::add_project_roots)
+    source_roots: Vec<PathBuf>,
 }
 
-impl GraphBuilder {
+impl<D: Borrow<Database>> GraphBuilder<D> {
     /// Create a new graph builder with the given database
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            path_root: None,
+            source_roots: Vec::new(),
+        }
+    }
+
+    fn db(&self) -> &Database {
+        self.db.borrow()
+    }
+
+    /// Store file paths under `root` relative to it
+    ///
+    /// Files outside `root`, such as those in extra source roots elsewhere,
+    /// keep their paths as given.
+    pub fn with_relative_paths(mut self, root: &Path) -> Self {
+        self.path_root = Some(root.to_path_buf());
+        self
+    }
+
+    /// The path a file is stored under
+    fn stored_path(&self, file_path: &Path) -> String {
+        let relative = self
+            .path_root
+            .as_deref()
+            .and_then(|root| file_path.strip_prefix(root).ok());
+        relative.unwrap_or(file_path).to_string_lossy().to_string()
+    }
+
+    /// `file_path` relative to the innermost source root containing it, so
+    /// directories above the project play no part in classifying the file
+    fn root_relative<'p>(&self, file_path: &'p Path) -> &'p Path {
+        self.source_roots
+            .iter()
+            .filter_map(|root| file_path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count())
+            .unwrap_or(file_path)
     }
 
     /// Create or get an existing project
-    pub fn create_or_get_project(&self, name: &str, root_path: &Path) -> Result<i64> {
+    pub fn create_or_get_project(&mut self, name: &str, root_path: &Path) -> Result<i64> {
         let root_path_str = root_path.to_string_lossy().to_string();
+        self.source_roots.push(root_path.to_path_buf());
 
-        // Try to find existing project
-        if let Some(project) = self.db.get_project_by_path(&root_path_str)? {
-            debug!("Found existing project: {} (id={})", project.name, project.id);
-            return Ok(project.id);
-        }
+        self.db().write_transaction(|| {
+            // Try to find existing project
+            if let Some(project) = self.db().get_project_by_path(&root_path_str)? {
+                debug!(
+                    "Found existing project: {} (id={})",
+                    project.name, project.id
+                );
+                return Ok(project.id);
+            }
 
-        // Create new project
-        let project = ProjectRecord {
-            id: 0,
-            name: name.to_string(),
-            root_path: root_path_str,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        };
+            // Create new project
+            let project = ProjectRecord {
+                id: 0,
+                name: name.to_string(),
+                root_path: root_path_str.clone(),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+
+            let id = self.db().insert_project(&project)?;
+            debug!("Created new project: {} (id={})", name, id);
+            Ok(id)
+        })
+    }
+
+    /// Record additional source roots for a project
+    ///
+    /// Returns every additional root of the project, including those recorded
+    /// by earlier parses, in the order they were first added.
+    pub fn add_project_roots(
+        &mut self,
+        project_id: i64,
+        roots: &[PathBuf],
+    ) -> Result<Vec<PathBuf>> {
+        for root in roots {
+            self.db()
+                .add_project_root(project_id, &root.to_string_lossy())?;
+        }
 
-        let id = self.db.insert_project(&project)?;
-        debug!("Created new project: {} (id={})", name, id);
-        Ok(id)
+        let roots: Vec<PathBuf> = self
+            .db()
+            .get_project_roots(project_id)?
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        self.source_roots.extend(roots.iter().cloned());
+        Ok(roots)
     }
 
     /// Store graph data for a single file
+    ///
+    /// All of the file's rows are written in one transaction, which is
+    /// retried as a whole while another connection holds the write lock.
     pub fn store_file_graph(
         &mut self,
         project_id: i64,
@@ -52,10 +172,25 @@ impl GraphBuilder {
         language: &str,
         graph_data: FileGraphData,
     ) -> Result<i64> {
-        let file_path_str = file_path.to_string_lossy().to_string();
+        // Reject malformed extractor output before writing anything
+        graph_data.validate(file_path)?;
+
+        self.db().write_transaction(|| {
+            self.write_file_graph(project_id, file_path, language, &graph_data)
+        })
+    }
+
+    fn write_file_graph(
+        &self,
+        project_id: i64,
+        file_path: &Path,
+        language: &str,
+        graph_data: &FileGraphData,
+    ) -> Result<i64> {
+        let file_path_str = self.stored_path(file_path);
 
         // Check if file already exists
-        if let Some(existing) = self.db.get_file_by_path(project_id, &file_path_str)? {
+        if let Some(existing) = self.db().get_file_by_path(project_id, &file_path_str)? {
             // Check if content changed
             if existing.content_hash == graph_data.content_hash {
                 debug!("File unchanged, skipping: {:?}", file_path);
@@ -64,7 +199,7 @@ impl GraphBuilder {
 
             // Delete old data and re-parse
             debug!("File changed, re-parsing: {:?}", file_path);
-            self.db.delete_file_data(existing.id)?;
+            self.db().delete_file_data(existing.id)?;
         }
 
         // Insert file record
@@ -73,69 +208,383 @@ impl GraphBuilder {
             project_id,
             path: file_path_str,
             language: language.to_string(),
-            content_hash: graph_data.content_hash,
+            content_hash: graph_data.content_hash.clone(),
             parsed_at: chrono::Utc::now(),
+            line_count: graph_data.line_count,
+            is_test: is_test_file(self.root_relative(file_path)),
+            encoding: graph_data.encoding.clone(),
         };
-        let file_id = self.db.insert_file(&file)?;
+        let file_id = self.db().insert_file(&file)?;
 
         // Insert nodes
-        let mut node_id_map = std::collections::HashMap::new();
-        for (idx, node_data) in graph_data.nodes.into_iter().enumerate() {
+        let mut node_ids = Vec::with_capacity(graph_data.nodes.len());
+        for node_data in &graph_data.nodes {
             let node = NodeRecord {
                 id: 0,
                 file_id,
-                node_type: node_data.node_type,
-                name: node_data.name,
-                qualified_name: node_data.qualified_name,
+                node_type: node_data.node_type.clone(),
+                name: node_data.name.clone(),
+                qualified_name: node_data.qualified_name.clone(),
                 start_line: node_data.start_line,
                 start_column: node_data.start_column,
                 end_line: node_data.end_line,
                 end_column: node_data.end_column,
-                attributes: node_data.attributes,
+                attributes: node_data.attributes.clone(),
             };
-            let node_id = self.db.insert_node(&node)?;
-            node_id_map.insert(idx, node_id);
+            node_ids.push(self.db().insert_file_node(&node, &file.path)?);
         }
 
-        // Insert edges (using local indices)
-        let edges_count = graph_data.edges.len();
-        for edge_data in graph_data.edges {
-            if let (Some(&source_id), Some(&target_id)) = (
-                node_id_map.get(&(edge_data.source_idx as usize)),
-                node_id_map.get(&(edge_data.target_idx as usize)),
-            ) {
-                let edge = EdgeRecord {
-                    id: 0,
-                    source_id,
-                    target_id,
-                    edge_type: edge_data.edge_type,
-                    attributes: edge_data.attributes,
-                };
-                self.db.insert_edge(&edge)?;
-            }
+        // Insert edges (local indices were validated above)
+        for edge_data in &graph_data.edges {
+            let edge = EdgeRecord {
+                id: 0,
+                source_id: node_ids[edge_data.source_idx as usize],
+                target_id: node_ids[edge_data.target_idx as usize],
+                edge_type: edge_data.edge_type.clone(),
+                attributes: edge_data.attributes.clone(),
+            };
+            self.db().insert_edge(&edge)?;
         }
 
         debug!(
             "Stored graph for {:?}: {} nodes, {} edges",
             file_path,
-            node_id_map.len(),
-            edges_count
+            node_ids.len(),
+            graph_data.edges.len()
         );
 
         Ok(file_id)
     }
 
+    /// Record that a parse of the project has finished
+    pub fn mark_parsed(&self, project_id: i64) -> Result<()> {
+        self.db().mark_project_parsed(project_id)
+    }
+
+    /// Remove a file and all of its nodes and edges from the graph
+    ///
+    /// Returns false if the file was not indexed.
+    pub fn remove_file(&mut self, project_id: i64, file_path: &Path) -> Result<bool> {
+        let file_path_str = self.stored_path(file_path);
+
+        self.db().write_transaction(|| {
+            match self.db().get_file_by_path(project_id, &file_path_str)? {
+                Some(existing) => {
+                    self.db().delete_file_data(existing.id)?;
+                    self.db().bump_project_generation(project_id)?;
+                    debug!("Removed file: {:?}", file_path);
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+    }
+
+    /// Remove a file deleted from disk and resolve references again, without
+    /// re-parsing the project
+    ///
+    /// Edges into the file's nodes go with them, so references from other
+    /// files that pointed at its symbols are left unresolved, or linked to
+    /// another definition of the same name. Returns `None` if the file was
+    /// not indexed.
+    pub fn remove_file_and_relink(
+        &mut self,
+        project_id: i64,
+        file_path: &Path,
+    ) -> Result<Option<FileRemoval>> {
+        if !self.remove_file(project_id, file_path)? {
+            return Ok(None);
+        }
+        let relinked = self.build_cross_references(project_id)?;
+        Ok(Some(FileRemoval {
+            path: self.stored_path(file_path),
+            relinked,
+        }))
+    }
+
     /// Build cross-file references after all files are parsed
-    pub fn build_cross_references(&mut self, project_id: i64) -> Result<()> {
+    ///
+    /// Returns the number of references linked, counting calls and field
+    /// accesses linked through their receiver's type.
+    pub fn build_cross_references(&mut self, project_id: i64) -> Result<usize> {
         debug!("Building cross-file references for project {}", project_id);
 
         // Get all unresolved references (nodes without target)
-        let unresolved = self.db.get_unresolved_references(project_id)?;
+        let unresolved = self.db().get_unresolved_references(project_id)?;
         debug!("Found {} unresolved references", unresolved.len());
+        let mut resolved = self.link_references(project_id, unresolved)?;
+
+        // Link imports to the packages/types they resolve to; external
+        // dependencies have no matching node and stay unlinked
+        let imports = self.db().get_unresolved_imports(project_id)?;
+        debug!("Found {} unresolved imports", imports.len());
+        let go_modules = if imports.is_empty() {
+            Vec::new()
+        } else {
+            self.go_modules(project_id)?
+        };
+
+        for (import_node_id, import_name) in imports {
+            let target = match self.db().find_import_target(project_id, &import_name)? {
+                Some(target_id) => Some(target_id),
+                None => self.find_go_package(project_id, &go_modules, &import_name)?,
+            };
+            if let Some(target_id) = target {
+                let edge = EdgeRecord {
+                    id: 0,
+                    source_id: import_node_id,
+                    target_id,
+                    edge_type: "imports".to_string(),
+                    attributes: None,
+                };
+                self.db().insert_edge(&edge)?;
+                debug!(
+                    "Resolved import: {} -> {} ({})",
+                    import_node_id, target_id, import_name
+                );
+            }
+        }
+
+        let linked = self.link_method_calls(project_id)?;
+        debug!(
+            "Linked {} method calls and field accesses through receiver types",
+            linked
+        );
+        resolved += linked;
+
+        self.touch_project(project_id)?;
+        Ok(resolved)
+    }
+
+    /// Go modules declared by a `go.mod` at one of the project's roots,
+    /// longest module path first
+    fn go_modules(&self, project_id: i64) -> Result<Vec<GoModule>> {
+        let mut roots = self.db().get_project_roots(project_id)?;
+        roots.extend(self.db().get_project_root(project_id)?);
+
+        let mut modules: Vec<GoModule> = roots
+            .into_iter()
+            .filter_map(|root| {
+                let root = PathBuf::from(root);
+                let content = std::fs::read_to_string(root.join("go.mod")).ok()?;
+                Some(GoModule {
+                    path: go_module_path(&content)?,
+                    dir: root,
+                })
+            })
+            .collect();
+        modules.sort_by_key(|module| std::cmp::Reverse(module.path.len()));
+        Ok(modules)
+    }
+
+    /// Find the package an import path names within one of the project's Go
+    /// modules: `example.com/app/util` in module `example.com/app` is the
+    /// package declared by the files in the module's `util` directory
+    fn find_go_package(
+        &self,
+        project_id: i64,
+        modules: &[GoModule],
+        import_name: &str,
+    ) -> Result<Option<i64>> {
+        for module in modules {
+            let subdir = if import_name == module.path {
+                Some("")
+            } else {
+                import_name
+                    .strip_prefix(module.path.as_str())
+                    .and_then(|rest| rest.strip_prefix('/'))
+            };
+            if let Some(subdir) = subdir {
+                let directory = self.stored_path(&module.dir.join(subdir));
+                let directory = directory.trim_end_matches('/');
+                return self.db().find_package_in_directory(project_id, directory);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Update the project's timestamp and invalidate its cached query results
+    ///
+    /// [`build_cross_references`](Self::build_cross_references) does this
+    /// itself; call it directly when that pass is skipped.
+    pub fn touch_project(&self, project_id: i64) -> Result<()> {
+        self.db().update_project_timestamp(project_id)?;
+        self.db().bump_project_generation(project_id)
+    }
+
+    /// Drop every `references` edge in the project and resolve them again,
+    /// without re-parsing
+    ///
+    /// Useful after manual edits or a partial import left references stale.
+    /// Field accesses linked by the parser itself, such as Java uses of a
+    /// field of the enclosing class, are kept, since only the parser can
+    /// resolve them.
+    pub fn rebuild_cross_references(&mut self, project_id: i64) -> Result<ReferenceRebuild> {
+        let cleared = self.db().delete_reference_edges(project_id)?;
+        let resolved = self.build_cross_references(project_id)?;
+        let unresolved = self.db().get_unresolved_references(project_id)?.len();
+        debug!(
+            "Rebuilt references for project {}: {} cleared, {} resolved, {} unresolved",
+            project_id, cleared, resolved, unresolved
+        );
+
+        Ok(ReferenceRebuild {
+            cleared,
+            resolved,
+            unresolved,
+        })
+    }
+
+    /// Re-resolve cross references after a single file was re-parsed
+    ///
+    /// Re-parsing deletes the file's old nodes, and their edges go with them.
+    /// This links the file's own references and reconnects references from
+    /// other files to symbols the file still defines. Returns the number of
+    /// references linked.
+    pub fn relink_file(&mut self, project_id: i64, file_id: i64) -> Result<usize> {
+        let unresolved = self
+            .db()
+            .get_unresolved_references_for_file(project_id, file_id)?;
+        debug!(
+            "Found {} unresolved references affected by file {}",
+            unresolved.len(),
+            file_id
+        );
+        let linked = self.link_references(project_id, unresolved)?;
+        self.db().bump_project_generation(project_id)?;
+        Ok(linked)
+    }
+
+    /// Link calls on typed receivers to the method they invoke
+    ///
+    /// A call records its receiver's type in `receiver_type`. The method is
+    /// looked up in that type's method set, which includes methods promoted
+    /// from embedded fields: when `S` embeds `T`, `s.Method()` reaches
+    /// `T.Method` unless `S` declares `Method` itself. A name found at the
+    /// same embedding depth through two fields is ambiguous and not linked.
+    /// Field accesses such as `s.count`, which also record `receiver_type`,
+    /// are linked to the field the same way. Returns the number of calls and
+    /// accesses linked.
+    fn link_method_calls(&mut self, project_id: i64) -> Result<usize> {
+        let nodes: HashMap<i64, NodeRecord> = self
+            .db()
+            .get_project_nodes(project_id)?
+            .into_iter()
+            .map(|(_, node)| (node.id, node))
+            .collect();
+        let attribute = |node: &NodeRecord, key: &str| -> Option<serde_json::Value> {
+            let attributes: serde_json::Value =
+                serde_json::from_str(node.attributes.as_deref()?).ok()?;
+            attributes.get(key).cloned()
+        };
+
+        // Methods and fields by receiver type and name
+        let mut methods: HashMap<(String, String), i64> = HashMap::new();
+        let mut fields: HashMap<(String, String), i64> = HashMap::new();
+        for node in nodes.values().filter(|n| n.node_type == "method") {
+            if let Some(serde_json::Value::String(receiver)) = attribute(node, "receiver") {
+                methods.insert((receiver, node.name.clone()), node.id);
+            }
+        }
+
+        // Types embedded in each struct, and calls and accesses that already have a target
+        let mut embedded: HashMap<String, Vec<String>> = HashMap::new();
+        let mut linked_calls = HashSet::new();
+        let mut linked_accesses = HashSet::new();
+        for edge in self.db().get_project_edges(project_id)? {
+            let (Some(source), Some(target)) =
+                (nodes.get(&edge.source_id), nodes.get(&edge.target_id))
+            else {
+                continue;
+            };
+            if edge.edge_type == "contains" && source.node_type == "struct" {
+                fields.insert((source.name.clone(), target.name.clone()), target.id);
+                if attribute(target, "embedded") == Some(serde_json::Value::Bool(true)) {
+                    embedded
+                        .entry(source.name.clone())
+                        .or_default()
+                        .push(target.name.clone());
+                }
+            } else if edge.edge_type == "calls" && source.node_type == "call" {
+                linked_calls.insert(source.id);
+            } else if edge.edge_type == "references" {
+                linked_accesses.insert(source.id);
+            }
+        }
+
+        let mut linked = 0;
+        for call in nodes.values().filter(|n| n.node_type == "call") {
+            if linked_calls.contains(&call.id) {
+                continue;
+            }
+            let Some(serde_json::Value::String(receiver_type)) = attribute(call, "receiver_type")
+            else {
+                continue;
+            };
+            let method_name = call.name.rsplit('.').next().unwrap_or(&call.name);
+
+            let method_id = find_in_method_set(&methods, &embedded, &receiver_type, method_name);
+            if let Some(method_id) = method_id {
+                self.db().insert_edge(&EdgeRecord {
+                    id: 0,
+                    source_id: call.id,
+                    target_id: method_id,
+                    edge_type: "calls".to_string(),
+                    attributes: None,
+                })?;
+                linked += 1;
+            }
+        }
+
+        for access in nodes.values().filter(|n| n.node_type == "reference") {
+            if linked_accesses.contains(&access.id) || attribute(access, "access").is_none() {
+                continue;
+            }
+            let Some(serde_json::Value::String(receiver_type)) = attribute(access, "receiver_type")
+            else {
+                continue;
+            };
+
+            let field_id = find_in_method_set(&fields, &embedded, &receiver_type, &access.name);
+            if let Some(field_id) = field_id {
+                self.db().insert_edge(&EdgeRecord {
+                    id: 0,
+                    source_id: access.id,
+                    target_id: field_id,
+                    edge_type: "references".to_string(),
+                    attributes: None,
+                })?;
+                linked += 1;
+            }
+        }
+
+        Ok(linked)
+    }
+
+    /// Link each reference to a definition with the same name, if one exists
+    ///
+    /// A type in a package the reference's file imports with a wildcard wins
+    /// over same-named definitions elsewhere.
+    fn link_references(
+        &mut self,
+        project_id: i64,
+        unresolved: Vec<(i64, String)>,
+    ) -> Result<usize> {
+        let mut linked = 0;
+        let wildcard_imports = self.db().get_wildcard_imports(project_id)?;
 
         for (ref_node_id, ref_name) in unresolved {
-            // Try to find definition by name
-            if let Some(def_node_id) = self.db.find_definition_by_name(project_id, &ref_name)? {
+            // Prefer a type from a wildcard-imported package, then any definition by name
+            let def_node_id = match self.resolve_wildcard_import(
+                project_id,
+                &wildcard_imports,
+                ref_node_id,
+                &ref_name,
+            )? {
+                Some(id) => Some(id),
+                None => self.db().find_definition_by_name(project_id, &ref_name)?,
+            };
+            if let Some(def_node_id) = def_node_id {
                 // Create reference edge
                 let edge = EdgeRecord {
                     id: 0,
@@ -144,7 +593,8 @@ impl GraphBuilder {
                     edge_type: "references".to_string(),
                     attributes: None,
                 };
-                self.db.insert_edge(&edge)?;
+                self.db().insert_edge(&edge)?;
+                linked += 1;
                 debug!(
                     "Resolved reference: {} -> {} ({})",
                     ref_node_id, def_node_id, ref_name
@@ -152,16 +602,81 @@ impl GraphBuilder {
             }
         }
 
-        // Update project timestamp
-        self.db.update_project_timestamp(project_id)?;
+        Ok(linked)
+    }
+
+    /// Resolve an unqualified reference to a type in a package that the
+    /// reference's file imports with a wildcard, e.g. `List` under
+    /// `import java.util.*`
+    fn resolve_wildcard_import(
+        &self,
+        project_id: i64,
+        wildcard_imports: &HashMap<i64, Vec<String>>,
+        ref_node_id: i64,
+        ref_name: &str,
+    ) -> Result<Option<i64>> {
+        if wildcard_imports.is_empty() || ref_name.contains('.') {
+            return Ok(None);
+        }
+        let Some(reference) = self.db().get_node(ref_node_id)? else {
+            return Ok(None);
+        };
+
+        for package in wildcard_imports
+            .get(&reference.file_id)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(type_id) = self
+                .db()
+                .find_type_in_package(project_id, package, ref_name)?
+            {
+                return Ok(Some(type_id));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Find a method or field in a type's member set, searching embedded types
+/// breadth first
+fn find_in_method_set(
+    members: &HashMap<(String, String), i64>,
+    embedded: &HashMap<String, Vec<String>>,
+    type_name: &str,
+    member_name: &str,
+) -> Option<i64> {
+    let mut visited = HashSet::new();
+    let mut level = vec![type_name.to_string()];
+
+    while !level.is_empty() {
+        let found: HashSet<i64> = level
+            .iter()
+            .filter_map(|t| members.get(&(t.clone(), member_name.to_string())).copied())
+            .collect();
+        match found.len() {
+            0 => {}
+            1 => return found.into_iter().next(),
+            _ => return None,
+        }
 
-        Ok(())
+        visited.extend(level.iter().cloned());
+        level = level
+            .iter()
+            .filter_map(|t| embedded.get(t))
+            .flatten()
+            .filter(|t| !visited.contains(*t))
+            .cloned()
+            .collect();
     }
+
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
     use crate::storage::models::{EdgeData, NodeData};
     use std::path::PathBuf;
     use tempfile::TempDir;
@@ -208,9 +723,104 @@ mod tests {
             nodes,
             edges,
             content_hash: "abc123".to_string(),
+            line_count: 10,
+            encoding: None,
+        }
+    }
+
+    /// A one-line node for cross-reference fixtures
+    fn line_node(node_type: &str, name: &str, line: u32) -> NodeData {
+        NodeData {
+            node_type: node_type.to_string(),
+            name: name.to_string(),
+            qualified_name: Some(name.to_string()),
+            start_line: line,
+            start_column: 1,
+            end_line: line,
+            end_column: 20,
+            attributes: None,
+        }
+    }
+
+    /// Store `nodes` as the graph of `path`, hashed by the nodes so that
+    /// storing different nodes replaces the file
+    fn store_nodes(
+        builder: &mut GraphBuilder,
+        project_id: i64,
+        path: &Path,
+        language: &str,
+        nodes: Vec<NodeData>,
+    ) -> i64 {
+        let graph = FileGraphData {
+            content_hash: format!("{}{:?}", path.display(), nodes),
+            nodes,
+            edges: vec![],
+            line_count: 10,
+            encoding: None,
+        };
+        builder
+            .store_file_graph(project_id, path, language, graph)
+            .unwrap()
+    }
+
+    /// Two `User` classes, in `com.a` and `com.b`, and an app that only
+    /// imports `com.b.*` and references `User`
+    fn store_wildcard_import_fixture(builder: &mut GraphBuilder, project_id: i64) {
+        let mut import = line_node("import", "com.b", 3);
+        import.attributes = Some(r#"{"wildcard":true}"#.to_string());
+        let files = [
+            (
+                "/test/a/User.java",
+                vec![
+                    line_node("package", "com.a", 1),
+                    line_node("class", "User", 3),
+                ],
+            ),
+            (
+                "/test/b/User.java",
+                vec![
+                    line_node("package", "com.b", 1),
+                    line_node("class", "User", 3),
+                ],
+            ),
+            (
+                "/test/app/App.java",
+                vec![
+                    line_node("package", "com.app", 1),
+                    import,
+                    line_node("reference", "User", 6),
+                ],
+            ),
+        ];
+        for (path, nodes) in files {
+            store_nodes(builder, project_id, Path::new(path), "java", nodes);
         }
     }
 
+    /// ID of the first `node_type` node stored for `path`
+    fn node_id(builder: &GraphBuilder, project_id: i64, path: &str, node_type: &str) -> i64 {
+        builder
+            .db
+            .get_project_nodes(project_id)
+            .unwrap()
+            .into_iter()
+            .find(|(p, n)| p == path && n.node_type == node_type)
+            .map(|(_, n)| n.id)
+            .unwrap()
+    }
+
+    /// Targets of the `references` edges leaving a node
+    fn reference_targets(builder: &GraphBuilder, project_id: i64, source_id: i64) -> Vec<i64> {
+        builder
+            .db
+            .get_project_edges(project_id)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.edge_type == "references" && e.source_id == source_id)
+            .map(|e| e.target_id)
+            .collect()
+    }
+
     #[test]
     fn test_graph_builder_new() {
         let (_temp_dir, db) = setup_test_db();
@@ -221,7 +831,7 @@ mod tests {
     #[test]
     fn test_create_new_project() {
         let (temp_dir, db) = setup_test_db();
-        let builder = GraphBuilder::new(db);
+        let mut builder = GraphBuilder::new(db);
 
         let project_id = builder
             .create_or_get_project("test-project", temp_dir.path())
@@ -233,7 +843,7 @@ mod tests {
     #[test]
     fn test_get_existing_project() {
         let (temp_dir, db) = setup_test_db();
-        let builder = GraphBuilder::new(db);
+        let mut builder = GraphBuilder::new(db);
 
         let project_id1 = builder
             .create_or_get_project("test-project", temp_dir.path())
@@ -265,6 +875,31 @@ mod tests {
         assert!(file_id > 0);
     }
 
+    #[test]
+    fn test_store_file_graph_is_test_ignores_dirs_above_root() {
+        let (_temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+
+        // A checkout under a `tests` directory is not all tests
+        let root = Path::new("/home/ci/tests/myrepo");
+        let project_id = builder.create_or_get_project("test-project", root).unwrap();
+
+        let is_test = |builder: &mut GraphBuilder, path: &str| {
+            let path = root.join(path);
+            builder
+                .store_file_graph(project_id, &path, "java", create_test_graph_data())
+                .unwrap();
+            builder
+                .db()
+                .get_file_by_path(project_id, &path.to_string_lossy())
+                .unwrap()
+                .unwrap()
+                .is_test
+        };
+        assert!(!is_test(&mut builder, "src/Main.java"));
+        assert!(is_test(&mut builder, "tests/Fixtures.java"));
+    }
+
     #[test]
     fn test_store_file_graph_unchanged() {
         let (temp_dir, db) = setup_test_db();
@@ -338,14 +973,232 @@ mod tests {
         let mut graph2 = create_test_graph_data();
         graph2.content_hash = "hash2".to_string();
 
-        let file_id1 = builder.store_file_graph(project_id, &file1, "java", graph1).unwrap();
-        let file_id2 = builder.store_file_graph(project_id, &file2, "java", graph2).unwrap();
+        let file_id1 = builder
+            .store_file_graph(project_id, &file1, "java", graph1)
+            .unwrap();
+        let file_id2 = builder
+            .store_file_graph(project_id, &file2, "java", graph2)
+            .unwrap();
 
         assert!(file_id1 > 0);
         assert!(file_id2 > 0);
         assert_ne!(file_id1, file_id2);
     }
 
+    #[test]
+    fn test_store_file_graph_retries_when_another_connection_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("shared.db");
+        // A short busy timeout, so contention reaches the transaction retry
+        let options = crate::storage::sqlite::OpenOptions {
+            busy_timeout: std::time::Duration::from_millis(1),
+            ..Default::default()
+        };
+        let db = Database::open_with_options(&db_path, &options).unwrap();
+        db.init_schema().unwrap();
+        let project_id = GraphBuilder::new(&db)
+            .create_or_get_project("shared", temp_dir.path())
+            .unwrap();
+
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let (db_path, options) = (db_path.clone(), options.clone());
+                std::thread::spawn(move || {
+                    let db = Database::open_with_options(&db_path, &options).unwrap();
+                    let mut builder = GraphBuilder::new(db);
+                    for i in 0..50 {
+                        let mut graph_data = create_test_graph_data();
+                        graph_data.content_hash = format!("hash{}_{}", writer, i);
+                        let path = PathBuf::from(format!("/test/Writer{}_{}.java", writer, i));
+                        builder.store_file_graph(project_id, &path, "java", graph_data)?;
+                    }
+                    Ok::<_, Error>(())
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer
+                .join()
+                .unwrap()
+                .expect("writer failed with a lock error");
+        }
+
+        assert_eq!(db.get_project_files(project_id).unwrap().len(), 100);
+        assert_eq!(db.count_nodes(project_id).unwrap(), 200);
+    }
+
+    #[test]
+    fn test_store_file_graph_many_nodes() {
+        let (temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+
+        let node_count = 2000;
+        let nodes: Vec<NodeData> = (0..node_count)
+            .map(|i| NodeData {
+                node_type: "method".to_string(),
+                name: format!("method{}", i),
+                qualified_name: None,
+                start_line: i + 1,
+                start_column: 1,
+                end_line: i + 1,
+                end_column: 10,
+                attributes: None,
+            })
+            .collect();
+        // Chain each node to the next so edges exercise the index-to-id mapping
+        let edges: Vec<EdgeData> = (0..node_count - 1)
+            .map(|i| EdgeData {
+                source_idx: i,
+                target_idx: i + 1,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .collect();
+
+        let file_id = builder
+            .store_file_graph(
+                project_id,
+                &PathBuf::from("/test/Big.java"),
+                "java",
+                FileGraphData {
+                    nodes,
+                    edges,
+                    content_hash: "big".to_string(),
+                    line_count: node_count,
+                    encoding: None,
+                },
+            )
+            .unwrap();
+
+        let stored = builder.db.get_nodes_by_file(file_id, None).unwrap();
+        assert_eq!(stored.len(), node_count as usize);
+
+        let first = stored.iter().find(|n| n.name == "method0").unwrap();
+        let callees = builder.db.find_callees(first.id, 10, 0).unwrap();
+        assert_eq!(callees.len(), 1);
+        assert_eq!(callees[0].name, "method1");
+
+        let counts = builder.db.counts_by_language(project_id).unwrap();
+        assert_eq!(counts[0].edge_count, node_count - 1);
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let (temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+
+        let file_path = PathBuf::from("/test/TestClass.java");
+        builder
+            .store_file_graph(project_id, &file_path, "java", create_test_graph_data())
+            .unwrap();
+
+        assert!(builder.remove_file(project_id, &file_path).unwrap());
+        assert!(!builder.remove_file(project_id, &file_path).unwrap());
+        assert!(builder
+            .db
+            .find_symbol_by_name(project_id, "TestClass")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_stable_id_survives_reparse() {
+        let (temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+
+        let file_path = PathBuf::from("/test/TestClass.java");
+        builder
+            .store_file_graph(project_id, &file_path, "java", create_test_graph_data())
+            .unwrap();
+        let method = builder
+            .db
+            .find_symbol_by_name(project_id, "testMethod")
+            .unwrap()
+            .unwrap();
+        let stable_id = method.stable_id("/test/TestClass.java");
+        assert_eq!(
+            builder
+                .db
+                .find_node_by_stable_id(project_id, &stable_id)
+                .unwrap()
+                .unwrap()
+                .id,
+            method.id
+        );
+
+        // The method's body changes, but not its declaration
+        let mut changed = create_test_graph_data();
+        changed.content_hash = "def456".to_string();
+        changed.nodes[1].end_line = 9;
+        builder
+            .store_file_graph(project_id, &file_path, "java", changed)
+            .unwrap();
+
+        let reparsed = builder
+            .db
+            .find_node_by_stable_id(project_id, &stable_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reparsed.name, "testMethod");
+        assert_eq!(reparsed.end_line, 9);
+
+        // Moving the declaration gives it a new id
+        let mut moved = create_test_graph_data();
+        moved.content_hash = "ghi789".to_string();
+        moved.nodes[1].start_line = 4;
+        builder
+            .store_file_graph(project_id, &file_path, "java", moved)
+            .unwrap();
+        assert!(builder
+            .db
+            .find_node_by_stable_id(project_id, &stable_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_store_relative_paths() {
+        let (temp_dir, db) = setup_test_db();
+        let root = temp_dir.path();
+        let mut builder = GraphBuilder::new(db).with_relative_paths(root);
+        let project_id = builder.create_or_get_project("test-project", root).unwrap();
+
+        let file_path = root.join("src").join("Test.java");
+        let file_id = builder
+            .store_file_graph(project_id, &file_path, "java", create_test_graph_data())
+            .unwrap();
+
+        let file = builder.db.get_file(file_id).unwrap().unwrap();
+        assert_eq!(Path::new(&file.path), Path::new("src").join("Test.java"));
+        assert_eq!(
+            builder.db.get_file_disk_path(file_id).unwrap(),
+            Some(file_path.clone())
+        );
+
+        // Files outside the root keep their full path
+        let outside = PathBuf::from("/elsewhere/Other.java");
+        let outside_id = builder
+            .store_file_graph(project_id, &outside, "java", create_test_graph_data())
+            .unwrap();
+        assert_eq!(
+            builder.db.get_file_disk_path(outside_id).unwrap(),
+            Some(outside)
+        );
+
+        assert!(builder.remove_file(project_id, &file_path).unwrap());
+    }
+
     #[test]
     fn test_build_cross_references() {
         let (temp_dir, db) = setup_test_db();
@@ -383,6 +1236,8 @@ mod tests {
             nodes,
             edges: vec![],
             content_hash: "test_hash".to_string(),
+            line_count: 10,
+            encoding: None,
         };
 
         let file_path = PathBuf::from("/test/UserService.java");
@@ -393,6 +1248,270 @@ mod tests {
         // Build cross references (should not fail even with unresolved refs)
         let result = builder.build_cross_references(project_id);
         assert!(result.is_ok());
+
+        // Cached query results from before the parse are invalidated
+        assert_eq!(builder.db.get_project_generation(project_id).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_wildcard_import_resolves_reference() {
+        let (temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+
+        store_wildcard_import_fixture(&mut builder, project_id);
+        builder.build_cross_references(project_id).unwrap();
+
+        let reference_id = node_id(&builder, project_id, "/test/app/App.java", "reference");
+        assert_eq!(
+            reference_targets(&builder, project_id, reference_id),
+            vec![node_id(&builder, project_id, "/test/b/User.java", "class")]
+        );
+
+        // Removing the imported class relinks the reference to the other one
+        let removed = builder
+            .remove_file_and_relink(project_id, Path::new("/test/b/User.java"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(removed.path, "/test/b/User.java");
+        assert_eq!(removed.relinked, 1);
+        assert!(builder
+            .remove_file_and_relink(project_id, Path::new("/test/b/User.java"))
+            .unwrap()
+            .is_none());
+
+        let nodes = builder.db.get_project_nodes(project_id).unwrap();
+        assert!(nodes.iter().all(|(path, _)| path != "/test/b/User.java"));
+        assert_eq!(
+            reference_targets(&builder, project_id, reference_id),
+            vec![node_id(&builder, project_id, "/test/a/User.java", "class")]
+        );
+    }
+
+    #[test]
+    fn test_build_cross_references_links_imports() {
+        let (temp_dir, db) = setup_test_db();
+        std::fs::write(
+            temp_dir.path().join("go.mod"),
+            "module example.com/app\n\ngo 1.21\n",
+        )
+        .unwrap();
+        let mut builder = GraphBuilder::new(db);
+
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+        let mut store = |path: &str, nodes: Vec<NodeData>| {
+            store_nodes(
+                &mut builder,
+                project_id,
+                &temp_dir.path().join(path),
+                "go",
+                nodes,
+            );
+        };
+
+        // Two files of package util, each using a function of the other
+        store(
+            "util/parse.go",
+            vec![
+                line_node("package", "util", 1),
+                line_node("function", "Parse", 3),
+                line_node("reference", "Format", 4),
+            ],
+        );
+        store(
+            "util/format.go",
+            vec![
+                line_node("package", "util", 1),
+                line_node("function", "Format", 3),
+                line_node("reference", "Parse", 4),
+            ],
+        );
+        store(
+            "main.go",
+            vec![
+                line_node("package", "main", 1),
+                line_node("import", "example.com/app/util", 3),
+                line_node("import", "example.com/other/util", 4),
+                line_node("import", "fmt", 5),
+            ],
+        );
+
+        builder.build_cross_references(project_id).unwrap();
+
+        let db = &builder.db;
+        let util_dir = temp_dir.path().join("util").to_string_lossy().to_string();
+        let util_pkg = db
+            .find_package_in_directory(project_id, &util_dir)
+            .unwrap()
+            .unwrap();
+        let importers = db.find_importers(util_pkg).unwrap();
+        assert_eq!(importers.len(), 1);
+        assert_eq!(importers[0].name, "example.com/app/util");
+
+        let linked: HashMap<String, String> = db
+            .get_project_edges(project_id)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.edge_type == "references")
+            .map(|e| {
+                let source = db.get_node(e.source_id).unwrap().unwrap();
+                let target = db.get_node(e.target_id).unwrap().unwrap();
+                (source.name, target.node_type)
+            })
+            .collect();
+        assert_eq!(linked.get("Parse").map(String::as_str), Some("function"));
+        assert_eq!(linked.get("Format").map(String::as_str), Some("function"));
+
+        // Imports outside the module stay unlinked, even when their last
+        // segment names a package of the project
+        let mut unresolved: Vec<String> = db
+            .get_unresolved_imports(project_id)
+            .unwrap()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        unresolved.sort();
+        assert_eq!(unresolved, vec!["example.com/other/util", "fmt"]);
+    }
+
+    #[test]
+    fn test_go_module_path() {
+        assert_eq!(
+            go_module_path("// comment\nmodule \"example.com/app\" // app\n").as_deref(),
+            Some("example.com/app")
+        );
+        assert_eq!(go_module_path("modulex foo\ngo 1.21\n"), None);
+    }
+
+    #[test]
+    fn test_references_link_to_enum_and_type_declarations() {
+        // Java `private Color color;` with `enum Color`, and Go `ID` in a
+        // field type with `type ID string`
+        let cases = [
+            (
+                "java",
+                "/test/Car.java",
+                "/test/Color.java",
+                "enum",
+                "Color",
+            ),
+            ("go", "/test/user.go", "/test/id.go", "type", "ID"),
+        ];
+        for (language, user_path, decl_path, decl_type, name) in cases {
+            let (temp_dir, db) = setup_test_db();
+            let mut builder = GraphBuilder::new(db);
+            let project_id = builder
+                .create_or_get_project("test-project", temp_dir.path())
+                .unwrap();
+
+            store_nodes(
+                &mut builder,
+                project_id,
+                Path::new(user_path),
+                language,
+                vec![line_node("reference", name, 3)],
+            );
+            store_nodes(
+                &mut builder,
+                project_id,
+                Path::new(decl_path),
+                language,
+                vec![line_node(decl_type, name, 1)],
+            );
+            assert_eq!(builder.build_cross_references(project_id).unwrap(), 1);
+            let reference_id = node_id(&builder, project_id, user_path, "reference");
+            assert_eq!(
+                reference_targets(&builder, project_id, reference_id),
+                vec![node_id(&builder, project_id, decl_path, decl_type)]
+            );
+
+            // Moving the declaration drops the edge, and relinking restores it
+            let file_id = store_nodes(
+                &mut builder,
+                project_id,
+                Path::new(decl_path),
+                language,
+                vec![line_node(decl_type, name, 2)],
+            );
+            assert_eq!(builder.relink_file(project_id, file_id).unwrap(), 1);
+            assert_eq!(
+                reference_targets(&builder, project_id, reference_id),
+                vec![node_id(&builder, project_id, decl_path, decl_type)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_relink_file_after_reparse() {
+        let (temp_dir, db) = setup_test_db();
+        let mut builder = GraphBuilder::new(db);
+
+        let project_id = builder
+            .create_or_get_project("test-project", temp_dir.path())
+            .unwrap();
+
+        let repo_path = Path::new("/test/Repository.java");
+        store_nodes(
+            &mut builder,
+            project_id,
+            repo_path,
+            "java",
+            vec![
+                line_node("class", "Repository", 1),
+                line_node("method", "save", 3),
+            ],
+        );
+        store_nodes(
+            &mut builder,
+            project_id,
+            Path::new("/test/Service.java"),
+            "java",
+            vec![
+                line_node("class", "Service", 1),
+                line_node("reference", "save", 5),
+            ],
+        );
+        builder.build_cross_references(project_id).unwrap();
+        assert!(builder
+            .db
+            .get_unresolved_references(project_id)
+            .unwrap()
+            .is_empty());
+
+        // Re-parse without `save`: the external reference loses its target
+        let file_id = store_nodes(
+            &mut builder,
+            project_id,
+            repo_path,
+            "java",
+            vec![line_node("class", "Repository", 1)],
+        );
+        assert_eq!(builder.relink_file(project_id, file_id).unwrap(), 0);
+        let unresolved = builder.db.get_unresolved_references(project_id).unwrap();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].1, "save");
+
+        // Re-parse with `save` restored: the external reference is reconnected
+        let file_id = store_nodes(
+            &mut builder,
+            project_id,
+            repo_path,
+            "java",
+            vec![
+                line_node("class", "Repository", 1),
+                line_node("method", "save", 4),
+            ],
+        );
+        assert_eq!(builder.relink_file(project_id, file_id).unwrap(), 1);
+        assert!(builder
+            .db
+            .get_unresolved_references(project_id)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
@@ -438,6 +1557,8 @@ mod tests {
             nodes,
             edges,
             content_hash: "edge_test_hash".to_string(),
+            line_count: 10,
+            encoding: None,
         };
 
         let file_path = PathBuf::from("/test/main.go");
@@ -480,12 +1601,24 @@ mod tests {
             nodes,
             edges,
             content_hash: "invalid_edge_hash".to_string(),
+            line_count: 10,
+            encoding: None,
         };
 
         let file_path = PathBuf::from("/test/invalid.go");
 
-        // Should not fail, just skip invalid edges
+        // The whole file is rejected before anything is written
         let result = builder.store_file_graph(project_id, &file_path, "go", graph_data);
-        assert!(result.is_ok());
+        match result {
+            Err(Error::Parse { message, .. }) => {
+                assert!(message.contains("edge 0 (calls) references node 99"));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(builder
+            .db
+            .get_file_by_path(project_id, "/test/invalid.go")
+            .unwrap()
+            .is_none());
     }
 }
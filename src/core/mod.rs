@@ -1,56 +1,461 @@
 //! Core engine for code graph parsing and querying
 
+#[cfg(feature = "service")]
+pub mod archive;
+pub mod cache;
 pub mod config;
+#[cfg(feature = "service")]
+pub mod diff;
+pub mod git;
+#[cfg(feature = "service")]
 pub mod graph;
 pub mod parser;
+pub mod projects;
+#[cfg(feature = "service")]
 pub mod query;
 pub mod registry;
 
-use std::path::Path;
-use tracing::info;
+#[cfg(feature = "service")]
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+#[cfg(feature = "service")]
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use serde::Serialize;
+#[cfg(feature = "service")]
+use tracing::{debug, info, warn};
+
+use crate::core::config::ParseConfig;
+#[cfg(feature = "service")]
+use crate::error::Error;
+use crate::error::Result;
 use crate::languages::LanguageRegistry;
+#[cfg(feature = "service")]
+use crate::storage::sqlite::OpenOptions;
+#[cfg(feature = "service")]
 use crate::storage::Database;
 
+/// Summary of a project parse
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParseReport {
+    pub project_id: i64,
+    pub files_parsed: usize,
+    pub files_failed: usize,
+    pub files_removed: usize,
+    pub skipped: Vec<SkippedFile>,
+    /// Files that failed to parse, with the error
+    pub failed: Vec<SkippedFile>,
+}
+
+/// A file that was not parsed, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Build the language registry, adding the configured extra extensions
+fn language_registry(config: &ParseConfig) -> Result<LanguageRegistry> {
+    let mut registry = LanguageRegistry::new();
+    for (extension, language_id) in &config.extra_extensions {
+        registry.register_extension(language_id, extension)?;
+    }
+    Ok(registry)
+}
+
+/// Build a parser with the configured languages, limits and dialects
+fn code_parser(config: &ParseConfig) -> Result<parser::CodeParser> {
+    parser::CodeParser::new(language_registry(config)?)
+        .with_max_file_bytes(config.max_file_bytes)
+        .with_parse_timeout(Duration::from_millis(config.parse_timeout_ms))
+        .with_max_syntax_nodes(config.max_syntax_nodes)
+        .with_max_syntax_depth(config.max_syntax_depth)
+        .with_follow_symlinks(config.follow_symlinks)
+        .with_max_depth(config.max_depth)
+        .with_skip_generated(config.skip_generated)
+        .with_column_encoding(config.column_encoding)
+        .with_path_filters(&config.include, &config.exclude)?
+        .with_fallback_encoding(config.fallback_encoding.as_deref())?
+        .with_dialects(&config.dialects)
+}
+
+/// Files a parse would process, grouped by language
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DryRunReport {
+    pub total: usize,
+    pub files_by_language: BTreeMap<String, Vec<String>>,
+    /// Paths that would not be parsed, and why
+    pub skipped: Vec<parser::SkippedEntry>,
+}
+
+/// Collect the files a parse would index without touching the database
+pub fn dry_run(
+    project_path: &Path,
+    extra_roots: &[PathBuf],
+    languages: Option<&[String]>,
+    config: &ParseConfig,
+) -> Result<DryRunReport> {
+    let parser = code_parser(config)?;
+
+    let mut roots = vec![project_path.to_path_buf()];
+    roots.extend_from_slice(extra_roots);
+
+    let collected = parser.collect_files_report_in_roots(&roots, languages);
+    let mut report = DryRunReport {
+        skipped: collected.skipped,
+        ..Default::default()
+    };
+    for (path, language) in collected.files {
+        report
+            .files_by_language
+            .entry(language)
+            .or_default()
+            .push(path.to_string_lossy().to_string());
+        report.total += 1;
+    }
+    for files in report.files_by_language.values_mut() {
+        files.sort();
+    }
+
+    Ok(report)
+}
+
 /// Parse a project and build the code graph
+///
+/// With `since`, only files changed since that git ref are parsed and files
+/// deleted since then are removed. Outside a git work tree the whole project
+/// is parsed.
+#[cfg(feature = "service")]
 pub async fn parse_project(
     db_path: &Path,
     project_name: &str,
     project_path: &Path,
     languages: Option<&[String]>,
-) -> anyhow::Result<()> {
-    let db = Database::open(db_path)?;
+    since: Option<&str>,
+    config: &ParseConfig,
+) -> Result<ParseReport> {
+    let options = ParseOptions {
+        languages,
+        since,
+        ..Default::default()
+    };
+    parse_project_with_options(
+        db_path,
+        &OpenOptions::default(),
+        project_name,
+        project_path,
+        &options,
+        config,
+    )
+    .await
+}
+
+/// Optional inputs to a project parse
+#[derive(Default)]
+pub struct ParseOptions<'a> {
+    /// Source roots besides the primary one. They are recorded with the
+    /// project, so later parses walk them too without repeating them.
+    pub extra_roots: &'a [PathBuf],
+    /// Only parse files in these languages
+    pub languages: Option<&'a [String]>,
+    /// Only parse files changed since this git ref
+    pub since: Option<&'a str>,
+    /// Skip resolving references between symbols after the files are
+    /// stored, for indexes only used to search symbols
+    pub skip_cross_references: bool,
+    /// Receives per-file progress
+    pub progress: Option<&'a dyn ParseProgress>,
+}
+
+/// Receives progress updates while a project is parsed
+pub trait ParseProgress: Send + Sync {
+    /// Called once with the number of files about to be parsed, or 0 with
+    /// a parallel walk, which reports each file to
+    /// [`file_found`](Self::file_found) instead
+    fn start(&self, total: usize);
+
+    /// Called for each file the parallel walk finds, before it is parsed
+    fn file_found(&self, _path: &Path) {}
+
+    /// Called after each file, whether it was parsed, skipped or failed
+    fn file_done(&self, path: &Path);
+
+    /// Called once all files have been processed
+    fn finish(&self, report: &ParseReport);
+}
+
+/// Parse a project with extra roots, filters or progress reporting
+///
+/// `project_path` is the primary root, used to identify and display the
+/// project. The database is opened with `open_options`.
+#[cfg(feature = "service")]
+pub async fn parse_project_with_options(
+    db_path: &Path,
+    open_options: &OpenOptions,
+    project_name: &str,
+    project_path: &Path,
+    options: &ParseOptions<'_>,
+    config: &ParseConfig,
+) -> Result<ParseReport> {
+    let db = Database::open_with_options(db_path, open_options)?;
     db.init_schema()?;
+    parse_project_into(db, project_name, project_path, options, config)
+}
 
-    let registry = LanguageRegistry::new();
-    let parser = parser::CodeParser::new(registry);
+/// Parse a project into an open database whose schema is initialized
+///
+/// Like [`parse_project_with_options`], for callers that already hold a
+/// connection, such as the server parsing into its in-memory database.
+#[cfg(feature = "service")]
+pub fn parse_project_into<D: Borrow<Database>>(
+    db: D,
+    project_name: &str,
+    project_path: &Path,
+    options: &ParseOptions<'_>,
+    config: &ParseConfig,
+) -> Result<ParseReport> {
+    let parser = code_parser(config)?;
     let mut builder = graph::GraphBuilder::new(db);
+    if config.relative_paths {
+        builder = builder.with_relative_paths(project_path);
+    }
 
     // Create or get project
     let project_id = builder.create_or_get_project(project_name, project_path)?;
 
     info!("Project ID: {}", project_id);
 
-    // Collect files to parse
-    let files = parser.collect_files(project_path, languages)?;
-    info!("Found {} files to parse", files.len());
+    let mut report = ParseReport {
+        project_id,
+        ..Default::default()
+    };
+
+    let mut roots = vec![project_path.to_path_buf()];
+    for root in builder.add_project_roots(project_id, options.extra_roots)? {
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+
+    // Work out what changed before walking, so files can be parsed as soon
+    // as they are found
+    let changes = match options.since {
+        Some(git_ref) => match changed_files_in_roots(&roots, git_ref)? {
+            Some(changes) => {
+                for deleted in &changes.deleted {
+                    if builder.remove_file(project_id, deleted)? {
+                        report.files_removed += 1;
+                    }
+                }
+                info!(
+                    "{} files changed and {} removed since {}",
+                    changes.changed.len(),
+                    report.files_removed,
+                    git_ref
+                );
+                Some(changes.changed.into_iter().collect::<HashSet<_>>())
+            }
+            None => {
+                warn!(
+                    "{:?} is not inside a git repository, parsing all files",
+                    roots
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let wanted = |path: &PathBuf| {
+        changes
+            .as_ref()
+            .is_none_or(|changed| changed.contains(path))
+    };
 
-    // Parse each file
-    for (file_path, language) in files {
+    let mut parse_one = |file_path: PathBuf, language: String| -> Result<()> {
         info!("Parsing {:?} as {}", file_path, language);
         match parser.parse_file(&file_path, &language) {
             Ok(graph_data) => {
                 builder.store_file_graph(project_id, &file_path, &language, graph_data)?;
+                report.files_parsed += 1;
+            }
+            Err(e) if e.is_budget_exceeded() => {
+                warn!("Skipping {:?}: {}", file_path, e);
+                report.skipped.push(SkippedFile {
+                    path: file_path.to_string_lossy().to_string(),
+                    reason: e.to_string(),
+                });
             }
             Err(e) => {
-                tracing::warn!("Failed to parse {:?}: {}", file_path, e);
+                warn!("Failed to parse {:?}: {}", file_path, e);
+                report.files_failed += 1;
+                report.failed.push(SkippedFile {
+                    path: file_path.to_string_lossy().to_string(),
+                    reason: e.to_string(),
+                });
             }
         }
+        if let Some(progress) = options.progress {
+            progress.file_done(&file_path);
+        }
+        Ok(())
+    };
+
+    if config.parallel_walk {
+        // Parse each file as the walker finds it; the total is not known
+        // until the walk ends
+        if let Some(progress) = options.progress {
+            progress.start(0);
+        }
+        parser.stream_files_parallel(&roots, options.languages, |files| {
+            for (file_path, language) in files.filter(|(path, _)| wanted(path)) {
+                if let Some(progress) = options.progress {
+                    progress.file_found(&file_path);
+                }
+                parse_one(file_path, language)?;
+            }
+            Ok::<_, Error>(())
+        })?;
+    } else {
+        let mut files = parser.collect_files_in_roots(&roots, options.languages)?;
+        files.retain(|(path, _)| wanted(path));
+        info!("Found {} files to parse", files.len());
+        if let Some(progress) = options.progress {
+            progress.start(files.len());
+        }
+        for (file_path, language) in files {
+            parse_one(file_path, language)?;
+        }
     }
+    // The parallel walk finds files in no fixed order
+    report.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+    report.failed.sort_by(|a, b| a.path.cmp(&b.path));
 
     // Build cross-file references
-    builder.build_cross_references(project_id)?;
+    if options.skip_cross_references {
+        info!("Skipping cross-reference resolution");
+        builder.touch_project(project_id)?;
+    } else {
+        builder.build_cross_references(project_id)?;
+    }
+    builder.mark_parsed(project_id)?;
 
     info!("Project parsing complete");
-    Ok(())
+    if let Some(progress) = options.progress {
+        progress.finish(&report);
+    }
+    Ok(report)
+}
+
+/// Parse the source files inside a `.tar`, `.tar.gz` or `.zip` archive
+///
+/// Entries are read straight from the archive and never extracted. Files are
+/// stored under their path inside the archive, and the project's root is the
+/// archive itself. Hidden entries and files the include/exclude globs or
+/// language filter leave out are ignored, as in a directory parse.
+#[cfg(feature = "service")]
+pub async fn parse_archive(
+    db_path: &Path,
+    open_options: &OpenOptions,
+    project_name: &str,
+    archive_path: &Path,
+    languages: Option<&[String]>,
+    config: &ParseConfig,
+) -> Result<ParseReport> {
+    let db = Database::open_with_options(db_path, open_options)?;
+    db.init_schema()?;
+
+    let parser = code_parser(config)?;
+    let mut builder = graph::GraphBuilder::new(db);
+    let project_id = builder.create_or_get_project(project_name, archive_path)?;
+    info!("Project ID: {}", project_id);
+
+    let mut report = ParseReport {
+        project_id,
+        ..Default::default()
+    };
+
+    archive::for_each_file(archive_path, |path, size, reader| {
+        let hidden = path
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if hidden {
+            return Ok(());
+        }
+        let Ok(language) = parser.select_language(path, path, languages) else {
+            return Ok(());
+        };
+        if size > config.max_file_bytes {
+            let e = Error::FileTooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit: config.max_file_bytes,
+            };
+            warn!("Skipping {:?}: {}", path, e);
+            report.skipped.push(SkippedFile {
+                path: path.to_string_lossy().to_string(),
+                reason: e.to_string(),
+            });
+            return Ok(());
+        }
+
+        let mut bytes = Vec::with_capacity(size as usize);
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::io(archive_path, e))?;
+        let (content, encoding) = parser.decode(&bytes);
+        if parser.is_generated(&language, &content) {
+            debug!("Skipping generated file {:?}", path);
+            return Ok(());
+        }
+
+        info!("Parsing {:?} as {}", path, language);
+        match parser.parse_source(&content, &language) {
+            Ok(mut graph_data) => {
+                graph_data.encoding = Some(encoding.to_string());
+                builder.store_file_graph(project_id, path, &language, graph_data)?;
+                report.files_parsed += 1;
+            }
+            Err(e) if e.is_budget_exceeded() => {
+                warn!("Skipping {:?}: {}", path, e);
+                report.skipped.push(SkippedFile {
+                    path: path.to_string_lossy().to_string(),
+                    reason: e.to_string(),
+                });
+            }
+            Err(e) => {
+                warn!("Failed to parse {:?}: {}", path, e);
+                report.files_failed += 1;
+                report.failed.push(SkippedFile {
+                    path: path.to_string_lossy().to_string(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        Ok(())
+    })?;
+
+    builder.build_cross_references(project_id)?;
+    builder.mark_parsed(project_id)?;
+
+    info!("Archive parsing complete");
+    Ok(report)
+}
+
+/// Merge the git changes under each root; `None` if any root is outside a
+/// git work tree
+#[cfg(feature = "service")]
+fn changed_files_in_roots(roots: &[PathBuf], git_ref: &str) -> Result<Option<git::GitChanges>> {
+    let mut merged = git::GitChanges::default();
+    for root in roots {
+        match git::changed_files_since(root, git_ref)? {
+            Some(changes) => {
+                merged.changed.extend(changes.changed);
+                merged.deleted.extend(changes.deleted);
+            }
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(merged))
 }
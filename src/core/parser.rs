@@ -1,32 +1,288 @@
 //! Code parser using tree-sitter
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
-
-use anyhow::{Context, Result};
-use tracing::debug;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+use encoding_rs::{Encoding, UTF_8};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
 use walkdir::WalkDir;
 
-use crate::languages::LanguageRegistry;
+use crate::core::config::ParseConfig;
+use crate::error::{Error, Result};
+use crate::languages::{LanguageRegistry, QueryBasedExtractor};
 use crate::storage::models::{EdgeData, NodeData};
 
+/// Files the parallel walker may queue before its threads wait for the
+/// consumer to catch up
+const FILE_CHANNEL_CAPACITY: usize = 1024;
+
 /// Parsed graph data from a single file
 #[derive(Debug, Clone)]
 pub struct FileGraphData {
     pub nodes: Vec<NodeData>,
     pub edges: Vec<EdgeData>,
     pub content_hash: String,
+    pub line_count: u32,
+    /// Encoding the source was decoded from, when read from disk
+    pub encoding: Option<String>,
+}
+
+impl FileGraphData {
+    /// Check that every edge connects two of this file's nodes
+    ///
+    /// Returns [`Error::Parse`] naming the first edge whose source or target
+    /// index is out of bounds.
+    pub fn validate(&self, path: &Path) -> Result<()> {
+        let node_count = self.nodes.len();
+        for (i, edge) in self.edges.iter().enumerate() {
+            for idx in [edge.source_idx, edge.target_idx] {
+                if idx as usize >= node_count {
+                    return Err(Error::Parse {
+                        path: path.to_path_buf(),
+                        message: format!(
+                            "edge {} ({}) references node {}, but only {} nodes were extracted",
+                            i, edge.edge_type, idx, node_count
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Unit in which node columns are counted
+///
+/// Tree-sitter reports byte offsets, so a column after a multi-byte
+/// character is larger than the character position editors show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnEncoding {
+    /// UTF-8 bytes, as tree-sitter reports them
+    #[default]
+    Byte,
+    /// Unicode scalar values
+    Char,
+    /// UTF-16 code units, as used by LSP clients
+    Utf16,
+}
+
+impl ColumnEncoding {
+    /// Convert a 1-based byte column on `line` to this encoding
+    pub fn convert(self, line: &str, byte_column: u32) -> u32 {
+        if self == ColumnEncoding::Byte {
+            return byte_column;
+        }
+
+        let mut end = (byte_column.saturating_sub(1) as usize).min(line.len());
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        let prefix = &line[..end];
+        let units = match self {
+            ColumnEncoding::Utf16 => prefix.encode_utf16().count(),
+            _ => prefix.chars().count(),
+        };
+        units as u32 + 1
+    }
+}
+
+/// Why a path under a project root is not parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No registered language handles the file name
+    UnsupportedExtension,
+    /// The file's language is not among the requested languages
+    FilteredLanguage,
+    /// Hidden files and directories are not walked
+    Ignored,
+    /// The path matches an `--exclude` glob, or no `--include` glob
+    Excluded,
+    /// The file exceeds the configured size limit
+    TooLarge,
+    /// Symbolic links are only followed when enabled
+    Symlink,
+    /// The entry is deeper below the root than the configured `max_depth`
+    TooDeep,
+    /// A directory already walked, reached again through a symlink
+    AlreadyVisited,
+    /// The file is marked as generated code
+    Generated,
+    /// The entry could not be read, e.g. a broken link or symlink loop
+    Unreadable,
+}
+
+impl SkipReason {
+    /// The reason as it is serialized, e.g. `too_large`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::UnsupportedExtension => "unsupported_extension",
+            SkipReason::FilteredLanguage => "filtered_language",
+            SkipReason::Ignored => "ignored",
+            SkipReason::Excluded => "excluded",
+            SkipReason::TooLarge => "too_large",
+            SkipReason::Symlink => "symlink",
+            SkipReason::TooDeep => "too_deep",
+            SkipReason::AlreadyVisited => "already_visited",
+            SkipReason::Generated => "generated",
+            SkipReason::Unreadable => "unreadable",
+        }
+    }
+}
+
+/// A path that was not collected, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEntry {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// Files collected under a root, with every path that was passed over
+#[derive(Debug, Clone, Default)]
+pub struct CollectionReport {
+    pub files: Vec<(PathBuf, String)>,
+    pub skipped: Vec<SkippedEntry>,
 }
 
 /// Code parser that uses tree-sitter for syntax analysis
 pub struct CodeParser {
     registry: LanguageRegistry,
+    max_file_bytes: u64,
+    parse_timeout: Duration,
+    max_syntax_nodes: usize,
+    max_syntax_depth: usize,
+    follow_symlinks: bool,
+    max_depth: usize,
+    skip_generated: bool,
+    dialects: HashMap<String, String>,
+    column_encoding: ColumnEncoding,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    fallback_encoding: Option<&'static Encoding>,
 }
 
 impl CodeParser {
     /// Create a new parser with the given language registry
     pub fn new(registry: LanguageRegistry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            max_file_bytes: ParseConfig::default().max_file_bytes,
+            parse_timeout: Duration::from_millis(ParseConfig::default().parse_timeout_ms),
+            max_syntax_nodes: ParseConfig::default().max_syntax_nodes,
+            max_syntax_depth: ParseConfig::default().max_syntax_depth,
+            follow_symlinks: false,
+            max_depth: ParseConfig::default().max_depth,
+            skip_generated: false,
+            dialects: HashMap::new(),
+            column_encoding: ColumnEncoding::default(),
+            include: None,
+            exclude: None,
+            fallback_encoding: None,
+        }
+    }
+
+    /// Only collect files matching an `include` glob and no `exclude` glob
+    ///
+    /// Globs are matched against paths relative to the root being walked,
+    /// e.g. `src/**` or `**/generated/**`. An empty `include` list includes
+    /// everything.
+    pub fn with_path_filters(mut self, include: &[String], exclude: &[String]) -> Result<Self> {
+        self.include = build_glob_set(include)?;
+        self.exclude = build_glob_set(exclude)?;
+        Ok(self)
+    }
+
+    /// Set the unit node columns are reported in
+    pub fn with_column_encoding(mut self, column_encoding: ColumnEncoding) -> Self {
+        self.column_encoding = column_encoding;
+        self
+    }
+
+    /// Set whether symbolic links are followed while collecting files
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Set how many directory levels below a root are walked
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set whether files marked as generated code are skipped
+    pub fn with_skip_generated(mut self, skip_generated: bool) -> Self {
+        self.skip_generated = skip_generated;
+        self
+    }
+
+    /// Set the dialect used for each language ID
+    ///
+    /// Fails if a language does not exist or has no such dialect.
+    pub fn with_dialects<'a>(
+        mut self,
+        dialects: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> Result<Self> {
+        for (language_id, dialect) in dialects {
+            let lang = self
+                .registry
+                .get(language_id)
+                .ok_or_else(|| Error::UnsupportedLanguage(language_id.clone()))?;
+            lang.grammar_for(Some(dialect))?;
+            self.dialects.insert(language_id.clone(), dialect.clone());
+        }
+        Ok(self)
+    }
+
+    /// Decode files that have no byte order mark and are not valid UTF-8
+    /// with the encoding named by `label`, e.g. `windows-1252` or `shift_jis`
+    ///
+    /// Without one, invalid sequences become U+FFFD. Fails if the label is
+    /// not a known encoding.
+    pub fn with_fallback_encoding(mut self, label: Option<&str>) -> Result<Self> {
+        self.fallback_encoding = label
+            .map(|label| {
+                Encoding::for_label(label.trim().as_bytes())
+                    .ok_or_else(|| Error::Config(format!("Unknown encoding {:?}", label)))
+            })
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Set the maximum size of a file that will be parsed
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Set how long tree-sitter may spend parsing one file; zero means no limit
+    pub fn with_parse_timeout(mut self, parse_timeout: Duration) -> Self {
+        self.parse_timeout = parse_timeout;
+        self
+    }
+
+    /// Set the most syntax nodes a file may have before extraction refuses
+    /// it; zero means no limit
+    pub fn with_max_syntax_nodes(mut self, max_syntax_nodes: usize) -> Self {
+        self.max_syntax_nodes = max_syntax_nodes;
+        self
+    }
+
+    /// Set how deeply a file's syntax tree may nest before extraction
+    /// refuses it; zero means no limit
+    pub fn with_max_syntax_depth(mut self, max_syntax_depth: usize) -> Self {
+        self.max_syntax_depth = max_syntax_depth;
+        self
     }
 
     /// Collect all parseable files in a directory
@@ -35,28 +291,304 @@ impl CodeParser {
         root: &Path,
         filter_languages: Option<&[String]>,
     ) -> Result<Vec<(PathBuf, String)>> {
+        Ok(self.walk(root, filter_languages, false).files)
+    }
+
+    /// Collect the files in a directory and explain why other paths were skipped
+    ///
+    /// Unlike [`collect_files`](Self::collect_files), files over the size
+    /// limit are left out and reported as [`SkipReason::TooLarge`].
+    pub fn collect_files_report(
+        &self,
+        root: &Path,
+        filter_languages: Option<&[String]>,
+    ) -> CollectionReport {
+        self.walk(root, filter_languages, true)
+    }
+
+    /// Collect the files under several roots, explaining skipped paths
+    pub fn collect_files_report_in_roots(
+        &self,
+        roots: &[PathBuf],
+        filter_languages: Option<&[String]>,
+    ) -> CollectionReport {
+        let mut seen = HashSet::new();
+        let mut report = CollectionReport::default();
+
+        for root in roots {
+            let collected = self.walk(root, filter_languages, true);
+            for (path, language) in collected.files {
+                if seen.insert(path.clone()) {
+                    report.files.push((path, language));
+                }
+            }
+            for entry in collected.skipped {
+                if seen.insert(entry.path.clone()) {
+                    report.skipped.push(entry);
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Walk a root, optionally checking file sizes
+    fn walk(
+        &self,
+        root: &Path,
+        filter_languages: Option<&[String]>,
+        check_size: bool,
+    ) -> CollectionReport {
+        let mut report = CollectionReport::default();
+        let mut skip = |path: &Path, reason| {
+            report.skipped.push(SkippedEntry {
+                path: path.to_path_buf(),
+                reason,
+            })
+        };
         let mut files = Vec::new();
+        // Canonical directories already walked, so symlinked directories that
+        // point back into the tree are not descended into twice
+        let mut visited = HashSet::new();
+
+        let mut entries = WalkDir::new(root)
+            .follow_links(self.follow_symlinks)
+            .into_iter();
+        while let Some(entry) = entries.next() {
+            // Symlink loops and unreadable entries are reported by walkdir as errors
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let path = e.path().unwrap_or(root);
+                    warn!("Skipping {:?}: {}", path, e);
+                    skip(path, SkipReason::Unreadable);
+                    continue;
+                }
+            };
 
-        for entry in WalkDir::new(root)
-            .follow_links(true)
-            .into_iter()
-            .filter_entry(|e| !is_hidden(e))
-        {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                if let Some(ext) = entry.path().extension().and_then(|e| e.to_str()) {
-                    if let Some(lang) = self.registry.get_by_extension(ext) {
-                        let lang_id = lang.language_id().to_string();
-
-                        // Apply language filter if specified
-                        if let Some(filters) = filter_languages {
-                            if !filters.contains(&lang_id) {
-                                continue;
-                            }
+            // The root itself is walked even if its name starts with a dot
+            if entry.depth() > 0 && is_hidden(&entry) {
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                skip(entry.path(), SkipReason::Ignored);
+                continue;
+            }
+            if entry.file_type().is_symlink() {
+                skip(entry.path(), SkipReason::Symlink);
+                continue;
+            }
+            if entry.depth() > self.max_depth {
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                skip(entry.path(), SkipReason::TooDeep);
+                continue;
+            }
+            if entry.file_type().is_dir() {
+                if self.follow_symlinks {
+                    let canonical = entry
+                        .path()
+                        .canonicalize()
+                        .unwrap_or_else(|_| entry.path().to_path_buf());
+                    if !visited.insert(canonical) {
+                        entries.skip_current_dir();
+                        skip(entry.path(), SkipReason::AlreadyVisited);
+                    }
+                }
+                continue;
+            }
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            let lang_id = match self.select_language(entry.path(), relative, filter_languages) {
+                Ok(lang_id) => lang_id,
+                Err(reason) => {
+                    skip(entry.path(), reason);
+                    continue;
+                }
+            };
+            if check_size
+                && entry
+                    .metadata()
+                    .is_ok_and(|m| m.len() > self.max_file_bytes)
+            {
+                skip(entry.path(), SkipReason::TooLarge);
+                continue;
+            }
+            if self.is_generated_file(entry.path(), &lang_id) {
+                skip(entry.path(), SkipReason::Generated);
+                continue;
+            }
+
+            files.push((entry.path().to_path_buf(), lang_id));
+        }
+
+        report.files = files;
+        report
+    }
+
+    /// Collect all parseable files under several roots, walking directories
+    /// on several threads
+    ///
+    /// Finds the same files as [`collect_files_in_roots`](Self::collect_files_in_roots),
+    /// except that paths matched by `.gitignore` or `.ignore` files are left
+    /// out too, but is much faster on slow filesystems such as network
+    /// mounts. Files are sorted by path.
+    pub fn collect_files_parallel(
+        &self,
+        roots: &[PathBuf],
+        filter_languages: Option<&[String]>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let mut files: Vec<_> =
+            self.stream_files_parallel(roots, filter_languages, |files| files.collect());
+        files.sort();
+        Ok(files)
+    }
+
+    /// Walk several roots on several threads, passing the files to `consume`
+    /// as they are found so they can be parsed while the walk goes on
+    ///
+    /// Each file is passed once, in no particular order. If `consume` returns
+    /// before reading every file, the walk stops.
+    pub fn stream_files_parallel<T>(
+        &self,
+        roots: &[PathBuf],
+        filter_languages: Option<&[String]>,
+        consume: impl FnOnce(&mut dyn Iterator<Item = (PathBuf, String)>) -> T,
+    ) -> T {
+        let (sender, receiver) = mpsc::sync_channel(FILE_CHANNEL_CAPACITY);
+        thread::scope(|scope| {
+            scope.spawn(move || self.send_files_parallel(roots, filter_languages, sender));
+            let mut seen = HashSet::new();
+            let mut files = receiver
+                .into_iter()
+                .filter(move |(path, _): &(PathBuf, String)| seen.insert(path.clone()));
+            consume(&mut files)
+        })
+    }
+
+    /// Walk several roots on several threads, sending each parseable file to
+    /// `sender` as soon as it is found
+    ///
+    /// The channel should be bounded, so a slow consumer such as the parse
+    /// stage holds the walk back instead of letting paths pile up. Returns
+    /// once every root is walked or the receiver is dropped.
+    pub fn send_files_parallel(
+        &self,
+        roots: &[PathBuf],
+        filter_languages: Option<&[String]>,
+        sender: SyncSender<(PathBuf, String)>,
+    ) {
+        for root in roots {
+            let walker = WalkBuilder::new(root)
+                .hidden(true)
+                .require_git(false)
+                .follow_links(self.follow_symlinks)
+                .max_depth(Some(self.max_depth))
+                .build_parallel();
+            walker.run(|| {
+                let sender = sender.clone();
+                Box::new(move |entry| {
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            warn!("Skipping unreadable entry: {}", e);
+                            return WalkState::Continue;
                         }
+                    };
+                    if !entry.file_type().is_some_and(|t| t.is_file()) {
+                        return WalkState::Continue;
+                    }
 
-                        files.push((entry.path().to_path_buf(), lang_id));
+                    let path = entry.path();
+                    let relative = path.strip_prefix(root).unwrap_or(path);
+                    let Ok(lang_id) = self.select_language(path, relative, filter_languages) else {
+                        return WalkState::Continue;
+                    };
+                    if self.is_generated_file(path, &lang_id) {
+                        return WalkState::Continue;
+                    }
+                    match sender.send((path.to_path_buf(), lang_id)) {
+                        Ok(()) => WalkState::Continue,
+                        Err(_) => WalkState::Quit,
                     }
+                })
+            });
+        }
+    }
+
+    /// Whether generated files are skipped and the file at `path` is one
+    fn is_generated_file(&self, path: &Path, language_id: &str) -> bool {
+        self.skip_generated
+            && fs::read(path)
+                .is_ok_and(|bytes| self.is_generated(language_id, &self.decode(&bytes).0))
+    }
+
+    /// Pick the language for a file at `path`, `relative` to its root
+    ///
+    /// Applies the include/exclude globs and the language filter; returns why
+    /// the file is skipped otherwise.
+    pub fn select_language(
+        &self,
+        path: &Path,
+        relative: &Path,
+        filter_languages: Option<&[String]>,
+    ) -> std::result::Result<String, SkipReason> {
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|globs| globs.is_match(relative));
+        let excluded = self
+            .exclude
+            .as_ref()
+            .is_some_and(|globs| globs.is_match(relative));
+        if !included || excluded {
+            return Err(SkipReason::Excluded);
+        }
+
+        let lang = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .and_then(|file_name| self.registry.get_by_file_name(file_name))
+            .ok_or(SkipReason::UnsupportedExtension)?;
+        let lang_id = lang.language_id().to_string();
+
+        // Apply language filter if specified
+        if filter_languages.is_some_and(|filters| !filters.contains(&lang_id)) {
+            return Err(SkipReason::FilteredLanguage);
+        }
+        Ok(lang_id)
+    }
+
+    /// Whether generated files are skipped and `source`, a file in
+    /// `language_id`, is one
+    pub fn is_generated(&self, language_id: &str, source: &str) -> bool {
+        self.skip_generated
+            && self
+                .registry
+                .get(language_id)
+                .is_some_and(|lang| lang.is_generated(source))
+    }
+
+    /// Collect all parseable files under several roots
+    ///
+    /// Files reachable from more than one root, e.g. when one root is nested
+    /// in another, are returned once.
+    pub fn collect_files_in_roots(
+        &self,
+        roots: &[PathBuf],
+        filter_languages: Option<&[String]>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+
+        for root in roots {
+            for (path, language) in self.collect_files(root, filter_languages)? {
+                if seen.insert(path.clone()) {
+                    files.push((path, language));
                 }
             }
         }
@@ -66,41 +598,212 @@ impl CodeParser {
 
     /// Parse a single file and extract graph data
     pub fn parse_file(&self, path: &Path, language_id: &str) -> Result<FileGraphData> {
+        // Refuse oversized files before reading them into memory
+        let size = fs::metadata(path).map_err(|e| Error::io(path, e))?.len();
+        if size > self.max_file_bytes {
+            return Err(Error::FileTooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit: self.max_file_bytes,
+            });
+        }
+
         // Read file as bytes first to handle non-UTF8 encodings
-        let bytes = fs::read(path)
-            .with_context(|| format!("Failed to read file: {:?}", path))?;
+        let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+        let (content, encoding) = self.decode(&bytes);
+
+        let mut data = self.parse_content(&content, language_id, path)?;
+        data.encoding = Some(encoding.to_string());
+        Ok(data)
+    }
+
+    /// Decode file contents to text, returning the name of the encoding used
+    ///
+    /// A byte order mark decides the encoding and is stripped, so it never
+    /// shifts the columns of the first line. Otherwise valid UTF-8 is taken
+    /// as-is and anything else is decoded with the fallback encoding, or as
+    /// lossy UTF-8 when there is none.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> (Cow<'a, str>, &'static str) {
+        let (content, encoding) = decode_source(bytes, self.fallback_encoding);
+        (content, encoding.name())
+    }
 
-        // Convert to UTF-8, replacing invalid sequences with replacement character
-        let content = String::from_utf8_lossy(&bytes).into_owned();
+    /// Parse in-memory source code and extract graph data
+    ///
+    /// Only tree-sitter and the language's extractor run: no filesystem or
+    /// database access, so this is available without the `service` feature
+    /// (e.g. when compiling to WASM). Errors refer to the path `<source>`.
+    pub fn parse_source(&self, source: &str, language_id: &str) -> Result<FileGraphData> {
+        let path = Path::new("<source>");
+        let size = source.len() as u64;
+        if size > self.max_file_bytes {
+            return Err(Error::FileTooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit: self.max_file_bytes,
+            });
+        }
+        self.parse_content(source, language_id, path)
+    }
 
-        let content_hash = compute_hash(&content);
+    /// Run tree-sitter and the extractor over `content`, read from `path`
+    fn parse_content(
+        &self,
+        content: &str,
+        language_id: &str,
+        path: &Path,
+    ) -> Result<FileGraphData> {
+        let content_hash = compute_hash(content, self.column_encoding);
+        let line_count = content.lines().count() as u32;
 
         let lang = self
             .registry
             .get(language_id)
-            .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language_id))?;
+            .ok_or_else(|| Error::UnsupportedLanguage(language_id.to_string()))?;
 
         // Create tree-sitter parser
+        let dialect = self.dialects.get(language_id).map(String::as_str);
+        let grammar = lang.grammar_for(dialect)?;
         let mut parser = tree_sitter::Parser::new();
-        parser
-            .set_language(&lang.grammar())
-            .with_context(|| format!("Failed to set language: {}", language_id))?;
-
-        // Parse the source code
-        let tree = parser
-            .parse(&content, None)
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse file: {:?}", path))?;
-
-        debug!("Parsed {:?}, root node: {:?}", path, tree.root_node().kind());
+        parser.set_language(&grammar).map_err(|e| Error::Parse {
+            path: path.to_path_buf(),
+            message: format!("failed to set language {}: {}", language_id, e),
+        })?;
+
+        // Parse the source code. With a timeout set, tree-sitter gives up
+        // and returns no tree once the budget is spent.
+        if !self.parse_timeout.is_zero() {
+            parser.set_timeout_micros(
+                u64::try_from(self.parse_timeout.as_micros()).unwrap_or(u64::MAX),
+            );
+        }
+        let tree = parser.parse(content, None).ok_or_else(|| {
+            if self.parse_timeout.is_zero() {
+                Error::Parse {
+                    path: path.to_path_buf(),
+                    message: "tree-sitter returned no tree".to_string(),
+                }
+            } else {
+                Error::ParseTimeout {
+                    path: path.to_path_buf(),
+                    limit_ms: u64::try_from(self.parse_timeout.as_micros().div_ceil(1000))
+                        .unwrap_or(u64::MAX),
+                }
+            }
+        })?;
+
+        // Extractors recurse over the whole tree, so refuse pathological
+        // trees before walking them
+        let count = tree.root_node().descendant_count();
+        if self.max_syntax_nodes > 0 && count > self.max_syntax_nodes {
+            return Err(Error::TooManySyntaxNodes {
+                path: path.to_path_buf(),
+                count,
+                limit: self.max_syntax_nodes,
+            });
+        }
+        if self.max_syntax_depth > 0 && exceeds_depth(&tree, self.max_syntax_depth) {
+            return Err(Error::SyntaxTooDeep {
+                path: path.to_path_buf(),
+                limit: self.max_syntax_depth,
+            });
+        }
 
-        // Extract graph data using language-specific rules
-        let (nodes, edges) = lang.extract_graph(&content, &tree)?;
+        debug!(
+            "Parsed {:?}, root node: {:?}",
+            path,
+            tree.root_node().kind()
+        );
+
+        // Extract graph data using the language's query or its own rules. A
+        // panicking extractor fails this file only, not the whole parse run.
+        let extracted = panic::catch_unwind(AssertUnwindSafe(|| match lang.extraction_query() {
+            Some(query) => QueryBasedExtractor::new(&grammar, query)
+                .map(|extractor| (extractor.extract(content, &tree), Vec::new())),
+            None => lang.extract_graph_for(content, &tree, dialect),
+        }))
+        .map_err(|payload| Error::Parse {
+            path: path.to_path_buf(),
+            message: format!("extractor panicked: {}", panic_message(payload.as_ref())),
+        })?;
+        let (mut nodes, edges) = extracted?;
+        if self.column_encoding != ColumnEncoding::Byte {
+            convert_columns(content, &mut nodes, self.column_encoding);
+        }
 
-        Ok(FileGraphData {
+        let data = FileGraphData {
             nodes,
             edges,
             content_hash,
-        })
+            line_count,
+            encoding: None,
+        };
+        data.validate(path)?;
+        Ok(data)
+    }
+}
+
+/// Text of a panic payload, if it is a string
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Compile glob patterns into one set; `None` if there are none
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| Error::Config(format!("Invalid glob {:?}: {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| Error::Config(format!("Invalid globs: {}", e)))
+}
+
+/// Decode `bytes` by BOM, then as UTF-8, then with `fallback`
+fn decode_source<'a>(
+    bytes: &'a [u8],
+    fallback: Option<&'static Encoding>,
+) -> (Cow<'a, str>, &'static Encoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (content, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (content, encoding);
+    }
+    if let Ok(content) = std::str::from_utf8(bytes) {
+        return (Cow::Borrowed(content), UTF_8);
+    }
+    match fallback {
+        Some(encoding) => {
+            let (content, _) = encoding.decode_without_bom_handling(bytes);
+            (content, encoding)
+        }
+        None => (String::from_utf8_lossy(bytes), UTF_8),
+    }
+}
+
+/// Re-express node columns, which tree-sitter gives in bytes, in `encoding`
+fn convert_columns(content: &str, nodes: &mut [NodeData], encoding: ColumnEncoding) {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let line = |number: u32| {
+        lines
+            .get(number.saturating_sub(1) as usize)
+            .copied()
+            .unwrap_or("")
+    };
+
+    for node in nodes {
+        node.start_column = encoding.convert(line(node.start_line), node.start_column);
+        node.end_column = encoding.convert(line(node.end_line), node.end_column);
     }
 }
 
@@ -113,11 +816,63 @@ fn is_hidden(entry: &walkdir::DirEntry) -> bool {
         .unwrap_or(false)
 }
 
+/// Whether a file holds tests: `*_test.go`, `*Test.java` or `*Tests.java`,
+/// or any file under a `test` or `tests` directory
+///
+/// Pass `path` relative to the project root, or directories above the
+/// project will count too.
+pub fn is_test_file(path: &Path) -> bool {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    if file_name.ends_with("_test.go")
+        || file_name.ends_with("Test.java")
+        || file_name.ends_with("Tests.java")
+    {
+        return true;
+    }
+
+    path.parent()
+        .map(|dir| {
+            dir.components()
+                .any(|c| matches!(c.as_os_str().to_str(), Some("test") | Some("tests")))
+        })
+        .unwrap_or(false)
+}
+
 /// Compute SHA-256 hash of content
-fn compute_hash(content: &str) -> String {
+/// Whether any node of `tree` is nested more than `limit` levels below the
+/// root, found without recursing so deep trees cannot overflow the stack
+fn exceeds_depth(tree: &tree_sitter::Tree, limit: usize) -> bool {
+    let mut cursor = tree.walk();
+    let mut depth = 0;
+    loop {
+        if cursor.goto_first_child() {
+            depth += 1;
+            if depth > limit {
+                return true;
+            }
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return false;
+            }
+            depth -= 1;
+        }
+    }
+}
+
+/// Hash of the content and the column encoding, so that changing the
+/// encoding re-parses unchanged files. Byte columns hash the content alone.
+fn compute_hash(content: &str, column_encoding: ColumnEncoding) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
     hasher.update(content.as_bytes());
+    if column_encoding != ColumnEncoding::Byte {
+        hasher.update(format!("\0columns={:?}", column_encoding).as_bytes());
+    }
     let result = hasher.finalize();
     hex::encode(result)
 }
@@ -159,6 +914,17 @@ mod tests {
         drop(parser);
     }
 
+    #[test]
+    fn test_is_test_file() {
+        assert!(is_test_file(Path::new("/repo/pkg/Foo_test.go")));
+        assert!(is_test_file(Path::new("/repo/src/FooTest.java")));
+        assert!(is_test_file(Path::new("/repo/src/test/java/Fixtures.java")));
+        assert!(is_test_file(Path::new("/repo/tests/helpers.go")));
+        assert!(!is_test_file(Path::new("/repo/pkg/foo.go")));
+        assert!(!is_test_file(Path::new("/repo/src/Testing.java")));
+        assert!(!is_test_file(Path::new("/repo/testdata/input.go")));
+    }
+
     #[test]
     fn test_collect_files_empty_dir() {
         let temp_dir = TempDir::new().unwrap();
@@ -200,7 +966,9 @@ mod tests {
         let parser = CodeParser::new(registry);
 
         let filter = vec!["java".to_string()];
-        let files = parser.collect_files(temp_dir.path(), Some(&filter)).unwrap();
+        let files = parser
+            .collect_files(temp_dir.path(), Some(&filter))
+            .unwrap();
         // All returned files should be Java (if any)
         assert!(files.iter().all(|(_, lang)| lang == "java"));
     }
@@ -218,7 +986,259 @@ mod tests {
 
         let files = parser.collect_files(temp_dir.path(), None).unwrap();
         // Hidden files should not be included
-        assert!(files.iter().all(|(p, _)| !p.to_string_lossy().contains(".hidden")));
+        assert!(files
+            .iter()
+            .all(|(p, _)| !p.to_string_lossy().contains(".hidden")));
+    }
+
+    #[test]
+    fn test_parallel_collection_matches_serial() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let other = temp_dir.path().join("other");
+        for dir in ["src/main/java/com/example", "pkg/server", ".cache", "docs"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::create_dir_all(&other).unwrap();
+        for (path, content) in [
+            ("src/main/java/com/example/App.java", "class App {}"),
+            ("src/main/java/com/example/User.java", "class User {}"),
+            ("pkg/server/server.go", "package server"),
+            ("main.go", "package main"),
+            (".cache/Stale.java", "class Stale {}"),
+            ("docs/notes.txt", "notes"),
+        ] {
+            std::fs::write(root.join(path), content).unwrap();
+        }
+        std::fs::write(other.join("extra.go"), "package other").unwrap();
+
+        let parser = CodeParser::new(create_test_registry());
+        let roots = vec![root.clone(), other, root.join("pkg")];
+        let mut serial = parser.collect_files_in_roots(&roots, None).unwrap();
+        serial.sort();
+        let parallel = parser.collect_files_parallel(&roots, None).unwrap();
+        assert_eq!(parallel.len(), 5);
+        assert_eq!(parallel, serial);
+
+        let filter = vec!["go".to_string()];
+        let mut serial = parser
+            .collect_files_in_roots(&roots, Some(&filter))
+            .unwrap();
+        serial.sort();
+        assert_eq!(
+            parser
+                .collect_files_parallel(&roots, Some(&filter))
+                .unwrap(),
+            serial
+        );
+
+        // Unlike the serial walk, the parallel one honors .gitignore
+        std::fs::write(root.join(".gitignore"), "pkg/\n").unwrap();
+        let parallel = parser
+            .collect_files_parallel(std::slice::from_ref(&root), None)
+            .unwrap();
+        assert_eq!(parallel.len(), 3);
+        assert!(parallel
+            .iter()
+            .all(|(path, _)| !path.starts_with(root.join("pkg"))));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_collect_files_report_explains_skips() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("Main.java"), "class Main {}").unwrap();
+        std::fs::write(
+            root.join("Big.java"),
+            "class Big { void a() {} void b() {} }",
+        )
+        .unwrap();
+        std::fs::write(root.join("main.go"), "package main").unwrap();
+        std::fs::write(root.join("notes.txt"), "not code").unwrap();
+        std::fs::write(root.join(".git").join("Config.java"), "class Config {}").unwrap();
+        std::os::unix::fs::symlink(root.join("Main.java"), root.join("Link.java")).unwrap();
+
+        let parser = CodeParser::new(create_test_registry()).with_max_file_bytes(20);
+        let filter = vec!["java".to_string()];
+        let report = parser.collect_files_report(&root, Some(&filter));
+
+        assert_eq!(report.files.len(), 1);
+        assert!(report.files[0].0.ends_with("Main.java"));
+
+        let reason = |name: &str| {
+            report
+                .skipped
+                .iter()
+                .find(|s| s.path.ends_with(name))
+                .map(|s| s.reason)
+        };
+        assert_eq!(reason("Big.java"), Some(SkipReason::TooLarge));
+        assert_eq!(reason("main.go"), Some(SkipReason::FilteredLanguage));
+        assert_eq!(reason("notes.txt"), Some(SkipReason::UnsupportedExtension));
+        assert_eq!(reason(".git"), Some(SkipReason::Ignored));
+        assert_eq!(reason("Link.java"), Some(SkipReason::Symlink));
+        assert_eq!(report.skipped.len(), 5);
+    }
+
+    #[test]
+    fn test_collect_files_include_exclude_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("src/generated")).unwrap();
+        std::fs::create_dir_all(root.join("test")).unwrap();
+        std::fs::write(root.join("src/Main.java"), "class Main {}").unwrap();
+        std::fs::write(root.join("src/generated/Proto.java"), "class Proto {}").unwrap();
+        std::fs::write(root.join("test/MainTest.java"), "class MainTest {}").unwrap();
+
+        let names = |parser: &CodeParser| {
+            let mut names: Vec<String> = parser
+                .collect_files(root, None)
+                .unwrap()
+                .into_iter()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let exclude = vec!["**/generated/**".to_string()];
+        let parser = CodeParser::new(create_test_registry())
+            .with_path_filters(&[], &exclude)
+            .unwrap();
+        assert_eq!(names(&parser), vec!["Main.java", "MainTest.java"]);
+
+        let include = vec!["src/**".to_string()];
+        let parser = CodeParser::new(create_test_registry())
+            .with_path_filters(&include, &[])
+            .unwrap();
+        assert_eq!(names(&parser), vec!["Main.java", "Proto.java"]);
+
+        let parser = CodeParser::new(create_test_registry())
+            .with_path_filters(&include, &exclude)
+            .unwrap();
+        assert_eq!(names(&parser), vec!["Main.java"]);
+
+        let invalid = vec!["src/[".to_string()];
+        assert!(CodeParser::new(create_test_registry())
+            .with_path_filters(&invalid, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_collect_files_skip_generated() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("main.go"), "package main\n\nfunc main() {}\n").unwrap();
+        std::fs::write(
+            root.join("api.pb.go"),
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\n// source: api.proto\n\npackage main\n",
+        )
+        .unwrap();
+
+        let names = |report: &CollectionReport| {
+            let mut names: Vec<String> = report
+                .files
+                .iter()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let parser = CodeParser::new(create_test_registry());
+        assert_eq!(
+            names(&parser.collect_files_report(root, None)),
+            vec!["api.pb.go", "main.go"]
+        );
+
+        let parser = CodeParser::new(create_test_registry()).with_skip_generated(true);
+        let report = parser.collect_files_report(root, None);
+        assert_eq!(names(&report), vec!["main.go"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].path, root.join("api.pb.go"));
+        assert_eq!(report.skipped[0].reason, SkipReason::Generated);
+    }
+
+    #[test]
+    fn test_collect_files_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let mut dir = root.to_path_buf();
+        for level in 1..=8 {
+            std::fs::write(dir.join(format!("Level{}.java", level)), "class L {}").unwrap();
+            dir = dir.join(format!("d{}", level));
+            std::fs::create_dir(&dir).unwrap();
+        }
+
+        let parser = CodeParser::new(create_test_registry()).with_max_depth(3);
+        let report = parser.collect_files_report(root, None);
+        let mut names: Vec<String> = report
+            .files
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Level1.java", "Level2.java", "Level3.java"]);
+
+        // Only the first level past the limit is reported; deeper directories are never read
+        let mut too_deep: Vec<_> = report
+            .skipped
+            .iter()
+            .filter(|entry| entry.reason == SkipReason::TooDeep)
+            .map(|entry| entry.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        too_deep.sort();
+        assert_eq!(
+            too_deep,
+            vec![
+                PathBuf::from("d1/d2/d3/Level4.java"),
+                PathBuf::from("d1/d2/d3/d4")
+            ]
+        );
+
+        let parser = CodeParser::new(create_test_registry());
+        assert_eq!(parser.collect_files(root, None).unwrap().len(), 8);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_collect_files_symlinked_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(root.join("Main.java"), "class Main {}").unwrap();
+        std::fs::write(outside.join("Lib.java"), "class Lib {}").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("linked")).unwrap();
+
+        let parser = CodeParser::new(create_test_registry());
+        let files = parser.collect_files(&root, None).unwrap();
+        assert_eq!(files.len(), 1);
+
+        let parser = CodeParser::new(create_test_registry()).with_follow_symlinks(true);
+        let files = parser.collect_files(&root, None).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|(p, _)| p.ends_with("linked/Lib.java")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_collect_files_self_referential_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        // Temp dir names start with a dot, so walk a visible subdirectory
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("Main.java"), "class Main {}").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+
+        for follow in [false, true] {
+            let parser = CodeParser::new(create_test_registry()).with_follow_symlinks(follow);
+            let files = parser.collect_files(&root, None).unwrap();
+            assert_eq!(files.len(), 1);
+        }
     }
 
     #[test]
@@ -268,6 +1288,311 @@ func main() {
         assert!(node_types.contains(&"function"));
     }
 
+    #[test]
+    fn test_parse_file_line_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = create_test_registry();
+        let parser = CodeParser::new(registry);
+
+        let java_code = "public class Test {\n    void run() {}\n}\n";
+        let path = create_temp_file(&temp_dir, "Test.java", java_code);
+        let result = parser.parse_file(&path, "java").unwrap();
+
+        assert_eq!(result.line_count, 3);
+    }
+
+    #[test]
+    fn test_parse_source_in_memory() {
+        let source = "package main\n\nfunc helper() {}\n\nfunc main() {\n    helper()\n}\n";
+        let parser = CodeParser::new(create_test_registry());
+
+        let data = parser.parse_source(source, "go").unwrap();
+        assert!(data
+            .nodes
+            .iter()
+            .any(|n| n.node_type == "function" && n.name == "main"));
+        assert!(data
+            .nodes
+            .iter()
+            .any(|n| n.node_type == "call" && n.name == "helper"));
+        assert_eq!(data.line_count, 7);
+
+        assert!(matches!(
+            parser.parse_source(source, "cobol"),
+            Err(Error::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_file_invalid_utf8() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Bad.java");
+        let mut source = b"class Bad { String s = \"".to_vec();
+        source.extend_from_slice(&[0xff, 0xfe, 0xc3]);
+        source.extend_from_slice(b"\"; void run\xe2\x82() {} }");
+        std::fs::write(&path, source).unwrap();
+
+        let parser = CodeParser::new(create_test_registry());
+        let data = parser.parse_file(&path, "java").unwrap();
+        assert!(data.nodes.iter().any(|n| n.name == "Bad"));
+    }
+
+    #[test]
+    fn test_parse_file_utf8_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Bom.java");
+        std::fs::write(&path, b"\xef\xbb\xbfclass Bom { void run() {} }\n").unwrap();
+
+        let parser = CodeParser::new(create_test_registry());
+        let data = parser.parse_file(&path, "java").unwrap();
+        let class = data.nodes.iter().find(|n| n.name == "Bom").unwrap();
+        assert_eq!(class.start_line, 1);
+        assert_eq!(class.start_column, 1);
+        assert_eq!(data.encoding.as_deref(), Some("UTF-8"));
+    }
+
+    #[test]
+    fn test_parse_file_fallback_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Cafe.java");
+        // `Café` in windows-1252, where `é` is the single byte 0xe9
+        std::fs::write(&path, b"class Caf\xe9 { void run() {} }\n").unwrap();
+
+        let parser = CodeParser::new(create_test_registry())
+            .with_fallback_encoding(Some("windows-1252"))
+            .unwrap();
+        let data = parser.parse_file(&path, "java").unwrap();
+        assert!(data.nodes.iter().any(|n| n.name == "Café"));
+        assert_eq!(data.encoding.as_deref(), Some("windows-1252"));
+
+        // Valid UTF-8 is never reinterpreted with the fallback
+        let utf8 = create_temp_file(&temp_dir, "Utf8.java", "class Café {}\n");
+        let data = parser.parse_file(&utf8, "java").unwrap();
+        assert!(data.nodes.iter().any(|n| n.name == "Café"));
+        assert_eq!(data.encoding.as_deref(), Some("UTF-8"));
+
+        assert!(matches!(
+            CodeParser::new(create_test_registry()).with_fallback_encoding(Some("klingon")),
+            Err(Error::Config(_))
+        ));
+    }
+
+    struct PanickingLanguage;
+
+    impl crate::languages::LanguageSupport for PanickingLanguage {
+        fn language_id(&self) -> &str {
+            "panicking"
+        }
+
+        fn file_extensions(&self) -> &[&str] {
+            &[".boom"]
+        }
+
+        fn grammar(&self) -> tree_sitter::Language {
+            tree_sitter_java::LANGUAGE.into()
+        }
+
+        fn extract_graph(
+            &self,
+            _source: &str,
+            _tree: &tree_sitter::Tree,
+        ) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
+            panic!("index out of bounds");
+        }
+    }
+
+    #[test]
+    fn test_parse_file_extractor_panic_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_temp_file(&temp_dir, "Main.boom", "class Main {}");
+
+        let mut registry = create_test_registry();
+        registry.register(std::sync::Arc::new(PanickingLanguage));
+        let parser = CodeParser::new(registry);
+
+        match parser.parse_file(&path, "panicking") {
+            Err(Error::Parse { message, .. }) => {
+                assert!(message.contains("index out of bounds"));
+            }
+            other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    struct QueryLanguage;
+
+    impl crate::languages::LanguageSupport for QueryLanguage {
+        fn language_id(&self) -> &str {
+            "query"
+        }
+
+        fn file_extensions(&self) -> &[&str] {
+            &[".q"]
+        }
+
+        fn grammar(&self) -> tree_sitter::Language {
+            tree_sitter_go::LANGUAGE.into()
+        }
+
+        fn extract_graph(
+            &self,
+            _source: &str,
+            _tree: &tree_sitter::Tree,
+        ) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
+            unreachable!("extraction goes through the query")
+        }
+
+        fn extraction_query(&self) -> Option<&str> {
+            Some("(function_declaration name: (identifier) @function.name) @function.definition")
+        }
+    }
+
+    #[test]
+    fn test_parse_file_with_extraction_query() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_temp_file(&temp_dir, "main.q", "package main\n\nfunc run() {\n}\n");
+
+        let mut registry = create_test_registry();
+        registry.register(std::sync::Arc::new(QueryLanguage));
+        let parser = CodeParser::new(registry);
+
+        let result = parser.parse_file(&path, "query").unwrap();
+        assert_eq!(result.nodes.len(), 1);
+        assert_eq!(result.nodes[0].node_type, "function");
+        assert_eq!(result.nodes[0].name, "run");
+        assert_eq!(
+            (result.nodes[0].start_line, result.nodes[0].end_line),
+            (3, 4)
+        );
+        assert!(result.edges.is_empty());
+    }
+
+    #[test]
+    fn test_parse_file_column_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_temp_file(
+            &temp_dir,
+            "A.java",
+            "class A { String s = \"é😀\"; void run() {} }\n",
+        );
+
+        let run_column = |encoding| {
+            let parser = CodeParser::new(create_test_registry()).with_column_encoding(encoding);
+            let result = parser.parse_file(&path, "java").unwrap();
+            result
+                .nodes
+                .iter()
+                .find(|n| n.name == "run")
+                .unwrap()
+                .start_column
+        };
+
+        // `é` is 2 bytes and 1 UTF-16 unit; `😀` is 4 bytes and 2 UTF-16 units
+        assert_eq!(run_column(ColumnEncoding::Byte), 32);
+        assert_eq!(run_column(ColumnEncoding::Char), 28);
+        assert_eq!(run_column(ColumnEncoding::Utf16), 29);
+    }
+
+    #[test]
+    fn test_content_hash_depends_on_column_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_temp_file(&temp_dir, "A.java", "class A { String s = \"é\"; }\n");
+
+        let hash = |encoding| {
+            let parser = CodeParser::new(create_test_registry()).with_column_encoding(encoding);
+            parser.parse_file(&path, "java").unwrap().content_hash
+        };
+
+        // Switching encodings must re-parse files whose content is unchanged
+        assert_ne!(hash(ColumnEncoding::Byte), hash(ColumnEncoding::Char));
+        assert_ne!(hash(ColumnEncoding::Char), hash(ColumnEncoding::Utf16));
+        assert_eq!(hash(ColumnEncoding::Utf16), hash(ColumnEncoding::Utf16));
+    }
+
+    #[test]
+    fn test_parse_file_too_large() {
+        let temp_dir = TempDir::new().unwrap();
+        let parser = CodeParser::new(create_test_registry()).with_max_file_bytes(16);
+
+        let path = create_temp_file(&temp_dir, "Big.java", "public class Big { void a() {} }");
+        match parser.parse_file(&path, "java") {
+            Err(Error::FileTooLarge { size, limit, .. }) => {
+                assert_eq!(limit, 16);
+                assert!(size > 16);
+            }
+            other => panic!("expected FileTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_file_budgets() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut source = String::from("class Big {\n");
+        for i in 0..20_000 {
+            source.push_str(&format!(
+                "    int f{i}(int a, int b) {{ return a * {i} + b; }}\n"
+            ));
+        }
+        source.push_str("}\n");
+        let path = create_temp_file(&temp_dir, "Big.java", &source);
+
+        // A sub-millisecond limit is reported rounded up rather than as 0ms
+        let parser =
+            CodeParser::new(create_test_registry()).with_parse_timeout(Duration::from_micros(1));
+        assert!(matches!(
+            parser.parse_file(&path, "java"),
+            Err(Error::ParseTimeout { limit_ms: 1, .. })
+        ));
+
+        let parser = CodeParser::new(create_test_registry()).with_max_syntax_nodes(1_000);
+        match parser.parse_file(&path, "java") {
+            Err(e @ Error::TooManySyntaxNodes { .. }) => assert!(e.is_budget_exceeded()),
+            other => panic!("expected a node budget error, got {:?}", other.map(|_| ())),
+        }
+
+        let parser = CodeParser::new(create_test_registry()).with_parse_timeout(Duration::ZERO);
+        assert!(parser.parse_file(&path, "java").is_ok());
+    }
+
+    #[test]
+    fn test_parse_file_depth_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let blocks = |depth: usize| format!("{}{}", "{".repeat(depth), "}".repeat(depth));
+        let java = |depth| format!("class Deep {{ void m() {{ {} }} }}\n", blocks(depth));
+        let go = |depth| format!("package deep\n\nfunc m() {{ {} }}\n", blocks(depth));
+
+        // Refused before the recursive extractors can overflow the stack
+        let parser = CodeParser::new(create_test_registry());
+        let path = create_temp_file(&temp_dir, "Deep.java", &java(100_000));
+        match parser.parse_file(&path, "java") {
+            Err(e @ Error::SyntaxTooDeep { .. }) => assert!(e.is_budget_exceeded()),
+            other => panic!("expected a depth budget error, got {:?}", other.map(|_| ())),
+        }
+
+        // Trees within the default limit fit on a default thread stack
+        let depth = ParseConfig::default().max_syntax_depth - 10;
+        for (file, language, source) in [
+            ("Nested.java", "java", java(depth)),
+            ("nested.go", "go", go(depth)),
+        ] {
+            let path = create_temp_file(&temp_dir, file, &source);
+            let result = parser.parse_file(&path, language).unwrap();
+            assert!(result.nodes.iter().any(|n| n.name == "m"), "{}", language);
+        }
+    }
+
+    #[test]
+    fn test_with_dialects_rejects_unknown_dialect() {
+        let dialects = [("java".to_string(), "generics".to_string())];
+        let result = CodeParser::new(create_test_registry())
+            .with_dialects(dialects.iter().map(|(l, d)| (l, d)));
+        assert!(matches!(result, Err(Error::Config(_))));
+
+        let dialects = [("go".to_string(), "generics".to_string())];
+        assert!(CodeParser::new(create_test_registry())
+            .with_dialects(dialects.iter().map(|(l, d)| (l, d)))
+            .is_ok());
+    }
+
     #[test]
     fn test_parse_file_unsupported_language() {
         let temp_dir = TempDir::new().unwrap();
@@ -277,8 +1602,7 @@ func main() {
         let path = create_temp_file(&temp_dir, "test.rs", "fn main() {}");
         let result = parser.parse_file(&path, "rust");
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Unsupported language"));
+        assert!(matches!(result, Err(Error::UnsupportedLanguage(ref id)) if id == "rust"));
     }
 
     #[test]
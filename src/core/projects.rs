@@ -0,0 +1,143 @@
+//! Project lookup by name
+//!
+//! Long project names are tedious to type, so a unique prefix is accepted in
+//! place of the full name. When nothing matches, the closest names by edit
+//! distance are offered as suggestions.
+
+use crate::storage::models::ProjectRecord;
+
+/// Most suggestions offered for a name that matches no project
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Outcome of looking up a project by name
+#[derive(Debug)]
+pub enum ProjectMatch<'a> {
+    /// Exactly one project has this name, or this unique prefix
+    Found(&'a ProjectRecord),
+    /// Several projects start with the given prefix
+    Ambiguous(Vec<&'a ProjectRecord>),
+    /// Nothing matched; the closest names, nearest first
+    NotFound(Vec<&'a ProjectRecord>),
+}
+
+/// Find a project by exact name, then by unique prefix
+pub fn match_project_name<'a>(projects: &'a [ProjectRecord], name: &str) -> ProjectMatch<'a> {
+    if let Some(project) = projects.iter().find(|p| p.name == name) {
+        return ProjectMatch::Found(project);
+    }
+
+    let mut prefixed: Vec<_> = projects
+        .iter()
+        .filter(|p| p.name.starts_with(name))
+        .collect();
+    match prefixed.len() {
+        0 => {}
+        1 => return ProjectMatch::Found(prefixed[0]),
+        _ => {
+            prefixed.sort_by(|a, b| a.name.cmp(&b.name));
+            return ProjectMatch::Ambiguous(prefixed);
+        }
+    }
+
+    // Allow roughly one typo per three characters
+    let max_distance = (name.chars().count() / 3).max(2);
+    let mut suggestions: Vec<_> = projects
+        .iter()
+        .map(|p| (levenshtein(name, &p.name), p))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    suggestions.sort_by(|(da, a), (db, b)| da.cmp(db).then_with(|| a.name.cmp(&b.name)));
+
+    ProjectMatch::NotFound(
+        suggestions
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, p)| p)
+            .collect(),
+    )
+}
+
+/// Edit distance between two strings, counted in characters
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projects(names: &[&str]) -> Vec<ProjectRecord> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ProjectRecord {
+                id: i as i64 + 1,
+                name: name.to_string(),
+                root_path: format!("/src/{}", name),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_exact_name_wins_over_prefix() {
+        let projects = projects(&["api", "api-gateway"]);
+        assert!(
+            matches!(match_project_name(&projects, "api"), ProjectMatch::Found(p) if p.id == 1)
+        );
+    }
+
+    #[test]
+    fn test_unique_prefix_resolves() {
+        let projects = projects(&["payments-service", "inventory-service"]);
+        match match_project_name(&projects, "pay") {
+            ProjectMatch::Found(p) => assert_eq!(p.name, "payments-service"),
+            other => panic!("expected a match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_prefix_lists_candidates() {
+        let projects = projects(&["billing-worker", "billing-api", "search"]);
+        match match_project_name(&projects, "billing") {
+            ProjectMatch::Ambiguous(candidates) => {
+                let names: Vec<_> = candidates.iter().map(|p| p.name.as_str()).collect();
+                assert_eq!(names, ["billing-api", "billing-worker"]);
+            }
+            other => panic!("expected an ambiguous match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_name_suggests_closest() {
+        let projects = projects(&["codegraph", "codegen", "website"]);
+        match match_project_name(&projects, "codegrpah") {
+            ProjectMatch::NotFound(suggestions) => {
+                assert_eq!(suggestions[0].name, "codegraph");
+                assert!(!suggestions.iter().any(|p| p.name == "website"));
+            }
+            other => panic!("expected no match, got {:?}", other),
+        }
+    }
+}
@@ -1,10 +1,18 @@
 //! Query executor for code graph queries
 
+use std::borrow::Borrow;
+use std::collections::HashSet;
 use std::path::Path;
+use std::sync::Arc;
 
-use anyhow::Result;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::core::cache::QueryCache;
+use crate::error::{Error, Result};
+use crate::languages::{LanguageRegistry, SymbolKind};
+use crate::storage::models::{unqualified_name, D3Graph, D3Link, D3Node, NodeRecord, TestFilter};
+use crate::storage::sqlite::DEFAULT_RESULT_LIMIT;
 use crate::storage::Database;
 
 /// Result of a definition query
@@ -14,11 +22,25 @@ pub struct DefinitionResult {
     pub definition: Option<SymbolLocation>,
 }
 
+/// Result of a definition query that may match several symbols
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionsResult {
+    pub found: bool,
+    pub count: usize,
+    pub definitions: Vec<SymbolLocation>,
+}
+
 /// Result of a references query
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReferencesResult {
+    /// Number of references; the definition is not counted
     pub count: usize,
+    /// References, preceded by the definition when it was requested
     pub references: Vec<SymbolLocation>,
+    /// The referenced symbol's definition, when requested, with `kind`
+    /// `definition`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<SymbolLocation>,
 }
 
 /// Result of a call graph query
@@ -27,6 +49,127 @@ pub struct CallGraphResult {
     pub center: SymbolInfo,
     pub callers: Vec<SymbolInfo>,
     pub callees: Vec<SymbolInfo>,
+    /// Locations where the center symbol is invoked, when requested
+    #[serde(default)]
+    pub call_sites: Vec<SymbolLocation>,
+    /// How far the traversal went, to explain unexpectedly large graphs
+    #[serde(default)]
+    pub traversal_stats: TraversalStats,
+}
+
+/// Size and depth of a call graph traversal, over both directions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraversalStats {
+    /// Callers and callees listed in the result
+    pub nodes_visited: usize,
+    /// Deepest level at which a node was found; 0 if none was
+    pub max_depth_reached: u32,
+    /// Whether the depth limit cut off further callers or callees
+    pub truncated: bool,
+}
+
+impl CallGraphResult {
+    /// Render as a Mermaid `graph TD` diagram, with edges pointing from
+    /// caller to callee and the center node highlighted
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("graph TD\n");
+
+        let mut declared = HashSet::new();
+        let symbols = std::iter::once(&self.center)
+            .chain(&self.callers)
+            .chain(&self.callees);
+        for symbol in symbols {
+            if declared.insert(symbol.id) {
+                out.push_str(&format!(
+                    "    n{}[\"{}\"]\n",
+                    symbol.id,
+                    mermaid_label(symbol)
+                ));
+            }
+        }
+
+        for caller in &self.callers {
+            let callee_id = caller.parent_id.unwrap_or(self.center.id);
+            out.push_str(&format!("    n{} --> n{}\n", caller.id, callee_id));
+        }
+        for callee in &self.callees {
+            let caller_id = callee.parent_id.unwrap_or(self.center.id);
+            out.push_str(&format!("    n{} --> n{}\n", caller_id, callee.id));
+        }
+
+        out.push_str("    classDef center fill:#fde68a,stroke:#b45309,stroke-width:2px\n");
+        out.push_str(&format!("    class n{} center\n", self.center.id));
+        out
+    }
+
+    /// Render as `{nodes, links}` JSON for d3-force, with links pointing
+    /// from caller to callee
+    pub fn to_d3_json(&self) -> serde_json::Result<String> {
+        let mut graph = D3Graph::default();
+
+        let mut declared = HashSet::new();
+        let symbols = std::iter::once(&self.center)
+            .chain(&self.callers)
+            .chain(&self.callees);
+        for symbol in symbols {
+            if declared.insert(symbol.id) {
+                graph.nodes.push(D3Node {
+                    id: symbol.id,
+                    label: symbol
+                        .qualified_name
+                        .clone()
+                        .unwrap_or_else(|| symbol.name.clone()),
+                    group: symbol.node_type.clone(),
+                });
+            }
+        }
+
+        let call = |source, target| D3Link {
+            source,
+            target,
+            link_type: "calls".to_string(),
+        };
+        for caller in &self.callers {
+            graph
+                .links
+                .push(call(caller.id, caller.parent_id.unwrap_or(self.center.id)));
+        }
+        for callee in &self.callees {
+            graph
+                .links
+                .push(call(callee.parent_id.unwrap_or(self.center.id), callee.id));
+        }
+
+        serde_json::to_string_pretty(&graph)
+    }
+}
+
+/// Label for a Mermaid node; a `"` would end the label, so it is escaped
+fn mermaid_label(symbol: &SymbolInfo) -> String {
+    symbol
+        .qualified_name
+        .as_deref()
+        .unwrap_or(&symbol.name)
+        .replace('"', "#quot;")
+}
+
+/// Which way call graph edges are followed
+#[derive(Debug, Clone, Copy)]
+enum CallDirection {
+    Callers,
+    Callees,
+}
+
+/// Files and top-level symbols of a package, gathered across its files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageSummary {
+    pub package: String,
+    /// The package's doc comment, from the first file that has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    pub files: Vec<String>,
+    pub count: usize,
+    pub symbols: Vec<SymbolInfo>,
 }
 
 /// Result of a symbol search
@@ -36,6 +179,57 @@ pub struct SymbolSearchResult {
     pub symbols: Vec<SymbolInfo>,
 }
 
+/// Result of a symbol search across all projects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalSymbolSearchResult {
+    pub count: usize,
+    pub symbols: Vec<ProjectSymbolInfo>,
+}
+
+/// A symbol annotated with the project it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSymbolInfo {
+    pub project_id: i64,
+    pub project: String,
+    #[serde(flatten)]
+    pub symbol: SymbolInfo,
+}
+
+/// A node with the edges that connect it to its neighbors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDetail {
+    pub node: NodeRecord,
+    pub outgoing: Vec<EdgeSummary>,
+    pub incoming: Vec<EdgeSummary>,
+}
+
+/// Nodes overlapping a line range of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeResult {
+    pub file: String,
+    pub count: usize,
+    pub nodes: Vec<NodeRecord>,
+}
+
+/// The symbols enclosing a position, e.g. a class and then its method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolStack {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    /// Outermost symbol first; the last is the innermost
+    pub symbols: Vec<SymbolInfo>,
+}
+
+/// An edge seen from one of its endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeSummary {
+    pub edge_id: i64,
+    pub edge_type: String,
+    /// The node at the other end of the edge
+    pub neighbor: SymbolInfo,
+}
+
 /// Location of a symbol in the source code
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolLocation {
@@ -46,28 +240,111 @@ pub struct SymbolLocation {
     pub name: String,
     pub qualified_name: Option<String>,
     pub context: Option<String>,
+    /// How a reference was matched to its symbol: `resolved` through a graph
+    /// edge, or `unverified` when only the name matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    /// For a reference to a field or variable, whether the site `read`s it
+    /// or `write`s it, as on the left of an assignment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
 }
 
 /// Information about a symbol
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolInfo {
+    /// Node id, which `parent_id` refers to
+    #[serde(default)]
+    pub id: i64,
+    /// In a call graph, the node this one was reached through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i64>,
     pub name: String,
     pub qualified_name: Option<String>,
     pub node_type: String,
+    /// Language-independent kind; `node_type` keeps the raw type
+    #[serde(default)]
+    pub kind: SymbolKind,
     pub file: String,
     pub line: u32,
     pub column: u32,
+    /// Declaration header of a function or method, e.g.
+    /// `func NewServer(port int) *Server`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Id that survives re-parsing, unlike `id`; see [`NodeRecord::stable_id`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+}
+
+/// Everything known about one symbol, for a one-shot dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolDetails {
+    /// The definition's location, type and names
+    #[serde(flatten)]
+    pub symbol: SymbolInfo,
+    pub end_line: u32,
+    pub attributes: Option<String>,
+    /// Distinct functions or methods that call the symbol
+    pub callers: usize,
+    /// Calls made from the symbol's body
+    pub callees: usize,
+    /// Nodes linked to the symbol by a `references` edge
+    pub references: usize,
 }
 
 /// Query executor for the code graph
-pub struct QueryExecutor {
-    db: Database,
+///
+/// Owns its database by default, but also works over a borrowed one, such
+/// as the server's shared connection.
+pub struct QueryExecutor<D = Database> {
+    db: D,
+    registry: LanguageRegistry,
+    cache: Option<Arc<QueryCache>>,
+    tests: TestFilter,
 }
 
-impl QueryExecutor {
+impl<D: Borrow<Database>> QueryExecutor<D> {
     /// Create a new query executor
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            registry: LanguageRegistry::new(),
+            cache: None,
+            tests: TestFilter::All,
+        }
+    }
+
+    /// Answer repeated project queries from a shared result cache
+    pub fn with_cache(mut self, cache: Arc<QueryCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Include or exclude test files in symbol searches and call graphs
+    pub fn with_test_filter(mut self, tests: TestFilter) -> Self {
+        self.tests = tests;
+        self
+    }
+
+    fn db(&self) -> &Database {
+        self.db.borrow()
+    }
+
+    /// Run a project query through the cache, if one is configured
+    fn cached<T, F>(&self, project_id: i64, signature: &str, compute: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T>,
+    {
+        match &self.cache {
+            Some(cache) => {
+                let generation = self.db().get_project_generation(project_id)?;
+                let signature = format!("{}:{:?}", signature, self.tests);
+                cache.get_or_insert_with(project_id, generation, &signature, compute)
+            }
+            None => compute(),
+        }
     }
 
     /// Find the definition of a symbol at the given location
@@ -77,17 +354,231 @@ impl QueryExecutor {
         file: &str,
         line: u32,
         column: u32,
+    ) -> Result<DefinitionResult> {
+        let signature = format!("definition:{}:{}:{}", file, line, column);
+        self.cached(project_id, &signature, || {
+            self.find_definition_uncached(project_id, file, line, column)
+        })
+    }
+
+    /// Find references to a symbol at the given location
+    ///
+    /// Hot symbols can have thousands of references, so results are returned
+    /// one page of `limit` rows at a time, starting at `offset`.
+    pub fn find_references(
+        &self,
+        project_id: i64,
+        file: &str,
+        line: u32,
+        column: u32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<ReferencesResult> {
+        let signature = format!(
+            "references:{}:{}:{}:{}:{}",
+            file, line, column, limit, offset
+        );
+        self.cached(project_id, &signature, || {
+            self.find_references_uncached(project_id, file, line, column, limit, offset)
+        })
+    }
+
+    /// Get the call graph for a symbol
+    pub fn get_callgraph(
+        &self,
+        project_id: i64,
+        symbol: &str,
+        depth: u32,
+        direction: &str,
+        include_call_sites: bool,
+    ) -> Result<CallGraphResult> {
+        let signature = format!(
+            "callgraph:{}:{}:{}:{}",
+            symbol, depth, direction, include_call_sites
+        );
+        self.cached(project_id, &signature, || {
+            self.get_callgraph_uncached(project_id, symbol, depth, direction, include_call_sites)
+        })
+    }
+
+    /// Search for symbols matching a query
+    pub fn search_symbols(
+        &self,
+        project_id: i64,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<SymbolSearchResult> {
+        let signature = format!("symbols:{}:{:?}:{}", query, symbol_type, limit);
+        self.cached(project_id, &signature, || {
+            self.search_symbols_uncached(project_id, query, symbol_type, limit)
+        })
+    }
+
+    /// Search for symbols whose name or qualified name is exactly `query`,
+    /// without substring matching
+    pub fn search_symbols_exact(
+        &self,
+        project_id: i64,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<SymbolSearchResult> {
+        let signature = format!("symbols_exact:{}:{:?}:{}", query, symbol_type, limit);
+        self.cached(project_id, &signature, || {
+            let nodes = self.db().search_symbols_exact(
+                project_id,
+                query,
+                symbol_type,
+                limit,
+                self.tests,
+            )?;
+            self.symbol_search_result(nodes)
+        })
+    }
+
+    /// Search for symbols whose name matches a regular expression
+    pub fn search_symbols_regex(
+        &self,
+        project_id: i64,
+        pattern: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<SymbolSearchResult> {
+        let signature = format!("symbols_regex:{}:{:?}:{}", pattern, symbol_type, limit);
+        self.cached(project_id, &signature, || {
+            let nodes = self.db().search_symbols_regex(
+                project_id,
+                pattern,
+                symbol_type,
+                limit,
+                self.tests,
+            )?;
+            self.symbol_search_result(nodes)
+        })
+    }
+
+    /// Search for symbols matching a query within one file of a project
+    pub fn search_symbols_in_file(
+        &self,
+        project_id: i64,
+        file: &str,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<SymbolSearchResult> {
+        let signature = format!(
+            "file_symbols:{}:{}:{:?}:{}",
+            file, query, symbol_type, limit
+        );
+        self.cached(project_id, &signature, || {
+            let nodes =
+                self.db()
+                    .search_symbols_in_file(project_id, file, query, symbol_type, limit)?;
+            self.symbol_search_result(nodes)
+        })
+    }
+
+    /// Summarize a symbol: its definition plus caller, callee and
+    /// reference counts
+    ///
+    /// When several definitions share the name, concrete ones are preferred
+    /// over abstract declarations. Returns [`Error::SymbolNotFound`] when the
+    /// project defines no such symbol.
+    pub fn symbol_info(&self, project_id: i64, symbol: &str) -> Result<SymbolDetails> {
+        let signature = format!("info:{}", symbol);
+        self.cached(project_id, &signature, || {
+            self.symbol_info_uncached(project_id, symbol)
+        })
+    }
+
+    fn symbol_info_uncached(&self, project_id: i64, symbol: &str) -> Result<SymbolDetails> {
+        let mut definitions = self.db().find_definitions(project_id, symbol)?;
+        definitions.sort_by_key(|n| n.is_abstract());
+        let name_is_unique = definitions.len() <= 1;
+        let definition = definitions
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::SymbolNotFound(symbol.to_string()))?;
+
+        // Callers are linked directly or through a call node; a call node
+        // counts as its enclosing function
+        let mut callers = HashSet::new();
+        let mut calls = Vec::new();
+        for caller in self
+            .db()
+            .find_callers(definition.id, DEFAULT_RESULT_LIMIT, 0)?
+        {
+            if caller.node_type == "call" {
+                calls.push(caller);
+            } else {
+                callers.insert(caller.id);
+            }
+        }
+        // Unlinked calls by name only count when the name is unambiguous
+        if name_is_unique {
+            calls.extend(
+                self.db()
+                    .find_unlinked_call_sites(project_id, &definition.name)?,
+            );
+        }
+        for call in calls {
+            let enclosing = self.db().find_callers(call.id, 1, 0)?.into_iter().next();
+            callers.insert(enclosing.map_or(call.id, |c| c.id));
+        }
+
+        let callees = self
+            .db()
+            .find_callees(definition.id, DEFAULT_RESULT_LIMIT, 0)?
+            .len();
+        let references = self
+            .db()
+            .find_all_references(definition.id, DEFAULT_RESULT_LIMIT, 0)?
+            .len();
+
+        Ok(SymbolDetails {
+            end_line: definition.end_line,
+            attributes: definition.attributes.clone(),
+            symbol: self.node_symbol_info(definition)?,
+            callers: callers.len(),
+            callees,
+            references,
+        })
+    }
+
+    fn find_definition_uncached(
+        &self,
+        project_id: i64,
+        file: &str,
+        line: u32,
+        column: u32,
     ) -> Result<DefinitionResult> {
         // Find the node at the given position
         let node = self
-            .db
+            .db()
             .find_node_at_position(project_id, file, line, column)?;
 
         match node {
             Some(n) => {
                 // If this is a reference, find its target
-                if let Some(target) = self.db.find_reference_target(n.id)? {
-                    let file_info = self.db.get_file(target.file_id)?;
+                if let Some(target) = self.db().find_reference_target(n.id)? {
+                    let file_info = self.db().get_file(target.file_id)?;
+                    Ok(DefinitionResult {
+                        found: true,
+                        definition: Some(SymbolLocation {
+                            file: file_info.map(|f| f.path).unwrap_or_default(),
+                            line: target.start_line,
+                            column: target.start_column,
+                            node_type: target.node_type,
+                            name: target.name,
+                            qualified_name: target.qualified_name,
+                            context: None,
+                            resolution: None,
+                            kind: None,
+                        }),
+                    })
+                } else if let Some(target) = self.resolve_overload(project_id, &n)? {
+                    let file_info = self.db().get_file(target.file_id)?;
                     Ok(DefinitionResult {
                         found: true,
                         definition: Some(SymbolLocation {
@@ -98,11 +589,13 @@ impl QueryExecutor {
                             name: target.name,
                             qualified_name: target.qualified_name,
                             context: None,
+                            resolution: None,
+                            kind: None,
                         }),
                     })
                 } else {
                     // This might be the definition itself
-                    let file_info = self.db.get_file(n.file_id)?;
+                    let file_info = self.db().get_file(n.file_id)?;
                     Ok(DefinitionResult {
                         found: true,
                         definition: Some(SymbolLocation {
@@ -113,6 +606,8 @@ impl QueryExecutor {
                             name: n.name,
                             qualified_name: n.qualified_name,
                             context: None,
+                            resolution: None,
+                            kind: None,
                         }),
                     })
                 }
@@ -124,25 +619,52 @@ impl QueryExecutor {
         }
     }
 
-    /// Find all references to a symbol at the given location
-    pub fn find_references(
+    /// Pick the method a call invokes from its overloads by argument count
+    ///
+    /// Only calls that record how many arguments they pass are resolved.
+    /// Overloads in the call's own file are preferred, and the first overload
+    /// is used when none has a matching arity.
+    fn resolve_overload(&self, project_id: i64, call: &NodeRecord) -> Result<Option<NodeRecord>> {
+        let Some(args) = call.call_args().filter(|_| call.node_type == "call") else {
+            return Ok(None);
+        };
+        let name = unqualified_name(&call.name);
+        let mut overloads: Vec<NodeRecord> = self
+            .db()
+            .find_definitions(project_id, name)?
+            .into_iter()
+            .filter(|n| n.node_type == "method" && n.name == name)
+            .collect();
+        overloads.sort_by_key(|n| n.file_id != call.file_id);
+
+        let matching = overloads.iter().position(|n| n.accepts_args(args));
+        Ok(match matching {
+            Some(i) => Some(overloads.swap_remove(i)),
+            None => overloads.into_iter().next(),
+        })
+    }
+
+    fn find_references_uncached(
         &self,
         project_id: i64,
         file: &str,
         line: u32,
         column: u32,
+        limit: u32,
+        offset: u32,
     ) -> Result<ReferencesResult> {
         let node = self
-            .db
+            .db()
             .find_node_at_position(project_id, file, line, column)?;
 
         match node {
             Some(n) => {
-                let refs = self.db.find_all_references(n.id)?;
+                let refs = self.db().find_all_references(n.id, limit, offset)?;
                 let mut references = Vec::new();
 
                 for ref_node in refs {
-                    let file_info = self.db.get_file(ref_node.file_id)?;
+                    let file_info = self.db().get_file(ref_node.file_id)?;
+                    let kind = ref_node.access();
                     references.push(SymbolLocation {
                         file: file_info.map(|f| f.path).unwrap_or_default(),
                         line: ref_node.start_line,
@@ -151,51 +673,64 @@ impl QueryExecutor {
                         name: ref_node.name,
                         qualified_name: ref_node.qualified_name,
                         context: None,
+                        resolution: None,
+                        kind,
                     });
                 }
 
                 Ok(ReferencesResult {
                     count: references.len(),
                     references,
+                    definition: None,
                 })
             }
             None => Ok(ReferencesResult {
                 count: 0,
                 references: vec![],
+                definition: None,
             }),
         }
     }
 
-    /// Get the call graph for a symbol
-    pub fn get_callgraph(
+    fn get_callgraph_uncached(
         &self,
         project_id: i64,
         symbol: &str,
         depth: u32,
         direction: &str,
+        include_call_sites: bool,
     ) -> Result<CallGraphResult> {
-        let center_node = self.db.find_symbol_by_name(project_id, symbol)?;
+        let center_node = self.db().find_symbol_by_name(project_id, symbol)?;
 
         match center_node {
             Some(n) => {
-                let file_info = self.db.get_file(n.file_id)?;
-                let center = SymbolInfo {
-                    name: n.name.clone(),
-                    qualified_name: n.qualified_name.clone(),
-                    node_type: n.node_type.clone(),
-                    file: file_info.map(|f| f.path).unwrap_or_default(),
-                    line: n.start_line,
-                    column: n.start_column,
+                let node_id = n.id;
+                let call_sites = if include_call_sites {
+                    self.collect_call_sites(project_id, &n)?
+                } else {
+                    vec![]
                 };
+                let center = self.node_symbol_info(n)?;
+                let mut traversal_stats = TraversalStats::default();
 
                 let callers = if direction == "callers" || direction == "both" {
-                    self.collect_callers(n.id, depth)?
+                    self.collect_call_tree(
+                        node_id,
+                        depth,
+                        CallDirection::Callers,
+                        &mut traversal_stats,
+                    )?
                 } else {
                     vec![]
                 };
 
                 let callees = if direction == "callees" || direction == "both" {
-                    self.collect_callees(n.id, depth)?
+                    self.collect_call_tree(
+                        node_id,
+                        depth,
+                        CallDirection::Callees,
+                        &mut traversal_stats,
+                    )?
                 } else {
                     vec![]
                 };
@@ -204,33 +739,32 @@ impl QueryExecutor {
                     center,
                     callers,
                     callees,
+                    call_sites,
+                    traversal_stats,
                 })
             }
-            None => Err(anyhow::anyhow!("Symbol not found: {}", symbol)),
+            None => Err(Error::SymbolNotFound(symbol.to_string())),
         }
     }
 
-    /// Search for symbols matching a query
-    pub fn search_symbols(
+    fn search_symbols_uncached(
         &self,
         project_id: i64,
         query: &str,
         symbol_type: Option<&str>,
         limit: u32,
     ) -> Result<SymbolSearchResult> {
-        let nodes = self.db.search_symbols(project_id, query, symbol_type, limit)?;
+        let nodes =
+            self.db()
+                .search_symbols_filtered(project_id, query, symbol_type, limit, self.tests)?;
+        self.symbol_search_result(nodes)
+    }
+
+    fn symbol_search_result(&self, nodes: Vec<NodeRecord>) -> Result<SymbolSearchResult> {
         let mut symbols = Vec::new();
 
         for n in nodes {
-            let file_info = self.db.get_file(n.file_id)?;
-            symbols.push(SymbolInfo {
-                name: n.name,
-                qualified_name: n.qualified_name,
-                node_type: n.node_type,
-                file: file_info.map(|f| f.path).unwrap_or_default(),
-                line: n.start_line,
-                column: n.start_column,
-            });
+            symbols.push(self.node_symbol_info(n)?);
         }
 
         Ok(SymbolSearchResult {
@@ -239,68 +773,461 @@ impl QueryExecutor {
         })
     }
 
-    fn collect_callers(&self, node_id: i64, depth: u32) -> Result<Vec<SymbolInfo>> {
-        if depth == 0 {
-            return Ok(vec![]);
+    /// Search for symbols matching a query in every project
+    pub fn search_symbols_global(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<GlobalSymbolSearchResult> {
+        let matches = self.db().search_symbols_global(query, symbol_type, limit)?;
+        let mut symbols = Vec::new();
+
+        for (project_id, project, n) in matches {
+            symbols.push(ProjectSymbolInfo {
+                project_id,
+                project,
+                symbol: self.node_symbol_info(n)?,
+            });
+        }
+
+        Ok(GlobalSymbolSearchResult {
+            count: symbols.len(),
+            symbols,
+        })
+    }
+
+    /// Follow `calls` edges breadth first, up to `depth` levels from the root
+    ///
+    /// Each node is listed once, with `parent_id` set to the node it was first
+    /// reached through, so clients can rebuild the tree. Nodes in files
+    /// rejected by the test filter are neither listed nor followed. The
+    /// traversal's size and depth are added to `stats`.
+    fn collect_call_tree(
+        &self,
+        root_id: i64,
+        depth: u32,
+        direction: CallDirection,
+        stats: &mut TraversalStats,
+    ) -> Result<Vec<SymbolInfo>> {
+        let mut result = Vec::new();
+        let mut visited = HashSet::from([root_id]);
+        let mut frontier = vec![root_id];
+
+        for level in 1..=depth {
+            let mut next = Vec::new();
+            for parent_id in frontier {
+                for node in self.call_neighbours(parent_id, direction)? {
+                    if !visited.insert(node.id) || !self.passes_test_filter(&node)? {
+                        continue;
+                    }
+                    next.push(node.id);
+                    let mut info = self.node_symbol_info(node)?;
+                    info.parent_id = Some(parent_id);
+                    result.push(info);
+                    stats.max_depth_reached = stats.max_depth_reached.max(level);
+                }
+            }
+            frontier = next;
+        }
+        stats.nodes_visited += result.len();
+
+        // Anything new one level further means the depth limit cut the graph short
+        for parent_id in frontier {
+            for node in self.call_neighbours(parent_id, direction)? {
+                if !visited.contains(&node.id) && self.passes_test_filter(&node)? {
+                    stats.truncated = true;
+                    return Ok(result);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn call_neighbours(&self, node_id: i64, direction: CallDirection) -> Result<Vec<NodeRecord>> {
+        match direction {
+            CallDirection::Callers => self.db().find_callers(node_id, DEFAULT_RESULT_LIMIT, 0),
+            CallDirection::Callees => self.db().find_callees(node_id, DEFAULT_RESULT_LIMIT, 0),
         }
+    }
 
-        let callers = self.db.find_callers(node_id)?;
+    /// Locate every call to `definition`, with the enclosing function as
+    /// context
+    ///
+    /// Calls are found through their `calls` edge to the definition. Calls
+    /// that were never linked only count when no other definition shares
+    /// the name, so a method is not credited with calls to a same-named
+    /// method of another type.
+    fn collect_call_sites(
+        &self,
+        project_id: i64,
+        definition: &NodeRecord,
+    ) -> Result<Vec<SymbolLocation>> {
+        let mut calls: Vec<NodeRecord> = self
+            .db()
+            .find_callers(definition.id, DEFAULT_RESULT_LIMIT, 0)?
+            .into_iter()
+            .filter(|caller| caller.node_type == "call")
+            .collect();
+        if self
+            .db()
+            .find_definitions(project_id, &definition.name)?
+            .len()
+            <= 1
+        {
+            calls.extend(
+                self.db()
+                    .find_unlinked_call_sites(project_id, &definition.name)?,
+            );
+        }
         let mut result = Vec::new();
 
-        for caller in callers {
-            let file_info = self.db.get_file(caller.file_id)?;
-            result.push(SymbolInfo {
-                name: caller.name,
-                qualified_name: caller.qualified_name,
-                node_type: caller.node_type,
+        for call in calls {
+            let file_info = self.db().get_file(call.file_id)?;
+            if !self
+                .tests
+                .allows(file_info.as_ref().is_some_and(|f| f.is_test))
+            {
+                continue;
+            }
+            let context = self
+                .db()
+                .find_callers(call.id, 1, 0)?
+                .into_iter()
+                .next()
+                .map(|c| c.name);
+            result.push(SymbolLocation {
                 file: file_info.map(|f| f.path).unwrap_or_default(),
-                line: caller.start_line,
-                column: caller.start_column,
+                line: call.start_line,
+                column: call.start_column,
+                node_type: call.node_type,
+                name: call.name,
+                qualified_name: call.qualified_name,
+                context,
+                resolution: None,
+                kind: None,
             });
         }
+        result.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
 
         Ok(result)
     }
 
-    fn collect_callees(&self, node_id: i64, depth: u32) -> Result<Vec<SymbolInfo>> {
-        if depth == 0 {
-            return Ok(vec![]);
+    /// Whether the file containing a node passes the test filter
+    fn passes_test_filter(&self, node: &NodeRecord) -> Result<bool> {
+        if self.tests == TestFilter::All {
+            return Ok(true);
         }
+        let is_test = self.db().get_file(node.file_id)?.is_some_and(|f| f.is_test);
+        Ok(self.tests.allows(is_test))
+    }
 
-        let callees = self.db.find_callees(node_id)?;
-        let mut result = Vec::new();
+    /// Get a node and its incoming and outgoing edges
+    ///
+    /// Returns `None` if the node does not exist or belongs to another project.
+    pub fn get_node_detail(&self, project_id: i64, node_id: i64) -> Result<Option<NodeDetail>> {
+        let Some(node) = self.db().get_node(node_id)? else {
+            return Ok(None);
+        };
+        let in_project = self
+            .db()
+            .get_file(node.file_id)?
+            .is_some_and(|f| f.project_id == project_id);
+        if !in_project {
+            return Ok(None);
+        }
+
+        let mut outgoing = Vec::new();
+        for edge in self.db().get_outgoing_edges(node_id)? {
+            if let Some(neighbor) = self.db().get_node(edge.target_id)? {
+                outgoing.push(EdgeSummary {
+                    edge_id: edge.id,
+                    edge_type: edge.edge_type,
+                    neighbor: self.node_symbol_info(neighbor)?,
+                });
+            }
+        }
 
-        for callee in callees {
-            let file_info = self.db.get_file(callee.file_id)?;
-            result.push(SymbolInfo {
-                name: callee.name,
-                qualified_name: callee.qualified_name,
-                node_type: callee.node_type,
+        let mut incoming = Vec::new();
+        for edge in self.db().get_incoming_edges(node_id)? {
+            if let Some(neighbor) = self.db().get_node(edge.source_id)? {
+                incoming.push(EdgeSummary {
+                    edge_id: edge.id,
+                    edge_type: edge.edge_type,
+                    neighbor: self.node_symbol_info(neighbor)?,
+                });
+            }
+        }
+
+        Ok(Some(NodeDetail {
+            node,
+            outgoing,
+            incoming,
+        }))
+    }
+
+    /// Find the nodes in a file overlapping a line range
+    pub fn find_nodes_in_range(
+        &self,
+        project_id: i64,
+        file: &str,
+        start_line: u32,
+        end_line: u32,
+    ) -> Result<RangeResult> {
+        let nodes = self
+            .db()
+            .find_nodes_in_range(project_id, file, start_line, end_line)?;
+        Ok(RangeResult {
+            file: file.to_string(),
+            count: nodes.len(),
+            nodes,
+        })
+    }
+
+    /// Find the chain of symbols containing a position, outermost first
+    ///
+    /// Empty when nothing in the file spans the position.
+    pub fn symbol_stack(
+        &self,
+        project_id: i64,
+        file: &str,
+        line: u32,
+        column: u32,
+    ) -> Result<SymbolStack> {
+        let signature = format!("stack:{}:{}:{}", file, line, column);
+        self.cached(project_id, &signature, || {
+            let mut symbols = Vec::new();
+            for node in self
+                .db()
+                .find_enclosing_nodes(project_id, file, line, column)?
+            {
+                symbols.push(self.node_symbol_info(node)?);
+            }
+            Ok(SymbolStack {
+                file: file.to_string(),
+                line,
+                column,
+                symbols,
+            })
+        })
+    }
+
+    /// Find the symbols in a project carrying the given annotation
+    pub fn find_annotated(&self, project_id: i64, annotation: &str) -> Result<SymbolSearchResult> {
+        let nodes = self.db().find_nodes_by_annotation(project_id, annotation)?;
+        self.symbol_search_result(nodes)
+    }
+
+    /// Summarize a package declared across one or more files
+    ///
+    /// Returns [`Error::SymbolNotFound`] when no file declares the package.
+    pub fn package_summary(&self, project_id: i64, package: &str) -> Result<PackageSummary> {
+        let clauses = self.db().find_package_nodes(project_id, package)?;
+        if clauses.is_empty() {
+            return Err(Error::SymbolNotFound(package.to_string()));
+        }
+
+        let doc = clauses.iter().find_map(NodeRecord::doc);
+        let mut files = Vec::new();
+        for clause in &clauses {
+            if let Some(file) = self.db().get_file(clause.file_id)? {
+                files.push(file.path);
+            }
+        }
+
+        let nodes = self.db().find_package_symbols(project_id, package)?;
+        let symbols = self.symbol_search_result(nodes)?.symbols;
+        Ok(PackageSummary {
+            package: package.to_string(),
+            doc,
+            files,
+            count: symbols.len(),
+            symbols,
+        })
+    }
+
+    /// Find every definition of a symbol by name
+    ///
+    /// The same name can be defined in several files or packages, so all matches
+    /// are returned for the caller to disambiguate. Concrete implementations are
+    /// listed before abstract declarations such as interface methods.
+    pub fn find_definitions_by_symbol(
+        &self,
+        project_id: i64,
+        symbol: &str,
+    ) -> Result<DefinitionsResult> {
+        let mut nodes = self.db().find_definitions(project_id, symbol)?;
+        nodes.sort_by_key(|n| (!n.is_named(symbol), n.is_abstract()));
+
+        let mut definitions = Vec::new();
+        for node in nodes {
+            let file_info = self.db().get_file(node.file_id)?;
+            definitions.push(SymbolLocation {
                 file: file_info.map(|f| f.path).unwrap_or_default(),
-                line: callee.start_line,
-                column: callee.start_column,
+                line: node.start_line,
+                column: node.start_column,
+                node_type: node.node_type,
+                name: node.name,
+                qualified_name: node.qualified_name,
+                context: None,
+                resolution: None,
+                kind: None,
             });
         }
 
+        Ok(DefinitionsResult {
+            found: !definitions.is_empty(),
+            count: definitions.len(),
+            definitions,
+        })
+    }
+
+    /// Find all references to a symbol by name (where the symbol is called/used)
+    ///
+    /// References linked to a matching definition by a `calls` or `references`
+    /// edge are tagged `resolved`. Call sites that merely share the symbol's name
+    /// are added as `unverified`, but only when no other definition has that
+    /// name; otherwise references to same-named symbols would bleed in.
+    pub fn find_references_by_symbol(
+        &self,
+        project_id: i64,
+        symbol: &str,
+        limit: u32,
+    ) -> Result<ReferencesResult> {
+        let db = self.db();
+        let mut seen = HashSet::new();
+        let mut references = Vec::new();
+
+        for target in db.find_definitions(project_id, symbol)? {
+            let callers = db.find_callers(target.id, limit, 0)?;
+            let referrers = db.find_all_references(target.id, limit, 0)?;
+            for node in callers.into_iter().chain(referrers) {
+                if seen.insert(node.id) {
+                    references.push(reference_location(db, node, "resolved")?);
+                }
+            }
+        }
+
+        let name = unqualified_name(symbol);
+        if db.find_definitions(project_id, name)?.len() <= 1 {
+            for call in db.find_unlinked_call_sites(project_id, name)? {
+                if seen.insert(call.id) {
+                    references.push(reference_location(db, call, "unverified")?);
+                }
+            }
+        }
+
+        references.truncate(limit as usize);
+        Ok(ReferencesResult {
+            count: references.len(),
+            references,
+            definition: None,
+        })
+    }
+
+    /// Find all references to a symbol by name, along with its definition
+    ///
+    /// Like [`find_references_by_symbol`](Self::find_references_by_symbol),
+    /// but the first definition [`find_definitions_by_symbol`](Self::find_definitions_by_symbol)
+    /// returns, tagged with `kind` `definition`, is put in front of
+    /// `references` and in `definition`. `count` still counts references only.
+    pub fn find_references_with_definition(
+        &self,
+        project_id: i64,
+        symbol: &str,
+        limit: u32,
+    ) -> Result<ReferencesResult> {
+        let mut result = self.find_references_by_symbol(project_id, symbol, limit)?;
+        let definition = self
+            .find_definitions_by_symbol(project_id, symbol)?
+            .definitions
+            .into_iter()
+            .next()
+            .map(|definition| SymbolLocation {
+                kind: Some("definition".to_string()),
+                ..definition
+            });
+        if let Some(definition) = &definition {
+            result.references.insert(0, definition.clone());
+        }
+        result.definition = definition;
         Ok(result)
     }
+
+    /// Build a `SymbolInfo` for a node, normalizing its kind by file language
+    fn node_symbol_info(&self, node: NodeRecord) -> Result<SymbolInfo> {
+        let file_info = self.db().get_file(node.file_id)?;
+        let kind = file_info
+            .as_ref()
+            .and_then(|f| self.registry.get(&f.language))
+            .map(|lang| lang.symbol_kind(&node.node_type))
+            .unwrap_or_else(|| SymbolKind::from_node_type(&node.node_type));
+
+        let signature = node.signature();
+        let stable_id = file_info.as_ref().map(|f| node.stable_id(&f.path));
+        Ok(SymbolInfo {
+            id: node.id,
+            parent_id: None,
+            name: node.name,
+            qualified_name: node.qualified_name,
+            node_type: node.node_type,
+            kind,
+            file: file_info.map(|f| f.path).unwrap_or_default(),
+            line: node.start_line,
+            column: node.start_column,
+            signature,
+            stable_id,
+        })
+    }
 }
 
 // Standalone functions for CLI usage (default project_id = 1)
-pub fn find_definition(db_path: &Path, file: &Path, line: u32, column: u32) -> Result<DefinitionResult> {
+pub fn find_definition(
+    db_path: &Path,
+    file: &Path,
+    line: u32,
+    column: u32,
+) -> Result<DefinitionResult> {
     find_definition_with_project(db_path, 1, file, line, column)
 }
 
-pub fn find_references(db_path: &Path, file: &Path, line: u32, column: u32) -> Result<ReferencesResult> {
-    find_references_with_project(db_path, 1, file, line, column)
+pub fn find_references(
+    db_path: &Path,
+    file: &Path,
+    line: u32,
+    column: u32,
+    limit: u32,
+    offset: u32,
+) -> Result<ReferencesResult> {
+    find_references_with_project(db_path, 1, file, line, column, limit, offset)
 }
 
-pub fn get_callgraph(db_path: &Path, symbol: &str, depth: u32, direction: &str) -> Result<CallGraphResult> {
-    get_callgraph_with_project(db_path, 1, symbol, depth, direction)
+pub fn get_callgraph(
+    db_path: &Path,
+    symbol: &str,
+    depth: u32,
+    direction: &str,
+    include_call_sites: bool,
+) -> Result<CallGraphResult> {
+    get_callgraph_with_project(
+        db_path,
+        1,
+        symbol,
+        depth,
+        direction,
+        include_call_sites,
+        TestFilter::All,
+    )
 }
 
-pub fn search_symbols(db_path: &Path, query: &str, symbol_type: Option<&str>, limit: u32) -> Result<SymbolSearchResult> {
-    search_symbols_with_project(db_path, 1, query, symbol_type, limit)
+pub fn search_symbols(
+    db_path: &Path,
+    query: &str,
+    symbol_type: Option<&str>,
+    limit: u32,
+) -> Result<SymbolSearchResult> {
+    search_symbols_with_project(db_path, 1, query, symbol_type, limit, TestFilter::All)
 }
 
 // Standalone functions with explicit project_id
@@ -323,11 +1250,13 @@ pub fn find_references_with_project(
     file: &Path,
     line: u32,
     column: u32,
+    limit: u32,
+    offset: u32,
 ) -> Result<ReferencesResult> {
     let db = Database::open(db_path)?;
     let executor = QueryExecutor::new(db);
     let file_str = file.to_string_lossy();
-    executor.find_references(project_id, &file_str, line, column)
+    executor.find_references(project_id, &file_str, line, column, limit, offset)
 }
 
 pub fn get_callgraph_with_project(
@@ -336,10 +1265,12 @@ pub fn get_callgraph_with_project(
     symbol: &str,
     depth: u32,
     direction: &str,
+    include_call_sites: bool,
+    tests: TestFilter,
 ) -> Result<CallGraphResult> {
     let db = Database::open(db_path)?;
-    let executor = QueryExecutor::new(db);
-    executor.get_callgraph(project_id, symbol, depth, direction)
+    let executor = QueryExecutor::new(db).with_test_filter(tests);
+    executor.get_callgraph(project_id, symbol, depth, direction, include_call_sites)
 }
 
 pub fn search_symbols_with_project(
@@ -348,57 +1279,124 @@ pub fn search_symbols_with_project(
     query: &str,
     symbol_type: Option<&str>,
     limit: u32,
+    tests: TestFilter,
 ) -> Result<SymbolSearchResult> {
     let db = Database::open(db_path)?;
-    let executor = QueryExecutor::new(db);
+    let executor = QueryExecutor::new(db).with_test_filter(tests);
     executor.search_symbols(project_id, query, symbol_type, limit)
 }
 
-/// Find symbol definition by name
-pub fn find_definition_by_symbol(
+pub fn search_symbols_exact_with_project(
     db_path: &Path,
     project_id: i64,
-    symbol: &str,
-) -> Result<DefinitionResult> {
+    query: &str,
+    symbol_type: Option<&str>,
+    limit: u32,
+    tests: TestFilter,
+) -> Result<SymbolSearchResult> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db).with_test_filter(tests);
+    executor.search_symbols_exact(project_id, query, symbol_type, limit)
+}
+
+pub fn search_symbols_regex_with_project(
+    db_path: &Path,
+    project_id: i64,
+    pattern: &str,
+    symbol_type: Option<&str>,
+    limit: u32,
+    tests: TestFilter,
+) -> Result<SymbolSearchResult> {
     let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db).with_test_filter(tests);
+    executor.search_symbols_regex(project_id, pattern, symbol_type, limit)
+}
 
-    // Search for the symbol definition (exclude call nodes)
-    let nodes = db.search_symbols(project_id, symbol, None, 50)?;
+pub fn symbol_stack_with_project(
+    db_path: &Path,
+    project_id: i64,
+    file: &str,
+    line: u32,
+    column: u32,
+) -> Result<SymbolStack> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db);
+    executor.symbol_stack(project_id, file, line, column)
+}
 
-    // Filter to definition types only (class, method, function, interface, struct, field)
-    let definition_types = ["class", "method", "function", "interface", "struct", "field", "variable"];
+pub fn find_annotated_with_project(
+    db_path: &Path,
+    project_id: i64,
+    annotation: &str,
+) -> Result<SymbolSearchResult> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db);
+    executor.find_annotated(project_id, annotation)
+}
 
-    for node in nodes {
-        if definition_types.contains(&node.node_type.as_str()) {
-            // Check if name matches exactly or qualified_name matches
-            let name_matches = node.name == symbol
-                || node.qualified_name.as_ref().map(|q| q == symbol || q.ends_with(&format!(".{}", symbol))).unwrap_or(false);
+pub fn package_summary_with_project(
+    db_path: &Path,
+    project_id: i64,
+    package: &str,
+) -> Result<PackageSummary> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db);
+    executor.package_summary(project_id, package)
+}
 
-            if name_matches {
-                let file_info = db.get_file(node.file_id)?;
-                return Ok(DefinitionResult {
-                    found: true,
-                    definition: Some(SymbolLocation {
-                        file: file_info.map(|f| f.path).unwrap_or_default(),
-                        line: node.start_line,
-                        column: node.start_column,
-                        node_type: node.node_type,
-                        name: node.name,
-                        qualified_name: node.qualified_name,
-                        context: None,
-                    }),
-                });
-            }
-        }
-    }
+pub fn symbol_info_with_project(
+    db_path: &Path,
+    project_id: i64,
+    symbol: &str,
+) -> Result<SymbolDetails> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db);
+    executor.symbol_info(project_id, symbol)
+}
+
+pub fn search_symbols_global(
+    db_path: &Path,
+    query: &str,
+    symbol_type: Option<&str>,
+    limit: u32,
+) -> Result<GlobalSymbolSearchResult> {
+    let db = Database::open(db_path)?;
+    let executor = QueryExecutor::new(db);
+    executor.search_symbols_global(query, symbol_type, limit)
+}
+
+/// Find symbol definition by name
+///
+/// When several definitions share the name, the first one (by file path) is
+/// returned; use [`find_definitions_by_symbol`] to get all of them.
+pub fn find_definition_by_symbol(
+    db_path: &Path,
+    project_id: i64,
+    symbol: &str,
+) -> Result<DefinitionResult> {
+    let result = find_definitions_by_symbol(db_path, project_id, symbol)?;
 
     Ok(DefinitionResult {
-        found: false,
-        definition: None,
+        found: result.found,
+        definition: result.definitions.into_iter().next(),
     })
 }
 
+/// Find every definition of a symbol by name
+///
+/// See [`QueryExecutor::find_definitions_by_symbol`].
+pub fn find_definitions_by_symbol(
+    db_path: &Path,
+    project_id: i64,
+    symbol: &str,
+) -> Result<DefinitionsResult> {
+    let db = Database::open(db_path)?;
+    QueryExecutor::new(db).find_definitions_by_symbol(project_id, symbol)
+}
+
 /// Find all references to a symbol by name (where the symbol is called/used)
+///
+/// See [`QueryExecutor::find_references_by_symbol`].
 pub fn find_references_by_symbol(
     db_path: &Path,
     project_id: i64,
@@ -406,83 +1404,36 @@ pub fn find_references_by_symbol(
     limit: u32,
 ) -> Result<ReferencesResult> {
     let db = Database::open(db_path)?;
+    QueryExecutor::new(db).find_references_by_symbol(project_id, symbol, limit)
+}
 
-    // First find the symbol definition
-    let target_node = db.find_symbol_by_name(project_id, symbol)?;
-
-    match target_node {
-        Some(node) => {
-            // Find all callers (nodes that call this symbol)
-            let callers = db.find_callers(node.id)?;
-
-            let mut references = Vec::new();
-            let mut count = 0;
-
-            for caller in callers {
-                if count >= limit {
-                    break;
-                }
-                let file_info = db.get_file(caller.file_id)?;
-                references.push(SymbolLocation {
-                    file: file_info.map(|f| f.path).unwrap_or_default(),
-                    line: caller.start_line,
-                    column: caller.start_column,
-                    node_type: caller.node_type,
-                    name: caller.name,
-                    qualified_name: caller.qualified_name,
-                    context: None,
-                });
-                count += 1;
-            }
-
-            // Also find call nodes (method_invocation/call_expression) with matching name
-            let call_nodes = db.search_symbols(project_id, symbol, Some("call"), limit)?;
-            for call_node in call_nodes {
-                if count >= limit {
-                    break;
-                }
-                let file_info = db.get_file(call_node.file_id)?;
-                references.push(SymbolLocation {
-                    file: file_info.map(|f| f.path).unwrap_or_default(),
-                    line: call_node.start_line,
-                    column: call_node.start_column,
-                    node_type: call_node.node_type,
-                    name: call_node.name,
-                    qualified_name: call_node.qualified_name,
-                    context: None,
-                });
-                count += 1;
-            }
-
-            Ok(ReferencesResult {
-                count: references.len(),
-                references,
-            })
-        }
-        None => {
-            // Symbol definition not found, try searching for call nodes directly
-            let call_nodes = db.search_symbols(project_id, symbol, Some("call"), limit)?;
-
-            let mut references = Vec::new();
-            for call_node in call_nodes {
-                let file_info = db.get_file(call_node.file_id)?;
-                references.push(SymbolLocation {
-                    file: file_info.map(|f| f.path).unwrap_or_default(),
-                    line: call_node.start_line,
-                    column: call_node.start_column,
-                    node_type: call_node.node_type,
-                    name: call_node.name,
-                    qualified_name: call_node.qualified_name,
-                    context: None,
-                });
-            }
+/// Find all references to a symbol by name, along with its definition
+///
+/// See [`QueryExecutor::find_references_with_definition`].
+pub fn find_references_with_definition(
+    db_path: &Path,
+    project_id: i64,
+    symbol: &str,
+    limit: u32,
+) -> Result<ReferencesResult> {
+    let db = Database::open(db_path)?;
+    QueryExecutor::new(db).find_references_with_definition(project_id, symbol, limit)
+}
 
-            Ok(ReferencesResult {
-                count: references.len(),
-                references,
-            })
-        }
-    }
+fn reference_location(db: &Database, node: NodeRecord, resolution: &str) -> Result<SymbolLocation> {
+    let file_info = db.get_file(node.file_id)?;
+    let kind = node.access();
+    Ok(SymbolLocation {
+        file: file_info.map(|f| f.path).unwrap_or_default(),
+        line: node.start_line,
+        column: node.start_column,
+        node_type: node.node_type,
+        name: node.name,
+        qualified_name: node.qualified_name,
+        context: None,
+        resolution: Some(resolution.to_string()),
+        kind,
+    })
 }
 
 #[cfg(test)]
@@ -517,6 +1468,9 @@ mod tests {
             language: language.to_string(),
             content_hash: "test_hash".to_string(),
             parsed_at: chrono::Utc::now(),
+            line_count: 0,
+            is_test: false,
+            encoding: None,
         };
         db.insert_file(&file).unwrap()
     }
@@ -570,7 +1524,14 @@ mod tests {
         let db = setup_test_db();
         let project_id = create_test_project(&db);
         let file_id = create_test_file(&db, project_id, "/test/UserService.java", "java");
-        create_test_node(&db, file_id, "class", "UserService", Some("com.example.UserService"), 1);
+        create_test_node(
+            &db,
+            file_id,
+            "class",
+            "UserService",
+            Some("com.example.UserService"),
+            1,
+        );
 
         let executor = QueryExecutor::new(db);
         let result = executor
@@ -584,37 +1545,286 @@ mod tests {
         assert_eq!(def.node_type, "class");
     }
 
+    #[test]
+    fn test_symbol_stack_method_in_class() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/UserService.java", "java");
+        let node = |node_type: &str, name: &str, start_line, end_line| NodeRecord {
+            id: 0,
+            file_id,
+            node_type: node_type.to_string(),
+            name: name.to_string(),
+            qualified_name: None,
+            start_line,
+            start_column: 5,
+            end_line,
+            end_column: 6,
+            attributes: None,
+        };
+        db.insert_node(&node("class", "UserService", 1, 20))
+            .unwrap();
+        db.insert_node(&node("method", "save", 3, 8)).unwrap();
+        db.insert_node(&node("method", "load", 10, 15)).unwrap();
+        db.insert_node(&node("call", "repository.save", 4, 6))
+            .unwrap();
+
+        let executor = QueryExecutor::new(db);
+        let stack = executor
+            .symbol_stack(project_id, "/test/UserService.java", 5, 10)
+            .unwrap();
+        let chain: Vec<_> = stack
+            .symbols
+            .iter()
+            .map(|s| (s.node_type.as_str(), s.name.as_str()))
+            .collect();
+        assert_eq!(chain, vec![("class", "UserService"), ("method", "save")]);
+
+        // Between the methods only the class encloses the position
+        let stack = executor
+            .symbol_stack(project_id, "/test/UserService.java", 9, 1)
+            .unwrap();
+        assert_eq!(stack.symbols.len(), 1);
+        assert!(executor
+            .symbol_stack(project_id, "/test/Other.java", 5, 10)
+            .unwrap()
+            .symbols
+            .is_empty());
+    }
+
     #[test]
     fn test_find_references_empty() {
         let db = setup_test_db();
         let project_id = create_test_project(&db);
-        let executor = QueryExecutor::new(db);
+        let executor = QueryExecutor::new(db);
+
+        let result = executor
+            .find_references(
+                project_id,
+                "/nonexistent/file.java",
+                10,
+                5,
+                DEFAULT_RESULT_LIMIT,
+                0,
+            )
+            .unwrap();
+
+        assert_eq!(result.count, 0);
+        assert!(result.references.is_empty());
+    }
+
+    #[test]
+    fn test_search_symbols() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/Service.java", "java");
+
+        create_test_node(
+            &db,
+            file_id,
+            "class",
+            "UserService",
+            Some("com.example.UserService"),
+            1,
+        );
+        create_test_node(
+            &db,
+            file_id,
+            "method",
+            "getUser",
+            Some("com.example.UserService.getUser"),
+            10,
+        );
+        create_test_node(
+            &db,
+            file_id,
+            "method",
+            "createUser",
+            Some("com.example.UserService.createUser"),
+            20,
+        );
+
+        let executor = QueryExecutor::new(db);
+        let result = executor
+            .search_symbols(project_id, "User", None, 10)
+            .unwrap();
+
+        assert!(result.count >= 2);
+        assert!(result.symbols.iter().any(|s| s.name == "UserService"));
+    }
+
+    #[test]
+    fn test_search_symbols_exact() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/UserService.java", "java");
+        create_test_node(&db, file_id, "class", "User", Some("com.example.User"), 1);
+        create_test_node(
+            &db,
+            file_id,
+            "class",
+            "UserService",
+            Some("com.example.UserService"),
+            10,
+        );
+        create_test_node(
+            &db,
+            file_id,
+            "method",
+            "getUser",
+            Some("com.example.UserService.getUser"),
+            12,
+        );
+
+        let executor = QueryExecutor::new(db);
+        let substring = executor
+            .search_symbols(project_id, "User", None, 10)
+            .unwrap();
+        assert_eq!(substring.count, 3);
+
+        let exact = executor
+            .search_symbols_exact(project_id, "User", None, 10)
+            .unwrap();
+        assert_eq!(exact.count, 1);
+        assert_eq!(exact.symbols[0].name, "User");
+
+        // The qualified name matches too, but not as a suffix or substring
+        let qualified = executor
+            .search_symbols_exact(project_id, "com.example.UserService", None, 10)
+            .unwrap();
+        assert_eq!(qualified.count, 1);
+        assert_eq!(qualified.symbols[0].name, "UserService");
+        assert_eq!(
+            executor
+                .search_symbols_exact(project_id, "Use", None, 10)
+                .unwrap()
+                .count,
+            0
+        );
+        assert_eq!(
+            executor
+                .search_symbols_exact(project_id, "User", Some("method"), 10)
+                .unwrap()
+                .count,
+            0
+        );
+    }
+
+    #[test]
+    fn test_search_symbols_regex() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/UserService.java", "java");
+        for (i, name) in [
+            "getUser",
+            "getAdminUser",
+            "getUsers",
+            "findUser",
+            "UserService",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            create_test_node(&db, file_id, "method", name, None, i as u32 + 1);
+        }
 
+        let executor = QueryExecutor::new(db);
         let result = executor
-            .find_references(project_id, "/nonexistent/file.java", 10, 5)
+            .search_symbols_regex(project_id, "^get.*User$", None, 10)
             .unwrap();
+        let mut names: Vec<_> = result.symbols.iter().map(|s| s.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["getAdminUser", "getUser"]);
 
-        assert_eq!(result.count, 0);
-        assert!(result.references.is_empty());
+        let limited = executor
+            .search_symbols_regex(project_id, "User", None, 2)
+            .unwrap();
+        assert_eq!(limited.count, 2);
+
+        let invalid = executor.search_symbols_regex(project_id, "get(", None, 10);
+        assert!(matches!(invalid, Err(Error::Query(_))));
     }
 
     #[test]
-    fn test_search_symbols() {
-        let db = setup_test_db();
-        let project_id = create_test_project(&db);
-        let file_id = create_test_file(&db, project_id, "/test/Service.java", "java");
+    fn test_search_symbols_cached_until_reparse() {
+        use crate::core::graph::GraphBuilder;
+        use crate::core::parser::FileGraphData;
+        use crate::storage::models::NodeData;
 
-        create_test_node(&db, file_id, "class", "UserService", Some("com.example.UserService"), 1);
-        create_test_node(&db, file_id, "method", "getUser", Some("com.example.UserService.getUser"), 10);
-        create_test_node(&db, file_id, "method", "createUser", Some("com.example.UserService.createUser"), 20);
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let mut builder = GraphBuilder::new(db);
+        let project_id = builder
+            .create_or_get_project("cached", temp_dir.path())
+            .unwrap();
 
-        let executor = QueryExecutor::new(db);
-        let result = executor
-            .search_symbols(project_id, "User", None, 10)
+        let class_node = |name: &str| NodeData {
+            node_type: "class".to_string(),
+            name: name.to_string(),
+            qualified_name: None,
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 20,
+            attributes: None,
+        };
+        let file_path = temp_dir.path().join("Service.java");
+        builder
+            .store_file_graph(
+                project_id,
+                &file_path,
+                "java",
+                FileGraphData {
+                    nodes: vec![class_node("UserService")],
+                    edges: vec![],
+                    content_hash: "v1".to_string(),
+                    line_count: 1,
+                    encoding: None,
+                },
+            )
             .unwrap();
+        builder.build_cross_references(project_id).unwrap();
 
-        assert!(result.count >= 2);
-        assert!(result.symbols.iter().any(|s| s.name == "UserService"));
+        let cache = Arc::new(QueryCache::default());
+        let executor =
+            QueryExecutor::new(Database::open(&db_path).unwrap()).with_cache(cache.clone());
+
+        let first = executor
+            .search_symbols(project_id, "Service", None, 10)
+            .unwrap();
+        let second = executor
+            .search_symbols(project_id, "Service", None, 10)
+            .unwrap();
+        assert_eq!(first.count, 1);
+        assert_eq!(second.count, 1);
+        assert_eq!((cache.misses(), cache.hits()), (1, 1));
+
+        // Re-parsing the file bumps the project generation
+        builder
+            .store_file_graph(
+                project_id,
+                &file_path,
+                "java",
+                FileGraphData {
+                    nodes: vec![class_node("UserService"), class_node("OrderService")],
+                    edges: vec![],
+                    content_hash: "v2".to_string(),
+                    line_count: 2,
+                    encoding: None,
+                },
+            )
+            .unwrap();
+        builder.build_cross_references(project_id).unwrap();
+
+        let third = executor
+            .search_symbols(project_id, "Service", None, 10)
+            .unwrap();
+        assert_eq!(third.count, 2);
+        assert_eq!(cache.misses(), 2);
     }
 
     #[test]
@@ -663,15 +1873,72 @@ mod tests {
         assert_eq!(result.count, 5);
     }
 
+    #[test]
+    fn test_search_symbols_normalizes_kind() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let java_file = create_test_file(&db, project_id, "/test/Account.java", "java");
+        let go_file = create_test_file(&db, project_id, "/test/account.go", "go");
+        create_test_node(&db, java_file, "class", "AccountJava", None, 1);
+        create_test_node(&db, go_file, "struct", "AccountGo", None, 1);
+
+        let executor = QueryExecutor::new(db);
+        let result = executor
+            .search_symbols(project_id, "Account", None, 10)
+            .unwrap();
+
+        assert_eq!(result.count, 2);
+        for symbol in &result.symbols {
+            assert_eq!(symbol.kind, SymbolKind::Type);
+        }
+        assert!(result.symbols.iter().any(|s| s.node_type == "class"));
+        assert!(result.symbols.iter().any(|s| s.node_type == "struct"));
+    }
+
+    #[test]
+    fn test_search_symbols_global() {
+        let db = setup_test_db();
+        let project_a = create_test_project(&db);
+        let project_b = db
+            .insert_project(&ProjectRecord {
+                id: 0,
+                name: "other-project".to_string(),
+                root_path: "/test/other".to_string(),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+
+        let file_a = create_test_file(&db, project_a, "/test/project/A.java", "java");
+        let file_b = create_test_file(&db, project_b, "/test/other/B.java", "java");
+        create_test_node(&db, file_a, "class", "UserService", None, 1);
+        create_test_node(&db, file_b, "class", "UserRepository", None, 1);
+
+        let executor = QueryExecutor::new(db);
+        let result = executor.search_symbols_global("User", None, 10).unwrap();
+
+        assert_eq!(result.count, 2);
+        assert!(result
+            .symbols
+            .iter()
+            .any(|s| s.project == "test-project" && s.symbol.name == "UserService"));
+        assert!(result
+            .symbols
+            .iter()
+            .any(|s| s.project_id == project_b && s.symbol.file == "/test/other/B.java"));
+    }
+
     #[test]
     fn test_get_callgraph_symbol_not_found() {
         let db = setup_test_db();
         let project_id = create_test_project(&db);
         let executor = QueryExecutor::new(db);
 
-        let result = executor.get_callgraph(project_id, "nonExistentSymbol", 2, "both");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Symbol not found"));
+        let result = executor.get_callgraph(project_id, "nonExistentSymbol", 2, "both", false);
+        match result {
+            Err(Error::SymbolNotFound(symbol)) => assert_eq!(symbol, "nonExistentSymbol"),
+            other => panic!("expected SymbolNotFound, got {:?}", other),
+        }
     }
 
     #[test]
@@ -681,7 +1948,8 @@ mod tests {
         let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
 
         let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
-        let helper_id = create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let helper_id =
+            create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
 
         // main calls helper
         let edge = EdgeRecord {
@@ -695,7 +1963,7 @@ mod tests {
 
         let executor = QueryExecutor::new(db);
         let result = executor
-            .get_callgraph(project_id, "main", 1, "both")
+            .get_callgraph(project_id, "main", 1, "both", false)
             .unwrap();
 
         assert_eq!(result.center.name, "main");
@@ -710,7 +1978,8 @@ mod tests {
         let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
 
         let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
-        let helper_id = create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let helper_id =
+            create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
 
         let edge = EdgeRecord {
             id: 0,
@@ -723,7 +1992,7 @@ mod tests {
 
         let executor = QueryExecutor::new(db);
         let result = executor
-            .get_callgraph(project_id, "helper", 1, "callers")
+            .get_callgraph(project_id, "helper", 1, "callers", false)
             .unwrap();
 
         assert_eq!(result.center.name, "helper");
@@ -737,7 +2006,8 @@ mod tests {
         let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
 
         let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
-        let helper_id = create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let helper_id =
+            create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
 
         let edge = EdgeRecord {
             id: 0,
@@ -750,7 +2020,7 @@ mod tests {
 
         let executor = QueryExecutor::new(db);
         let result = executor
-            .get_callgraph(project_id, "main", 1, "callees")
+            .get_callgraph(project_id, "main", 1, "callees", false)
             .unwrap();
 
         assert_eq!(result.center.name, "main");
@@ -758,6 +2028,235 @@ mod tests {
         assert!(result.callees.iter().any(|c| c.name == "helper"));
     }
 
+    #[test]
+    fn test_callgraph_to_mermaid() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
+
+        // handle -> main -> {load, save}
+        let handle_id =
+            create_test_node(&db, file_id, "function", "handle", Some("main.handle"), 20);
+        let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
+        let load_id = create_test_node(&db, file_id, "call", "load", None, 2);
+        let save_id = create_test_node(&db, file_id, "call", "save", None, 3);
+        for (source_id, target_id) in [(handle_id, main_id), (main_id, load_id), (main_id, save_id)]
+        {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let executor = QueryExecutor::new(db);
+        let result = executor
+            .get_callgraph(project_id, "main", 1, "both", false)
+            .unwrap();
+        let mermaid = result.to_mermaid();
+        let lines: Vec<&str> = mermaid.lines().map(str::trim).collect();
+
+        assert_eq!(lines[0], "graph TD");
+        assert!(lines.contains(&format!("n{}[\"main.main\"]", main_id).as_str()));
+        assert!(lines.contains(&format!("n{}[\"load\"]", load_id).as_str()));
+        for (from, to) in [(handle_id, main_id), (main_id, load_id), (main_id, save_id)] {
+            assert!(lines.contains(&format!("n{} --> n{}", from, to).as_str()));
+        }
+        assert_eq!(lines.iter().filter(|l| l.contains("-->")).count(), 3);
+        assert!(lines.contains(&format!("class n{} center", main_id).as_str()));
+
+        let d3: serde_json::Value = serde_json::from_str(&result.to_d3_json().unwrap()).unwrap();
+        let ids: HashSet<i64> = d3["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, HashSet::from([handle_id, main_id, load_id, save_id]));
+        let links = d3["links"].as_array().unwrap();
+        assert_eq!(links.len(), 3);
+        for link in links {
+            assert!(ids.contains(&link["source"].as_i64().unwrap()));
+            assert!(ids.contains(&link["target"].as_i64().unwrap()));
+            assert_eq!(link["type"], "calls");
+        }
+    }
+
+    #[test]
+    fn test_callgraph_records_parents() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
+
+        // main -> load -> read, main -> save
+        let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
+        let load_id = create_test_node(&db, file_id, "call", "load", None, 2);
+        let save_id = create_test_node(&db, file_id, "call", "save", None, 3);
+        let read_id = create_test_node(&db, file_id, "call", "read", None, 11);
+        for (source_id, target_id) in [(main_id, load_id), (main_id, save_id), (load_id, read_id)] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let executor = QueryExecutor::new(db);
+        let result = executor
+            .get_callgraph(project_id, "main", 2, "callees", false)
+            .unwrap();
+
+        assert_eq!(result.center.id, main_id);
+        let parent = |name: &str| {
+            result
+                .callees
+                .iter()
+                .find(|c| c.name == name)
+                .and_then(|c| c.parent_id)
+        };
+        assert_eq!(result.callees.len(), 3);
+        assert_eq!(parent("load"), Some(main_id));
+        assert_eq!(parent("save"), Some(main_id));
+        assert_eq!(parent("read"), Some(load_id));
+
+        let shallow = executor
+            .get_callgraph(project_id, "main", 1, "callees", false)
+            .unwrap();
+        assert_eq!(shallow.callees.len(), 2);
+    }
+
+    #[test]
+    fn test_callgraph_call_sites() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
+
+        let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
+        create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let call_id = create_test_node(&db, file_id, "call", "helper", None, 3);
+
+        let edge = EdgeRecord {
+            id: 0,
+            source_id: main_id,
+            target_id: call_id,
+            edge_type: "calls".to_string(),
+            attributes: None,
+        };
+        db.insert_edge(&edge).unwrap();
+
+        let executor = QueryExecutor::new(db);
+        let without = executor
+            .get_callgraph(project_id, "helper", 1, "both", false)
+            .unwrap();
+        assert!(without.call_sites.is_empty());
+
+        let result = executor
+            .get_callgraph(project_id, "helper", 1, "both", true)
+            .unwrap();
+        assert_eq!(result.call_sites.len(), 1);
+        assert_eq!(result.call_sites[0].line, 3);
+        assert_eq!(result.call_sites[0].context.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_callgraph_call_sites_of_same_named_methods() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/Main.java", "java");
+
+        let foo_save = create_test_node(&db, file_id, "method", "save", Some("Foo.save"), 1);
+        let bar_save = create_test_node(&db, file_id, "method", "save", Some("Bar.save"), 10);
+        let main_id = create_test_node(&db, file_id, "method", "main", Some("Main.main"), 20);
+        let foo_call = create_test_node(&db, file_id, "call", "save", None, 21);
+        let bar_call = create_test_node(&db, file_id, "call", "save", None, 22);
+        // Not linked to either method, so it is credited to neither
+        create_test_node(&db, file_id, "call", "save", None, 23);
+
+        for (source_id, target_id) in [
+            (main_id, foo_call),
+            (main_id, bar_call),
+            (foo_call, foo_save),
+            (bar_call, bar_save),
+        ] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let executor = QueryExecutor::new(db);
+        let result = executor
+            .get_callgraph(project_id, "Foo.save", 1, "callers", true)
+            .unwrap();
+        assert_eq!(result.call_sites.len(), 1);
+        assert_eq!(result.call_sites[0].line, 21);
+        assert_eq!(result.call_sites[0].context.as_deref(), Some("main"));
+
+        let result = executor
+            .get_callgraph(project_id, "Bar.save", 1, "callers", true)
+            .unwrap();
+        assert_eq!(result.call_sites.len(), 1);
+        assert_eq!(result.call_sites[0].line, 22);
+    }
+
+    #[test]
+    fn test_symbol_info_counts_distinct_callers() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
+
+        let helper_id =
+            create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
+        let run_id = create_test_node(&db, file_id, "function", "run", Some("main.run"), 10);
+        // main calls helper twice, run once
+        for (caller_id, line) in [(main_id, 2), (main_id, 3), (run_id, 11)] {
+            let call_id = create_test_node(&db, file_id, "call", "helper", None, line);
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id: caller_id,
+                target_id: call_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+        let log_call = create_test_node(&db, file_id, "call", "log", None, 21);
+        db.insert_edge(&EdgeRecord {
+            id: 0,
+            source_id: helper_id,
+            target_id: log_call,
+            edge_type: "calls".to_string(),
+            attributes: None,
+        })
+        .unwrap();
+
+        let executor = QueryExecutor::new(db);
+        let info = executor.symbol_info(project_id, "helper").unwrap();
+        assert_eq!(info.symbol.node_type, "function");
+        assert_eq!(info.symbol.qualified_name.as_deref(), Some("main.helper"));
+        assert_eq!(info.symbol.file, "/test/main.go");
+        assert_eq!(info.symbol.line, 20);
+        assert_eq!(info.callers, 2);
+        assert_eq!(info.callees, 1);
+        assert_eq!(info.references, 0);
+
+        assert!(matches!(
+            executor.symbol_info(project_id, "missing"),
+            Err(Error::SymbolNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_callgraph_depth_zero() {
         let db = setup_test_db();
@@ -765,7 +2264,8 @@ mod tests {
         let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
 
         let main_id = create_test_node(&db, file_id, "function", "main", Some("main.main"), 1);
-        let helper_id = create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
+        let helper_id =
+            create_test_node(&db, file_id, "function", "helper", Some("main.helper"), 20);
 
         let edge = EdgeRecord {
             id: 0,
@@ -778,12 +2278,65 @@ mod tests {
 
         let executor = QueryExecutor::new(db);
         let result = executor
-            .get_callgraph(project_id, "main", 0, "both")
+            .get_callgraph(project_id, "main", 0, "both", false)
             .unwrap();
 
         assert_eq!(result.center.name, "main");
         assert!(result.callers.is_empty());
         assert!(result.callees.is_empty());
+        assert!(result.traversal_stats.truncated);
+    }
+
+    #[test]
+    fn test_callgraph_traversal_stats() {
+        let db = setup_test_db();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/main.go", "go");
+
+        // main -> a -> b -> c
+        let names = ["main", "a", "b", "c"];
+        let ids: Vec<i64> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                create_test_node(&db, file_id, "function", name, None, i as u32 * 10 + 1)
+            })
+            .collect();
+        for pair in ids.windows(2) {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id: pair[0],
+                target_id: pair[1],
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let executor = QueryExecutor::new(db);
+        let shallow = executor
+            .get_callgraph(project_id, "main", 2, "callees", false)
+            .unwrap();
+        assert_eq!(
+            shallow.traversal_stats,
+            TraversalStats {
+                nodes_visited: 2,
+                max_depth_reached: 2,
+                truncated: true,
+            }
+        );
+
+        let full = executor
+            .get_callgraph(project_id, "main", 5, "callees", false)
+            .unwrap();
+        assert_eq!(
+            full.traversal_stats,
+            TraversalStats {
+                nodes_visited: 3,
+                max_depth_reached: 3,
+                truncated: false,
+            }
+        );
     }
 
     #[test]
@@ -798,6 +2351,8 @@ mod tests {
                 name: "TestClass".to_string(),
                 qualified_name: Some("com.example.TestClass".to_string()),
                 context: None,
+                resolution: None,
+                kind: None,
             }),
         };
 
@@ -820,7 +2375,10 @@ mod tests {
                 name: "TestClass".to_string(),
                 qualified_name: None,
                 context: None,
+                resolution: None,
+                kind: None,
             }],
+            definition: None,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -834,22 +2392,34 @@ mod tests {
     fn test_callgraph_result_serialization() {
         let result = CallGraphResult {
             center: SymbolInfo {
+                id: 1,
+                parent_id: None,
                 name: "main".to_string(),
                 qualified_name: Some("main.main".to_string()),
                 node_type: "function".to_string(),
+                kind: SymbolKind::Function,
                 file: "/test/main.go".to_string(),
                 line: 1,
                 column: 1,
+                signature: None,
+                stable_id: None,
             },
             callers: vec![],
             callees: vec![SymbolInfo {
+                id: 2,
+                parent_id: Some(1),
                 name: "helper".to_string(),
                 qualified_name: None,
                 node_type: "function".to_string(),
+                kind: SymbolKind::Function,
                 file: "/test/main.go".to_string(),
                 line: 20,
                 column: 1,
+                signature: None,
+                stable_id: None,
             }],
+            call_sites: vec![],
+            traversal_stats: TraversalStats::default(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -865,20 +2435,30 @@ mod tests {
             count: 2,
             symbols: vec![
                 SymbolInfo {
+                    id: 1,
+                    parent_id: None,
                     name: "func1".to_string(),
                     qualified_name: None,
                     node_type: "function".to_string(),
+                    kind: SymbolKind::Function,
                     file: "/test.go".to_string(),
                     line: 1,
                     column: 1,
+                    signature: None,
+                    stable_id: None,
                 },
                 SymbolInfo {
+                    id: 2,
+                    parent_id: None,
                     name: "func2".to_string(),
                     qualified_name: None,
                     node_type: "function".to_string(),
+                    kind: SymbolKind::Function,
                     file: "/test.go".to_string(),
                     line: 10,
                     column: 1,
+                    signature: None,
+                    stable_id: None,
                 },
             ],
         };
@@ -899,7 +2479,14 @@ mod tests {
         db.init_schema().unwrap();
         let project_id = create_test_project(&db);
         let file_id = create_test_file(&db, project_id, "/test/Service.java", "java");
-        create_test_node(&db, file_id, "class", "UserService", Some("com.example.UserService"), 1);
+        create_test_node(
+            &db,
+            file_id,
+            "class",
+            "UserService",
+            Some("com.example.UserService"),
+            1,
+        );
         drop(db);
 
         let result = find_definition_by_symbol(&db_path, project_id, "UserService").unwrap();
@@ -909,6 +2496,143 @@ mod tests {
         assert_eq!(def.name, "UserService");
     }
 
+    #[test]
+    fn test_find_definition_by_partially_qualified_symbol() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/UserService.java", "java");
+        create_test_node(
+            &db,
+            file_id,
+            "method",
+            "getUser",
+            Some("com.example.UserService.getUser"),
+            4,
+        );
+        drop(db);
+
+        let short = find_definition_by_symbol(&db_path, project_id, "getUser").unwrap();
+        assert_eq!(short.definition.unwrap().line, 4);
+
+        let partial =
+            find_definition_by_symbol(&db_path, project_id, "UserService.getUser").unwrap();
+        assert!(partial.found);
+        let def = partial.definition.unwrap();
+        assert_eq!(
+            def.qualified_name.as_deref(),
+            Some("com.example.UserService.getUser")
+        );
+    }
+
+    #[test]
+    fn test_find_definitions_by_symbol_prefers_exact_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let a_file = create_test_file(&db, project_id, "/test/a/User.java", "java");
+        let b_file = create_test_file(&db, project_id, "/test/b/User.java", "java");
+        create_test_node(&db, a_file, "class", "User", Some("com.example.a.User"), 1);
+        create_test_node(&db, b_file, "class", "User", Some("a.User"), 1);
+        drop(db);
+
+        let result = find_definitions_by_symbol(&db_path, project_id, "a.User").unwrap();
+        assert_eq!(result.count, 2);
+        assert_eq!(result.definitions[0].file, "/test/b/User.java");
+    }
+
+    #[test]
+    fn test_find_definitions_by_symbol_reports_all_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let model_file = create_test_file(&db, project_id, "/test/model/User.java", "java");
+        let api_file = create_test_file(&db, project_id, "/test/api/User.java", "java");
+        create_test_node(
+            &db,
+            model_file,
+            "class",
+            "User",
+            Some("com.example.model.User"),
+            3,
+        );
+        create_test_node(
+            &db,
+            api_file,
+            "class",
+            "User",
+            Some("com.example.api.User"),
+            5,
+        );
+        create_test_node(&db, api_file, "call", "User", None, 20);
+        drop(db);
+
+        let result = find_definitions_by_symbol(&db_path, project_id, "User").unwrap();
+
+        assert!(result.found);
+        assert_eq!(result.count, 2);
+        assert!(result
+            .definitions
+            .iter()
+            .any(|d| d.file == "/test/model/User.java"));
+        assert!(result
+            .definitions
+            .iter()
+            .any(|d| d.file == "/test/api/User.java"));
+        assert!(result.definitions.iter().all(|d| d.node_type == "class"));
+    }
+
+    #[test]
+    fn test_find_definitions_prefers_concrete_implementation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let iface_file = create_test_file(&db, project_id, "/test/a/Handler.java", "java");
+        let impl_file = create_test_file(&db, project_id, "/test/b/HandlerImpl.java", "java");
+        db.insert_node(&NodeRecord {
+            id: 0,
+            file_id: iface_file,
+            node_type: "method".to_string(),
+            name: "handle".to_string(),
+            qualified_name: Some("com.example.Handler.handle".to_string()),
+            start_line: 2,
+            start_column: 5,
+            end_line: 2,
+            end_column: 20,
+            attributes: Some(r#"{"abstract":true}"#.to_string()),
+        })
+        .unwrap();
+        create_test_node(
+            &db,
+            impl_file,
+            "method",
+            "handle",
+            Some("com.example.HandlerImpl.handle"),
+            4,
+        );
+        drop(db);
+
+        let result = find_definitions_by_symbol(&db_path, project_id, "handle").unwrap();
+        assert_eq!(result.count, 2);
+        assert_eq!(result.definitions[0].file, "/test/b/HandlerImpl.java");
+        assert_eq!(result.definitions[1].file, "/test/a/Handler.java");
+
+        let single = find_definition_by_symbol(&db_path, project_id, "handle").unwrap();
+        assert_eq!(single.definition.unwrap().file, "/test/b/HandlerImpl.java");
+    }
+
     #[test]
     fn test_find_references_by_symbol() {
         let temp_dir = TempDir::new().unwrap();
@@ -919,7 +2643,14 @@ mod tests {
         let project_id = create_test_project(&db);
         let file_id = create_test_file(&db, project_id, "/test/Service.java", "java");
 
-        let method_id = create_test_node(&db, file_id, "method", "getUser", Some("UserService.getUser"), 10);
+        let method_id = create_test_node(
+            &db,
+            file_id,
+            "method",
+            "getUser",
+            Some("UserService.getUser"),
+            10,
+        );
         let call_id = create_test_node(&db, file_id, "call", "getUser", None, 30);
 
         // Create a calls edge
@@ -936,6 +2667,127 @@ mod tests {
         let result = find_references_by_symbol(&db_path, project_id, "getUser", 10).unwrap();
 
         assert!(result.count >= 1);
+        assert!(result.definition.is_none());
+    }
+
+    #[test]
+    fn test_find_references_with_definition() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/Service.java", "java");
+
+        let method_id = create_test_node(
+            &db,
+            file_id,
+            "method",
+            "getUser",
+            Some("UserService.getUser"),
+            10,
+        );
+        for line in [30, 40] {
+            let call_id = create_test_node(&db, file_id, "call", "getUser", None, line);
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id: call_id,
+                target_id: method_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+        drop(db);
+
+        let result = find_references_with_definition(&db_path, project_id, "getUser", 10).unwrap();
+        assert_eq!(result.count, 2);
+        assert_eq!(result.references.len(), 3);
+        assert_eq!(result.references[0].line, 10);
+        assert_eq!(result.references[0].kind.as_deref(), Some("definition"));
+        assert_eq!(result.references.iter().filter(|r| r.line == 10).count(), 1);
+
+        let definition = result.definition.unwrap();
+        assert_eq!(definition.line, 10);
+        assert_eq!(definition.kind.as_deref(), Some("definition"));
+
+        let json = serde_json::to_value(
+            find_references_by_symbol(&db_path, project_id, "getUser", 10).unwrap(),
+        )
+        .unwrap();
+        assert!(json.get("definition").is_none());
+    }
+
+    #[test]
+    fn test_find_references_by_symbol_does_not_cross_classes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let project_id = create_test_project(&db);
+        let file_id = create_test_file(&db, project_id, "/test/Repos.java", "java");
+
+        let order_save = create_test_node(
+            &db,
+            file_id,
+            "method",
+            "save",
+            Some("com.example.OrderRepo.save"),
+            10,
+        );
+        let user_save = create_test_node(
+            &db,
+            file_id,
+            "method",
+            "save",
+            Some("com.example.UserRepo.save"),
+            20,
+        );
+        let order_call = create_test_node(&db, file_id, "call", "save", None, 40);
+        let user_call = create_test_node(&db, file_id, "call", "save", None, 50);
+        // A call nothing links to; it could mean either save()
+        create_test_node(&db, file_id, "call", "save", None, 60);
+        for (source_id, target_id) in [(order_call, order_save), (user_call, user_save)] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: "calls".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        // Another method with a unique name and only an unlinked call
+        create_test_node(
+            &db,
+            file_id,
+            "method",
+            "load",
+            Some("com.example.OrderRepo.load"),
+            30,
+        );
+        create_test_node(&db, file_id, "call", "load", None, 70);
+        drop(db);
+
+        let result = find_references_by_symbol(&db_path, project_id, "OrderRepo.save", 10).unwrap();
+        let lines: Vec<_> = result.references.iter().map(|r| r.line).collect();
+        assert_eq!(lines, [40]);
+        assert_eq!(result.references[0].resolution.as_deref(), Some("resolved"));
+
+        let result = find_references_by_symbol(&db_path, project_id, "UserRepo.save", 10).unwrap();
+        let lines: Vec<_> = result.references.iter().map(|r| r.line).collect();
+        assert_eq!(lines, [50]);
+
+        let result = find_references_by_symbol(&db_path, project_id, "load", 10).unwrap();
+        assert_eq!(result.count, 1);
+        assert_eq!(result.references[0].line, 70);
+        assert_eq!(
+            result.references[0].resolution.as_deref(),
+            Some("unverified")
+        );
     }
 
     #[test]
@@ -0,0 +1,124 @@
+//! Error types for the CodeGraph library
+
+use std::path::PathBuf;
+
+/// Errors returned by the CodeGraph library
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A file or directory could not be read or written
+    #[error("I/O error on {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Source code could not be parsed
+    #[error("Failed to parse {path:?}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    /// A database operation failed
+    #[cfg(feature = "service")]
+    #[error("Database error: {0}")]
+    Db(#[from] rusqlite::Error),
+
+    /// A database file could not be opened
+    #[cfg(feature = "service")]
+    #[error("Failed to open database {path:?}: {source}")]
+    DbOpen {
+        path: PathBuf,
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    /// A tree-sitter extraction query failed to compile
+    #[error("Invalid extraction query: {0}")]
+    Query(String),
+
+    /// No language support is registered for the requested language
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+
+    /// A file was larger than the configured size limit
+    #[error("File {path:?} is {size} bytes, exceeding the {limit} byte limit")]
+    FileTooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+
+    /// Tree-sitter did not finish parsing a file within the time budget
+    #[error("Parsing {path:?} took longer than the {limit_ms} ms limit")]
+    ParseTimeout { path: PathBuf, limit_ms: u64 },
+
+    /// A file's syntax tree has more nodes than extraction will walk
+    #[error("File {path:?} has {count} syntax nodes, exceeding the {limit} node limit")]
+    TooManySyntaxNodes {
+        path: PathBuf,
+        count: usize,
+        limit: usize,
+    },
+
+    /// A file's syntax tree nests deeper than extraction will recurse
+    #[error("File {path:?} nests syntax more than {limit} levels deep")]
+    SyntaxTooDeep { path: PathBuf, limit: usize },
+
+    /// The requested symbol does not exist in the project
+    #[error("Symbol not found: {0}")]
+    SymbolNotFound(String),
+
+    /// The requested project does not exist
+    #[error("Project not found: {0}")]
+    ProjectNotFound(String),
+
+    /// Another project already uses the requested name
+    #[error("Project already exists: {0}")]
+    ProjectExists(String),
+
+    /// A git command failed
+    #[error("Git error: {0}")]
+    Git(String),
+
+    /// A configuration file could not be loaded or saved
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    /// The HTTP server could not be started
+    #[error("Server error: {0}")]
+    Server(String),
+}
+
+impl Error {
+    /// Build an I/O error for the given path
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Whether a file was refused for exceeding a size, time, node or depth
+    /// budget, rather than failing to parse
+    pub fn is_budget_exceeded(&self) -> bool {
+        matches!(
+            self,
+            Error::FileTooLarge { .. }
+                | Error::ParseTimeout { .. }
+                | Error::TooManySyntaxNodes { .. }
+                | Error::SyntaxTooDeep { .. }
+        )
+    }
+
+    /// Whether the database was locked by another connection past the busy
+    /// timeout, so the write may succeed if tried again
+    #[cfg(feature = "service")]
+    pub fn is_busy(&self) -> bool {
+        matches!(
+            self,
+            Error::Db(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::DatabaseBusy
+        )
+    }
+}
+
+/// Result type used throughout the CodeGraph library
+pub type Result<T> = std::result::Result<T, Error>;
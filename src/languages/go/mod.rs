@@ -1,9 +1,10 @@
 //! Go language support
 
-use anyhow::Result;
 use tree_sitter::{Node, Tree};
 
-use crate::languages::LanguageSupport;
+use crate::error::Result;
+use crate::languages::scope::Scopes;
+use crate::languages::{LanguageSupport, SymbolKind};
 use crate::storage::models::{EdgeData, NodeData};
 
 /// Go language support implementation
@@ -34,11 +35,75 @@ impl LanguageSupport for GoLanguage {
         tree_sitter_go::LANGUAGE.into()
     }
 
+    fn dialects(&self) -> &[&str] {
+        &[GENERICS_DIALECT]
+    }
+
     fn extract_graph(&self, source: &str, tree: &Tree) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
+        self.extract_graph_for(source, tree, None)
+    }
+
+    fn extract_graph_for(
+        &self,
+        source: &str,
+        tree: &Tree,
+        dialect: Option<&str>,
+    ) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
         let mut extractor = GoGraphExtractor::new(source);
+        extractor.type_parameters = dialect == Some(GENERICS_DIALECT);
         extractor.extract(tree.root_node());
         Ok((extractor.nodes, extractor.edges))
     }
+
+    fn symbol_kind(&self, node_type: &str) -> SymbolKind {
+        match node_type {
+            "struct" | "type" => SymbolKind::Type,
+            _ => SymbolKind::from_node_type(node_type),
+        }
+    }
+
+    fn node_types(&self) -> &[&str] {
+        &[
+            "package",
+            "import",
+            "function",
+            "method",
+            "struct",
+            "interface",
+            "type",
+            "field",
+            "constant",
+            "variable",
+            "parameter",
+            "call",
+            "closure",
+            "reference",
+        ]
+    }
+
+    /// Go marks generated files with a `// Code generated ... DO NOT EDIT.`
+    /// line before the package clause
+    fn is_generated(&self, source: &str) -> bool {
+        source
+            .lines()
+            .take_while(|line| !line.starts_with("package "))
+            .any(|line| {
+                let line = line.trim_end();
+                line.starts_with("// Code generated ") && line.ends_with(" DO NOT EDIT.")
+            })
+    }
+}
+
+/// Dialect that records the type parameters of generic functions and types
+const GENERICS_DIALECT: &str = "generics";
+
+/// What a local name is bound to
+#[derive(Debug, Clone, Default)]
+struct Binding {
+    /// Index of the parameter node, if the name has one
+    param_idx: Option<usize>,
+    /// Named type of the binding, without any pointer
+    type_name: Option<String>,
 }
 
 /// Helper for extracting graph data from Go source
@@ -49,6 +114,9 @@ struct GoGraphExtractor<'a> {
     current_package: Option<String>,
     current_func: Option<usize>,
     current_type: Option<String>,
+    scopes: Scopes<Binding>,
+    /// Record type parameters as `type_parameters` attributes
+    type_parameters: bool,
 }
 
 impl<'a> GoGraphExtractor<'a> {
@@ -60,6 +128,8 @@ impl<'a> GoGraphExtractor<'a> {
             current_package: None,
             current_func: None,
             current_type: None,
+            scopes: Scopes::default(),
+            type_parameters: false,
         }
     }
 
@@ -70,7 +140,56 @@ impl<'a> GoGraphExtractor<'a> {
             "function_declaration" => self.extract_function(node),
             "method_declaration" => self.extract_method(node),
             "type_declaration" => self.extract_type_declaration(node),
+            "const_declaration" => self.extract_const_declaration(node),
+            "var_declaration" if self.current_func.is_none() => {
+                self.extract_package_variables(node);
+                self.extract_children(node);
+            }
             "call_expression" => self.extract_call(node),
+            "func_literal" => self.extract_closure(node),
+            "selector_expression" => self.extract_selector(node, "read"),
+            "assignment_statement" => {
+                if let Some(left) = node.child_by_field_name("left") {
+                    let mut cursor = left.walk();
+                    for target in left.named_children(&mut cursor) {
+                        self.extract_assignment_target(target);
+                    }
+                }
+                if let Some(right) = node.child_by_field_name("right") {
+                    self.extract(right);
+                }
+            }
+            "inc_statement" | "dec_statement" => {
+                let mut cursor = node.walk();
+                for target in node.named_children(&mut cursor) {
+                    self.extract_assignment_target(target);
+                }
+            }
+            // Names declared in an `if`, `for` or `switch` init clause, as in
+            // `if err := run(); err != nil`, are scoped to the statement
+            "block"
+            | "if_statement"
+            | "for_statement"
+            | "expression_switch_statement"
+            | "type_switch_statement" => {
+                self.scopes.push();
+                self.extract_children(node);
+                self.scopes.pop();
+            }
+            "short_var_declaration" | "range_clause" => {
+                // The right-hand side still sees any outer binding of the name
+                self.extract_children(node);
+                if let Some(left) = node.child_by_field_name("left") {
+                    self.declare_identifiers(left);
+                }
+            }
+            "var_spec" => {
+                self.extract_children(node);
+                let mut cursor = node.walk();
+                for name in node.children_by_field_name("name", &mut cursor) {
+                    self.scopes.declare(self.node_text(name), None);
+                }
+            }
             _ => {
                 // Recurse into children
                 for i in 0..node.child_count() {
@@ -82,12 +201,17 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// Extract the package clause, keeping the comment directly above it as
+    /// `{"doc": "..."}`
     fn extract_package(&mut self, node: Node) {
         for i in 0..node.child_count() {
             if let Some(child) = node.child(i) {
                 if child.kind() == "package_identifier" {
                     let name = self.node_text(child);
                     self.current_package = Some(name.clone());
+                    let attributes = self
+                        .doc_comment(node)
+                        .map(|doc| serde_json::json!({ "doc": doc }).to_string());
                     self.nodes.push(NodeData {
                         node_type: "package".to_string(),
                         name: name.clone(),
@@ -96,7 +220,7 @@ impl<'a> GoGraphExtractor<'a> {
                         start_column: node.start_position().column as u32 + 1,
                         end_line: node.end_position().row as u32 + 1,
                         end_column: node.end_position().column as u32 + 1,
-                        attributes: None,
+                        attributes,
                     });
                     break;
                 }
@@ -104,6 +228,38 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// The comment group directly above `node`, without comment markers
+    ///
+    /// A blank line ends the group, so build constraints and license headers
+    /// set apart from the declaration are not included.
+    fn doc_comment(&self, node: Node) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut next_row = node.start_position().row;
+        let mut sibling = node.prev_sibling();
+        while let Some(comment) = sibling.filter(|s| s.kind() == "comment") {
+            if comment.end_position().row + 1 < next_row {
+                break;
+            }
+            let text = self.node_text(comment);
+            let text = match text.strip_prefix("//") {
+                Some(line) => line.strip_prefix(' ').unwrap_or(line).to_string(),
+                None => text
+                    .trim_start_matches("/*")
+                    .trim_end_matches("*/")
+                    .trim()
+                    .to_string(),
+            };
+            lines.push(text);
+            next_row = comment.start_position().row;
+            sibling = comment.prev_sibling();
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
     fn extract_imports(&mut self, node: Node) {
         for i in 0..node.child_count() {
             if let Some(child) = node.child(i) {
@@ -152,9 +308,11 @@ impl<'a> GoGraphExtractor<'a> {
                 start_column: node.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes: self.function_attributes(node, None),
             });
 
+            self.scopes.push();
+
             // Extract parameters
             if let Some(params) = node.child_by_field_name("parameters") {
                 self.extract_parameters(func_idx, params);
@@ -165,14 +323,11 @@ impl<'a> GoGraphExtractor<'a> {
             self.current_func = Some(func_idx);
 
             if let Some(body) = node.child_by_field_name("body") {
-                for i in 0..body.child_count() {
-                    if let Some(child) = body.child(i) {
-                        self.extract(child);
-                    }
-                }
+                self.extract_children(body);
             }
 
             self.current_func = old_func;
+            self.scopes.pop();
         }
     }
 
@@ -186,7 +341,7 @@ impl<'a> GoGraphExtractor<'a> {
                 .and_then(|r| self.extract_receiver_type(r));
 
             let qualified_name = if let Some(ref recv) = receiver_type {
-                format!("{}.{}", recv, name)
+                GoLanguage.qualify(recv, &name)
             } else {
                 self.qualify_name(&name)
             };
@@ -200,9 +355,15 @@ impl<'a> GoGraphExtractor<'a> {
                 start_column: node.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: receiver_type.map(|t| format!(r#"{{"receiver":"{}"}}"#, t)),
+                attributes: self.function_attributes(node, receiver_type.as_deref()),
             });
 
+            // The receiver has no node, but its type resolves method calls on it
+            self.scopes.push();
+            if let Some(receiver) = node.child_by_field_name("receiver") {
+                self.declare_receiver(receiver);
+            }
+
             // Extract parameters
             if let Some(params) = node.child_by_field_name("parameters") {
                 self.extract_parameters(method_idx, params);
@@ -213,14 +374,11 @@ impl<'a> GoGraphExtractor<'a> {
             self.current_func = Some(method_idx);
 
             if let Some(body) = node.child_by_field_name("body") {
-                for i in 0..body.child_count() {
-                    if let Some(child) = body.child(i) {
-                        self.extract(child);
-                    }
-                }
+                self.extract_children(body);
             }
 
             self.current_func = old_func;
+            self.scopes.pop();
         }
     }
 
@@ -279,6 +437,14 @@ impl<'a> GoGraphExtractor<'a> {
                             edge_type: "has_parameter".to_string(),
                             attributes: None,
                         });
+                        let binding = Binding {
+                            param_idx: Some(param_idx),
+                            type_name: param
+                                .child_by_field_name("type")
+                                .and_then(|t| self.named_type(t)),
+                        };
+                        self.scopes
+                            .declare(self.node_text(name_node), Some(binding));
                     }
                 }
             }
@@ -321,7 +487,7 @@ impl<'a> GoGraphExtractor<'a> {
                 start_column: node.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes: self.type_parameter_attributes(node),
             });
 
             // Extract struct fields
@@ -338,6 +504,157 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// Extract the constants of a `const` declaration or group
+    ///
+    /// Within a group, `iota` is the index of each spec, and a spec without
+    /// a value repeats the previous spec's type and expressions. Integer
+    /// expressions over literals and `iota` are evaluated; anything else is
+    /// recorded as written.
+    fn extract_const_declaration(&mut self, node: Node) {
+        let mut inherited: Option<(Option<Node>, Vec<Node>)> = None;
+        let mut cursor = node.walk();
+        let specs: Vec<Node> = node
+            .children(&mut cursor)
+            .filter(|c| c.kind() == "const_spec")
+            .collect();
+
+        for (iota, spec) in specs.into_iter().enumerate() {
+            if let Some(value_list) = spec.child_by_field_name("value") {
+                let mut cursor = value_list.walk();
+                let values = value_list.named_children(&mut cursor).collect();
+                inherited = Some((spec.child_by_field_name("type"), values));
+            }
+
+            let mut cursor = spec.walk();
+            let names: Vec<Node> = spec.children_by_field_name("name", &mut cursor).collect();
+
+            for (position, name_node) in names.into_iter().enumerate() {
+                let name = self.node_text(name_node);
+                if name == "_" {
+                    continue;
+                }
+
+                let mut attributes = serde_json::Map::new();
+                if let Some((type_node, values)) = &inherited {
+                    if let Some(type_node) = type_node {
+                        attributes.insert("type".to_string(), self.node_text(*type_node).into());
+                    }
+                    if let Some(value) = values.get(position) {
+                        let value = match self.eval_const_expr(*value, iota as i64) {
+                            Some(v) => v.to_string(),
+                            None => self.node_text(*value),
+                        };
+                        attributes.insert("value".to_string(), value.into());
+                    }
+                }
+
+                self.nodes.push(NodeData {
+                    node_type: "constant".to_string(),
+                    qualified_name: Some(self.qualify_name(&name)),
+                    name,
+                    start_line: spec.start_position().row as u32 + 1,
+                    start_column: spec.start_position().column as u32 + 1,
+                    end_line: spec.end_position().row as u32 + 1,
+                    end_column: spec.end_position().column as u32 + 1,
+                    attributes: (!attributes.is_empty())
+                        .then(|| serde_json::Value::Object(attributes).to_string()),
+                });
+            }
+        }
+    }
+
+    /// Extract the variables of a package-level `var` declaration or group,
+    /// with their declared type as `{"type": "..."}`
+    ///
+    /// Variables declared inside functions are only tracked as local names.
+    fn extract_package_variables(&mut self, node: Node) {
+        let mut cursor = node.walk();
+        let mut specs = Vec::new();
+        for child in node.named_children(&mut cursor) {
+            match child.kind() {
+                "var_spec" => specs.push(child),
+                "var_spec_list" => {
+                    let mut cursor = child.walk();
+                    specs.extend(
+                        child
+                            .named_children(&mut cursor)
+                            .filter(|c| c.kind() == "var_spec"),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        for spec in specs {
+            let attributes = spec
+                .child_by_field_name("type")
+                .map(|t| serde_json::json!({ "type": self.node_text(t) }).to_string());
+
+            let mut cursor = spec.walk();
+            let names: Vec<Node> = spec.children_by_field_name("name", &mut cursor).collect();
+            for name_node in names {
+                let name = self.node_text(name_node);
+                if name == "_" {
+                    continue;
+                }
+                self.nodes.push(NodeData {
+                    node_type: "variable".to_string(),
+                    qualified_name: Some(self.qualify_name(&name)),
+                    name,
+                    start_line: spec.start_position().row as u32 + 1,
+                    start_column: spec.start_position().column as u32 + 1,
+                    end_line: spec.end_position().row as u32 + 1,
+                    end_column: spec.end_position().column as u32 + 1,
+                    attributes: attributes.clone(),
+                });
+            }
+        }
+    }
+
+    /// Evaluate an integer constant expression built from literals and `iota`
+    fn eval_const_expr(&self, node: Node, iota: i64) -> Option<i64> {
+        match node.kind() {
+            "int_literal" => parse_int_literal(&self.node_text(node)),
+            "iota" => Some(iota),
+            "identifier" if self.node_text(node) == "iota" => Some(iota),
+            "parenthesized_expression" => self.eval_const_expr(node.named_child(0)?, iota),
+            "unary_expression" => {
+                let operand = self.eval_const_expr(node.child_by_field_name("operand")?, iota)?;
+                match self
+                    .node_text(node.child_by_field_name("operator")?)
+                    .as_str()
+                {
+                    "-" => operand.checked_neg(),
+                    "+" => Some(operand),
+                    "^" => Some(!operand),
+                    _ => None,
+                }
+            }
+            "binary_expression" => {
+                let left = self.eval_const_expr(node.child_by_field_name("left")?, iota)?;
+                let right = self.eval_const_expr(node.child_by_field_name("right")?, iota)?;
+                match self
+                    .node_text(node.child_by_field_name("operator")?)
+                    .as_str()
+                {
+                    "+" => left.checked_add(right),
+                    "-" => left.checked_sub(right),
+                    "*" => left.checked_mul(right),
+                    "/" => left.checked_div(right),
+                    "%" => left.checked_rem(right),
+                    "<<" => left.checked_shl(u32::try_from(right).ok()?),
+                    ">>" => left.checked_shr(u32::try_from(right).ok()?),
+                    "&" => Some(left & right),
+                    "|" => Some(left | right),
+                    "^" => Some(left ^ right),
+                    "&^" => Some(left & !right),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn extract_struct_fields(&mut self, struct_idx: usize, node: Node) {
         for i in 0..node.child_count() {
             if let Some(child) = node.child(i) {
@@ -347,6 +664,11 @@ impl<'a> GoGraphExtractor<'a> {
                             if field.kind() == "field_declaration" {
                                 if let Some(name_node) = field.child_by_field_name("name") {
                                     let name = self.node_text(name_node);
+                                    let type_node = field.child_by_field_name("type");
+                                    let attributes = type_node.map(|t| {
+                                        serde_json::json!({ "type": self.node_text(t) }).to_string()
+                                    });
+
                                     let field_idx = self.nodes.len();
                                     self.nodes.push(NodeData {
                                         node_type: "field".to_string(),
@@ -356,7 +678,7 @@ impl<'a> GoGraphExtractor<'a> {
                                         start_column: field.start_position().column as u32 + 1,
                                         end_line: field.end_position().row as u32 + 1,
                                         end_column: field.end_position().column as u32 + 1,
-                                        attributes: None,
+                                        attributes,
                                     });
                                     self.edges.push(EdgeData {
                                         source_idx: struct_idx as u32,
@@ -364,6 +686,12 @@ impl<'a> GoGraphExtractor<'a> {
                                         edge_type: "contains".to_string(),
                                         attributes: None,
                                     });
+
+                                    if let Some(type_node) = type_node {
+                                        self.extract_field_type(field_idx, type_node);
+                                    }
+                                } else if let Some(type_node) = field.child_by_field_name("type") {
+                                    self.extract_embedded_field(struct_idx, field, type_node);
                                 }
                             }
                         }
@@ -373,10 +701,50 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// Extract an embedded field, named after its type as in `s.Logger`
+    fn extract_embedded_field(&mut self, struct_idx: usize, field: Node, type_node: Node) {
+        let name_node = match type_node.kind() {
+            "qualified_type" => type_node.child_by_field_name("name"),
+            "generic_type" => type_node.child_by_field_name("type"),
+            _ => Some(type_node),
+        };
+        let name = self.node_text(name_node.unwrap_or(type_node));
+
+        let mut cursor = field.walk();
+        let pointer = field.children(&mut cursor).any(|c| c.kind() == "*");
+        let type_text = format!(
+            "{}{}",
+            if pointer { "*" } else { "" },
+            self.node_text(type_node)
+        );
+        let attributes = serde_json::json!({ "type": type_text, "embedded": true }).to_string();
+
+        let field_idx = self.nodes.len();
+        self.nodes.push(NodeData {
+            node_type: "field".to_string(),
+            name,
+            qualified_name: None,
+            start_line: field.start_position().row as u32 + 1,
+            start_column: field.start_position().column as u32 + 1,
+            end_line: field.end_position().row as u32 + 1,
+            end_column: field.end_position().column as u32 + 1,
+            attributes: Some(attributes),
+        });
+        self.edges.push(EdgeData {
+            source_idx: struct_idx as u32,
+            target_idx: field_idx as u32,
+            edge_type: "contains".to_string(),
+            attributes: None,
+        });
+        self.extract_field_type(field_idx, type_node);
+    }
+
     fn extract_interface_methods(&mut self, interface_idx: usize, node: Node) {
         for i in 0..node.child_count() {
             if let Some(child) = node.child(i) {
-                if child.kind() == "method_spec" {
+                if let Some(embedded) = embedded_interface(child) {
+                    self.extract_interface_embedding(interface_idx, embedded);
+                } else if matches!(child.kind(), "method_spec" | "method_elem") {
                     if let Some(name_node) = child.child_by_field_name("name") {
                         let name = self.node_text(name_node);
                         let method_idx = self.nodes.len();
@@ -402,20 +770,120 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// Link an interface to a reference node for an interface it embeds
+    fn extract_interface_embedding(&mut self, interface_idx: usize, type_node: Node) {
+        let ref_idx = self.nodes.len();
+        self.nodes.push(NodeData {
+            node_type: "reference".to_string(),
+            name: self.node_text(type_node),
+            qualified_name: None,
+            start_line: type_node.start_position().row as u32 + 1,
+            start_column: type_node.start_position().column as u32 + 1,
+            end_line: type_node.end_position().row as u32 + 1,
+            end_column: type_node.end_position().column as u32 + 1,
+            attributes: None,
+        });
+        self.edges.push(EdgeData {
+            source_idx: interface_idx as u32,
+            target_idx: ref_idx as u32,
+            edge_type: "extends".to_string(),
+            attributes: None,
+        });
+    }
+
+    fn extract_closure(&mut self, node: Node) {
+        let closure_idx = self.nodes.len();
+        self.nodes.push(NodeData {
+            node_type: "closure".to_string(),
+            name: "<closure>".to_string(),
+            qualified_name: None,
+            start_line: node.start_position().row as u32 + 1,
+            start_column: node.start_position().column as u32 + 1,
+            end_line: node.end_position().row as u32 + 1,
+            end_column: node.end_position().column as u32 + 1,
+            attributes: None,
+        });
+
+        // Link closure to the enclosing function
+        if let Some(func_idx) = self.current_func {
+            self.edges.push(EdgeData {
+                source_idx: func_idx as u32,
+                target_idx: closure_idx as u32,
+                edge_type: "contains".to_string(),
+                attributes: None,
+            });
+        }
+
+        // Calls inside the closure belong to the closure, not the enclosing function
+        let old_func = self.current_func.take();
+        self.current_func = Some(closure_idx);
+
+        // Closure parameters shadow outer names but have no nodes of their own
+        self.scopes.push();
+        if let Some(params) = node.child_by_field_name("parameters") {
+            self.declare_parameter_names(params);
+        }
+
+        if let Some(body) = node.child_by_field_name("body") {
+            self.extract_children(body);
+        }
+
+        self.scopes.pop();
+        self.current_func = old_func;
+    }
+
     fn extract_call(&mut self, node: Node) {
-        if let Some(func_node) = node.child_by_field_name("function") {
-            let name = self.node_text(func_node);
-            let call_idx = self.nodes.len();
+        if let Some(func_node) = node
+            .child_by_field_name("function")
+            .filter(|f| f.kind() == "func_literal")
+        {
+            // Immediately invoked func literal: func() { ... }()
+            self.extract_closure(func_node);
+        } else if let Some(func_node) = node.child_by_field_name("function") {
+            // `Map[int](xs)` calls `Map`; a single type argument parses as an index
+            let operand = func_node.child_by_field_name("operand");
+            // A call later in a chain starts at its own name rather than at
+            // the start of the chain
+            let (name, start) = match (func_node.kind(), operand) {
+                ("index_expression", Some(operand)) => (self.node_text(operand), node),
+                // `Stream` in `repo.FindAll().Stream()`, without the earlier calls
+                ("selector_expression", Some(operand))
+                    if !matches!(operand.kind(), "identifier" | "selector_expression") =>
+                {
+                    match func_node.child_by_field_name("field") {
+                        Some(field) => (self.node_text(field), field),
+                        None => (self.node_text(func_node), node),
+                    }
+                }
+                _ => (self.node_text(func_node), node),
+            };
+            // `p.Do()` or `p()` may go through a parameter or receiver `p`
+            let receiver = match func_node.kind() {
+                "selector_expression" => func_node
+                    .child_by_field_name("operand")
+                    .filter(|operand| operand.kind() == "identifier"),
+                "identifier" => Some(func_node),
+                _ => None,
+            };
+            let binding = receiver.and_then(|r| self.scopes.resolve(&self.node_text(r)).cloned());
+
+            // Record the receiver's type so the method can be found in its method set
+            let attributes = binding
+                .as_ref()
+                .and_then(|b| b.type_name.as_ref())
+                .filter(|_| func_node.kind() == "selector_expression")
+                .map(|t| serde_json::json!({ "receiver_type": t }).to_string());
 
+            let call_idx = self.nodes.len();
             self.nodes.push(NodeData {
                 node_type: "call".to_string(),
                 name: name.clone(),
                 qualified_name: None,
-                start_line: node.start_position().row as u32 + 1,
-                start_column: node.start_position().column as u32 + 1,
+                start_line: start.start_position().row as u32 + 1,
+                start_column: start.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes,
             });
 
             // Link call to current function
@@ -427,6 +895,24 @@ impl<'a> GoGraphExtractor<'a> {
                     attributes: None,
                 });
             }
+
+            // Link the call to the parameter it goes through
+            if let Some(target_idx) = binding.and_then(|b| b.param_idx) {
+                self.edges.push(EdgeData {
+                    source_idx: call_idx as u32,
+                    target_idx: target_idx as u32,
+                    edge_type: "resolves_to".to_string(),
+                    attributes: None,
+                });
+            }
+
+            // Calls earlier in a chain, e.g. `FindAll()` in `repo.FindAll().Stream()`
+            let chained = operand
+                .filter(|_| func_node.kind() == "selector_expression")
+                .filter(|operand| operand.kind() != "identifier");
+            if let Some(operand) = chained {
+                self.extract(operand);
+            }
         }
 
         // Recurse into arguments
@@ -439,19 +925,242 @@ impl<'a> GoGraphExtractor<'a> {
         }
     }
 
+    /// Record `x.field` on a receiver, parameter or local of a known type as
+    /// a `read` or `write` of that type's field
+    fn extract_selector(&mut self, node: Node, access: &str) {
+        let operand = node.child_by_field_name("operand");
+        let receiver_type = operand
+            .filter(|operand| operand.kind() == "identifier")
+            .and_then(|operand| self.scopes.resolve(&self.node_text(operand)))
+            .and_then(|binding| binding.type_name.clone());
+        let (Some(receiver_type), Some(field)) = (receiver_type, node.child_by_field_name("field"))
+        else {
+            // `a.b.c` still reads `a.b`
+            if let Some(operand) = operand {
+                self.extract(operand);
+            }
+            return;
+        };
+
+        self.nodes.push(NodeData {
+            node_type: "reference".to_string(),
+            name: self.node_text(field),
+            qualified_name: None,
+            start_line: node.start_position().row as u32 + 1,
+            start_column: node.start_position().column as u32 + 1,
+            end_line: node.end_position().row as u32 + 1,
+            end_column: node.end_position().column as u32 + 1,
+            attributes: Some(
+                serde_json::json!({ "access": access, "receiver_type": receiver_type }).to_string(),
+            ),
+        });
+    }
+
+    /// Extract the left-hand side of an assignment, where a selector is written
+    fn extract_assignment_target(&mut self, target: Node) {
+        if target.kind() == "selector_expression" {
+            self.extract_selector(target, "write");
+        } else {
+            self.extract(target);
+        }
+    }
+
+    /// Link a field to a reference node named after its declared type
+    fn extract_field_type(&mut self, field_idx: usize, type_node: Node) {
+        // `*Server`, `[]Server` and `[10]Server` refer to `Server`
+        let type_node = element_type(type_node);
+        let name = self.node_text(type_node);
+
+        let ref_idx = self.nodes.len();
+        self.nodes.push(NodeData {
+            node_type: "reference".to_string(),
+            name,
+            qualified_name: None,
+            start_line: type_node.start_position().row as u32 + 1,
+            start_column: type_node.start_position().column as u32 + 1,
+            end_line: type_node.end_position().row as u32 + 1,
+            end_column: type_node.end_position().column as u32 + 1,
+            attributes: None,
+        });
+        self.edges.push(EdgeData {
+            source_idx: field_idx as u32,
+            target_idx: ref_idx as u32,
+            edge_type: "has_type".to_string(),
+            attributes: None,
+        });
+    }
+
+    /// `{"type_parameters":[...]}` for a generic declaration, when enabled
+    fn type_parameter_attributes(&self, node: Node) -> Option<String> {
+        let names = self.type_parameter_names(node)?;
+        Some(serde_json::json!({ "type_parameters": names }).to_string())
+    }
+
+    fn type_parameter_names(&self, node: Node) -> Option<Vec<String>> {
+        if !self.type_parameters {
+            return None;
+        }
+        let params = node.child_by_field_name("type_parameters")?;
+
+        let mut names = Vec::new();
+        let mut cursor = params.walk();
+        for declaration in params.named_children(&mut cursor) {
+            let mut cursor = declaration.walk();
+            for name in declaration.children_by_field_name("name", &mut cursor) {
+                names.push(self.node_text(name));
+            }
+        }
+        Some(names)
+    }
+
+    /// Attributes of a function or method: its receiver type, type
+    /// parameters and `signature`
+    fn function_attributes(&self, node: Node, receiver_type: Option<&str>) -> Option<String> {
+        let mut attributes = serde_json::Map::new();
+        if let Some(receiver_type) = receiver_type {
+            attributes.insert("receiver".to_string(), receiver_type.into());
+        }
+        attributes.insert("signature".to_string(), self.signature(node).into());
+        if let Some(names) = self.type_parameter_names(node) {
+            attributes.insert("type_parameters".to_string(), names.into());
+        }
+        Some(serde_json::Value::Object(attributes).to_string())
+    }
+
+    /// The declaration up to its body, e.g. `func (s *Server) Start(port int) error`
+    ///
+    /// Whitespace is collapsed, and a parameter list split over lines loses
+    /// its padding and trailing comma, so it reads as if written on one line.
+    fn signature(&self, node: Node) -> String {
+        let end = node
+            .child_by_field_name("body")
+            .map_or(node.end_byte(), |body| body.start_byte());
+        let text = self.source.get(node.start_byte()..end).unwrap_or_default();
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace("( ", "(")
+            .replace(" )", ")")
+            .replace(",)", ")")
+    }
+
+    fn extract_children(&mut self, node: Node) {
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                self.extract(child);
+            }
+        }
+    }
+
+    /// Declare each identifier in an expression list as a local
+    fn declare_identifiers(&mut self, list: Node) {
+        for i in 0..list.child_count() {
+            if let Some(child) = list.child(i).filter(|c| c.kind() == "identifier") {
+                self.scopes.declare(self.node_text(child), None);
+            }
+        }
+    }
+
+    /// Declare a method's receiver with its type
+    fn declare_receiver(&mut self, receiver: Node) {
+        for i in 0..receiver.child_count() {
+            if let Some(param) = receiver
+                .child(i)
+                .filter(|p| p.kind() == "parameter_declaration")
+            {
+                let binding = Binding {
+                    param_idx: None,
+                    type_name: param
+                        .child_by_field_name("type")
+                        .and_then(|t| self.named_type(t)),
+                };
+                if let Some(name) = param.child_by_field_name("name") {
+                    self.scopes.declare(self.node_text(name), Some(binding));
+                }
+            }
+        }
+    }
+
+    /// Name of a type declared in this package, looking through pointers
+    fn named_type(&self, node: Node) -> Option<String> {
+        match node.kind() {
+            "type_identifier" => Some(self.node_text(node)),
+            "pointer_type" => node.named_child(0).and_then(|t| self.named_type(t)),
+            _ => None,
+        }
+    }
+
+    /// Declare the names in a parameter list without creating nodes
+    fn declare_parameter_names(&mut self, params: Node) {
+        for i in 0..params.child_count() {
+            if let Some(param) = params
+                .child(i)
+                .filter(|p| p.kind() == "parameter_declaration")
+            {
+                let mut cursor = param.walk();
+                for name in param.children_by_field_name("name", &mut cursor) {
+                    self.scopes.declare(self.node_text(name), None);
+                }
+            }
+        }
+    }
+
     fn node_text(&self, node: Node) -> String {
-        self.source[node.byte_range()].to_string()
+        // Out-of-range nodes yield no text rather than panicking
+        self.source
+            .get(node.byte_range())
+            .unwrap_or_default()
+            .to_string()
     }
 
     fn qualify_name(&self, name: &str) -> String {
         if let Some(ref pkg) = self.current_package {
-            format!("{}.{}", pkg, name)
+            GoLanguage.qualify(pkg, name)
         } else {
             name.to_string()
         }
     }
 }
 
+/// The type named by an embedded interface element such as `Reader` or
+/// `io.Reader`; type set constraints like `~int | ~string` are not embeddings
+fn embedded_interface(node: Node) -> Option<Node> {
+    let type_node = match node.kind() {
+        "type_elem" if node.named_child_count() == 1 => node.named_child(0)?,
+        "type_elem" => return None,
+        _ => node,
+    };
+    matches!(type_node.kind(), "type_identifier" | "qualified_type").then_some(type_node)
+}
+
+/// Parse a Go integer literal such as `42`, `0x2A`, `0o52`, `052` or `1_000`
+fn parse_int_literal(text: &str) -> Option<i64> {
+    let digits = text.replace('_', "");
+    let lower = digits.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else if let Some(oct) = lower.strip_prefix("0o") {
+        i64::from_str_radix(oct, 8).ok()
+    } else if lower.len() > 1 && lower.starts_with('0') {
+        i64::from_str_radix(&lower[1..], 8).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// Innermost element of pointer, slice, array, map and channel types
+fn element_type(node: Node) -> Node {
+    let inner = match node.kind() {
+        "pointer_type" | "parenthesized_type" => node.named_child(0),
+        "slice_type" | "array_type" => node.child_by_field_name("element"),
+        "map_type" | "channel_type" => node.child_by_field_name("value"),
+        _ => None,
+    };
+    inner.map_or(node, element_type)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -471,6 +1180,17 @@ mod tests {
         assert_eq!(go.language_id(), "go");
     }
 
+    #[test]
+    fn test_go_is_generated() {
+        let go = GoLanguage::new();
+        assert!(go.is_generated("// Code generated by protoc-gen-go. DO NOT EDIT.\n\npackage pb\n"));
+        assert!(!go.is_generated("// Code generated by hand, edit freely.\npackage main\n"));
+        assert!(!go.is_generated("package main\n"));
+        assert!(!go.is_generated(
+            "package main\n\nconst doc = `\n// Code generated by x. DO NOT EDIT.\n`\n"
+        ));
+    }
+
     #[test]
     fn test_go_language_default() {
         let go = GoLanguage::default();
@@ -501,6 +1221,60 @@ mod tests {
         assert_eq!(pkg.name, "main");
     }
 
+    #[test]
+    fn test_package_doc_comment() {
+        let source = r#"//go:build linux
+
+// Package server handles HTTP requests.
+// It routes them to handlers.
+package server
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let pkg = nodes.iter().find(|n| n.node_type == "package").unwrap();
+        let attributes: serde_json::Value =
+            serde_json::from_str(pkg.attributes.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            attributes["doc"],
+            "Package server handles HTTP requests.\nIt routes them to handlers."
+        );
+
+        let (nodes, _) = parse_go("package main\n");
+        assert_eq!(nodes[0].attributes, None);
+    }
+
+    #[test]
+    fn test_function_signatures() {
+        let source = r#"package server
+
+func NewServer(port int, name string) *Server {
+    return nil
+}
+
+func (s *Server) Start(
+    ctx context.Context,
+) (bool, error) {
+    return false, nil
+}
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let signature = |name: &str| -> serde_json::Value {
+            let node = nodes.iter().find(|n| n.name == name).unwrap();
+            let attributes: serde_json::Value =
+                serde_json::from_str(node.attributes.as_deref().unwrap()).unwrap();
+            attributes["signature"].clone()
+        };
+        assert_eq!(
+            signature("NewServer"),
+            "func NewServer(port int, name string) *Server"
+        );
+        assert_eq!(
+            signature("Start"),
+            "func (s *Server) Start(ctx context.Context) (bool, error)"
+        );
+    }
+
     #[test]
     fn test_extract_import_single() {
         let source = r#"
@@ -627,6 +1401,127 @@ type Config struct {
         assert_eq!(contains_edges.len(), 2);
     }
 
+    #[test]
+    fn test_struct_field_type() {
+        let source = r#"
+package main
+
+type Config struct {
+    Port int
+    Next *Config
+    Ring [10]Config
+    Peers []*Config
+    ByName map[string]Config
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let port_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "field" && n.name == "Port")
+            .unwrap();
+        assert_eq!(
+            nodes[port_idx].attributes.as_deref(),
+            Some(r#"{"type":"int"}"#)
+        );
+
+        let type_edge = edges
+            .iter()
+            .find(|e| e.edge_type == "has_type" && e.source_idx as usize == port_idx)
+            .unwrap();
+        let type_ref = &nodes[type_edge.target_idx as usize];
+        assert_eq!(type_ref.node_type, "reference");
+        assert_eq!(type_ref.name, "int");
+
+        // Pointer types reference the pointee
+        let next = nodes.iter().find(|n| n.name == "Next").unwrap();
+        assert_eq!(next.attributes.as_deref(), Some(r#"{"type":"*Config"}"#));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "reference" && n.name == "Config"));
+
+        // Arrays, slices and map values reference their element type
+        for field in ["Ring", "Peers", "ByName"] {
+            let field_idx = nodes
+                .iter()
+                .position(|n| n.node_type == "field" && n.name == field)
+                .unwrap();
+            let type_edge = edges
+                .iter()
+                .find(|e| e.edge_type == "has_type" && e.source_idx as usize == field_idx)
+                .unwrap();
+            assert_eq!(
+                nodes[type_edge.target_idx as usize].name, "Config",
+                "{field}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_selector_field_access_read_write() {
+        let source = r#"
+package main
+
+type Counter struct {
+    count int
+}
+
+func (c *Counter) Incr(step int) int {
+    c.count = c.count + step
+    c.count++
+    return c.count
+}
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let accesses: Vec<(u32, &str)> = nodes
+            .iter()
+            .filter(|n| n.node_type == "reference" && n.name == "count")
+            .map(|n| {
+                let access = n.attributes.as_deref().unwrap();
+                let kind = if access.contains(r#""access":"write""#) {
+                    "write"
+                } else {
+                    "read"
+                };
+                assert!(access.contains(r#""receiver_type":"Counter""#));
+                (n.start_line, kind)
+            })
+            .collect();
+        assert_eq!(
+            accesses,
+            vec![(9, "write"), (9, "read"), (10, "write"), (11, "read")]
+        );
+    }
+
+    #[test]
+    fn test_extract_interface_embedding() {
+        let source = r#"
+package main
+
+type ReadWriter interface {
+    Reader
+    io.Writer
+    Flush() error
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let iface_idx = nodes.iter().position(|n| n.name == "ReadWriter").unwrap();
+        let embedded: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type == "extends" && e.source_idx as usize == iface_idx)
+            .map(|e| &nodes[e.target_idx as usize])
+            .collect();
+        assert_eq!(embedded.len(), 2);
+        assert!(embedded.iter().all(|n| n.node_type == "reference"));
+        assert!(embedded.iter().any(|n| n.name == "Reader"));
+        assert!(embedded.iter().any(|n| n.name == "io.Writer"));
+        assert!(!nodes
+            .iter()
+            .any(|n| n.node_type == "method" && n.name == "Reader"));
+    }
+
     #[test]
     fn test_extract_interface_methods() {
         let source = r#"
@@ -640,17 +1535,18 @@ type Handler interface {
         let (nodes, edges) = parse_go(source);
 
         // Verify interface is extracted
-        assert!(nodes.iter().any(|n| n.node_type == "interface" && n.name == "Handler"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "interface" && n.name == "Handler"));
 
         // Interface methods may or may not be extracted depending on tree-sitter behavior
         // Just verify we have some method nodes if they exist
-        let interface_methods: Vec<_> = nodes.iter()
-            .filter(|n| n.node_type == "method")
-            .collect();
+        let interface_methods: Vec<_> = nodes.iter().filter(|n| n.node_type == "method").collect();
 
         // If methods are extracted, they should have contains edges
         if !interface_methods.is_empty() {
-            let contains_edges: Vec<_> = edges.iter().filter(|e| e.edge_type == "contains").collect();
+            let contains_edges: Vec<_> =
+                edges.iter().filter(|e| e.edge_type == "contains").collect();
             assert!(!contains_edges.is_empty());
         }
     }
@@ -665,12 +1561,18 @@ func process(input string, count int) {
 "#;
         let (nodes, edges) = parse_go(source);
 
-        let params: Vec<_> = nodes.iter().filter(|n| n.node_type == "parameter").collect();
+        let params: Vec<_> = nodes
+            .iter()
+            .filter(|n| n.node_type == "parameter")
+            .collect();
         assert_eq!(params.len(), 2);
         assert!(params.iter().any(|p| p.name == "input"));
         assert!(params.iter().any(|p| p.name == "count"));
 
-        let param_edges: Vec<_> = edges.iter().filter(|e| e.edge_type == "has_parameter").collect();
+        let param_edges: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type == "has_parameter")
+            .collect();
         assert_eq!(param_edges.len(), 2);
     }
 
@@ -690,13 +1592,60 @@ func main() {
 
         let calls: Vec<_> = nodes.iter().filter(|n| n.node_type == "call").collect();
         assert!(calls.len() >= 2);
-        assert!(calls.iter().any(|c| c.name.contains("Println") || c.name.contains("fmt")));
+        assert!(calls
+            .iter()
+            .any(|c| c.name.contains("Println") || c.name.contains("fmt")));
         assert!(calls.iter().any(|c| c.name == "helper"));
 
         let call_edges: Vec<_> = edges.iter().filter(|e| e.edge_type == "calls").collect();
         assert!(call_edges.len() >= 2);
     }
 
+    #[test]
+    fn test_call_on_parameter_resolves_to_parameter() {
+        let source = r#"
+package main
+
+func handle(conn Conn, logger Logger) {
+    conn.Close()
+    if true {
+        logger := NewLogger()
+        logger.Print("shadowed")
+    }
+    logger.Print("done")
+    fmt.Println("unresolved")
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let idx = |node_type: &str, name: &str, line: u32| {
+            nodes
+                .iter()
+                .position(|n| {
+                    n.node_type == node_type
+                        && n.name == name
+                        && (line == 0 || n.start_line == line)
+                })
+                .unwrap() as u32
+        };
+        let resolves: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type == "resolves_to")
+            .map(|e| (e.source_idx, e.target_idx))
+            .collect();
+
+        assert!(resolves.contains(&(idx("call", "conn.Close", 0), idx("parameter", "conn", 0))));
+        assert!(resolves.contains(&(
+            idx("call", "logger.Print", 10),
+            idx("parameter", "logger", 0)
+        )));
+        // The local declared in the block shadows the parameter
+        assert!(!resolves
+            .iter()
+            .any(|(source, _)| *source == idx("call", "logger.Print", 8)));
+        assert_eq!(resolves.len(), 2);
+    }
+
     #[test]
     fn test_method_receiver_type() {
         let source = r#"
@@ -710,7 +1659,10 @@ func (s *Server) Start() error {
 "#;
         let (nodes, _) = parse_go(source);
 
-        let method = nodes.iter().find(|n| n.node_type == "method" && n.name == "Start").unwrap();
+        let method = nodes
+            .iter()
+            .find(|n| n.node_type == "method" && n.name == "Start")
+            .unwrap();
         assert!(method.attributes.as_ref().unwrap().contains("Server"));
     }
 
@@ -765,6 +1717,129 @@ func main() {
         assert_eq!(calls.len(), 2);
     }
 
+    #[test]
+    fn test_chained_calls() {
+        let source = r#"
+package main
+
+func active(repo Repo) []User {
+    return repo.FindAll().
+        Stream().
+        Collect()
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let func_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "function")
+            .unwrap();
+        let mut calls: Vec<(u32, u32, &str)> = edges
+            .iter()
+            .filter(|e| e.edge_type == "calls" && e.source_idx as usize == func_idx)
+            .map(|e| &nodes[e.target_idx as usize])
+            .map(|n| (n.start_line, n.start_column, n.name.as_str()))
+            .collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![(5, 12, "repo.FindAll"), (6, 9, "Stream"), (7, 9, "Collect")]
+        );
+    }
+
+    #[test]
+    fn test_calls_in_return_and_init_clauses() {
+        let source = r#"
+package main
+
+func serve(srv Server) error {
+    if srv := newServer(); srv != nil {
+        srv.Warm()
+    }
+    for i := count(); i > 0; i-- {
+    }
+    switch mode := detect(); mode {
+    }
+    srv.Listen()
+    return http.ListenAndServe(addr(), nil)
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let func_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "function")
+            .unwrap();
+        let mut calls: Vec<&str> = edges
+            .iter()
+            .filter(|e| e.edge_type == "calls" && e.source_idx as usize == func_idx)
+            .map(|e| nodes[e.target_idx as usize].name.as_str())
+            .collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![
+                "addr",
+                "count",
+                "detect",
+                "http.ListenAndServe",
+                "newServer",
+                "srv.Listen",
+                "srv.Warm",
+            ]
+        );
+
+        // The `srv` declared in the `if` does not outlive it
+        let param_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "parameter")
+            .unwrap();
+        let resolves_to = |name: &str| {
+            let call_idx = nodes.iter().position(|n| n.name == name).unwrap();
+            edges.iter().any(|e| {
+                e.edge_type == "resolves_to"
+                    && e.source_idx as usize == call_idx
+                    && e.target_idx as usize == param_idx
+            })
+        };
+        assert!(!resolves_to("srv.Warm"));
+        assert!(resolves_to("srv.Listen"));
+    }
+
+    #[test]
+    fn test_extract_closure() {
+        let source = r#"
+package main
+
+func main() {
+    func() {
+        helper()
+    }()
+}
+"#;
+        let (nodes, edges) = parse_go(source);
+
+        let main_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "function" && n.name == "main")
+            .unwrap();
+        let closure_idx = nodes.iter().position(|n| n.node_type == "closure").unwrap();
+        let helper_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "call" && n.name == "helper")
+            .unwrap();
+
+        assert!(edges.iter().any(|e| e.edge_type == "contains"
+            && e.source_idx as usize == main_idx
+            && e.target_idx as usize == closure_idx));
+        assert!(edges.iter().any(|e| e.edge_type == "calls"
+            && e.source_idx as usize == closure_idx
+            && e.target_idx as usize == helper_idx));
+        assert!(!edges.iter().any(|e| e.edge_type == "calls"
+            && e.source_idx as usize == main_idx
+            && e.target_idx as usize == helper_idx));
+    }
+
     #[test]
     fn test_empty_struct() {
         let source = r#"
@@ -774,7 +1849,9 @@ type Empty struct {}
 "#;
         let (nodes, edges) = parse_go(source);
 
-        let struc = nodes.iter().find(|n| n.node_type == "struct" && n.name == "Empty");
+        let struc = nodes
+            .iter()
+            .find(|n| n.node_type == "struct" && n.name == "Empty");
         assert!(struc.is_some());
 
         // Empty struct should have no contains edges
@@ -798,6 +1875,105 @@ type Handler func()
         assert!(types.iter().any(|t| t.name == "Handler"));
     }
 
+    #[test]
+    fn test_extract_package_variables() {
+        let source = r#"
+package config
+
+var Debug bool
+
+var (
+    Port    = 8080
+    Name, _ string
+)
+
+func Load() {
+    var local int
+    _ = local
+}
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let variables: Vec<_> = nodes.iter().filter(|n| n.node_type == "variable").collect();
+        let names: Vec<_> = variables.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["Debug", "Port", "Name"]);
+        assert_eq!(
+            variables[0].qualified_name,
+            Some("config.Debug".to_string())
+        );
+        assert_eq!(
+            variables[0].attributes.as_deref(),
+            Some(r#"{"type":"bool"}"#)
+        );
+        assert!(variables[1].attributes.is_none());
+    }
+
+    fn constant_value(nodes: &[NodeData], name: &str) -> serde_json::Value {
+        let constant = nodes
+            .iter()
+            .find(|n| n.node_type == "constant" && n.name == name)
+            .unwrap_or_else(|| panic!("constant {} not extracted", name));
+        serde_json::from_str(constant.attributes.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_extract_constants_with_values() {
+        let source = r#"
+package config
+
+const Timeout = 30
+const Name string = "codegraph"
+const Mask = 0x1F
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let constants: Vec<_> = nodes.iter().filter(|n| n.node_type == "constant").collect();
+        assert_eq!(constants.len(), 3);
+        assert_eq!(
+            constants[0].qualified_name,
+            Some("config.Timeout".to_string())
+        );
+
+        assert_eq!(constant_value(&nodes, "Timeout")["value"], "30");
+        assert_eq!(constant_value(&nodes, "Name")["value"], "\"codegraph\"");
+        assert_eq!(constant_value(&nodes, "Name")["type"], "string");
+        assert_eq!(constant_value(&nodes, "Mask")["value"], "31");
+    }
+
+    #[test]
+    fn test_extract_iota_group() {
+        let source = r#"
+package main
+
+type Weekday int
+
+const (
+    Sunday Weekday = iota
+    Monday
+    _
+    Wednesday
+)
+
+const (
+    _  = iota
+    KB = 1 << (10 * iota)
+    MB
+)
+"#;
+        let (nodes, _) = parse_go(source);
+
+        assert_eq!(constant_value(&nodes, "Sunday")["value"], "0");
+        assert_eq!(constant_value(&nodes, "Monday")["value"], "1");
+        assert_eq!(constant_value(&nodes, "Wednesday")["value"], "3");
+        assert_eq!(constant_value(&nodes, "Monday")["type"], "Weekday");
+        assert!(!nodes
+            .iter()
+            .any(|n| n.node_type == "constant" && n.name == "_"));
+
+        assert_eq!(constant_value(&nodes, "KB")["value"], "1024");
+        assert_eq!(constant_value(&nodes, "MB")["value"], "1048576");
+    }
+
     #[test]
     fn test_complex_go_file() {
         let source = r#"
@@ -837,19 +2013,143 @@ func main() {
         // Check node types
         assert!(nodes.iter().any(|n| n.node_type == "package"));
         assert_eq!(nodes.iter().filter(|n| n.node_type == "import").count(), 2);
-        assert!(nodes.iter().any(|n| n.node_type == "struct" && n.name == "Server"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "struct" && n.name == "Server"));
         assert_eq!(nodes.iter().filter(|n| n.node_type == "field").count(), 2);
-        assert!(nodes.iter().any(|n| n.node_type == "function" && n.name == "NewServer"));
-        assert!(nodes.iter().any(|n| n.node_type == "function" && n.name == "main"));
-        assert!(nodes.iter().any(|n| n.node_type == "method" && n.name == "Start"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "function" && n.name == "NewServer"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "function" && n.name == "main"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "method" && n.name == "Start"));
 
         // Check calls
         let calls: Vec<_> = nodes.iter().filter(|n| n.node_type == "call").collect();
         assert!(calls.len() > 0);
-        assert!(calls.iter().any(|c| c.name.contains("Printf") || c.name.contains("fmt")));
+        assert!(calls
+            .iter()
+            .any(|c| c.name.contains("Printf") || c.name.contains("fmt")));
         assert!(calls.iter().any(|c| c.name == "NewServer"));
 
         // Check edges exist
         assert!(!edges.is_empty());
     }
+
+    const GENERIC_SOURCE: &str = r#"
+package main
+
+type Stack[T any] struct {
+    items []T
+}
+
+func Map[T, U any](xs []T, f func(T) U) []U {
+    out := make([]U, 0, len(xs))
+    for _, x := range xs {
+        out = append(out, f(x))
+    }
+    return out
+}
+
+func main() {
+    Map[int]([]int{1}, double)
+}
+"#;
+
+    #[test]
+    fn test_generic_function() {
+        let (nodes, edges) = parse_go(GENERIC_SOURCE);
+
+        let map = nodes
+            .iter()
+            .position(|n| n.node_type == "function" && n.name == "Map")
+            .unwrap();
+        // Type parameters are only recorded by the generics dialect
+        assert!(!nodes[map]
+            .attributes
+            .as_deref()
+            .unwrap_or_default()
+            .contains("type_parameters"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "struct" && n.name == "Stack"));
+
+        let params: Vec<_> = edges
+            .iter()
+            .filter(|e| e.source_idx as usize == map && e.edge_type == "has_parameter")
+            .map(|e| nodes[e.target_idx as usize].name.as_str())
+            .collect();
+        assert_eq!(params, vec!["xs", "f"]);
+
+        // An explicit instantiation still calls the generic function
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "call" && n.name == "Map"));
+    }
+
+    #[test]
+    fn test_generics_dialect_records_type_parameters() {
+        let go = GoLanguage::new();
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&go.grammar_for(Some("generics")).unwrap())
+            .unwrap();
+        let tree = parser.parse(GENERIC_SOURCE, None).unwrap();
+        let (nodes, _) = go
+            .extract_graph_for(GENERIC_SOURCE, &tree, Some("generics"))
+            .unwrap();
+
+        let type_parameters = |name: &str| -> serde_json::Value {
+            let node = nodes.iter().find(|n| n.name == name).unwrap();
+            serde_json::from_str(node.attributes.as_deref().unwrap()).unwrap()
+        };
+        assert_eq!(
+            type_parameters("Map")["type_parameters"],
+            serde_json::json!(["T", "U"])
+        );
+        assert_eq!(
+            type_parameters("Stack")["type_parameters"],
+            serde_json::json!(["T"])
+        );
+
+        assert!(go.grammar_for(Some("go2")).is_err());
+    }
+
+    #[test]
+    fn test_embedded_field_and_receiver_type() {
+        let source = r#"
+package main
+
+type Server struct {
+    *Logger
+    port int
+}
+
+func (s *Server) Start() {
+    s.Log("start")
+}
+"#;
+        let (nodes, _) = parse_go(source);
+
+        let embedded = nodes
+            .iter()
+            .find(|n| n.node_type == "field" && n.name == "Logger")
+            .unwrap();
+        let attributes: serde_json::Value =
+            serde_json::from_str(embedded.attributes.as_deref().unwrap()).unwrap();
+        assert_eq!(attributes["type"], "*Logger");
+        assert_eq!(attributes["embedded"], true);
+
+        let call = nodes
+            .iter()
+            .find(|n| n.node_type == "call" && n.name == "s.Log")
+            .unwrap();
+        assert_eq!(
+            call.attributes.as_deref(),
+            Some(r#"{"receiver_type":"Server"}"#)
+        );
+    }
 }
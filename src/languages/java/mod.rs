@@ -1,9 +1,12 @@
 //! Java language support
 
-use anyhow::Result;
+use std::collections::HashMap;
+
 use tree_sitter::{Node, Tree};
 
-use crate::languages::LanguageSupport;
+use crate::error::Result;
+use crate::languages::scope::Scopes;
+use crate::languages::{LanguageSupport, SymbolKind};
 use crate::storage::models::{EdgeData, NodeData};
 
 /// Java language support implementation
@@ -39,6 +42,137 @@ impl LanguageSupport for JavaLanguage {
         extractor.extract(tree.root_node());
         Ok((extractor.nodes, extractor.edges))
     }
+
+    fn symbol_kind(&self, node_type: &str) -> SymbolKind {
+        match node_type {
+            "class" | "enum" => SymbolKind::Type,
+            "enum_constant" => SymbolKind::Constant,
+            _ => SymbolKind::from_node_type(node_type),
+        }
+    }
+
+    fn node_types(&self) -> &[&str] {
+        &[
+            "package",
+            "import",
+            "class",
+            "interface",
+            "enum",
+            "enum_constant",
+            "method",
+            "constructor",
+            "field",
+            "parameter",
+            "call",
+            "closure",
+            "reference",
+        ]
+    }
+
+    /// Generated classes carry a `@Generated` annotation from
+    /// [`GENERATED_ANNOTATIONS`], written out in full or imported
+    ///
+    /// Other annotations named `Generated`, such as Lombok's, do not count.
+    fn is_generated(&self, source: &str) -> bool {
+        let mut parser = tree_sitter::Parser::new();
+        if parser.set_language(&self.grammar()).is_err() {
+            return false;
+        }
+        let Some(tree) = parser.parse(source, None) else {
+            return false;
+        };
+
+        let text = |node: Node| node.utf8_text(source.as_bytes()).unwrap_or_default();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        let items: Vec<Node> = root.named_children(&mut cursor).collect();
+
+        // What a plain `@Generated` refers to: a single-type import wins
+        // over a wildcard one
+        let mut single_import = None;
+        let mut wildcard_packages = Vec::new();
+        for import in items
+            .iter()
+            .filter(|item| item.kind() == "import_declaration")
+        {
+            let mut cursor = import.walk();
+            if import.children(&mut cursor).any(|c| c.kind() == "static") {
+                continue;
+            }
+            let mut cursor = import.walk();
+            let Some(path) = import
+                .named_children(&mut cursor)
+                .find(|c| matches!(c.kind(), "scoped_identifier" | "identifier"))
+            else {
+                continue;
+            };
+            let path = text(path);
+            let mut cursor = import.walk();
+            if import.children(&mut cursor).any(|c| c.kind() == "asterisk") {
+                wildcard_packages.push(path);
+            } else if path.rsplit('.').next() == Some("Generated") {
+                single_import = Some(path);
+            }
+        }
+        let is_generated_annotation = |name: &str| {
+            if name != "Generated" {
+                return GENERATED_ANNOTATIONS.contains(&name);
+            }
+            match single_import {
+                Some(import) => GENERATED_ANNOTATIONS.contains(&import),
+                None => wildcard_packages.iter().any(|package| {
+                    GENERATED_ANNOTATIONS.contains(&format!("{}.Generated", package).as_str())
+                }),
+            }
+        };
+
+        items
+            .iter()
+            .filter(|item| TYPE_DECLARATIONS.contains(&item.kind()))
+            .filter_map(|declaration| {
+                let mut cursor = declaration.walk();
+                let modifiers = declaration
+                    .children(&mut cursor)
+                    .find(|c| c.kind() == "modifiers");
+                modifiers
+            })
+            .any(|modifiers| {
+                let mut cursor = modifiers.walk();
+                let annotations: Vec<Node> = modifiers.named_children(&mut cursor).collect();
+                annotations
+                    .into_iter()
+                    .filter(|a| matches!(a.kind(), "marker_annotation" | "annotation"))
+                    .filter_map(|a| a.child_by_field_name("name"))
+                    .any(|name| is_generated_annotation(text(name)))
+            })
+    }
+}
+
+/// Annotations that mark a Java type as generated
+const GENERATED_ANNOTATIONS: &[&str] = &[
+    "javax.annotation.Generated",
+    "javax.annotation.processing.Generated",
+    "jakarta.annotation.Generated",
+];
+
+/// Top-level Java type declarations
+const TYPE_DECLARATIONS: &[&str] = &[
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+    "annotation_type_declaration",
+];
+
+/// Fields of a class being extracted, and uses of them seen so far
+///
+/// A field may be declared after the methods that use it, so uses are only
+/// turned into nodes once the whole class body has been read.
+#[derive(Default)]
+struct ClassFields {
+    fields: HashMap<String, usize>,
+    /// Reference nodes for possible field uses, not yet pushed
+    accesses: Vec<NodeData>,
 }
 
 /// Helper for extracting graph data from Java source
@@ -48,6 +182,9 @@ struct JavaGraphExtractor<'a> {
     edges: Vec<EdgeData>,
     current_class: Option<String>,
     current_method: Option<usize>,
+    scopes: Scopes,
+    /// Innermost class last
+    class_fields: Vec<ClassFields>,
 }
 
 impl<'a> JavaGraphExtractor<'a> {
@@ -58,6 +195,8 @@ impl<'a> JavaGraphExtractor<'a> {
             edges: Vec::new(),
             current_class: None,
             current_method: None,
+            scopes: Scopes::default(),
+            class_fields: Vec::new(),
         }
     }
 
@@ -67,10 +206,48 @@ impl<'a> JavaGraphExtractor<'a> {
             "import_declaration" => self.extract_import(node),
             "class_declaration" => self.extract_class(node),
             "interface_declaration" => self.extract_interface(node),
+            "enum_declaration" => self.extract_enum(node),
             "method_declaration" => self.extract_method(node),
             "constructor_declaration" => self.extract_constructor(node),
             "field_declaration" => self.extract_field(node),
             "method_invocation" => self.extract_method_invocation(node),
+            "lambda_expression" => self.extract_lambda(node),
+            "identifier" | "field_access" => self.extract_field_access(node, "read"),
+            "assignment_expression" => {
+                if let Some(left) = node.child_by_field_name("left") {
+                    self.extract_field_access(left, "write");
+                }
+                if let Some(right) = node.child_by_field_name("right") {
+                    self.extract(right);
+                }
+            }
+            "update_expression" => {
+                let mut cursor = node.walk();
+                for operand in node.named_children(&mut cursor) {
+                    self.extract_field_access(operand, "write");
+                }
+            }
+            "variable_declarator" => {
+                // The declared name is not a use of a field
+                if let Some(value) = node.child_by_field_name("value") {
+                    self.extract(value);
+                }
+            }
+            "block" => {
+                self.scopes.push();
+                self.extract_children(node);
+                self.scopes.pop();
+            }
+            "local_variable_declaration" => {
+                // Initializers still see any outer binding of the name
+                self.extract_children(node);
+                let mut cursor = node.walk();
+                for declarator in node.children_by_field_name("declarator", &mut cursor) {
+                    if let Some(name_node) = declarator.child_by_field_name("name") {
+                        self.scopes.declare(self.node_text(name_node), None);
+                    }
+                }
+            }
             _ => {
                 // Recurse into children
                 for i in 0..node.child_count() {
@@ -98,33 +275,68 @@ impl<'a> JavaGraphExtractor<'a> {
         }
     }
 
+    /// Extract an import, tagged `{"static":true}` and/or `{"wildcard":true}`
+    ///
+    /// For a wildcard import the name is the imported package (or class,
+    /// for `import static`) without the trailing `.*`.
     fn extract_import(&mut self, node: Node) {
-        // Find the scoped identifier or identifier
+        let mut name = None;
+        let mut is_static = false;
+        let mut is_wildcard = false;
         for i in 0..node.child_count() {
             if let Some(child) = node.child(i) {
-                if child.kind() == "scoped_identifier" || child.kind() == "identifier" {
-                    let name = self.node_text(child);
-                    self.nodes.push(NodeData {
-                        node_type: "import".to_string(),
-                        name: name.clone(),
-                        qualified_name: Some(name),
-                        start_line: node.start_position().row as u32 + 1,
-                        start_column: node.start_position().column as u32 + 1,
-                        end_line: node.end_position().row as u32 + 1,
-                        end_column: node.end_position().column as u32 + 1,
-                        attributes: None,
-                    });
-                    break;
+                match child.kind() {
+                    "scoped_identifier" | "identifier" if name.is_none() => {
+                        name = Some(self.node_text(child));
+                    }
+                    "static" => is_static = true,
+                    "asterisk" => is_wildcard = true,
+                    _ => {}
                 }
             }
         }
+        let Some(name) = name else {
+            return;
+        };
+
+        let mut flags = serde_json::Map::new();
+        if is_static {
+            flags.insert("static".to_string(), serde_json::Value::Bool(true));
+        }
+        if is_wildcard {
+            flags.insert("wildcard".to_string(), serde_json::Value::Bool(true));
+        }
+        let attributes = (!flags.is_empty()).then(|| serde_json::Value::Object(flags).to_string());
+
+        self.nodes.push(NodeData {
+            node_type: "import".to_string(),
+            name: name.clone(),
+            qualified_name: Some(name),
+            start_line: node.start_position().row as u32 + 1,
+            start_column: node.start_position().column as u32 + 1,
+            end_line: node.end_position().row as u32 + 1,
+            end_column: node.end_position().column as u32 + 1,
+            attributes,
+        });
     }
 
+    /// Extract a class, qualified by every class it is nested in
+    ///
+    /// A static nested class is tagged `{"static":true}`, so it can be told
+    /// apart from an inner class, which belongs to an instance of the outer
+    /// one. Classes declared in an interface are implicitly static.
     fn extract_class(&mut self, node: Node) {
         if let Some(name_node) = node.child_by_field_name("name") {
             let name = self.node_text(name_node);
             let qualified_name = self.qualify_name(&name);
 
+            let is_static = self.current_class.is_some()
+                && (has_modifier(node, "static")
+                    || node
+                        .parent()
+                        .is_some_and(|parent| parent.kind() == "interface_body"));
+            let attributes = is_static.then(|| serde_json::json!({ "static": true }).to_string());
+
             let class_idx = self.nodes.len();
             self.nodes.push(NodeData {
                 node_type: "class".to_string(),
@@ -134,7 +346,7 @@ impl<'a> JavaGraphExtractor<'a> {
                 start_column: node.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes,
             });
 
             // Check for superclass
@@ -167,6 +379,7 @@ impl<'a> JavaGraphExtractor<'a> {
             // Process body
             let old_class = self.current_class.take();
             self.current_class = Some(qualified_name);
+            self.class_fields.push(ClassFields::default());
 
             if let Some(body) = node.child_by_field_name("body") {
                 for i in 0..body.child_count() {
@@ -176,6 +389,7 @@ impl<'a> JavaGraphExtractor<'a> {
                 }
             }
 
+            self.link_field_accesses();
             self.current_class = old_class;
         }
     }
@@ -212,6 +426,82 @@ impl<'a> JavaGraphExtractor<'a> {
         }
     }
 
+    fn extract_enum(&mut self, node: Node) {
+        if let Some(name_node) = node.child_by_field_name("name") {
+            let name = self.node_text(name_node);
+            let qualified_name = self.qualify_name(&name);
+
+            let enum_idx = self.nodes.len();
+            self.nodes.push(NodeData {
+                node_type: "enum".to_string(),
+                name: name.clone(),
+                qualified_name: Some(qualified_name.clone()),
+                start_line: node.start_position().row as u32 + 1,
+                start_column: node.start_position().column as u32 + 1,
+                end_line: node.end_position().row as u32 + 1,
+                end_column: node.end_position().column as u32 + 1,
+                attributes: None,
+            });
+
+            if let Some(interfaces) = node.child_by_field_name("interfaces") {
+                self.extract_implements(enum_idx, interfaces);
+            }
+
+            // Process body: constants first, then any fields and methods
+            let old_class = self.current_class.take();
+            self.current_class = Some(qualified_name);
+            self.class_fields.push(ClassFields::default());
+
+            if let Some(body) = node.child_by_field_name("body") {
+                for i in 0..body.child_count() {
+                    if let Some(child) = body.child(i) {
+                        if child.kind() == "enum_constant" {
+                            self.extract_enum_constant(enum_idx, child);
+                        } else {
+                            self.extract(child);
+                        }
+                    }
+                }
+            }
+
+            self.link_field_accesses();
+            self.current_class = old_class;
+        }
+    }
+
+    fn extract_enum_constant(&mut self, enum_idx: usize, node: Node) {
+        if let Some(name_node) = node.child_by_field_name("name") {
+            let name = self.node_text(name_node);
+            let qualified_name = self.qualify_name(&name);
+
+            let constant_idx = self.nodes.len();
+            self.nodes.push(NodeData {
+                node_type: "enum_constant".to_string(),
+                name,
+                qualified_name: Some(qualified_name),
+                start_line: node.start_position().row as u32 + 1,
+                start_column: node.start_position().column as u32 + 1,
+                end_line: node.end_position().row as u32 + 1,
+                end_column: node.end_position().column as u32 + 1,
+                attributes: None,
+            });
+            self.edges.push(EdgeData {
+                source_idx: enum_idx as u32,
+                target_idx: constant_idx as u32,
+                edge_type: "contains".to_string(),
+                attributes: None,
+            });
+
+            // Constructor arguments and constant-specific bodies can hold calls
+            if let Some(arguments) = node.child_by_field_name("arguments") {
+                self.extract(arguments);
+            }
+            if let Some(body) = node.child_by_field_name("body") {
+                self.extract(body);
+            }
+        }
+    }
+
     fn extract_implements(&mut self, class_idx: usize, interfaces: Node) {
         for i in 0..interfaces.child_count() {
             if let Some(child) = interfaces.child(i) {
@@ -244,6 +534,18 @@ impl<'a> JavaGraphExtractor<'a> {
             let name = self.node_text(name_node);
             let qualified_name = self.qualify_method_name(&name);
 
+            // Interface and abstract methods have no body
+            let mut attributes = serde_json::Map::new();
+            if node.child_by_field_name("body").is_none() {
+                attributes.insert("abstract".to_string(), serde_json::Value::Bool(true));
+            }
+            // The parameter list tells overloads apart when resolving calls
+            if let Some(params) = node.child_by_field_name("parameters") {
+                self.describe_parameters(params, &mut attributes);
+            }
+            attributes.insert("signature".to_string(), self.method_signature(node).into());
+            let attributes = Some(serde_json::Value::Object(attributes).to_string());
+
             let method_idx = self.nodes.len();
             self.nodes.push(NodeData {
                 node_type: "method".to_string(),
@@ -253,9 +555,11 @@ impl<'a> JavaGraphExtractor<'a> {
                 start_column: node.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes,
             });
 
+            self.scopes.push();
+
             // Extract parameters
             if let Some(params) = node.child_by_field_name("parameters") {
                 self.extract_parameters(method_idx, params);
@@ -266,14 +570,11 @@ impl<'a> JavaGraphExtractor<'a> {
             self.current_method = Some(method_idx);
 
             if let Some(body) = node.child_by_field_name("body") {
-                for i in 0..body.child_count() {
-                    if let Some(child) = body.child(i) {
-                        self.extract(child);
-                    }
-                }
+                self.extract_children(body);
             }
 
             self.current_method = old_method;
+            self.scopes.pop();
         }
     }
 
@@ -294,19 +595,22 @@ impl<'a> JavaGraphExtractor<'a> {
                 attributes: None,
             });
 
+            // Constructor parameters have no nodes but shadow fields of the same name
+            self.scopes.push();
+            if let Some(params) = node.child_by_field_name("parameters") {
+                self.declare_parameter_names(params);
+            }
+
             // Process body
             let old_method = self.current_method.take();
             self.current_method = Some(method_idx);
 
             if let Some(body) = node.child_by_field_name("body") {
-                for i in 0..body.child_count() {
-                    if let Some(child) = body.child(i) {
-                        self.extract(child);
-                    }
-                }
+                self.extract_children(body);
             }
 
             self.current_method = old_method;
+            self.scopes.pop();
         }
     }
 
@@ -333,16 +637,84 @@ impl<'a> JavaGraphExtractor<'a> {
                             edge_type: "has_parameter".to_string(),
                             attributes: None,
                         });
+                        self.scopes
+                            .declare(self.node_text(name_node), Some(param_idx));
                     }
                 }
             }
         }
     }
 
+    /// The method header without annotations, throws clause or body, e.g.
+    /// `public Optional<User> findById(Long id)`
+    fn method_signature(&self, node: Node) -> String {
+        let mut parts = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "modifiers" => {
+                    let mut inner = child.walk();
+                    parts.extend(
+                        child
+                            .children(&mut inner)
+                            .filter(|m| !m.kind().ends_with("annotation"))
+                            .map(|m| self.node_text(m)),
+                    );
+                }
+                "formal_parameters" => {
+                    if let Some(name) = parts.pop() {
+                        parts.push(format!("{}{}", name, self.node_text(child)));
+                    }
+                }
+                "throws" | "block" | ";" => break,
+                _ => parts.push(self.node_text(child)),
+            }
+        }
+        parts
+            .join(" ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Record a parameter list's arity and whether it ends in varargs
+    fn describe_parameters(
+        &self,
+        params: Node,
+        attributes: &mut serde_json::Map<String, serde_json::Value>,
+    ) {
+        let mut arity = 0;
+        let mut varargs = false;
+        let mut cursor = params.walk();
+        for param in params.named_children(&mut cursor) {
+            match param.kind() {
+                "formal_parameter" => arity += 1,
+                "spread_parameter" => {
+                    arity += 1;
+                    varargs = true;
+                }
+                _ => {}
+            }
+        }
+
+        attributes.insert("arity".to_string(), arity.into());
+        if varargs {
+            attributes.insert("varargs".to_string(), serde_json::Value::Bool(true));
+        }
+    }
+
     fn extract_field(&mut self, node: Node) {
         if let Some(declarator) = node.child_by_field_name("declarator") {
             if let Some(name_node) = declarator.child_by_field_name("name") {
                 let name = self.node_text(name_node);
+                let type_node = node.child_by_field_name("type");
+                let attributes =
+                    type_node.map(|t| serde_json::json!({ "type": self.node_text(t) }).to_string());
+
+                let field_idx = self.nodes.len();
+                if let Some(class) = self.class_fields.last_mut() {
+                    class.fields.insert(name.clone(), field_idx);
+                }
                 self.nodes.push(NodeData {
                     node_type: "field".to_string(),
                     name,
@@ -351,8 +723,98 @@ impl<'a> JavaGraphExtractor<'a> {
                     start_column: node.start_position().column as u32 + 1,
                     end_line: node.end_position().row as u32 + 1,
                     end_column: node.end_position().column as u32 + 1,
-                    attributes: None,
+                    attributes,
                 });
+
+                // Link the field to a reference named after its type
+                if let Some(type_node) = type_node {
+                    let ref_idx = self.nodes.len();
+                    self.nodes.push(NodeData {
+                        node_type: "reference".to_string(),
+                        name: self.node_text(type_node),
+                        qualified_name: None,
+                        start_line: type_node.start_position().row as u32 + 1,
+                        start_column: type_node.start_position().column as u32 + 1,
+                        end_line: type_node.end_position().row as u32 + 1,
+                        end_column: type_node.end_position().column as u32 + 1,
+                        attributes: None,
+                    });
+                    self.edges.push(EdgeData {
+                        source_idx: field_idx as u32,
+                        target_idx: ref_idx as u32,
+                        edge_type: "has_type".to_string(),
+                        attributes: None,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Record a possible use of a field: a bare name that is not a local,
+    /// or `this.name`; anything else is extracted as usual
+    fn extract_field_access(&mut self, node: Node, access: &str) {
+        let name_node = match node.kind() {
+            "identifier" => Some(node),
+            "field_access" => node
+                .child_by_field_name("object")
+                .filter(|object| object.kind() == "this")
+                .and_then(|_| node.child_by_field_name("field")),
+            _ => None,
+        };
+        let Some(name_node) = name_node else {
+            match node.kind() {
+                "identifier" => {}
+                "field_access" => {
+                    if let Some(object) = node.child_by_field_name("object") {
+                        self.extract(object);
+                    }
+                }
+                _ => self.extract(node),
+            }
+            return;
+        };
+
+        let name = self.node_text(name_node);
+        if node.kind() == "identifier" && self.scopes.is_declared(&name) {
+            return;
+        }
+        if let Some(class) = self.class_fields.last_mut() {
+            class.accesses.push(NodeData {
+                node_type: "reference".to_string(),
+                name,
+                qualified_name: None,
+                start_line: node.start_position().row as u32 + 1,
+                start_column: node.start_position().column as u32 + 1,
+                end_line: node.end_position().row as u32 + 1,
+                end_column: node.end_position().column as u32 + 1,
+                attributes: Some(serde_json::json!({ "access": access }).to_string()),
+            });
+        }
+    }
+
+    /// Close the innermost class: link uses of its fields, and hand other
+    /// names on to the enclosing class, whose fields are also in scope
+    fn link_field_accesses(&mut self) {
+        let Some(class) = self.class_fields.pop() else {
+            return;
+        };
+        for access in class.accesses {
+            match class.fields.get(&access.name) {
+                Some(&field_idx) => {
+                    let ref_idx = self.nodes.len();
+                    self.nodes.push(access);
+                    self.edges.push(EdgeData {
+                        source_idx: ref_idx as u32,
+                        target_idx: field_idx as u32,
+                        edge_type: "references".to_string(),
+                        attributes: None,
+                    });
+                }
+                None => {
+                    if let Some(outer) = self.class_fields.last_mut() {
+                        outer.accesses.push(access);
+                    }
+                }
             }
         }
     }
@@ -361,16 +823,29 @@ impl<'a> JavaGraphExtractor<'a> {
         if let Some(name_node) = node.child_by_field_name("name") {
             let name = self.node_text(name_node);
             let call_idx = self.nodes.len();
+            let attributes = node
+                .child_by_field_name("arguments")
+                .map(|args| serde_json::json!({ "args": args.named_child_count() }).to_string());
+
+            // A call later in a chain, like `stream()` in `repo.findAll().stream()`,
+            // starts at its own name rather than at the start of the chain
+            let chained = node.child_by_field_name("object").is_some_and(|object| {
+                !matches!(
+                    object.kind(),
+                    "identifier" | "field_access" | "this" | "super"
+                )
+            });
+            let start = if chained { name_node } else { node };
 
             self.nodes.push(NodeData {
                 node_type: "call".to_string(),
                 name: name.clone(),
                 qualified_name: None,
-                start_line: node.start_position().row as u32 + 1,
-                start_column: node.start_position().column as u32 + 1,
+                start_line: start.start_position().row as u32 + 1,
+                start_column: start.start_position().column as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 end_column: node.end_position().column as u32 + 1,
-                attributes: None,
+                attributes,
             });
 
             // Link call to current method
@@ -382,6 +857,25 @@ impl<'a> JavaGraphExtractor<'a> {
                     attributes: None,
                 });
             }
+
+            // Link `p.run()` to the parameter `p` it is invoked on
+            let target_idx = node
+                .child_by_field_name("object")
+                .filter(|object| object.kind() == "identifier")
+                .and_then(|object| self.scopes.resolve(&self.node_text(object)).copied());
+            if let Some(target_idx) = target_idx {
+                self.edges.push(EdgeData {
+                    source_idx: call_idx as u32,
+                    target_idx: target_idx as u32,
+                    edge_type: "resolves_to".to_string(),
+                    attributes: None,
+                });
+            }
+        }
+
+        // Calls earlier in a chain, e.g. `findAll()` in `repo.findAll().stream()`
+        if let Some(object) = node.child_by_field_name("object") {
+            self.extract(object);
         }
 
         // Recurse into arguments
@@ -394,13 +888,87 @@ impl<'a> JavaGraphExtractor<'a> {
         }
     }
 
+    fn extract_lambda(&mut self, node: Node) {
+        let closure_idx = self.nodes.len();
+        self.nodes.push(NodeData {
+            node_type: "closure".to_string(),
+            name: "<closure>".to_string(),
+            qualified_name: None,
+            start_line: node.start_position().row as u32 + 1,
+            start_column: node.start_position().column as u32 + 1,
+            end_line: node.end_position().row as u32 + 1,
+            end_column: node.end_position().column as u32 + 1,
+            attributes: None,
+        });
+
+        // Link lambda to the enclosing method
+        if let Some(method_idx) = self.current_method {
+            self.edges.push(EdgeData {
+                source_idx: method_idx as u32,
+                target_idx: closure_idx as u32,
+                edge_type: "contains".to_string(),
+                attributes: None,
+            });
+        }
+
+        // Calls inside the lambda belong to the lambda, not the enclosing method
+        let old_method = self.current_method.take();
+        self.current_method = Some(closure_idx);
+
+        // Lambda parameters shadow outer names but have no nodes of their own
+        self.scopes.push();
+        if let Some(params) = node.child_by_field_name("parameters") {
+            if params.kind() == "identifier" {
+                self.scopes.declare(self.node_text(params), None);
+            } else {
+                self.declare_parameter_names(params);
+            }
+        }
+
+        if let Some(body) = node.child_by_field_name("body") {
+            self.extract(body);
+        }
+
+        self.scopes.pop();
+        self.current_method = old_method;
+    }
+
+    fn extract_children(&mut self, node: Node) {
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                self.extract(child);
+            }
+        }
+    }
+
+    /// Declare the names in `(a, b)` or `(int a, int b)` without creating nodes
+    fn declare_parameter_names(&mut self, params: Node) {
+        for i in 0..params.child_count() {
+            let Some(param) = params.child(i) else {
+                continue;
+            };
+            let name_node = match param.kind() {
+                "identifier" => Some(param),
+                "formal_parameter" => param.child_by_field_name("name"),
+                _ => None,
+            };
+            if let Some(name_node) = name_node {
+                self.scopes.declare(self.node_text(name_node), None);
+            }
+        }
+    }
+
     fn node_text(&self, node: Node) -> String {
-        self.source[node.byte_range()].to_string()
+        // Out-of-range nodes yield no text rather than panicking
+        self.source
+            .get(node.byte_range())
+            .unwrap_or_default()
+            .to_string()
     }
 
     fn qualify_name(&self, name: &str) -> String {
         if let Some(ref class) = self.current_class {
-            format!("{}.{}", class, name)
+            JavaLanguage.qualify(class, name)
         } else {
             name.to_string()
         }
@@ -408,13 +976,27 @@ impl<'a> JavaGraphExtractor<'a> {
 
     fn qualify_method_name(&self, name: &str) -> String {
         if let Some(ref class) = self.current_class {
-            format!("{}.{}", class, name)
+            JavaLanguage.qualify(class, name)
         } else {
             name.to_string()
         }
     }
 }
 
+/// Whether a declaration's modifiers include `keyword`, e.g. `static`
+fn has_modifier(node: Node, keyword: &str) -> bool {
+    let mut cursor = node.walk();
+    let found = node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "modifiers")
+        .any(|modifiers| {
+            let mut cursor = modifiers.walk();
+            let found = modifiers.children(&mut cursor).any(|m| m.kind() == keyword);
+            found
+        });
+    found
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -434,6 +1016,31 @@ mod tests {
         assert_eq!(java.language_id(), "java");
     }
 
+    #[test]
+    fn test_java_is_generated() {
+        let java = JavaLanguage::new();
+        assert!(java.is_generated(
+            "import javax.annotation.processing.Generated;\n\n@Generated(\"dagger\")\npublic final class A {}\n"
+        ));
+        assert!(java
+            .is_generated("@javax.annotation.processing.Generated(value = \"x\")\nclass B {}\n"));
+        assert!(java.is_generated("import jakarta.annotation.*;\n\n@Generated\nenum C {}\n"));
+        assert!(!java.is_generated("import lombok.Generated;\n\n@Generated\nclass D {}\n"));
+        assert!(!java.is_generated("@lombok.Generated\nclass E {}\n"));
+        assert!(!java.is_generated("@Generated\nclass F {}\n"));
+        assert!(!java
+            .is_generated("@Entity\nclass G {\n    @GeneratedValue\n    private Long id;\n}\n"));
+
+        // The annotation counts wherever the type starts, e.g. below a long
+        // license header
+        let license = "// Licensed under the Apache License.\n".repeat(60);
+        let source = format!(
+            "{}import javax.annotation.Generated;\n\n@Generated(\"x\")\nclass H {{}}\n",
+            license
+        );
+        assert!(java.is_generated(&source));
+    }
+
     #[test]
     fn test_java_language_default() {
         let java = JavaLanguage::default();
@@ -480,6 +1087,39 @@ import java.util.Map;
         assert!(imports.iter().any(|n| n.name.contains("Map")));
     }
 
+    #[test]
+    fn test_extract_import_kinds() {
+        let source = r#"
+import java.util.List;
+import java.util.*;
+import static org.junit.Assert.assertEquals;
+import static java.lang.Math.*;
+"#;
+        let (nodes, _) = parse_java(source);
+
+        let attributes = |name: &str| {
+            nodes
+                .iter()
+                .find(|n| n.node_type == "import" && n.name == name)
+                .unwrap_or_else(|| panic!("import {} missing", name))
+                .attributes
+                .clone()
+        };
+        assert_eq!(attributes("java.util.List"), None);
+        assert_eq!(
+            attributes("java.util").as_deref(),
+            Some(r#"{"wildcard":true}"#)
+        );
+        assert_eq!(
+            attributes("org.junit.Assert.assertEquals").as_deref(),
+            Some(r#"{"static":true}"#)
+        );
+        assert_eq!(
+            attributes("java.lang.Math").as_deref(),
+            Some(r#"{"static":true,"wildcard":true}"#)
+        );
+    }
+
     #[test]
     fn test_extract_class() {
         let source = r#"
@@ -505,6 +1145,62 @@ public interface UserRepository {
         assert_eq!(interface.name, "UserRepository");
     }
 
+    #[test]
+    fn test_extract_enum() {
+        let source = r#"
+package com.example;
+
+public enum Status {
+    ACTIVE("a"),
+    SUSPENDED("s"),
+    DELETED("d");
+
+    private final String code;
+
+    Status(String code) {
+        this.code = code;
+    }
+
+    public String code() {
+        return code;
+    }
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let status = nodes.iter().position(|n| n.node_type == "enum").unwrap();
+        assert_eq!(nodes[status].name, "Status");
+        assert_eq!(nodes[status].qualified_name, Some("Status".to_string()));
+
+        let constants: Vec<_> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.node_type == "enum_constant")
+            .collect();
+        let names: Vec<_> = constants.iter().map(|(_, n)| n.name.as_str()).collect();
+        assert_eq!(names, ["ACTIVE", "SUSPENDED", "DELETED"]);
+        assert_eq!(
+            constants[0].1.qualified_name,
+            Some("Status.ACTIVE".to_string())
+        );
+
+        for (idx, _) in &constants {
+            assert!(edges.iter().any(|e| e.edge_type == "contains"
+                && e.source_idx == status as u32
+                && e.target_idx == *idx as u32));
+        }
+
+        let method = nodes.iter().find(|n| n.node_type == "method").unwrap();
+        assert_eq!(method.name, "code");
+        assert_eq!(method.qualified_name, Some("Status.code".to_string()));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "constructor" && n.name == "Status"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "field" && n.name == "code"));
+    }
+
     #[test]
     fn test_extract_method() {
         let source = r#"
@@ -525,6 +1221,32 @@ public class Service {
         assert!(methods.iter().any(|m| m.name == "calculate"));
     }
 
+    #[test]
+    fn test_interface_method_marked_abstract() {
+        let source = r#"
+public interface Handler {
+    void handle();
+}
+
+public class HandlerImpl implements Handler {
+    public void handle() {
+    }
+}
+"#;
+        let (nodes, _) = parse_java(source);
+
+        let methods: Vec<_> = nodes.iter().filter(|n| n.name == "handle").collect();
+        assert_eq!(methods.len(), 2);
+        assert_eq!(
+            methods[0].attributes.as_deref(),
+            Some(r#"{"abstract":true,"arity":0,"signature":"void handle()"}"#)
+        );
+        assert_eq!(
+            methods[1].attributes.as_deref(),
+            Some(r#"{"arity":0,"signature":"public void handle()"}"#)
+        );
+    }
+
     #[test]
     fn test_extract_constructor() {
         let source = r#"
@@ -555,6 +1277,78 @@ public class User {
         assert!(fields.iter().any(|f| f.name == "age"));
     }
 
+    #[test]
+    fn test_field_type() {
+        let source = r#"
+public class UserService {
+    private UserRepository repository;
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let field_idx = nodes.iter().position(|n| n.node_type == "field").unwrap();
+        assert_eq!(nodes[field_idx].name, "repository");
+        assert_eq!(
+            nodes[field_idx].attributes.as_deref(),
+            Some(r#"{"type":"UserRepository"}"#)
+        );
+
+        let type_edge = edges.iter().find(|e| e.edge_type == "has_type").unwrap();
+        assert_eq!(type_edge.source_idx as usize, field_idx);
+        let type_ref = &nodes[type_edge.target_idx as usize];
+        assert_eq!(type_ref.node_type, "reference");
+        assert_eq!(type_ref.name, "UserRepository");
+    }
+
+    #[test]
+    fn test_field_access_read_write() {
+        let source = r#"
+public class Counter {
+    void reset(int count) {
+        this.count = count;
+    }
+
+    int next() {
+        int step = 1;
+        count += step;
+        return count;
+    }
+
+    private int count;
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let field_idx = nodes.iter().position(|n| n.node_type == "field").unwrap();
+        let mut accesses: Vec<(u32, String)> = edges
+            .iter()
+            .filter(|e| e.edge_type == "references" && e.target_idx as usize == field_idx)
+            .map(|e| &nodes[e.source_idx as usize])
+            .map(|n| {
+                let attributes: serde_json::Value =
+                    serde_json::from_str(n.attributes.as_deref().unwrap()).unwrap();
+                (
+                    n.start_line,
+                    attributes["access"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        accesses.sort();
+
+        // The parameter `count` and the local `step` are not field uses
+        assert_eq!(
+            accesses,
+            vec![
+                (4, "write".to_string()),
+                (9, "write".to_string()),
+                (10, "read".to_string()),
+            ]
+        );
+        assert!(!nodes
+            .iter()
+            .any(|n| n.node_type == "reference" && n.name == "step"));
+    }
+
     #[test]
     fn test_extract_method_parameters() {
         let source = r#"
@@ -565,16 +1359,54 @@ public class Service {
 "#;
         let (nodes, edges) = parse_java(source);
 
-        let params: Vec<_> = nodes.iter().filter(|n| n.node_type == "parameter").collect();
+        let params: Vec<_> = nodes
+            .iter()
+            .filter(|n| n.node_type == "parameter")
+            .collect();
         assert_eq!(params.len(), 2);
         assert!(params.iter().any(|p| p.name == "input"));
         assert!(params.iter().any(|p| p.name == "count"));
 
         // Check edges
-        let param_edges: Vec<_> = edges.iter().filter(|e| e.edge_type == "has_parameter").collect();
+        let param_edges: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type == "has_parameter")
+            .collect();
         assert_eq!(param_edges.len(), 2);
     }
 
+    #[test]
+    fn test_method_signature() {
+        let source = r#"
+public class UserRepository {
+    @Override
+    public Optional<User> findById(Long id) throws DataException {
+        return Optional.empty();
+    }
+
+    static <T> List<T> wrap(T item, String... tags) {
+        return null;
+    }
+}
+"#;
+        let (nodes, _) = parse_java(source);
+
+        let signature = |name: &str| -> serde_json::Value {
+            let node = nodes.iter().find(|n| n.name == name).unwrap();
+            let attributes: serde_json::Value =
+                serde_json::from_str(node.attributes.as_deref().unwrap()).unwrap();
+            attributes["signature"].clone()
+        };
+        assert_eq!(
+            signature("findById"),
+            "public Optional<User> findById(Long id)"
+        );
+        assert_eq!(
+            signature("wrap"),
+            "static <T> List<T> wrap(T item, String... tags)"
+        );
+    }
+
     #[test]
     fn test_extract_method_invocation() {
         let source = r#"
@@ -597,6 +1429,121 @@ public class Service {
         assert_eq!(call_edges.len(), 2);
     }
 
+    #[test]
+    fn test_nested_classes_qualified_by_every_outer_class() {
+        let source = r#"
+public class Outer {
+    static class Inner {
+        class Deepest {
+            void run() {}
+        }
+    }
+
+    interface Callback {
+        class Result {}
+    }
+}
+"#;
+        let (nodes, _) = parse_java(source);
+
+        let class = |name: &str| {
+            nodes
+                .iter()
+                .find(|n| n.node_type == "class" && n.name == name)
+                .unwrap()
+        };
+        assert_eq!(class("Outer").qualified_name.as_deref(), Some("Outer"));
+        assert_eq!(
+            class("Inner").qualified_name.as_deref(),
+            Some("Outer.Inner")
+        );
+        assert_eq!(
+            class("Deepest").qualified_name.as_deref(),
+            Some("Outer.Inner.Deepest")
+        );
+        assert_eq!(
+            class("Result").qualified_name.as_deref(),
+            Some("Outer.Callback.Result")
+        );
+
+        let run = nodes.iter().find(|n| n.name == "run").unwrap();
+        assert_eq!(
+            run.qualified_name.as_deref(),
+            Some("Outer.Inner.Deepest.run")
+        );
+
+        // Static nested classes are tagged; inner and top-level classes are not
+        assert_eq!(
+            class("Inner").attributes.as_deref(),
+            Some(r#"{"static":true}"#)
+        );
+        assert_eq!(
+            class("Result").attributes.as_deref(),
+            Some(r#"{"static":true}"#)
+        );
+        assert_eq!(class("Deepest").attributes, None);
+        assert_eq!(class("Outer").attributes, None);
+    }
+
+    #[test]
+    fn test_chained_method_invocations() {
+        let source = r#"
+public class UserService {
+    List<User> active() {
+        return repo.findAll()
+            .stream()
+            .collect(Collectors.toList());
+    }
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let method_idx = nodes.iter().position(|n| n.node_type == "method").unwrap();
+        let mut calls: Vec<(u32, u32, &str)> = edges
+            .iter()
+            .filter(|e| e.edge_type == "calls" && e.source_idx as usize == method_idx)
+            .map(|e| &nodes[e.target_idx as usize])
+            .map(|n| (n.start_line, n.start_column, n.name.as_str()))
+            .collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![
+                (4, 16, "findAll"),
+                (5, 14, "stream"),
+                (6, 14, "collect"),
+                (6, 22, "toList")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invocation_on_parameter_resolves_to_parameter() {
+        let source = r#"
+public class Service {
+    public void execute(Task task) {
+        task.run();
+        Runnable hook = task::run;
+        hook.run();
+        hooks.forEach(h -> h.run());
+    }
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let task_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "parameter" && n.name == "task")
+            .unwrap();
+        let resolves: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type == "resolves_to")
+            .collect();
+        assert_eq!(resolves.len(), 1);
+        assert_eq!(nodes[resolves[0].source_idx as usize].start_line, 4);
+        assert_eq!(resolves[0].target_idx as usize, task_idx);
+    }
+
     #[test]
     fn test_extract_extends() {
         let source = r#"
@@ -679,14 +1626,55 @@ public class Service {
         assert!(calls.iter().any(|c| c.name == "inner"));
     }
 
+    #[test]
+    fn test_extract_lambda() {
+        let source = r#"
+public class Service {
+    public void run() {
+        items.forEach(item -> process(item));
+    }
+}
+"#;
+        let (nodes, edges) = parse_java(source);
+
+        let run_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "method" && n.name == "run")
+            .unwrap();
+        let closure_idx = nodes.iter().position(|n| n.node_type == "closure").unwrap();
+        let for_each_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "call" && n.name == "forEach")
+            .unwrap();
+        let process_idx = nodes
+            .iter()
+            .position(|n| n.node_type == "call" && n.name == "process")
+            .unwrap();
+
+        assert!(edges.iter().any(|e| e.edge_type == "contains"
+            && e.source_idx as usize == run_idx
+            && e.target_idx as usize == closure_idx));
+        assert!(edges.iter().any(|e| e.edge_type == "calls"
+            && e.source_idx as usize == run_idx
+            && e.target_idx as usize == for_each_idx));
+        assert!(edges.iter().any(|e| e.edge_type == "calls"
+            && e.source_idx as usize == closure_idx
+            && e.target_idx as usize == process_idx));
+    }
+
     #[test]
     fn test_empty_class() {
         let source = "public class Empty {}";
         let (nodes, edges) = parse_java(source);
 
-        assert!(nodes.iter().any(|n| n.node_type == "class" && n.name == "Empty"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "class" && n.name == "Empty"));
         // Empty class should have no edges
-        let internal_edges: Vec<_> = edges.iter().filter(|e| e.edge_type != "extends" && e.edge_type != "implements").collect();
+        let internal_edges: Vec<_> = edges
+            .iter()
+            .filter(|e| e.edge_type != "extends" && e.edge_type != "implements")
+            .collect();
         assert!(internal_edges.is_empty());
     }
 
@@ -720,7 +1708,9 @@ public class UserService {
         assert!(!nodes.is_empty());
 
         // Check class is always extracted
-        assert!(nodes.iter().any(|n| n.node_type == "class" && n.name == "UserService"));
+        assert!(nodes
+            .iter()
+            .any(|n| n.node_type == "class" && n.name == "UserService"));
 
         // Check imports are extracted
         assert_eq!(nodes.iter().filter(|n| n.node_type == "import").count(), 2);
@@ -5,14 +5,67 @@
 
 pub mod go;
 pub mod java;
+pub mod query;
+mod scope;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use tree_sitter::Tree;
 
+use crate::error::{Error, Result};
 use crate::storage::models::{EdgeData, NodeData};
 
+pub use query::QueryBasedExtractor;
+
+/// Language-independent kind of a symbol
+///
+/// Raw node types differ between languages (a Go `struct` and a Java `class`
+/// are both types); the kind gives queries a common vocabulary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Package,
+    Import,
+    Type,
+    Interface,
+    Function,
+    Method,
+    Constructor,
+    Field,
+    Constant,
+    Variable,
+    Parameter,
+    Closure,
+    Call,
+    Reference,
+    #[default]
+    Other,
+}
+
+impl SymbolKind {
+    /// Map node types shared by all languages to a kind
+    pub fn from_node_type(node_type: &str) -> Self {
+        match node_type {
+            "package" => SymbolKind::Package,
+            "import" => SymbolKind::Import,
+            "interface" => SymbolKind::Interface,
+            "function" => SymbolKind::Function,
+            "method" => SymbolKind::Method,
+            "constructor" => SymbolKind::Constructor,
+            "field" => SymbolKind::Field,
+            "constant" => SymbolKind::Constant,
+            "variable" => SymbolKind::Variable,
+            "parameter" => SymbolKind::Parameter,
+            "closure" => SymbolKind::Closure,
+            "call" => SymbolKind::Call,
+            "reference" => SymbolKind::Reference,
+            _ => SymbolKind::Other,
+        }
+    }
+}
+
 /// Trait for language support plugins
 pub trait LanguageSupport: Send + Sync {
     /// Get the language identifier (e.g., "java", "go")
@@ -24,13 +77,87 @@ pub trait LanguageSupport: Send + Sync {
     /// Get the tree-sitter grammar
     fn grammar(&self) -> tree_sitter::Language;
 
+    /// Dialects that can be opted into, besides the default
+    fn dialects(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Get the grammar for a dialect, or the default one for `None`
+    fn grammar_for(&self, dialect: Option<&str>) -> Result<tree_sitter::Language> {
+        match dialect {
+            Some(d) if !self.dialects().contains(&d) => Err(Error::Config(format!(
+                "{} has no dialect {:?} (available: {})",
+                self.language_id(),
+                d,
+                self.dialects().join(", ")
+            ))),
+            _ => Ok(self.grammar()),
+        }
+    }
+
     /// Extract graph data from parsed source code
+    ///
+    /// Not called for languages that supply an
+    /// [`extraction_query`](Self::extraction_query).
     fn extract_graph(&self, source: &str, tree: &Tree) -> Result<(Vec<NodeData>, Vec<EdgeData>)>;
+
+    /// A tree-sitter query to extract nodes with instead of walking the tree
+    /// by hand; see [`QueryBasedExtractor`] for the capture naming
+    fn extraction_query(&self) -> Option<&str> {
+        None
+    }
+
+    /// Extract graph data with a dialect's node handling
+    fn extract_graph_for(
+        &self,
+        source: &str,
+        tree: &Tree,
+        _dialect: Option<&str>,
+    ) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
+        self.extract_graph(source, tree)
+    }
+
+    /// Map a node type produced by this language to its canonical kind
+    fn symbol_kind(&self, node_type: &str) -> SymbolKind {
+        SymbolKind::from_node_type(node_type)
+    }
+
+    /// Node types this language can produce
+    fn node_types(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Whether files of this language can be recognized from their content
+    /// rather than only their extension
+    fn sniffs_content(&self) -> bool {
+        false
+    }
+
+    /// Separator between the segments of a qualified name, e.g. `.` in
+    /// `com.example.Foo`; must be one of [`QUALIFIED_NAME_SEPARATORS`] so
+    /// queries can match on a suffix
+    ///
+    /// [`QUALIFIED_NAME_SEPARATORS`]: crate::storage::models::QUALIFIED_NAME_SEPARATORS
+    fn qualified_name_separator(&self) -> &str {
+        "."
+    }
+
+    /// Join `scope` and `name` into a qualified name
+    fn qualify(&self, scope: &str, name: &str) -> String {
+        format!("{}{}{}", scope, self.qualified_name_separator(), name)
+    }
+
+    /// Whether `source`, the content of a file, marks it as generated code
+    fn is_generated(&self, _source: &str) -> bool {
+        false
+    }
 }
 
 /// Registry for managing language support plugins
 pub struct LanguageRegistry {
     languages: Vec<Arc<dyn LanguageSupport>>,
+    /// Extensions registered on top of each language's own, mapped to language IDs
+    extra_extensions: HashMap<String, String>,
 }
 
 impl LanguageRegistry {
@@ -38,6 +165,7 @@ impl LanguageRegistry {
     pub fn new() -> Self {
         let mut registry = Self {
             languages: Vec::new(),
+            extra_extensions: HashMap::new(),
         };
 
         // Register built-in languages
@@ -52,9 +180,26 @@ impl LanguageRegistry {
         self.languages.push(language);
     }
 
+    /// Map an additional file extension (e.g. `.jav` or `.go.tmpl`) to a language
+    pub fn register_extension(&mut self, language_id: &str, extension: &str) -> Result<()> {
+        if self.get(language_id).is_none() {
+            return Err(Error::UnsupportedLanguage(language_id.to_string()));
+        }
+
+        let ext = if extension.starts_with('.') {
+            extension.to_string()
+        } else {
+            format!(".{}", extension)
+        };
+        self.extra_extensions.insert(ext, language_id.to_string());
+        Ok(())
+    }
+
     /// Get language support by ID
     pub fn get(&self, language_id: &str) -> Option<&Arc<dyn LanguageSupport>> {
-        self.languages.iter().find(|l| l.language_id() == language_id)
+        self.languages
+            .iter()
+            .find(|l| l.language_id() == language_id)
     }
 
     /// Get language support by file extension
@@ -68,6 +213,29 @@ impl LanguageRegistry {
         self.languages
             .iter()
             .find(|l| l.file_extensions().contains(&ext.as_str()))
+            .or_else(|| {
+                self.extra_extensions
+                    .get(&ext)
+                    .and_then(|language_id| self.get(language_id))
+            })
+    }
+
+    /// Get language support for a file name
+    ///
+    /// Registered extra extensions may span several dots (`.go.tmpl`); the
+    /// longest one matching the end of the name wins over the plain extension.
+    pub fn get_by_file_name(&self, file_name: &str) -> Option<&Arc<dyn LanguageSupport>> {
+        let extra = self
+            .extra_extensions
+            .iter()
+            .filter(|(ext, _)| file_name.len() > ext.len() && file_name.ends_with(ext.as_str()))
+            .max_by_key(|(ext, _)| ext.len())
+            .and_then(|(_, language_id)| self.get(language_id));
+
+        extra.or_else(|| {
+            let (_, ext) = file_name.rsplit_once('.')?;
+            self.get_by_extension(ext)
+        })
     }
 
     /// List all supported languages
@@ -81,3 +249,122 @@ impl Default for LanguageRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::models::QUALIFIED_NAME_SEPARATORS;
+
+    #[test]
+    fn test_symbol_kind_go_struct_and_java_class_match() {
+        let registry = LanguageRegistry::new();
+        let go = registry.get("go").unwrap();
+        let java = registry.get("java").unwrap();
+
+        assert_eq!(go.symbol_kind("struct"), SymbolKind::Type);
+        assert_eq!(java.symbol_kind("class"), SymbolKind::Type);
+        assert_eq!(go.symbol_kind("struct"), java.symbol_kind("class"));
+        assert_eq!(go.symbol_kind("interface"), java.symbol_kind("interface"));
+    }
+
+    #[test]
+    fn test_register_extension() {
+        let mut registry = LanguageRegistry::new();
+        assert!(registry.get_by_extension(".jav").is_none());
+
+        registry.register_extension("java", ".jav").unwrap();
+        registry.register_extension("go", "go.tmpl").unwrap();
+
+        assert_eq!(
+            registry.get_by_extension("jav").unwrap().language_id(),
+            "java"
+        );
+        assert_eq!(
+            registry.get_by_file_name("Foo.jav").unwrap().language_id(),
+            "java"
+        );
+        assert_eq!(
+            registry
+                .get_by_file_name("main.go.tmpl")
+                .unwrap()
+                .language_id(),
+            "go"
+        );
+        assert_eq!(
+            registry.get_by_file_name("main.go").unwrap().language_id(),
+            "go"
+        );
+        assert!(registry.get_by_file_name("README").is_none());
+    }
+
+    #[test]
+    fn test_register_extension_unknown_language() {
+        let mut registry = LanguageRegistry::new();
+        let result = registry.register_extension("cobol", ".cbl");
+        assert!(matches!(result, Err(Error::UnsupportedLanguage(id)) if id == "cobol"));
+    }
+
+    /// Stands in for a language that separates names with `::`, like Rust
+    struct RustLike;
+
+    impl LanguageSupport for RustLike {
+        fn language_id(&self) -> &str {
+            "rust"
+        }
+
+        fn file_extensions(&self) -> &[&str] {
+            &[".rs"]
+        }
+
+        fn grammar(&self) -> tree_sitter::Language {
+            tree_sitter_go::LANGUAGE.into()
+        }
+
+        fn extract_graph(
+            &self,
+            _source: &str,
+            _tree: &Tree,
+        ) -> Result<(Vec<NodeData>, Vec<EdgeData>)> {
+            Ok((Vec::new(), Vec::new()))
+        }
+
+        fn qualified_name_separator(&self) -> &str {
+            "::"
+        }
+    }
+
+    #[test]
+    fn test_qualified_name_separator() {
+        let mut registry = LanguageRegistry::new();
+        registry.register(Arc::new(RustLike));
+
+        assert_eq!(
+            registry
+                .get("java")
+                .unwrap()
+                .qualify("com.example.Foo", "bar"),
+            "com.example.Foo.bar"
+        );
+        assert_eq!(
+            registry.get("go").unwrap().qualify("main", "main"),
+            "main.main"
+        );
+        assert_eq!(
+            registry
+                .get("rust")
+                .unwrap()
+                .qualify("server::Router", "new"),
+            "server::Router::new"
+        );
+
+        // Suffix queries only understand the listed separators
+        for lang in registry.list_languages() {
+            assert!(QUALIFIED_NAME_SEPARATORS.contains(&lang.qualified_name_separator()));
+        }
+    }
+
+    #[test]
+    fn test_symbol_kind_unknown_type() {
+        assert_eq!(SymbolKind::from_node_type("widget"), SymbolKind::Other);
+    }
+}
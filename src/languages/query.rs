@@ -0,0 +1,142 @@
+//! Tree-sitter query based extraction
+//!
+//! An alternative to hand-written extractors: a language supplies a
+//! tree-sitter query whose capture names encode node types. A capture named
+//! `@<type>.name` produces a node of that type named after the captured text,
+//! and an optional `@<type>.definition` capture in the same pattern sets the
+//! node's span. For example:
+//!
+//! ```text
+//! (function_declaration name: (identifier) @function.name) @function.definition
+//! ```
+
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Query, QueryCursor, Tree};
+
+use crate::error::{Error, Result};
+use crate::storage::models::NodeData;
+
+/// Extracts nodes from a syntax tree using a tree-sitter query
+pub struct QueryBasedExtractor {
+    query: Query,
+}
+
+impl QueryBasedExtractor {
+    /// Compile a query for the given grammar
+    pub fn new(language: &tree_sitter::Language, source: &str) -> Result<Self> {
+        let query = Query::new(language, source).map_err(|e| Error::Query(e.to_string()))?;
+        Ok(Self { query })
+    }
+
+    /// Run the query over a parsed tree and collect the captured nodes
+    pub fn extract(&self, source: &str, tree: &Tree) -> Vec<NodeData> {
+        let names = self.query.capture_names();
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&self.query, tree.root_node(), source.as_bytes());
+        let mut nodes = Vec::new();
+
+        while let Some(m) = matches.next() {
+            for capture in m.captures {
+                let Some(node_type) = names[capture.index as usize].strip_suffix(".name") else {
+                    continue;
+                };
+
+                // Use the definition capture for the span, if the pattern has one
+                let span = m
+                    .captures
+                    .iter()
+                    .find(|c| {
+                        names[c.index as usize].strip_suffix(".definition") == Some(node_type)
+                    })
+                    .map(|c| c.node)
+                    .unwrap_or(capture.node);
+
+                let name = capture
+                    .node
+                    .utf8_text(source.as_bytes())
+                    .unwrap_or_default()
+                    .to_string();
+
+                nodes.push(NodeData {
+                    node_type: node_type.to_string(),
+                    name,
+                    qualified_name: None,
+                    start_line: span.start_position().row as u32 + 1,
+                    start_column: span.start_position().column as u32 + 1,
+                    end_line: span.end_position().row as u32 + 1,
+                    end_column: span.end_position().column as u32 + 1,
+                    attributes: None,
+                });
+            }
+        }
+
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_go(source: &str) -> (tree_sitter::Language, Tree) {
+        let language: tree_sitter::Language = tree_sitter_go::LANGUAGE.into();
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        (language, tree)
+    }
+
+    #[test]
+    fn test_query_extracts_go_functions() {
+        let source = r#"package main
+
+func main() {
+    helper()
+}
+
+func helper() {}
+
+type Server struct{}
+
+func (s *Server) Start() {}
+"#;
+        let (language, tree) = parse_go(source);
+        let extractor = QueryBasedExtractor::new(
+            &language,
+            "(function_declaration name: (identifier) @function.name) @function.definition",
+        )
+        .unwrap();
+
+        let nodes = extractor.extract(source, &tree);
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.iter().all(|n| n.node_type == "function"));
+        assert_eq!(nodes[0].name, "main");
+        assert_eq!((nodes[0].start_line, nodes[0].end_line), (3, 5));
+        assert_eq!(nodes[1].name, "helper");
+        assert_eq!(nodes[1].start_line, 7);
+    }
+
+    #[test]
+    fn test_query_without_definition_capture_uses_name_span() {
+        let source = "package main\n\ntype Server struct{}\n";
+        let (language, tree) = parse_go(source);
+        let extractor = QueryBasedExtractor::new(
+            &language,
+            "(type_spec name: (type_identifier) @struct.name)",
+        )
+        .unwrap();
+
+        let nodes = extractor.extract(source, &tree);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].node_type, "struct");
+        assert_eq!(nodes[0].name, "Server");
+        assert_eq!((nodes[0].start_column, nodes[0].end_column), (6, 12));
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        let (language, _) = parse_go("package main\n");
+        let result = QueryBasedExtractor::new(&language, "(not_a_node) @x.name");
+        assert!(matches!(result, Err(Error::Query(_))));
+    }
+}
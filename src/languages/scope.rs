@@ -0,0 +1,87 @@
+//! Lexical scopes for resolving local names within a function body
+//!
+//! Extractors push a scope when entering a function or block and declare the
+//! names bound there. Parameters are declared with a binding, by default
+//! their node index, so uses can be linked back to them; locals without a
+//! binding are still declared so that they shadow outer bindings of the same
+//! name.
+
+/// A stack of lexical scopes, innermost last
+#[derive(Debug)]
+pub(crate) struct Scopes<T = usize> {
+    frames: Vec<Vec<(String, Option<T>)>>,
+}
+
+impl<T> Default for Scopes<T> {
+    fn default() -> Self {
+        Self { frames: Vec::new() }
+    }
+}
+
+impl<T> Scopes<T> {
+    /// Enter a new scope
+    pub(crate) fn push(&mut self) {
+        self.frames.push(Vec::new());
+    }
+
+    /// Leave the innermost scope, dropping its declarations
+    pub(crate) fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Bind a name in the innermost scope; ignored outside any scope
+    pub(crate) fn declare(&mut self, name: impl Into<String>, binding: Option<T>) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push((name.into(), binding));
+        }
+    }
+
+    /// Binding of the nearest declaration of `name`
+    ///
+    /// Returns `None` when the name is unbound or its nearest declaration has
+    /// no binding.
+    pub(crate) fn resolve(&self, name: &str) -> Option<&T> {
+        self.frames
+            .iter()
+            .rev()
+            .flat_map(|frame| frame.iter().rev())
+            .find(|(declared, _)| declared == name)
+            .and_then(|(_, binding)| binding.as_ref())
+    }
+
+    /// Whether `name` is declared in any enclosing scope, with or without a
+    /// binding
+    pub(crate) fn is_declared(&self, name: &str) -> bool {
+        self.frames
+            .iter()
+            .flatten()
+            .any(|(declared, _)| declared == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inner_declaration_shadows_outer() {
+        let mut scopes: Scopes = Scopes::default();
+        scopes.declare("ignored", Some(0));
+        assert_eq!(scopes.resolve("ignored"), None);
+
+        scopes.push();
+        scopes.declare("conn", Some(3));
+        assert_eq!(scopes.resolve("conn"), Some(&3));
+
+        scopes.push();
+        scopes.declare("conn", None);
+        assert_eq!(scopes.resolve("conn"), None);
+
+        assert!(scopes.is_declared("conn"));
+
+        scopes.pop();
+        assert_eq!(scopes.resolve("conn"), Some(&3));
+        assert_eq!(scopes.resolve("other"), None);
+        assert!(!scopes.is_declared("other"));
+    }
+}
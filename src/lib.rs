@@ -4,13 +4,19 @@
 //! and building searchable code graphs.
 
 pub mod core;
+pub mod error;
 pub mod languages;
+#[cfg(feature = "service")]
 pub mod server;
 pub mod storage;
 
 pub use crate::core::config::Config;
+#[cfg(feature = "service")]
 pub use crate::core::graph::GraphBuilder;
 pub use crate::core::parser::CodeParser;
+#[cfg(feature = "service")]
 pub use crate::core::query::QueryExecutor;
+pub use crate::error::{Error, Result};
 pub use crate::languages::LanguageRegistry;
+#[cfg(feature = "service")]
 pub use crate::storage::Database;
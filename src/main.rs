@@ -4,15 +4,17 @@
 //! searchable code graphs with support for multiple programming languages.
 
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 mod core;
+mod error;
 mod languages;
 mod server;
 mod storage;
 
-pub use crate::core::config::Config;
+pub use crate::core::config::{CliOverrides, Config, LoggingConfig};
 
 /// CodeGraph - Multi-language code graph parsing service
 #[derive(Parser)]
@@ -35,21 +37,21 @@ struct Cli {
 enum Commands {
     /// Start the HTTP server
     Start {
-        /// Host to bind to
-        #[arg(short = 'H', long, default_value = "127.0.0.1")]
-        host: String,
+        /// Host to bind to [default: 127.0.0.1]
+        #[arg(short = 'H', long)]
+        host: Option<String>,
 
-        /// Port to listen on
-        #[arg(short, long, default_value_t = 8080)]
-        port: u16,
+        /// Port to listen on [default: 8080]
+        #[arg(short, long)]
+        port: Option<u16>,
 
-        /// Path to SQLite database file
-        #[arg(short, long, default_value = "codegraph.db")]
-        database: PathBuf,
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
     },
 
-    /// Parse a project and build the code graph
-    Parse {
+    /// Create a project from a directory and parse it; the first step before querying
+    Init {
         /// Path to the project root
         #[arg(short, long)]
         path: PathBuf,
@@ -58,34 +60,197 @@ enum Commands {
         #[arg(short, long)]
         name: Option<String>,
 
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+
+    /// Parse a project and build the code graph
+    Parse {
+        /// Path to the project root
+        #[arg(short, long, required_unless_present = "archive")]
+        path: Option<PathBuf>,
+
+        /// Parse a .tar, .tar.gz or .zip source archive without extracting it
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["path", "roots", "since", "dry_run"])]
+        archive: Option<PathBuf>,
+
+        /// Additional source root of the same project (repeatable)
+        #[arg(long = "root", value_name = "PATH")]
+        roots: Vec<PathBuf>,
+
+        /// Project name (defaults to directory name)
+        #[arg(short, long)]
+        name: Option<String>,
+
         /// Languages to parse (auto-detect if not specified)
         #[arg(short, long)]
         languages: Option<Vec<String>>,
 
-        /// Path to SQLite database file
-        #[arg(short, long, default_value = "codegraph.db")]
-        database: PathBuf,
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Follow symbolic links while collecting files
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Deepest directory level walked below each root [default: 50]
+        #[arg(long, value_name = "N")]
+        max_depth: Option<usize>,
+
+        /// Skip files marked as generated code
+        #[arg(long)]
+        skip_generated: bool,
+
+        /// Store file paths relative to the project root
+        #[arg(long)]
+        relative_paths: bool,
+
+        /// Only parse files matching this glob, e.g. "src/**" (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Skip files matching this glob, e.g. "**/generated/**" (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Only parse files changed since this git ref (full parse outside a git repo)
+        #[arg(long, value_name = "GIT_REF")]
+        since: Option<String>,
+
+        /// Skip resolving references between symbols; symbol search still works
+        #[arg(long, conflicts_with = "archive")]
+        no_cross_references: bool,
+
+        /// List the files that would be parsed, grouped by language, and exit
+        #[arg(long)]
+        dry_run: bool,
+
+        /// With --dry-run, also list skipped paths and why they were skipped
+        #[arg(long, requires = "dry_run")]
+        explain: bool,
+
+        /// Show a progress bar and summary instead of per-file logs
+        #[arg(short, long)]
+        quiet: bool,
     },
 
     /// Query the code graph
     Query {
-        /// Path to SQLite database file
-        #[arg(short, long, default_value = "codegraph.db")]
-        database: PathBuf,
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
 
         /// Project name or ID to query
         #[arg(short, long)]
         project: Option<String>,
 
+        /// Write the result to this file instead of stdout
+        #[arg(short, long, value_name = "FILE", global = true)]
+        output: Option<PathBuf>,
+
         #[command(subcommand)]
         query_type: QueryCommands,
     },
 
-    /// List all projects
+    /// List all projects, or manage one
     Projects {
-        /// Path to SQLite database file
-        #[arg(short, long, default_value = "codegraph.db")]
-        database: PathBuf,
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Output format for the project list: text, or json for scripting
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+
+        /// Include each project's file, node and edge counts and last parse time
+        #[arg(long)]
+        status: bool,
+
+        #[command(subcommand)]
+        action: Option<ProjectCommands>,
+    },
+
+    /// Show statistics for a parsed project
+    Stats {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Project name or ID
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Break statistics down per file (language, line count, node count)
+        #[arg(long)]
+        by_file: bool,
+
+        /// Break statistics down per language (files, nodes, edges)
+        #[arg(long, conflicts_with = "by_file")]
+        by_language: bool,
+
+        /// List the node and edge types present in the project's graph
+        #[arg(long, conflicts_with_all = ["by_file", "by_language"])]
+        types: bool,
+
+        /// Write the result to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Export a project's graph
+    Export {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Project name or ID
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "FILE", global = true)]
+        output: Option<PathBuf>,
+
+        #[command(subcommand)]
+        format: ExportCommands,
+    },
+
+    /// Remove a file deleted from disk from a project's graph, without re-parsing
+    RemoveFile {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Project name or ID
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// The file's path as it was parsed
+        #[arg(long)]
+        path: PathBuf,
+    },
+
+    /// Compare a project's graph against a previously exported snapshot
+    Diff {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+
+        /// Project name or ID
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Snapshot written earlier with `export json`
+        #[arg(short, long, value_name = "FILE")]
+        against: PathBuf,
+    },
+
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        action: DbCommands,
     },
 
     /// List supported languages
@@ -93,13 +258,57 @@ enum Commands {
 
     /// Show server status
     Status {
-        /// Host to connect to
-        #[arg(short = 'H', long, default_value = "127.0.0.1")]
-        host: String,
+        /// Host to connect to [default: 127.0.0.1]
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+
+        /// Port to connect to [default: 8080]
+        #[arg(short, long)]
+        port: Option<u16>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectCommands {
+    /// Rename a project
+    Rename {
+        /// Current project name or ID
+        #[arg(short, long)]
+        project: String,
+
+        /// New project name
+        #[arg(short, long)]
+        name: String,
+    },
+}
 
-        /// Port to connect to
-        #[arg(short, long, default_value_t = 8080)]
-        port: u16,
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Snapshot of the project's symbols and edges, usable as a `diff` baseline
+    Json,
+
+    /// Self-contained, compressed copy of the project's graph for read-only
+    /// querying without SQLite; requires --output
+    Bundle,
+
+    /// Whole-project graph as `{nodes, links}` JSON for d3-force or vis.js
+    D3,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Reclaim free pages (VACUUM) and refresh planner statistics (ANALYZE)
+    Vacuum {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+
+    /// Check the database file for corruption
+    IntegrityCheck {
+        /// Path to SQLite database file [default: database.path from --config, or codegraph.db]
+        #[arg(short, long)]
+        database: Option<PathBuf>,
     },
 }
 
@@ -121,6 +330,10 @@ enum QueryCommands {
         /// Maximum number of results
         #[arg(short, long, default_value_t = 100)]
         limit: u32,
+
+        /// Also return the symbol's definition, as `definition`
+        #[arg(long)]
+        include_definition: bool,
     },
 
     /// Get call graph for a symbol
@@ -136,6 +349,18 @@ enum QueryCommands {
         /// Direction: callers, callees, or both
         #[arg(long, default_value = "both")]
         direction: String,
+
+        /// Include the locations where the symbol is invoked
+        #[arg(long)]
+        call_sites: bool,
+
+        /// Output format: json, mermaid for a `graph TD` diagram, or d3 for
+        /// `{nodes, links}` JSON
+        #[arg(long, default_value = "json", value_parser = ["json", "mermaid", "d3"])]
+        format: String,
+
+        #[command(flatten)]
+        tests: TestFilterArgs,
     },
 
     /// Search for symbols
@@ -151,40 +376,259 @@ enum QueryCommands {
         /// Maximum number of results
         #[arg(short, long, default_value_t = 50)]
         limit: u32,
+
+        /// Search every project in the database instead of a single one
+        #[arg(long)]
+        all_projects: bool,
+
+        /// Treat the query as a regular expression, e.g. "^get.*User$"
+        #[arg(long, conflicts_with = "all_projects")]
+        regex: bool,
+
+        /// Only match symbols whose name or qualified name is exactly the query
+        #[arg(long, conflicts_with_all = ["all_projects", "regex"])]
+        exact: bool,
+
+        #[command(flatten)]
+        tests: TestFilterArgs,
     },
+
+    /// Show a symbol's definition with caller, callee and reference counts
+    #[command(alias = "symbol-info")]
+    Info {
+        /// Symbol name or qualified name
+        #[arg(short, long)]
+        symbol: String,
+    },
+
+    /// List the symbols containing a position, outermost first
+    Stack {
+        /// File path as stored in the database
+        #[arg(short, long)]
+        file: String,
+
+        /// Line number, starting at 1
+        #[arg(short, long)]
+        line: u32,
+
+        /// Column, in the configured column encoding
+        #[arg(short, long)]
+        column: u32,
+    },
+
+    /// Find symbols carrying an annotation
+    Annotated {
+        /// Annotation name, with or without the leading `@`
+        #[arg(short, long)]
+        annotation: String,
+    },
+
+    /// List the files and top-level symbols of a package across its files
+    Package {
+        /// Package name, e.g. `server`
+        #[arg(short, long)]
+        name: String,
+    },
+}
+
+/// Progress bar shown while parsing with `--quiet`
+struct ProgressBarReporter {
+    bar: indicatif::ProgressBar,
+}
+
+impl ProgressBarReporter {
+    fn new() -> Self {
+        let bar = indicatif::ProgressBar::new(0);
+        bar.set_style(
+            indicatif::ProgressStyle::with_template("{bar:40} {pos}/{len} files ({elapsed})")
+                .unwrap_or_else(|_| indicatif::ProgressStyle::default_bar()),
+        );
+        Self { bar }
+    }
+}
+
+impl core::ParseProgress for ProgressBarReporter {
+    fn start(&self, total: usize) {
+        self.bar.set_length(total as u64);
+    }
+
+    fn file_found(&self, _path: &Path) {
+        self.bar.inc_length(1);
+    }
+
+    fn file_done(&self, _path: &Path) {
+        self.bar.inc(1);
+    }
+
+    fn finish(&self, report: &core::ParseReport) {
+        self.bar.finish_and_clear();
+        println!(
+            "Parsed {} files ({} failed, {} skipped, {} removed) in {:.1?}",
+            report.files_parsed,
+            report.files_failed,
+            report.skipped.len(),
+            report.files_removed,
+            self.bar.elapsed()
+        );
+    }
 }
 
-fn init_logging(verbose: bool) {
-    let filter = if verbose {
-        "codegraph=debug,tower_http=debug"
-    } else {
-        "codegraph=info,tower_http=info"
+/// Flags choosing whether test files are queried
+#[derive(clap::Args)]
+struct TestFilterArgs {
+    /// Leave out symbols declared in test files
+    #[arg(long, conflicts_with = "only_tests")]
+    exclude_tests: bool,
+
+    /// Only consider symbols declared in test files
+    #[arg(long)]
+    only_tests: bool,
+}
+
+impl TestFilterArgs {
+    fn filter(&self) -> storage::models::TestFilter {
+        if self.exclude_tests {
+            storage::models::TestFilter::Exclude
+        } else if self.only_tests {
+            storage::models::TestFilter::Only
+        } else {
+            storage::models::TestFilter::All
+        }
+    }
+}
+
+/// Install the log subscriber described by the `[logging]` config
+fn init_logging(logging: &LoggingConfig) -> anyhow::Result<()> {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(logging.filter_directives()?)
+        .with_target(true);
+    match logging.format.as_str() {
+        "pretty" => builder.init(),
+        "compact" => builder.compact().init(),
+        "json" => builder.json().init(),
+        other => anyhow::bail!(
+            "Invalid log format: {} (expected pretty, json or compact)",
+            other
+        ),
+    }
+    Ok(())
+}
+
+/// Collect the settings given as command-line flags
+fn cli_overrides(cli: &Cli) -> CliOverrides {
+    let quiet = matches!(cli.command, Commands::Parse { quiet: true, .. });
+    let mut overrides = CliOverrides {
+        config_file: cli.config.clone(),
+        // Verbose logging wins over a quiet parse
+        log_level: if cli.verbose {
+            Some("debug".to_string())
+        } else if quiet {
+            Some("warn".to_string())
+        } else {
+            None
+        },
+        ..Default::default()
     };
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(true)
-        .init();
+    match &cli.command {
+        Commands::Start {
+            host,
+            port,
+            database,
+        } => {
+            overrides.host = host.clone();
+            overrides.port = *port;
+            overrides.database = database.clone();
+        }
+        Commands::Status { host, port } => {
+            overrides.host = host.clone();
+            overrides.port = *port;
+        }
+        Commands::Init { database, .. }
+        | Commands::Parse { database, .. }
+        | Commands::Query { database, .. }
+        | Commands::Projects { database, .. }
+        | Commands::Stats { database, .. }
+        | Commands::Export { database, .. }
+        | Commands::RemoveFile { database, .. }
+        | Commands::Diff { database, .. }
+        | Commands::Db {
+            action: DbCommands::Vacuum { database } | DbCommands::IntegrityCheck { database },
+        } => {
+            overrides.database = database.clone();
+        }
+        Commands::Languages => {}
+    }
+
+    overrides
+}
+
+/// Print a rendered result, or write it to `output` if given
+fn emit(output: Option<&Path>, content: &str) -> anyhow::Result<()> {
+    match output {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+/// Project name used when `--name` is not given: the directory's name
+fn default_project_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unnamed")
+        .to_string()
 }
 
 /// Resolve project name/id to project_id
 fn resolve_project(db: &storage::Database, project: Option<&str>) -> anyhow::Result<i64> {
+    // A database nothing has been parsed into yet has no tables
+    if !db.has_schema()? {
+        anyhow::bail!("No projects found. Create one with 'codegraph init --path <DIR>'.");
+    }
+
     match project {
         Some(p) => {
             // Try to parse as ID first
             if let Ok(id) = p.parse::<i64>() {
                 return Ok(id);
             }
-            // Otherwise, look up by name
-            db.get_project_by_name(p)?
-                .map(|proj| proj.id)
-                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", p))
+            // Otherwise, look up by name or unique name prefix
+            let projects = db.list_projects()?;
+            match core::projects::match_project_name(&projects, p) {
+                core::projects::ProjectMatch::Found(proj) => Ok(proj.id),
+                core::projects::ProjectMatch::Ambiguous(candidates) => {
+                    eprintln!("Project '{}' matches several projects:", p);
+                    for c in &candidates {
+                        eprintln!("  - {} (id={})", c.name, c.id);
+                    }
+                    anyhow::bail!("Project '{}' is ambiguous", p)
+                }
+                core::projects::ProjectMatch::NotFound(suggestions) => {
+                    if !suggestions.is_empty() {
+                        eprintln!("Did you mean:");
+                        for s in &suggestions {
+                            eprintln!("  - {} (id={})", s.name, s.id);
+                        }
+                    }
+                    anyhow::bail!(
+                        "Project '{}' not found. Create it with 'codegraph init --path <DIR> --name {}'.",
+                        p,
+                        p
+                    )
+                }
+            }
         }
         None => {
             // Get the first/default project
             let projects = db.list_projects()?;
             if projects.is_empty() {
-                anyhow::bail!("No projects found. Use 'codegraph parse' to create one.");
+                anyhow::bail!("No projects found. Create one with 'codegraph init --path <DIR>'.");
             }
             if projects.len() > 1 {
                 eprintln!("Multiple projects found. Use --project to specify one:");
@@ -202,86 +646,537 @@ fn resolve_project(db: &storage::Database, project: Option<&str>) -> anyhow::Res
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    init_logging(cli.verbose);
+    // Verbose logging wins over a quiet parse
+    let quiet = matches!(cli.command, Commands::Parse { quiet: true, .. }) && !cli.verbose;
+
+    let mut config = Config::load(&cli_overrides(&cli))?;
+    init_logging(&config.logging)?;
+    let database = config.database.path.clone();
+    let open_options = config.database.open_options();
 
     match cli.command {
-        Commands::Start {
-            host,
-            port,
-            database,
-        } => {
+        Commands::Start { .. } => {
+            let host = &config.server.host;
+            let port = config.server.port;
             info!("Starting CodeGraph server on {}:{}", host, port);
-            server::run_server(&host, port, &database).await?;
+            server::run_server(&config.server, &config.database, &config.parse).await?;
+        }
+
+        Commands::Init { path, name, .. } => {
+            let path = core::config::expand_path(&path);
+            if !path.is_dir() {
+                anyhow::bail!("{} is not a directory", path.display());
+            }
+            // An initialized root keeps its project; `init` re-parses it
+            let existing = {
+                let db = storage::Database::open_with_options(&database, &open_options)?;
+                db.init_schema()?;
+                db.get_project_by_path(&path.to_string_lossy())?
+            };
+            let project_name = match (existing, name) {
+                (Some(project), Some(name)) if project.name != name => anyhow::bail!(
+                    "{} is already initialized as project '{}' (id={}). Rename it with 'codegraph projects rename --project {} --name {}'.",
+                    path.display(),
+                    project.name,
+                    project.id,
+                    project.id,
+                    name
+                ),
+                (Some(project), _) => project.name,
+                (None, name) => name.unwrap_or_else(|| default_project_name(&path)),
+            };
+
+            info!("Initializing project '{}' at {:?}", project_name, path);
+            let report = core::parse_project_with_options(
+                &database,
+                &open_options,
+                &project_name,
+                &path,
+                &core::ParseOptions::default(),
+                &config.parse,
+            )
+            .await?;
+            println!(
+                "Initialized project '{}' (id={}): {} files parsed, {} failed, {} skipped",
+                project_name,
+                report.project_id,
+                report.files_parsed,
+                report.files_failed,
+                report.skipped.len()
+            );
+            println!(
+                "Next: codegraph query --project {} symbols --query <NAME>",
+                project_name
+            );
         }
 
         Commands::Parse {
             path,
+            archive,
+            roots,
             name,
             languages,
-            database,
+            follow_symlinks,
+            max_depth,
+            skip_generated,
+            relative_paths,
+            include,
+            exclude,
+            since,
+            no_cross_references,
+            dry_run,
+            explain,
+            ..
         } => {
-            let project_name = name.unwrap_or_else(|| {
-                path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unnamed")
-                    .to_string()
-            });
-
-            info!("Parsing project '{}' at {:?}", project_name, path);
-            core::parse_project(&database, &project_name, &path, languages.as_deref()).await?;
+            if follow_symlinks {
+                config.parse.follow_symlinks = true;
+            }
+            if let Some(max_depth) = max_depth {
+                config.parse.max_depth = max_depth;
+            }
+            if skip_generated {
+                config.parse.skip_generated = true;
+            }
+            if relative_paths {
+                config.parse.relative_paths = true;
+            }
+            config.parse.include.extend(include);
+            config.parse.exclude.extend(exclude);
+            let path = path.as_deref().map(core::config::expand_path);
+            let archive = archive.as_deref().map(core::config::expand_path);
+            // Roots are remembered on the project, so store them absolute
+            // rather than relative to wherever this parse ran
+            let roots = roots
+                .iter()
+                .map(|root| {
+                    let root = core::config::expand_path(root);
+                    root.canonicalize()
+                        .map_err(|e| anyhow::anyhow!("Invalid --root {}: {}", root.display(), e))
+                })
+                .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+
+            let report = if let Some(archive) = archive {
+                let format = core::archive::ArchiveFormat::from_path(&archive)?;
+                let project_name = name.unwrap_or_else(|| format.stem(&archive));
+                info!(
+                    "Parsing project '{}' from archive {:?}",
+                    project_name, archive
+                );
+                core::parse_archive(
+                    &database,
+                    &open_options,
+                    &project_name,
+                    &archive,
+                    languages.as_deref(),
+                    &config.parse,
+                )
+                .await?
+            } else {
+                let path = path.expect("clap requires --path without --archive");
+                if dry_run {
+                    let plan = core::dry_run(&path, &roots, languages.as_deref(), &config.parse)?;
+                    println!("Would parse {} files:", plan.total);
+                    for (language, files) in &plan.files_by_language {
+                        println!("  {} ({}):", language, files.len());
+                        for file in files {
+                            println!("    {}", file);
+                        }
+                    }
+                    if explain {
+                        let mut by_reason: BTreeMap<&str, Vec<&PathBuf>> = BTreeMap::new();
+                        for entry in &plan.skipped {
+                            by_reason
+                                .entry(entry.reason.as_str())
+                                .or_default()
+                                .push(&entry.path);
+                        }
+                        println!("Skipped {} paths:", plan.skipped.len());
+                        for (reason, paths) in &by_reason {
+                            println!("  {} ({}):", reason, paths.len());
+                            for path in paths {
+                                println!("    {}", path.display());
+                            }
+                        }
+                    }
+                    return Ok(());
+                }
+
+                let project_name = name.unwrap_or_else(|| default_project_name(&path));
+
+                info!("Parsing project '{}' at {:?}", project_name, path);
+                let progress = quiet.then(ProgressBarReporter::new);
+                let options = core::ParseOptions {
+                    extra_roots: &roots,
+                    languages: languages.as_deref(),
+                    since: since.as_deref(),
+                    skip_cross_references: no_cross_references,
+                    progress: progress.as_ref().map(|p| p as &dyn core::ParseProgress),
+                };
+                core::parse_project_with_options(
+                    &database,
+                    &open_options,
+                    &project_name,
+                    &path,
+                    &options,
+                    &config.parse,
+                )
+                .await?
+            };
+            info!(
+                "Parsed {} files ({} failed, {} skipped, {} removed)",
+                report.files_parsed,
+                report.files_failed,
+                report.skipped.len(),
+                report.files_removed
+            );
+
+            let db = storage::Database::open_with_options(&database, &open_options)?;
+            for counts in db.counts_by_language(report.project_id)? {
+                info!(
+                    "  {}: {} files, {} nodes, {} edges",
+                    counts.language, counts.file_count, counts.node_count, counts.edge_count
+                );
+            }
         }
 
         Commands::Query {
-            database,
             project,
+            output,
             query_type,
+            ..
         } => {
-            let db = storage::Database::open(&database)?;
+            if let QueryCommands::Symbols {
+                query,
+                symbol_type,
+                limit,
+                all_projects: true,
+                ..
+            } = &query_type
+            {
+                let result = core::query::search_symbols_global(
+                    &database,
+                    query,
+                    symbol_type.as_deref(),
+                    *limit,
+                )?;
+                emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
+                return Ok(());
+            }
+
+            let db = storage::Database::open_with_options(&database, &open_options)?;
             let project_id = resolve_project(&db, project.as_deref())?;
 
             match query_type {
                 QueryCommands::Definition { symbol } => {
-                    let result = core::query::find_definition_by_symbol(&database, project_id, &symbol)?;
-                    println!("{}", serde_json::to_string_pretty(&result)?);
+                    let result = core::query::QueryExecutor::new(&db)
+                        .find_definitions_by_symbol(project_id, &symbol)?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
                 }
-                QueryCommands::References { symbol, limit } => {
-                    let result = core::query::find_references_by_symbol(&database, project_id, &symbol, limit)?;
-                    println!("{}", serde_json::to_string_pretty(&result)?);
+                QueryCommands::References {
+                    symbol,
+                    limit,
+                    include_definition,
+                } => {
+                    let executor = core::query::QueryExecutor::new(&db);
+                    let result = if include_definition {
+                        executor.find_references_with_definition(project_id, &symbol, limit)?
+                    } else {
+                        executor.find_references_by_symbol(project_id, &symbol, limit)?
+                    };
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
                 }
                 QueryCommands::Callgraph {
                     symbol,
                     depth,
                     direction,
+                    call_sites,
+                    format,
+                    tests,
+                } => {
+                    let result = core::query::get_callgraph_with_project(
+                        &database,
+                        project_id,
+                        &symbol,
+                        depth,
+                        &direction,
+                        call_sites,
+                        tests.filter(),
+                    )?;
+                    let rendered = match format.as_str() {
+                        "mermaid" => result.to_mermaid(),
+                        "d3" => result.to_d3_json()?,
+                        _ => serde_json::to_string_pretty(&result)?,
+                    };
+                    emit(output.as_deref(), &rendered)?;
+                }
+                QueryCommands::Symbols {
+                    query,
+                    symbol_type,
+                    limit,
+                    exact: true,
+                    tests,
+                    ..
+                } => {
+                    let result = core::query::search_symbols_exact_with_project(
+                        &database,
+                        project_id,
+                        &query,
+                        symbol_type.as_deref(),
+                        limit,
+                        tests.filter(),
+                    )?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
+                }
+                QueryCommands::Symbols {
+                    query,
+                    symbol_type,
+                    limit,
+                    regex: true,
+                    tests,
+                    ..
                 } => {
-                    let result = core::query::get_callgraph_with_project(&database, project_id, &symbol, depth, &direction)?;
-                    println!("{}", serde_json::to_string_pretty(&result)?);
+                    let result = core::query::search_symbols_regex_with_project(
+                        &database,
+                        project_id,
+                        &query,
+                        symbol_type.as_deref(),
+                        limit,
+                        tests.filter(),
+                    )?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
                 }
                 QueryCommands::Symbols {
                     query,
                     symbol_type,
                     limit,
+                    tests,
+                    ..
                 } => {
-                    let result =
-                        core::query::search_symbols_with_project(&database, project_id, &query, symbol_type.as_deref(), limit)?;
-                    println!("{}", serde_json::to_string_pretty(&result)?);
+                    let result = core::query::search_symbols_with_project(
+                        &database,
+                        project_id,
+                        &query,
+                        symbol_type.as_deref(),
+                        limit,
+                        tests.filter(),
+                    )?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
+                }
+                QueryCommands::Info { symbol } => {
+                    match core::query::symbol_info_with_project(&database, project_id, &symbol) {
+                        Ok(info) => emit(output.as_deref(), &serde_json::to_string_pretty(&info)?)?,
+                        Err(error::Error::SymbolNotFound(_)) => {
+                            anyhow::bail!(
+                                "No definition of '{}' in project {}; try `query symbols --query {}`",
+                                symbol,
+                                project_id,
+                                symbol
+                            )
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                QueryCommands::Stack { file, line, column } => {
+                    let result = core::query::symbol_stack_with_project(
+                        &database, project_id, &file, line, column,
+                    )?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
+                }
+                QueryCommands::Annotated { annotation } => {
+                    let result = core::query::find_annotated_with_project(
+                        &database,
+                        project_id,
+                        &annotation,
+                    )?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&result)?)?;
+                }
+                QueryCommands::Package { name } => {
+                    match core::query::package_summary_with_project(&database, project_id, &name) {
+                        Ok(summary) => {
+                            emit(output.as_deref(), &serde_json::to_string_pretty(&summary)?)?
+                        }
+                        Err(error::Error::SymbolNotFound(_)) => {
+                            anyhow::bail!(
+                                "No file in project {} declares package '{}'",
+                                project_id,
+                                name
+                            )
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
                 }
             }
         }
 
-        Commands::Projects { database } => {
-            let db = storage::Database::open(&database)?;
-            let projects = db.list_projects()?;
+        Commands::Projects {
+            format,
+            status,
+            action,
+            ..
+        } => {
+            let db = storage::Database::open_with_options(&database, &open_options)?;
 
-            if projects.is_empty() {
-                println!("No projects found.");
+            match action {
+                None if status => {
+                    let statuses = db.all_project_statuses()?;
+
+                    if format == "json" {
+                        emit(None, &serde_json::to_string_pretty(&statuses)?)?;
+                    } else if statuses.is_empty() {
+                        println!("No projects found.");
+                    } else {
+                        println!("Projects:");
+                        for s in statuses {
+                            let last_parsed = s
+                                .last_parsed_at
+                                .map_or_else(|| "never".to_string(), |t| t.to_rfc3339());
+                            println!(
+                                "  - {} (id={}, files={}, nodes={}, edges={}, last parsed={})",
+                                s.name,
+                                s.project_id,
+                                s.files_parsed,
+                                s.nodes_count,
+                                s.edges_count,
+                                last_parsed
+                            );
+                        }
+                    }
+                }
+                None => {
+                    let projects = db.list_projects()?;
+
+                    if format == "json" {
+                        emit(None, &serde_json::to_string_pretty(&projects)?)?;
+                    } else if projects.is_empty() {
+                        println!("No projects found.");
+                    } else {
+                        println!("Projects:");
+                        for p in projects {
+                            println!("  - {} (id={}, path={})", p.name, p.id, p.root_path);
+                        }
+                    }
+                }
+                Some(ProjectCommands::Rename { project, name }) => {
+                    let project_id = resolve_project(&db, Some(&project))?;
+                    if !db.rename_project(project_id, &name)? {
+                        anyhow::bail!("Project '{}' not found", project);
+                    }
+                    println!("Renamed project '{}' to '{}'", project, name);
+                }
+            }
+        }
+
+        Commands::Stats {
+            project,
+            by_file,
+            by_language,
+            types,
+            output,
+            ..
+        } => {
+            let db = storage::Database::open_with_options(&database, &open_options)?;
+            let project_id = resolve_project(&db, project.as_deref())?;
+
+            if by_file {
+                let metrics = db.file_metrics(project_id)?;
+                emit(output.as_deref(), &serde_json::to_string_pretty(&metrics)?)?;
+            } else if by_language {
+                let counts = db.counts_by_language(project_id)?;
+                emit(output.as_deref(), &serde_json::to_string_pretty(&counts)?)?;
+            } else if types {
+                let types = serde_json::json!({
+                    "node_types": db.distinct_node_types(project_id)?,
+                    "edge_types": db.distinct_edge_types(project_id)?,
+                });
+                emit(output.as_deref(), &serde_json::to_string_pretty(&types)?)?;
             } else {
-                println!("Projects:");
-                for p in projects {
-                    println!("  - {} (id={}, path={})", p.name, p.id, p.root_path);
+                let status = db
+                    .get_project_status(project_id)?
+                    .ok_or_else(|| anyhow::anyhow!("Project {} not found", project_id))?;
+                emit(output.as_deref(), &serde_json::to_string_pretty(&status)?)?;
+            }
+        }
+
+        Commands::Export {
+            project,
+            output,
+            format,
+            ..
+        } => {
+            let db = storage::Database::open_with_options(&database, &open_options)?;
+            let project_id = resolve_project(&db, project.as_deref())?;
+
+            match format {
+                ExportCommands::Json => {
+                    let snapshot = core::diff::GraphSnapshot::from_database(&db, project_id)?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&snapshot)?)?;
+                }
+                ExportCommands::D3 => {
+                    let graph = db.export_d3(project_id)?;
+                    emit(output.as_deref(), &serde_json::to_string_pretty(&graph)?)?;
                 }
+                ExportCommands::Bundle => {
+                    let output = output
+                        .ok_or_else(|| anyhow::anyhow!("export bundle requires --output <FILE>"))?;
+                    let bundle = storage::GraphBundle::from_database(&db, project_id)?;
+                    bundle.write(&output)?;
+                    println!(
+                        "Exported {} files, {} nodes and {} edges to {:?}",
+                        bundle.files.len(),
+                        bundle.nodes.len(),
+                        bundle.edges.len(),
+                        output
+                    );
+                }
+            }
+        }
+
+        Commands::RemoveFile { project, path, .. } => {
+            let db = storage::Database::open_with_options(&database, &open_options)?;
+            let project_id = resolve_project(&db, project.as_deref())?;
+            let root = db
+                .list_projects()?
+                .into_iter()
+                .find(|p| p.id == project_id)
+                .map(|p| PathBuf::from(p.root_path))
+                .ok_or_else(|| anyhow::anyhow!("Project {} not found", project_id))?;
+
+            let mut builder = core::graph::GraphBuilder::new(db);
+            if config.parse.relative_paths {
+                builder = builder.with_relative_paths(&root);
             }
+            match builder.remove_file_and_relink(project_id, &path)? {
+                Some(removal) => println!("{}", serde_json::to_string_pretty(&removal)?),
+                None => anyhow::bail!("File {:?} is not indexed in project {}", path, project_id),
+            }
+        }
+
+        Commands::Diff {
+            project, against, ..
+        } => {
+            let db = storage::Database::open_with_options(&database, &open_options)?;
+            let project_id = resolve_project(&db, project.as_deref())?;
+            let diff = core::diff::diff_against(&db, project_id, &against)?;
+            println!("{}", serde_json::to_string_pretty(&diff)?);
         }
 
+        Commands::Db { action } => match action {
+            DbCommands::Vacuum { .. } => {
+                let db = storage::Database::open_with_options(&database, &open_options)?;
+                db.vacuum()?;
+                db.analyze()?;
+                println!("Database {:?} vacuumed and analyzed", database);
+            }
+            DbCommands::IntegrityCheck { .. } => {
+                let db = storage::Database::open_with_options(&database, &open_options)?;
+                if db.integrity_check()? {
+                    println!("Database {:?} passed the integrity check", database);
+                } else {
+                    anyhow::bail!("Database {:?} failed the integrity check", database);
+                }
+            }
+        },
+
         Commands::Languages => {
             let registry = languages::LanguageRegistry::new();
             println!("Supported languages:");
@@ -294,7 +1189,9 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        Commands::Status { host, port } => {
+        Commands::Status { .. } => {
+            let host = &config.server.host;
+            let port = config.server.port;
             let url = format!("http://{}:{}/api/v1/health", host, port);
             match reqwest::get(&url).await {
                 Ok(resp) => {
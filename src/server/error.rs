@@ -0,0 +1,140 @@
+//! Error responses with stable machine-readable codes
+//!
+//! Every failed request returns an [`ErrorResponse`] whose `error` field is
+//! one of the [`ErrorCode`] values, so clients can branch on it without
+//! parsing messages. Each request is given an id, returned in the
+//! `x-request-id` header and in error bodies, to correlate responses with
+//! server logs.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::Error;
+
+/// Header carrying the request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    /// Id of the request being handled
+    static REQUEST_ID: String;
+}
+
+/// Stable error codes returned in the `error` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    ProjectNotFound,
+    NodeNotFound,
+    SymbolNotFound,
+    FileNotFound,
+    ProjectExists,
+    InvalidRange,
+    InvalidQuery,
+    UnsupportedLanguage,
+    TooManyParseJobs,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// HTTP status returned with this code
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::ProjectNotFound
+            | ErrorCode::NodeNotFound
+            | ErrorCode::SymbolNotFound
+            | ErrorCode::FileNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::ProjectExists => StatusCode::CONFLICT,
+            ErrorCode::InvalidRange | ErrorCode::InvalidQuery | ErrorCode::UnsupportedLanguage => {
+                StatusCode::BAD_REQUEST
+            }
+            ErrorCode::TooManyParseJobs => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Body of every error response
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: ErrorCode,
+    pub message: String,
+    pub request_id: String,
+}
+
+/// An error returned by a handler
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let code = match &e {
+            Error::ProjectNotFound(_) => ErrorCode::ProjectNotFound,
+            Error::SymbolNotFound(_) => ErrorCode::SymbolNotFound,
+            Error::ProjectExists(_) => ErrorCode::ProjectExists,
+            Error::UnsupportedLanguage(_) => ErrorCode::UnsupportedLanguage,
+            Error::Query(_) => ErrorCode::InvalidQuery,
+            Error::Db(_) | Error::DbOpen { .. } => ErrorCode::DatabaseError,
+            _ => ErrorCode::InternalError,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let request_id = REQUEST_ID.try_with(Clone::clone).unwrap_or_default();
+        if self.code.status().is_server_error() {
+            warn!("Request {} failed: {}", request_id, self.message);
+        }
+
+        let body = ErrorResponse {
+            error: self.code,
+            message: self.message,
+            request_id,
+        };
+        (self.code.status(), Json(body)).into_response()
+    }
+}
+
+/// Assign each request an id, reusing the client's `x-request-id` if given
+pub async fn assign_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
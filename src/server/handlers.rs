@@ -1,20 +1,24 @@
 //! HTTP request handlers
 
 use std::sync::Arc;
+use std::time::Instant;
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use tracing::{error, info};
 
+use super::error::{ApiError, ErrorCode};
 use super::AppState;
+use crate::core::graph::GraphBuilder;
 use crate::core::query::QueryExecutor;
 use crate::languages::LanguageRegistry;
-use crate::storage::models::ProjectRecord;
-use crate::storage::Database;
+use crate::storage::models::{ProjectRecord, TestFilter};
+use crate::storage::sqlite::DEFAULT_RESULT_LIMIT;
 
 // ==================== Response Types ====================
 
@@ -24,12 +28,6 @@ pub struct HealthResponse {
     pub version: String,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub message: String,
-}
-
 #[derive(Serialize)]
 pub struct ProjectResponse {
     pub project_id: i64,
@@ -42,6 +40,10 @@ pub struct ProjectResponse {
 pub struct LanguageInfo {
     pub id: String,
     pub extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_types: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_sniffing: Option<bool>,
 }
 
 // ==================== Request Types ====================
@@ -54,10 +56,14 @@ pub struct CreateProjectRequest {
     pub languages: Option<Vec<String>>,
 }
 
+#[derive(Deserialize)]
+pub struct RenameProjectRequest {
+    pub name: String,
+}
+
 #[derive(Deserialize)]
 pub struct ParseProjectRequest {
-    #[serde(default)]
-    pub incremental: bool,
+    /// Not supported: a parse always walks the whole project
     #[serde(default)]
     pub paths: Option<Vec<String>>,
 }
@@ -69,11 +75,38 @@ pub struct DefinitionQuery {
     pub column: u32,
 }
 
+#[derive(Deserialize)]
+pub struct LanguagesQuery {
+    /// Include node types and content sniffing support
+    #[serde(default)]
+    pub detail: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RangeQuery {
+    pub file: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveFileQuery {
+    pub path: String,
+}
+
 #[derive(Deserialize)]
 pub struct ReferencesQuery {
     pub file: String,
     pub line: u32,
     pub column: u32,
+    #[serde(default = "default_references_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+fn default_references_limit() -> u32 {
+    DEFAULT_RESULT_LIMIT
 }
 
 #[derive(Deserialize)]
@@ -83,6 +116,11 @@ pub struct CallgraphQuery {
     pub depth: u32,
     #[serde(default = "default_direction")]
     pub direction: String,
+    #[serde(default)]
+    pub call_sites: bool,
+    /// Include, exclude or only consider test files
+    #[serde(default)]
+    pub tests: TestFilter,
 }
 
 fn default_depth() -> u32 {
@@ -100,6 +138,14 @@ pub struct SymbolsQuery {
     pub symbol_type: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: u32,
+    /// Only return symbols declared in this file
+    pub file: Option<String>,
+    /// Match the whole name or qualified name instead of a substring
+    #[serde(default)]
+    pub exact: bool,
+    /// Include, exclude or only consider test files
+    #[serde(default)]
+    pub tests: TestFilter,
 }
 
 fn default_limit() -> u32 {
@@ -116,10 +162,18 @@ pub async fn health_check() -> Json<HealthResponse> {
     })
 }
 
+/// Metrics in the Prometheus text format
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
 /// List all projects
 pub async fn list_projects(
     State(_state): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, ApiError> {
     // For now, return empty list - would need to add a list_projects method
     let projects: Vec<ProjectResponse> = vec![];
     Ok(Json(projects))
@@ -129,9 +183,7 @@ pub async fn list_projects(
 pub async fn create_project(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateProjectRequest>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let db = state.db.lock().await;
-
+) -> Result<impl IntoResponse, ApiError> {
     let project = ProjectRecord {
         id: 0,
         name: req.name.clone(),
@@ -140,7 +192,7 @@ pub async fn create_project(
         updated_at: chrono::Utc::now(),
     };
 
-    match db.insert_project(&project) {
+    match state.write(|db| db.insert_project(&project)).await {
         Ok(id) => Ok((
             StatusCode::CREATED,
             Json(ProjectResponse {
@@ -150,13 +202,7 @@ pub async fn create_project(
                 status: "created".to_string(),
             }),
         )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "database_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -164,25 +210,44 @@ pub async fn create_project(
 pub async fn get_project(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, ApiError> {
     let db = state.db.lock().await;
 
     match db.get_project_status(id) {
         Ok(Some(status)) => Ok(Json(status)),
-        Ok(None) => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "not_found".to_string(),
-                message: format!("Project {} not found", id),
-            }),
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::ProjectNotFound,
+            format!("Project {} not found", id),
         )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "database_error".to_string(),
-                message: e.to_string(),
-            }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Rename a project
+pub async fn rename_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<RenameProjectRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let renamed = state
+        .write(|db| match db.rename_project(id, &req.name)? {
+            true => db.get_project_by_name(&req.name),
+            false => Ok(None),
+        })
+        .await;
+
+    match renamed {
+        Ok(Some(project)) => Ok(Json(ProjectResponse {
+            project_id: project.id,
+            name: project.name,
+            root_path: project.root_path,
+            status: "renamed".to_string(),
+        })),
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::ProjectNotFound,
+            format!("Project {} not found", id),
         )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -190,42 +255,127 @@ pub async fn get_project(
 pub async fn get_project_status(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, ApiError> {
     let db = state.db.lock().await;
 
     match db.get_project_status(id) {
         Ok(Some(status)) => Ok(Json(status)),
-        Ok(None) => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "not_found".to_string(),
-                message: format!("Project {} not found", id),
-            }),
-        )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "database_error".to_string(),
-                message: e.to_string(),
-            }),
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::ProjectNotFound,
+            format!("Project {} not found", id),
         )),
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Parse a project
+/// Start parsing a project in the background
+///
+/// Answers `202 Accepted` as soon as the job starts; the project's status
+/// gets a new `last_parsed_at` once it is done. Unchanged files are skipped,
+/// so every parse is incremental.
 pub async fn parse_project(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
     Json(req): Json<ParseProjectRequest>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    // This would need to spawn a background task for parsing
-    // For now, return a placeholder response
-    Ok(Json(serde_json::json!({
-        "status": "parsing",
-        "project_id": id,
-        "incremental": req.incremental,
-        "message": "Parsing started (not yet implemented as background task)"
-    })))
+) -> Result<impl IntoResponse, ApiError> {
+    if req.paths.is_some() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidQuery,
+            "Parsing only some paths is not supported, omit \"paths\" to parse the whole project",
+        ));
+    }
+
+    // Held until the parse finishes, so at most `max_parse_jobs` run at once
+    let permit = state.parse_jobs.clone().try_acquire_owned().map_err(|_| {
+        ApiError::new(
+            ErrorCode::TooManyParseJobs,
+            "Too many parse jobs running, retry later",
+        )
+    })?;
+
+    let project = state
+        .db
+        .lock()
+        .await
+        .get_project_status(id)?
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::ProjectNotFound,
+                format!("Project {} not found", id),
+            )
+        })?;
+    let root = std::path::PathBuf::from(&project.root_path);
+    if !root.is_dir() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidQuery,
+            format!("Project root {} is not a directory", project.root_path),
+        ));
+    }
+    state.metrics.record_parse_job();
+
+    let job_state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        match job_state.parse_project(&project.name, &root) {
+            Ok(report) => info!(
+                "Parsed project {}: {} files parsed, {} failed",
+                id, report.files_parsed, report.files_failed
+            ),
+            Err(e) => error!("Parsing project {} failed: {}", id, e),
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "status": "parsing",
+            "project_id": id,
+        })),
+    ))
+}
+
+/// Clear a project's `references` edges and resolve them again
+pub async fn reindex_references(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    if state.db.lock().await.get_project_status(id)?.is_none() {
+        return Err(ApiError::new(
+            ErrorCode::ProjectNotFound,
+            format!("Project {} not found", id),
+        ));
+    }
+
+    let summary = state
+        .write(|db| GraphBuilder::new(db).rebuild_cross_references(id))
+        .await?;
+    Ok(Json(summary))
+}
+
+/// Remove a deleted file from a project's graph and resolve references again
+pub async fn remove_file(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<RemoveFileQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    if state.db.lock().await.get_project_status(id)?.is_none() {
+        return Err(ApiError::new(
+            ErrorCode::ProjectNotFound,
+            format!("Project {} not found", id),
+        ));
+    }
+
+    let path = std::path::Path::new(&query.path);
+    match state
+        .write(|db| GraphBuilder::new(db).remove_file_and_relink(id, path))
+        .await?
+    {
+        Some(removal) => Ok(Json(removal)),
+        None => Err(ApiError::new(
+            ErrorCode::FileNotFound,
+            format!("File {} is not indexed in project {}", query.path, id),
+        )),
+    }
 }
 
 /// Find symbol definition
@@ -233,31 +383,17 @@ pub async fn find_definition(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
     Query(query): Query<DefinitionQuery>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let query_db = match Database::open(&state.db_path) {
-        Ok(db) => db,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "database_error".to_string(),
-                    message: e.to_string(),
-                }),
-            ));
-        }
-    };
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db).with_cache(state.cache.clone());
 
-    let executor = QueryExecutor::new(query_db);
+    let started = Instant::now();
+    let result = executor.find_definition(id, &query.file, query.line, query.column);
+    state.metrics.record_query(started.elapsed());
 
-    match executor.find_definition(id, &query.file, query.line, query.column) {
+    match result {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -266,31 +402,24 @@ pub async fn find_references(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
     Query(query): Query<ReferencesQuery>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let query_db = match Database::open(&state.db_path) {
-        Ok(db) => db,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "database_error".to_string(),
-                    message: e.to_string(),
-                }),
-            ));
-        }
-    };
-
-    let executor = QueryExecutor::new(query_db);
-
-    match executor.find_references(id, &query.file, query.line, query.column) {
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db).with_cache(state.cache.clone());
+
+    let started = Instant::now();
+    let result = executor.find_references(
+        id,
+        &query.file,
+        query.line,
+        query.column,
+        query.limit,
+        query.offset,
+    );
+    state.metrics.record_query(started.elapsed());
+
+    match result {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -299,31 +428,25 @@ pub async fn get_callgraph(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
     Query(query): Query<CallgraphQuery>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let query_db = match Database::open(&state.db_path) {
-        Ok(db) => db,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "database_error".to_string(),
-                    message: e.to_string(),
-                }),
-            ));
-        }
-    };
-
-    let executor = QueryExecutor::new(query_db);
-
-    match executor.get_callgraph(id, &query.symbol, query.depth, &query.direction) {
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db)
+        .with_cache(state.cache.clone())
+        .with_test_filter(query.tests);
+
+    let started = Instant::now();
+    let result = executor.get_callgraph(
+        id,
+        &query.symbol,
+        query.depth,
+        &query.direction,
+        query.call_sites,
+    );
+    state.metrics.record_query(started.elapsed());
+
+    match result {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -332,36 +455,116 @@ pub async fn search_symbols(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
     Query(query): Query<SymbolsQuery>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let query_db = match Database::open(&state.db_path) {
-        Ok(db) => db,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "database_error".to_string(),
-                    message: e.to_string(),
-                }),
-            ));
+) -> Result<impl IntoResponse, ApiError> {
+    // The per-file search only matches substrings
+    if query.file.is_some() && query.exact {
+        return Err(ApiError::new(
+            ErrorCode::InvalidQuery,
+            "exact cannot be combined with file",
+        ));
+    }
+
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db)
+        .with_cache(state.cache.clone())
+        .with_test_filter(query.tests);
+
+    let started = Instant::now();
+    let result = match &query.file {
+        Some(file) => executor.search_symbols_in_file(
+            id,
+            file,
+            &query.query,
+            query.symbol_type.as_deref(),
+            query.limit,
+        ),
+        None if query.exact => executor.search_symbols_exact(
+            id,
+            &query.query,
+            query.symbol_type.as_deref(),
+            query.limit,
+        ),
+        None => {
+            executor.search_symbols(id, &query.query, query.symbol_type.as_deref(), query.limit)
         }
     };
+    state.metrics.record_query(started.elapsed());
 
-    let executor = QueryExecutor::new(query_db);
-
-    match executor.search_symbols(id, &query.query, query.symbol_type.as_deref(), query.limit) {
+    match result {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get a node with its incoming and outgoing edges
+pub async fn get_node(
+    State(state): State<Arc<AppState>>,
+    Path((id, node_id)): Path<(i64, i64)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db);
+
+    let started = Instant::now();
+    let result = executor.get_node_detail(id, node_id);
+    state.metrics.record_query(started.elapsed());
+
+    match result {
+        Ok(Some(detail)) => Ok(Json(detail)),
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::NodeNotFound,
+            format!("Node {} not found in project {}", node_id, id),
         )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Find the nodes overlapping a line range of a file
+pub async fn find_nodes_in_range(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<RangeQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    if query.start > query.end {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRange,
+            format!("Range start {} is after end {}", query.start, query.end),
+        ));
+    }
+
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db);
+
+    let started = Instant::now();
+    let result = executor.find_nodes_in_range(id, &query.file, query.start, query.end);
+    state.metrics.record_query(started.elapsed());
+
+    match result {
+        Ok(result) => Ok(Json(result)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the symbols enclosing a position, outermost first
+pub async fn symbol_stack(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<DefinitionQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let executor = QueryExecutor::new(&*db).with_cache(state.cache.clone());
+
+    let started = Instant::now();
+    let result = executor.symbol_stack(id, &query.file, query.line, query.column);
+    state.metrics.record_query(started.elapsed());
+
+    match result {
+        Ok(result) => Ok(Json(result)),
+        Err(e) => Err(e.into()),
     }
 }
 
 /// List supported languages
-pub async fn list_languages() -> Json<Vec<LanguageInfo>> {
+pub async fn list_languages(Query(query): Query<LanguagesQuery>) -> Json<Vec<LanguageInfo>> {
     let registry = LanguageRegistry::new();
     let languages: Vec<LanguageInfo> = registry
         .list_languages()
@@ -369,6 +572,10 @@ pub async fn list_languages() -> Json<Vec<LanguageInfo>> {
         .map(|l| LanguageInfo {
             id: l.language_id().to_string(),
             extensions: l.file_extensions().iter().map(|s| s.to_string()).collect(),
+            node_types: query
+                .detail
+                .then(|| l.node_types().iter().map(|s| s.to_string()).collect()),
+            content_sniffing: query.detail.then(|| l.sniffs_content()),
         })
         .collect();
 
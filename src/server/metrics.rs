@@ -0,0 +1,146 @@
+//! Prometheus-compatible server metrics
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use super::AppState;
+
+/// Upper bounds (in seconds) of the query latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0];
+
+/// Counters and histograms exposed on `/metrics`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests_total: AtomicU64,
+    parse_jobs_total: AtomicU64,
+    db_queries_total: AtomicU64,
+    query_latency: Histogram,
+}
+
+/// A fixed-bucket histogram; bucket counts are stored cumulatively
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// Count an HTTP request
+    pub fn record_request(&self) {
+        self.requests_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a parse job
+    pub fn record_parse_job(&self) {
+        self.parse_jobs_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a graph query and record how long it took
+    pub fn record_query(&self, elapsed: Duration) {
+        self.db_queries_total.fetch_add(1, Ordering::Relaxed);
+        self.query_latency.observe(elapsed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        write_counter(
+            &mut out,
+            "codegraph_http_requests_total",
+            "Total HTTP requests received",
+            self.requests_total.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "codegraph_parse_jobs_total",
+            "Total parse jobs requested",
+            self.parse_jobs_total.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "codegraph_db_queries_total",
+            "Total graph queries executed against the database",
+            self.db_queries_total.load(Ordering::Relaxed),
+        );
+
+        let name = "codegraph_query_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Graph query latency in seconds", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.query_latency.buckets) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{le=\"{}\"}} {}",
+                name,
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.query_latency.count.load(Ordering::Relaxed);
+        let sum = self.query_latency.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, count);
+
+        out
+    }
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Middleware counting every request that reaches the router
+pub async fn track_requests(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    state.metrics.record_request();
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::default();
+        metrics.record_query(Duration::from_millis(3));
+        metrics.record_query(Duration::from_millis(200));
+
+        let text = metrics.render();
+        assert!(text.contains("codegraph_db_queries_total 2"));
+        assert!(text.contains("codegraph_query_duration_seconds_bucket{le=\"0.001\"} 0"));
+        assert!(text.contains("codegraph_query_duration_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(text.contains("codegraph_query_duration_seconds_bucket{le=\"0.25\"} 2"));
+        assert!(text.contains("codegraph_query_duration_seconds_bucket{le=\"+Inf\"} 2"));
+        assert!(text.contains("codegraph_query_duration_seconds_count 2"));
+    }
+}
@@ -1,57 +1,877 @@
 //! HTTP server for the CodeGraph service
 
+mod error;
 mod handlers;
+mod metrics;
 mod routes;
 
+use std::future::Future;
 use std::net::SocketAddr;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::Result;
-use axum::Router;
-use tokio::sync::Mutex;
-use tower_http::cors::{Any, CorsLayer};
+use axum::http::HeaderValue;
+use axum::{middleware, Router};
+use tokio::sync::{Mutex, Semaphore};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
+use crate::core::cache::QueryCache;
+use crate::core::config::{DatabaseConfig, ParseConfig, ServerConfig};
+use crate::core::{parse_project_into, ParseOptions, ParseReport};
+use crate::error::{Error, Result};
+use crate::storage::sqlite::{busy_backoff, IN_MEMORY_PATH};
 use crate::storage::Database;
 
+pub use metrics::Metrics;
+
 /// Shared application state
 pub struct AppState {
-    pub db_path: PathBuf,
     pub db: Mutex<Database>,
+    pub metrics: Metrics,
+    pub cache: Arc<QueryCache>,
+    /// One permit per parse job allowed to run at once
+    pub parse_jobs: Arc<Semaphore>,
+    /// The database parse jobs write to
+    pub database: DatabaseConfig,
+    /// Settings parse jobs run with
+    pub parse: ParseConfig,
+}
+
+impl AppState {
+    /// Run `write` in one transaction on the shared connection, retrying the
+    /// whole transaction while another process holds the write lock
+    ///
+    /// Waits between attempts with a non-blocking sleep and without holding
+    /// the connection, so other requests keep being served.
+    pub async fn write<T>(&self, mut write: impl FnMut(&Database) -> Result<T> + Send) -> Result<T>
+    where
+        T: Send,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let db = self.db.lock().await;
+                db.try_write_transaction(|| write(&db))
+            };
+            match result {
+                Err(e) if e.is_busy() => match busy_backoff(attempt) {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Parse a project on a connection of its own, so queries on the shared
+    /// connection keep being answered while it runs
+    ///
+    /// An in-memory database is only reachable through the shared
+    /// connection, which is then held for the whole parse. Blocks until the
+    /// parse is done, so call it off the async runtime.
+    pub fn parse_project(&self, name: &str, root: &Path) -> Result<ParseReport> {
+        let options = ParseOptions::default();
+        if self.database.path == Path::new(IN_MEMORY_PATH) {
+            let db = self.db.blocking_lock();
+            return parse_project_into(&*db, name, root, &options, &self.parse);
+        }
+        let db = Database::open_with_options(&self.database.path, &self.database.open_options())?;
+        parse_project_into(db, name, root, &options, &self.parse)
+    }
+}
+
+/// Run the HTTP server until SIGINT or SIGTERM is received
+pub async fn run_server(
+    config: &ServerConfig,
+    database: &DatabaseConfig,
+    parse: &ParseConfig,
+) -> Result<()> {
+    run_server_with_shutdown(config, database, parse, shutdown_signal()).await
+}
+
+/// Run the HTTP server until the given shutdown future completes
+///
+/// In-flight requests are allowed to finish before this returns.
+pub async fn run_server_with_shutdown<F>(
+    config: &ServerConfig,
+    database: &DatabaseConfig,
+    parse: &ParseConfig,
+    shutdown: F,
+) -> Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let state = open_state(config, database, parse)?;
+    let app = build_router(state, cors_layer(config)?);
+
+    // Start server
+    let (host, port) = (&config.host, config.port);
+    let addr = format!("{}:{}", host, port)
+        .parse::<SocketAddr>()
+        .map_err(|e| Error::Server(format!("Invalid address {}:{}: {}", host, port, e)))?;
+    info!("Starting server on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::Server(format!("Failed to bind {}: {}", addr, e)))?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| Error::Server(e.to_string()))?;
+
+    info!("Server shut down cleanly");
+    Ok(())
 }
 
-/// Run the HTTP server
-pub async fn run_server(host: &str, port: u16, db_path: &Path) -> Result<()> {
-    // Initialize database
-    let db = Database::open(db_path)?;
+/// Open the database and build the state shared by every handler
+///
+/// Every request goes through the one connection in [`AppState::db`], so an
+/// in-memory database (`--database :memory:`) is seen by all of them.
+fn open_state(
+    config: &ServerConfig,
+    database: &DatabaseConfig,
+    parse: &ParseConfig,
+) -> Result<Arc<AppState>> {
+    let db = Database::open_with_options(&database.path, &database.open_options())?;
     db.init_schema()?;
 
-    let state = Arc::new(AppState {
-        db_path: db_path.to_path_buf(),
+    Ok(Arc::new(AppState {
         db: Mutex::new(db),
-    });
+        metrics: Metrics::default(),
+        cache: Arc::new(QueryCache::default()),
+        parse_jobs: Arc::new(Semaphore::new(config.max_parse_jobs)),
+        database: database.clone(),
+        parse: parse.clone(),
+    }))
+}
+
+/// Build the CORS layer from the server configuration
+///
+/// Returns `None` when CORS is disabled. Only the configured origins are
+/// allowed, unless the list contains `*`.
+fn cors_layer(config: &ServerConfig) -> Result<Option<CorsLayer>> {
+    if !config.cors_enabled {
+        return Ok(None);
+    }
 
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let allow_origin = if config.cors_origins.iter().any(|o| o == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        let origins = config
+            .cors_origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o)
+                    .map_err(|_| Error::Config(format!("Invalid CORS origin: {}", o)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
 
-    // Build router
-    let app = Router::new()
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any),
+    ))
+}
+
+/// Build the application router with its middleware
+fn build_router(state: Arc<AppState>, cors: Option<CorsLayer>) -> Router {
+    let router = Router::new()
         .merge(routes::api_routes())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track_requests,
+        ))
         .layer(TraceLayer::new_for_http())
-        .layer(cors)
-        .with_state(state);
+        .layer(middleware::from_fn(error::assign_request_id));
 
-    // Start server
-    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
-    info!("Starting server on {}", addr);
+    let router = match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router.with_state(state)
+}
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to install Ctrl-C handler: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
 
-    Ok(())
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::models::{EdgeRecord, FileRecord, NodeRecord, ProjectRecord};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn app_state(db: Database) -> Arc<AppState> {
+        Arc::new(AppState {
+            db: Mutex::new(db),
+            metrics: Metrics::default(),
+            cache: Arc::new(QueryCache::default()),
+            parse_jobs: Arc::new(Semaphore::new(ServerConfig::default().max_parse_jobs)),
+            database: DatabaseConfig {
+                path: std::path::PathBuf::from(IN_MEMORY_PATH),
+                ..DatabaseConfig::default()
+            },
+            parse: ParseConfig::default(),
+        })
+    }
+
+    async fn get_body(app: &Router, uri: &str) -> String {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn metric_value(text: &str, name: &str) -> u64 {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| panic!("metric {} missing", name))
+    }
+
+    fn create_project_with_file(db: &Database, name: &str) -> (i64, i64) {
+        let project_id = db
+            .insert_project(&ProjectRecord {
+                id: 0,
+                name: name.to_string(),
+                root_path: format!("/{}", name),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let file_id = db
+            .insert_file(&FileRecord {
+                id: 0,
+                project_id,
+                path: format!("/{}/main.go", name),
+                language: "go".to_string(),
+                content_hash: "abc".to_string(),
+                parsed_at: chrono::Utc::now(),
+                line_count: 3,
+                is_test: false,
+                encoding: None,
+            })
+            .unwrap();
+        (project_id, file_id)
+    }
+
+    fn create_node(db: &Database, file_id: i64, node_type: &str, name: &str) -> i64 {
+        db.insert_node(&NodeRecord {
+            id: 0,
+            file_id,
+            node_type: node_type.to_string(),
+            name: name.to_string(),
+            qualified_name: Some(format!("main.{}", name)),
+            start_line: 1,
+            start_column: 1,
+            end_line: 3,
+            end_column: 2,
+            attributes: None,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_missing_project_returns_stable_error_code() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let app = build_router(app_state(db), None);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/projects/999")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let header_id = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "PROJECT_NOT_FOUND");
+        assert_eq!(body["request_id"], header_id.as_str());
+        assert!(!header_id.is_empty());
+
+        // A client-supplied request id is echoed back
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/projects/999/status")
+                    .header("x-request-id", "abc-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], "abc-123");
+    }
+
+    async fn post_parse(app: &Router, project_id: i64, body: &'static str) -> StatusCode {
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/api/v1/projects/{}/parse", project_id))
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_parse_jobs_beyond_limit_are_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("main.go"),
+            "package main\n\nfunc Serve() {}\n",
+        )
+        .unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let project_id = db
+            .insert_project(&ProjectRecord {
+                id: 0,
+                name: "busy".to_string(),
+                root_path: root.path().to_string_lossy().to_string(),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let state = app_state(db);
+        let app = build_router(state.clone(), None);
+
+        // Occupy every slot as if that many parses were still running
+        let limit = ServerConfig::default().max_parse_jobs;
+        let running = state
+            .parse_jobs
+            .clone()
+            .acquire_many_owned(limit as u32)
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(
+                post_parse(&app, project_id, "{}").await,
+                StatusCode::TOO_MANY_REQUESTS
+            );
+        }
+
+        // A subset of paths is rejected before a slot is needed
+        assert_eq!(
+            post_parse(&app, project_id, r#"{"paths": ["main.go"]}"#).await,
+            StatusCode::BAD_REQUEST
+        );
+
+        drop(running);
+        assert_eq!(
+            post_parse(&app, project_id, "{}").await,
+            StatusCode::ACCEPTED
+        );
+
+        // The job holds its slot until the parse is done
+        let all_free = state.parse_jobs.acquire_many(limit as u32);
+        let all_free = tokio::time::timeout(Duration::from_secs(10), all_free)
+            .await
+            .expect("parse job did not finish")
+            .unwrap();
+        drop(all_free);
+        let body = get_body(
+            &app,
+            &format!("/api/v1/projects/{}/symbols?query=Serve", project_id),
+        )
+        .await;
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["count"], 1);
+
+        assert_eq!(
+            post_parse(&app, project_id + 1, "{}").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exact_symbol_search_rejects_file_filter() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let (project_id, file_id) = create_project_with_file(&db, "exact");
+        create_node(&db, file_id, "function", "Serve");
+        create_node(&db, file_id, "function", "ServeHTTP");
+        let app = build_router(app_state(db), None);
+
+        let body = get_body(
+            &app,
+            &format!(
+                "/api/v1/projects/{}/symbols?query=Serve&exact=true",
+                project_id
+            ),
+        )
+        .await;
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["count"], 1);
+
+        let uri = format!(
+            "/api/v1/projects/{}/symbols?query=Serve&exact=true&file=/exact/main.go",
+            project_id
+        );
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "INVALID_QUERY");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_requests() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+
+        let app = build_router(app_state(db), None);
+
+        let before = get_body(&app, "/metrics").await;
+        get_body(&app, "/api/v1/health").await;
+        get_body(&app, "/api/v1/projects/1/symbols?query=Foo").await;
+        get_body(&app, "/api/v1/projects/1/symbols?query=Bar").await;
+        let after = get_body(&app, "/metrics").await;
+
+        let requests_before = metric_value(&before, "codegraph_http_requests_total");
+        let requests_after = metric_value(&after, "codegraph_http_requests_total");
+        assert_eq!(requests_after - requests_before, 4);
+        assert_eq!(metric_value(&after, "codegraph_db_queries_total"), 2);
+        assert_eq!(
+            metric_value(&after, "codegraph_query_duration_seconds_count"),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_queries_share_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+
+        let (project_id, file_id) = create_project_with_file(&db, "shared");
+        create_node(&db, file_id, "function", "main");
+
+        let app = build_router(app_state(db), None);
+
+        let uris = [
+            format!("/api/v1/projects/{}/symbols?query=main", project_id),
+            format!("/api/v1/projects/{}/callgraph?symbol=main", project_id),
+            format!(
+                "/api/v1/projects/{}/definition?file=/shared/main.go&line=1&column=1",
+                project_id
+            ),
+            format!(
+                "/api/v1/projects/{}/references?file=/shared/main.go&line=1&column=1",
+                project_id
+            ),
+        ];
+
+        let mut requests = Vec::new();
+        for i in 0..32 {
+            let app = app.clone();
+            let kind = i % uris.len();
+            let uri = uris[kind].clone();
+            requests.push(tokio::spawn(async move {
+                let response = app
+                    .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    kind,
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                )
+            }));
+        }
+
+        for request in requests {
+            let (kind, status, body) = request.await.unwrap();
+            assert_eq!(status, StatusCode::OK, "{}: {}", uris[kind], body);
+            match kind {
+                0 => {
+                    assert_eq!(body["count"], 1);
+                    assert_eq!(body["symbols"][0]["name"], "main");
+                }
+                1 => assert_eq!(body["center"]["name"], "main"),
+                2 => {
+                    assert_eq!(body["found"], true);
+                    assert_eq!(body["definition"]["name"], "main");
+                }
+                _ => {
+                    assert_eq!(body["count"], 0);
+                    assert_eq!(body["references"], serde_json::json!([]));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_node_with_edges() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let (project_id, file_id) = create_project_with_file(&db, "nodes");
+        let main_id = create_node(&db, file_id, "function", "main");
+        let call_id = create_node(&db, file_id, "call", "helper");
+        let closure_id = create_node(&db, file_id, "closure", "<closure>");
+        for (source_id, target_id, edge_type) in [
+            (main_id, call_id, "calls"),
+            (closure_id, main_id, "references"),
+        ] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: edge_type.to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+        let (other_project_id, _) = create_project_with_file(&db, "other");
+
+        let app = build_router(app_state(db), None);
+
+        let body = get_body(
+            &app,
+            &format!("/api/v1/projects/{}/nodes/{}", project_id, main_id),
+        )
+        .await;
+        let detail: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(detail["node"]["name"], "main");
+        assert_eq!(detail["outgoing"].as_array().unwrap().len(), 1);
+        assert_eq!(detail["outgoing"][0]["edge_type"], "calls");
+        assert_eq!(detail["outgoing"][0]["neighbor"]["name"], "helper");
+        assert_eq!(detail["incoming"].as_array().unwrap().len(), 1);
+        assert_eq!(detail["incoming"][0]["edge_type"], "references");
+        assert_eq!(detail["incoming"][0]["neighbor"]["node_type"], "closure");
+
+        for uri in [
+            format!("/api/v1/projects/{}/nodes/{}", other_project_id, main_id),
+            format!("/api/v1/projects/{}/nodes/{}", project_id, main_id + 100),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_references_links_unresolved() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let (project_id, file_id) = create_project_with_file(&db, "reindex");
+        let main_id = create_node(&db, file_id, "function", "main");
+        let helper_id = create_node(&db, file_id, "function", "helper");
+        let unresolved_id = create_node(&db, file_id, "reference", "helper");
+        let stale_id = create_node(&db, file_id, "reference", "helper");
+        create_node(&db, file_id, "reference", "missing");
+        db.insert_edge(&EdgeRecord {
+            id: 0,
+            source_id: stale_id,
+            target_id: main_id,
+            edge_type: "references".to_string(),
+            attributes: None,
+        })
+        .unwrap();
+        let state = app_state(db);
+        let app = build_router(state.clone(), None);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/api/v1/projects/{}/reindex-references",
+                project_id
+            ))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let summary: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(summary["cleared"], 1);
+        assert_eq!(summary["resolved"], 2);
+        assert_eq!(summary["unresolved"], 1);
+
+        let db = state.db.lock().await;
+        for ref_id in [unresolved_id, stale_id] {
+            let target = db.find_reference_target(ref_id).unwrap().unwrap();
+            assert_eq!(target.id, helper_id);
+        }
+        drop(db);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/projects/999/reindex-references")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_remove_file() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let (project_id, file_id) = create_project_with_file(&db, "remove");
+        create_node(&db, file_id, "function", "helper");
+        let state = app_state(db);
+        let app = build_router(state.clone(), None);
+
+        let delete = |path: &str| {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!(
+                    "/api/v1/projects/{}/files?path={}",
+                    project_id, path
+                ))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(delete("/remove/main.go"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let removal: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(removal["path"], "/remove/main.go");
+        assert_eq!(removal["relinked"], 0);
+
+        let db = state.db.lock().await;
+        assert!(db
+            .find_symbol_by_name(project_id, "helper")
+            .unwrap()
+            .is_none());
+        drop(db);
+
+        let response = app.oneshot(delete("/remove/main.go")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "FILE_NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_languages_detail_lists_node_types() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let app = build_router(app_state(db), None);
+
+        let body = get_body(&app, "/api/v1/languages").await;
+        let languages: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(languages[0].get("node_types").is_none());
+
+        let body = get_body(&app, "/api/v1/languages?detail=true").await;
+        let languages: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        let node_types = |id: &str| -> Vec<String> {
+            let language = languages.iter().find(|l| l["id"] == id).unwrap();
+            assert!(language["content_sniffing"].is_boolean());
+            serde_json::from_value(language["node_types"].clone()).unwrap()
+        };
+        assert!(node_types("java").contains(&"class".to_string()));
+        assert!(node_types("go").contains(&"struct".to_string()));
+    }
+
+    async fn allowed_origin(app: &Router, origin: &str) -> Option<String> {
+        let request = Request::builder()
+            .uri("/api/v1/health")
+            .header("origin", origin)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_cors_restricted_to_configured_origins() {
+        let config = ServerConfig {
+            cors_origins: vec!["https://app.example.com".to_string()],
+            ..ServerConfig::default()
+        };
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let app = build_router(app_state(db), cors_layer(&config).unwrap());
+
+        assert_eq!(
+            allowed_origin(&app, "https://app.example.com")
+                .await
+                .as_deref(),
+            Some("https://app.example.com")
+        );
+        assert_eq!(allowed_origin(&app, "https://evil.example.com").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cors_wildcard_and_disabled() {
+        let wildcard = cors_layer(&ServerConfig::default()).unwrap();
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let app = build_router(app_state(db), wildcard);
+        assert_eq!(
+            allowed_origin(&app, "https://any.example.com")
+                .await
+                .as_deref(),
+            Some("*")
+        );
+
+        let disabled = ServerConfig {
+            cors_enabled: false,
+            ..ServerConfig::default()
+        };
+        assert!(cors_layer(&disabled).unwrap().is_none());
+
+        let invalid = ServerConfig {
+            cors_origins: vec!["bad\norigin".to_string()],
+            ..ServerConfig::default()
+        };
+        assert!(matches!(cors_layer(&invalid), Err(Error::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_database() {
+        let database = DatabaseConfig {
+            path: std::path::PathBuf::from(crate::storage::sqlite::IN_MEMORY_PATH),
+            ..DatabaseConfig::default()
+        };
+        let state =
+            open_state(&ServerConfig::default(), &database, &ParseConfig::default()).unwrap();
+        let app = build_router(state, None);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The schema was created, so project listing works
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/projects")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server = tokio::spawn(async move {
+            let config = ServerConfig {
+                port: 0,
+                ..ServerConfig::default()
+            };
+            let database = DatabaseConfig {
+                path: db_path,
+                ..DatabaseConfig::default()
+            };
+            run_server_with_shutdown(&config, &database, &ParseConfig::default(), async {
+                let _ = rx.await;
+            })
+            .await
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        tx.send(()).unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server did not shut down in time")
+            .unwrap();
+        assert!(result.is_ok());
+    }
 }
@@ -3,7 +3,7 @@
 use std::sync::Arc;
 
 use axum::{
-    routing::{get, post},
+    routing::{delete, get, patch, post},
     Router,
 };
 
@@ -15,17 +15,49 @@ pub fn api_routes() -> Router<Arc<AppState>> {
     Router::new()
         // Health check
         .route("/api/v1/health", get(handlers::health_check))
+        // Metrics
+        .route("/metrics", get(handlers::metrics))
         // Project management
         .route("/api/v1/projects", get(handlers::list_projects))
         .route("/api/v1/projects", post(handlers::create_project))
         .route("/api/v1/projects/:id", get(handlers::get_project))
-        .route("/api/v1/projects/:id/status", get(handlers::get_project_status))
+        .route("/api/v1/projects/:id", patch(handlers::rename_project))
+        .route(
+            "/api/v1/projects/:id/status",
+            get(handlers::get_project_status),
+        )
         .route("/api/v1/projects/:id/parse", post(handlers::parse_project))
+        .route("/api/v1/projects/:id/files", delete(handlers::remove_file))
+        .route(
+            "/api/v1/projects/:id/reindex-references",
+            post(handlers::reindex_references),
+        )
         // Query endpoints
-        .route("/api/v1/projects/:id/definition", get(handlers::find_definition))
-        .route("/api/v1/projects/:id/references", get(handlers::find_references))
-        .route("/api/v1/projects/:id/callgraph", get(handlers::get_callgraph))
-        .route("/api/v1/projects/:id/symbols", get(handlers::search_symbols))
+        .route(
+            "/api/v1/projects/:id/definition",
+            get(handlers::find_definition),
+        )
+        .route(
+            "/api/v1/projects/:id/references",
+            get(handlers::find_references),
+        )
+        .route(
+            "/api/v1/projects/:id/callgraph",
+            get(handlers::get_callgraph),
+        )
+        .route(
+            "/api/v1/projects/:id/symbols",
+            get(handlers::search_symbols),
+        )
+        .route(
+            "/api/v1/projects/:id/nodes/:node_id",
+            get(handlers::get_node),
+        )
+        .route(
+            "/api/v1/projects/:id/range",
+            get(handlers::find_nodes_in_range),
+        )
+        .route("/api/v1/projects/:id/stack", get(handlers::symbol_stack))
         // Languages
         .route("/api/v1/languages", get(handlers::list_languages))
 }
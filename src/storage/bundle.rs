@@ -0,0 +1,127 @@
+//! Self-contained graph bundles for read-only distribution
+//!
+//! A bundle is gzip-compressed JSON holding one project's files, nodes and
+//! edges with their database IDs. It does not depend on SQLite, so prebuilt
+//! indexes can be shipped to users who never run the parser; opening one
+//! with [`Database::open_bundle`] loads it into an in-memory database that
+//! the regular query functions accept.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::storage::models::{EdgeRecord, FileRecord, NodeRecord, ProjectRecord};
+use crate::storage::Database;
+
+/// Marks a file as a codegraph bundle
+pub const BUNDLE_FORMAT: &str = "codegraph-bundle";
+
+/// Bundle layout written by this version; bumped on incompatible changes
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// One project's graph, detached from the database it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphBundle {
+    pub format: String,
+    pub version: u32,
+    pub project: ProjectRecord,
+    pub files: Vec<FileRecord>,
+    pub nodes: Vec<NodeRecord>,
+    pub edges: Vec<EdgeRecord>,
+}
+
+impl GraphBundle {
+    /// Collect everything needed to query a project
+    ///
+    /// Edges into other projects are dropped, since their targets are not
+    /// part of the bundle.
+    pub fn from_database(db: &Database, project_id: i64) -> Result<Self> {
+        let project = db
+            .list_projects()?
+            .into_iter()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| Error::ProjectNotFound(project_id.to_string()))?;
+
+        let files = db.get_project_files(project_id)?;
+        let nodes: Vec<NodeRecord> = db
+            .get_project_nodes(project_id)?
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect();
+
+        let node_ids: HashSet<i64> = nodes.iter().map(|n| n.id).collect();
+        let edges = db
+            .get_project_edges(project_id)?
+            .into_iter()
+            .filter(|e| node_ids.contains(&e.target_id))
+            .collect();
+
+        Ok(Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            project,
+            files,
+            nodes,
+            edges,
+        })
+    }
+
+    /// Write the bundle to a file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).map_err(|e| Error::io(path, e))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self).map_err(|e| Error::io(path, e.into()))?;
+        encoder.finish().map_err(|e| Error::io(path, e))?;
+        Ok(())
+    }
+
+    /// Read a bundle, rejecting other files and newer bundle versions
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|e| Error::io(path, e))?;
+        let bundle: Self =
+            serde_json::from_reader(GzDecoder::new(BufReader::new(file))).map_err(|e| {
+                Error::Parse {
+                    path: path.to_path_buf(),
+                    message: format!("not a codegraph bundle: {}", e),
+                }
+            })?;
+
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(Error::Parse {
+                path: path.to_path_buf(),
+                message: format!("unexpected bundle format {:?}", bundle.format),
+            });
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(Error::Parse {
+                path: path.to_path_buf(),
+                message: format!(
+                    "bundle version {} is newer than the supported version {}",
+                    bundle.version, BUNDLE_VERSION
+                ),
+            });
+        }
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_rejects_non_bundle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("plain.json");
+        std::fs::write(&path, "{}").unwrap();
+
+        assert!(matches!(GraphBundle::read(&path), Err(Error::Parse { .. })));
+    }
+}
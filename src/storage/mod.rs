@@ -1,6 +1,12 @@
 //! Storage layer for persisting code graph data
 
+#[cfg(feature = "service")]
+pub mod bundle;
 pub mod models;
+#[cfg(feature = "service")]
 pub mod sqlite;
 
+#[cfg(feature = "service")]
+pub use bundle::GraphBundle;
+#[cfg(feature = "service")]
 pub use sqlite::Database;
@@ -1,5 +1,7 @@
 //! Data models for the code graph storage
 
+use std::path::{Path, PathBuf};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +15,26 @@ pub struct ProjectRecord {
     pub updated_at: DateTime<Utc>,
 }
 
+impl ProjectRecord {
+    /// Location on disk of a stored file path
+    ///
+    /// Paths stored relative to the project root are joined onto it;
+    /// absolute paths are returned as they are.
+    pub fn resolve_path(&self, stored: &str) -> PathBuf {
+        resolve_stored_path(&self.root_path, stored)
+    }
+}
+
+/// Join a stored file path onto its project root unless it is absolute
+pub(crate) fn resolve_stored_path(root_path: &str, stored: &str) -> PathBuf {
+    let stored = Path::new(stored);
+    if stored.is_absolute() {
+        stored.to_path_buf()
+    } else {
+        Path::new(root_path).join(stored)
+    }
+}
+
 /// File record in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileRecord {
@@ -22,6 +44,65 @@ pub struct FileRecord {
     pub language: String,
     pub content_hash: String,
     pub parsed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub line_count: u32,
+    /// Whether the file holds tests, judged by its name and directory
+    #[serde(default)]
+    pub is_test: bool,
+    /// Character encoding the source was decoded from, such as `UTF-8`
+    /// or `windows-1252`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// Which files a query considers, by whether they hold tests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFilter {
+    /// Test and non-test files alike
+    #[default]
+    All,
+    /// Only non-test files
+    Exclude,
+    /// Only test files
+    Only,
+}
+
+impl TestFilter {
+    /// The required `is_test` value, or `None` when any file matches
+    pub fn is_test(self) -> Option<bool> {
+        match self {
+            TestFilter::All => None,
+            TestFilter::Exclude => Some(false),
+            TestFilter::Only => Some(true),
+        }
+    }
+
+    /// Whether a file with the given `is_test` flag passes the filter
+    pub fn allows(self, is_test: bool) -> bool {
+        match self.is_test() {
+            Some(wanted) => wanted == is_test,
+            None => true,
+        }
+    }
+}
+
+/// Per-file statistics for dashboards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMetrics {
+    pub path: String,
+    pub language: String,
+    pub line_count: u32,
+    pub node_count: u32,
+}
+
+/// Per-language totals for a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageCounts {
+    pub language: String,
+    pub file_count: u32,
+    pub node_count: u32,
+    pub edge_count: u32,
 }
 
 /// Node record in the database (symbols: functions, classes, etc.)
@@ -39,6 +120,142 @@ pub struct NodeRecord {
     pub attributes: Option<String>,
 }
 
+impl NodeRecord {
+    /// Content-derived id that, unlike `id`, survives re-parsing; see
+    /// [`stable_node_id`]
+    pub fn stable_id(&self, file_path: &str) -> String {
+        stable_node_id(
+            file_path,
+            &self.node_type,
+            self.qualified_name.as_deref().unwrap_or(&self.name),
+            self.start_line,
+            self.start_column,
+        )
+    }
+
+    /// Whether this node is a declaration without a body, such as an
+    /// interface or abstract method
+    pub fn is_abstract(&self) -> bool {
+        self.attribute("abstract")
+            .and_then(|b| b.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Number of arguments a call node passes, when its language records it
+    pub fn call_args(&self) -> Option<usize> {
+        self.attribute("args")?.as_u64().map(|n| n as usize)
+    }
+
+    /// Whether a method or function can be called with `args` arguments
+    ///
+    /// Nodes without a recorded arity accept any count. A varargs parameter
+    /// matches zero or more trailing arguments.
+    pub fn accepts_args(&self, args: usize) -> bool {
+        let Some(arity) = self.attribute("arity").and_then(|a| a.as_u64()) else {
+            return true;
+        };
+        let arity = arity as usize;
+        if self.attribute("varargs").and_then(|v| v.as_bool()) == Some(true) {
+            args + 1 >= arity
+        } else {
+            args == arity
+        }
+    }
+
+    /// For a reference to a field or variable, `read` or `write`
+    pub fn access(&self) -> Option<String> {
+        match self.attribute("access")? {
+            serde_json::Value::String(access) => Some(access),
+            _ => None,
+        }
+    }
+
+    /// Doc comment recorded for the node, e.g. a Go package's
+    pub fn doc(&self) -> Option<String> {
+        match self.attribute("doc")? {
+            serde_json::Value::String(doc) => Some(doc),
+            _ => None,
+        }
+    }
+
+    /// Declaration header recorded for a function or method
+    pub fn signature(&self) -> Option<String> {
+        match self.attribute("signature")? {
+            serde_json::Value::String(signature) => Some(signature),
+            _ => None,
+        }
+    }
+
+    fn attribute(&self, key: &str) -> Option<serde_json::Value> {
+        let attributes: serde_json::Value =
+            serde_json::from_str(self.attributes.as_deref()?).ok()?;
+        attributes.get(key).cloned()
+    }
+
+    /// Whether `symbol` is exactly this node's name or qualified name
+    pub fn is_named(&self, symbol: &str) -> bool {
+        self.name == symbol || self.qualified_name.as_deref() == Some(symbol)
+    }
+
+    /// Whether the qualified name ends with `symbol` as whole segments, so
+    /// `UserService.getUser` matches `com.example.UserService.getUser` and
+    /// `Router::new` matches `server::Router::new`
+    pub fn has_qualified_suffix(&self, symbol: &str) -> bool {
+        self.qualified_name.as_deref().is_some_and(|q| {
+            q.strip_suffix(symbol).is_some_and(|prefix| {
+                QUALIFIED_NAME_SEPARATORS
+                    .iter()
+                    .any(|separator| prefix.ends_with(separator))
+            })
+        })
+    }
+}
+
+/// Separators between the segments of a qualified name, covering every
+/// language's [`qualified_name_separator`]
+///
+/// [`qualified_name_separator`]: crate::languages::LanguageSupport::qualified_name_separator
+pub const QUALIFIED_NAME_SEPARATORS: &[&str] = &[".", "::"];
+
+/// The last segment of a possibly qualified name, e.g. `getUser` for
+/// `UserService.getUser` or `new` for `Router::new`
+pub fn unqualified_name(name: &str) -> &str {
+    QUALIFIED_NAME_SEPARATORS
+        .iter()
+        .filter_map(|separator| name.rfind(separator).map(|i| i + separator.len()))
+        .max()
+        .map_or(name, |start| &name[start..])
+}
+
+/// Hash of a node's file path, type, qualified name and start position
+///
+/// Re-parsing a file gives its nodes new row ids, but a declaration that did
+/// not change or move keeps this id, so it can be bookmarked.
+pub fn stable_node_id(
+    file_path: &str,
+    node_type: &str,
+    name: &str,
+    line: u32,
+    column: u32,
+) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for part in [
+        file_path,
+        node_type,
+        name,
+        &line.to_string(),
+        &column.to_string(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
 /// Edge record in the database (relationships between nodes)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EdgeRecord {
@@ -82,12 +299,108 @@ pub struct ProjectStatus {
     pub nodes_count: u32,
     pub edges_count: u32,
     pub last_updated: DateTime<Utc>,
+    /// When a parse of the project last finished; `None` if never parsed
+    #[serde(default)]
+    pub last_parsed_at: Option<DateTime<Utc>>,
+}
+
+/// Graph in the `{nodes, links}` shape read by d3-force and vis.js
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct D3Graph {
+    pub nodes: Vec<D3Node>,
+    /// Every link's `source` and `target` is the `id` of a node in `nodes`
+    pub links: Vec<D3Link>,
+}
+
+/// A node of a [`D3Graph`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct D3Node {
+    pub id: i64,
+    /// Qualified name, or the plain name if there is none
+    pub label: String,
+    /// Node type, for coloring nodes by kind
+    pub group: String,
+}
+
+/// A link of a [`D3Graph`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct D3Link {
+    pub source: i64,
+    pub target: i64,
+    /// Edge type, e.g. `calls`
+    #[serde(rename = "type")]
+    pub link_type: String,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_node_record_is_abstract() {
+        let mut node = NodeRecord {
+            id: 1,
+            file_id: 1,
+            node_type: "method".to_string(),
+            name: "run".to_string(),
+            qualified_name: None,
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 10,
+            attributes: None,
+        };
+        assert!(!node.is_abstract());
+
+        node.attributes = Some(r#"{"abstract":true}"#.to_string());
+        assert!(node.is_abstract());
+
+        node.attributes = Some(r#"{"abstract":false}"#.to_string());
+        assert!(!node.is_abstract());
+    }
+
+    #[test]
+    fn test_node_record_accepts_args() {
+        let mut node = NodeRecord {
+            id: 1,
+            file_id: 1,
+            node_type: "method".to_string(),
+            name: "process".to_string(),
+            qualified_name: None,
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 10,
+            attributes: None,
+        };
+        assert!(node.accepts_args(3));
+
+        node.attributes =
+            Some(r#"{"arity":2,"signature":"void process(String a, int b)"}"#.to_string());
+        assert!(node.accepts_args(2));
+        assert!(!node.accepts_args(1));
+        assert!(!node.accepts_args(3));
+
+        node.attributes = Some(r#"{"arity":2,"varargs":true}"#.to_string());
+        assert!(!node.accepts_args(0));
+        assert!(node.accepts_args(1));
+        assert!(node.accepts_args(4));
+
+        node.node_type = "call".to_string();
+        node.attributes = Some(r#"{"args":2}"#.to_string());
+        assert_eq!(node.call_args(), Some(2));
+    }
+
+    #[test]
+    fn test_unqualified_name() {
+        assert_eq!(
+            unqualified_name("com.example.UserService.getUser"),
+            "getUser"
+        );
+        assert_eq!(unqualified_name("server::Router::new"), "new");
+        assert_eq!(unqualified_name("helper"), "helper");
+    }
+
     #[test]
     fn test_project_record_serialize() {
         let project = ProjectRecord {
@@ -143,6 +456,9 @@ mod tests {
             language: "java".to_string(),
             content_hash: "abc123".to_string(),
             parsed_at: Utc::now(),
+            line_count: 42,
+            is_test: false,
+            encoding: None,
         };
 
         let json = serde_json::to_string(&file).unwrap();
@@ -165,6 +481,7 @@ mod tests {
         assert_eq!(file.path, "/src/lib.go");
         assert_eq!(file.language, "go");
         assert_eq!(file.content_hash, "xyz789");
+        assert_eq!(file.line_count, 0);
     }
 
     #[test]
@@ -307,6 +624,7 @@ mod tests {
             nodes_count: 100,
             edges_count: 50,
             last_updated: Utc::now(),
+            last_parsed_at: None,
         };
 
         let json = serde_json::to_string(&status).unwrap();
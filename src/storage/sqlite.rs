@@ -1,11 +1,110 @@
 //! SQLite database implementation
 
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::bundle::GraphBundle;
+use super::models::{
+    resolve_stored_path, stable_node_id, D3Graph, D3Link, D3Node, EdgeRecord, FileMetrics,
+    FileRecord, LanguageCounts, NodeRecord, ProjectRecord, ProjectStatus, TestFilter,
+};
+
+/// Default page size for reference, caller and callee lookups
+pub const DEFAULT_RESULT_LIMIT: u32 = 1000;
+
+/// Node types that declare a symbol
+///
+/// Definition lookups, reference linking, the symbol stack and package
+/// summaries all use this list, so they agree on what counts as a declaration.
+pub const DECLARATION_NODE_TYPES: &[&str] = &[
+    "class",
+    "interface",
+    "enum",
+    "enum_constant",
+    "struct",
+    "type",
+    "method",
+    "constructor",
+    "function",
+    "field",
+    "variable",
+    "constant",
+];
+
+/// [`DECLARATION_NODE_TYPES`] as an SQL list, for `node_type IN (...)`
+fn declaration_types_sql() -> String {
+    let quoted: Vec<String> = DECLARATION_NODE_TYPES
+        .iter()
+        .map(|t| format!("'{}'", t))
+        .collect();
+    quoted.join(", ")
+}
+
+/// How many times a write is retried after the busy timeout runs out
+const MAX_BUSY_RETRIES: u32 = 5;
+
+/// Wait before the first retry of a busy write; doubled on each attempt
+const BUSY_RETRY_BACKOFF: Duration = Duration::from_millis(20);
+
+/// How long to wait before retry `attempt` (counting from 0) of a busy
+/// write, or `None` once the retries are used up
+pub fn busy_backoff(attempt: u32) -> Option<Duration> {
+    (attempt < MAX_BUSY_RETRIES).then(|| BUSY_RETRY_BACKOFF * 2u32.pow(attempt))
+}
+
+/// Connection settings for [`Database::open_with_options`]
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    /// How long SQLite waits for a lock held by another connection before
+    /// reporting the database as busy
+    pub busy_timeout: Duration,
+    /// Use write-ahead logging, so readers do not block the writer
+    pub wal: bool,
+    /// How often SQLite waits for writes to reach the disk
+    pub synchronous: Synchronous,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            busy_timeout: Duration::from_secs(5),
+            wal: true,
+            synchronous: Synchronous::default(),
+        }
+    }
+}
+
+/// SQLite `synchronous` levels, from fastest to most durable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    /// Safe from corruption in WAL mode; a power loss may drop the last commits
+    Normal,
+    #[default]
+    Full,
+    Extra,
+}
 
-use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
+impl Synchronous {
+    fn as_pragma(self) -> &'static str {
+        match self {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+            Synchronous::Extra => "EXTRA",
+        }
+    }
+}
 
-use super::models::{EdgeRecord, FileRecord, NodeRecord, ProjectRecord, ProjectStatus};
+/// Database path that opens a fresh in-memory database instead of a file
+pub const IN_MEMORY_PATH: &str = ":memory:";
 
 /// SQLite database wrapper
 pub struct Database {
@@ -15,11 +114,31 @@ pub struct Database {
 impl Database {
     /// Open or create a database at the given path
     pub fn open(path: &Path) -> Result<Self> {
-        let conn = Connection::open(path)
-            .with_context(|| format!("Failed to open database: {:?}", path))?;
+        Self::open_with_options(path, &OpenOptions::default())
+    }
+
+    /// Open or create a database at the given path with custom settings
+    ///
+    /// [`IN_MEMORY_PATH`] opens an in-memory database, which lives only as
+    /// long as this connection.
+    pub fn open_with_options(path: &Path, options: &OpenOptions) -> Result<Self> {
+        if path == Path::new(IN_MEMORY_PATH) {
+            return Self::open_in_memory();
+        }
+        let conn = Connection::open(path).map_err(|source| Error::DbOpen {
+            path: path.to_path_buf(),
+            source,
+        })?;
 
         // Enable foreign keys
         conn.execute("PRAGMA foreign_keys = ON", [])?;
+        conn.busy_timeout(options.busy_timeout)?;
+        if options.wal {
+            conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+                row.get::<_, String>(0)
+            })?;
+        }
+        conn.pragma_update(None, "synchronous", options.synchronous.as_pragma())?;
 
         Ok(Self { conn })
     }
@@ -31,6 +150,103 @@ impl Database {
         Ok(Self { conn })
     }
 
+    /// Open a bundle written by `export bundle` as an in-memory database
+    ///
+    /// Records keep the IDs they had in the exported database.
+    pub fn open_bundle(path: &Path) -> Result<Self> {
+        let bundle = GraphBundle::read(path)?;
+        let db = Self::open_in_memory()?;
+        db.init_schema()?;
+        db.load_bundle(&bundle)?;
+        Ok(db)
+    }
+
+    fn load_bundle(&self, bundle: &GraphBundle) -> Result<()> {
+        let tx = self.transaction()?;
+
+        let project = &bundle.project;
+        self.conn.execute(
+            "INSERT INTO projects (id, name, root_path, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                project.id,
+                project.name,
+                project.root_path,
+                project.created_at.to_rfc3339(),
+                project.updated_at.to_rfc3339()
+            ],
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO files (id, project_id, path, language, content_hash, parsed_at, line_count, is_test, encoding)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for file in &bundle.files {
+            stmt.execute(params![
+                file.id,
+                file.project_id,
+                file.path,
+                file.language,
+                file.content_hash,
+                file.parsed_at.to_rfc3339(),
+                file.line_count,
+                file.is_test,
+                file.encoding
+            ])?;
+        }
+
+        let paths: HashMap<i64, &str> = bundle
+            .files
+            .iter()
+            .map(|f| (f.id, f.path.as_str()))
+            .collect();
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO nodes (id, file_id, node_type, name, qualified_name, start_line, start_column, end_line, end_column, attributes, stable_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )?;
+        for node in &bundle.nodes {
+            let stable_id = paths.get(&node.file_id).map(|path| node.stable_id(path));
+            stmt.execute(params![
+                node.id,
+                node.file_id,
+                node.node_type,
+                node.name,
+                node.qualified_name,
+                node.start_line,
+                node.start_column,
+                node.end_line,
+                node.end_column,
+                node.attributes,
+                stable_id
+            ])?;
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("INSERT INTO edges (id, source_id, target_id, edge_type, attributes) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for edge in &bundle.edges {
+            stmt.execute(params![
+                edge.id,
+                edge.source_id,
+                edge.target_id,
+                edge.edge_type,
+                edge.attributes
+            ])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Whether [`init_schema`](Self::init_schema) has run on this database
+    pub fn has_schema(&self) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'projects'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     /// Initialize the database schema
     pub fn init_schema(&self) -> Result<()> {
         self.conn.execute_batch(
@@ -44,6 +260,15 @@ impl Database {
                 updated_at TEXT NOT NULL
             );
 
+            -- Additional source roots of multi-root projects
+            CREATE TABLE IF NOT EXISTS project_roots (
+                id INTEGER PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+                UNIQUE(project_id, path)
+            );
+
             -- Files table
             CREATE TABLE IF NOT EXISTS files (
                 id INTEGER PRIMARY KEY,
@@ -52,6 +277,9 @@ impl Database {
                 language TEXT NOT NULL,
                 content_hash TEXT NOT NULL,
                 parsed_at TEXT NOT NULL,
+                line_count INTEGER NOT NULL DEFAULT 0,
+                is_test INTEGER NOT NULL DEFAULT 0,
+                encoding TEXT,
                 FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
                 UNIQUE(project_id, path)
             );
@@ -68,6 +296,7 @@ impl Database {
                 end_line INTEGER NOT NULL,
                 end_column INTEGER NOT NULL,
                 attributes TEXT,
+                stable_id TEXT,
                 FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE CASCADE
             );
 
@@ -95,22 +324,207 @@ impl Database {
             "#,
         )?;
 
+        self.migrate()?;
+
+        Ok(())
+    }
+
+    /// Bring databases created by older versions up to the current schema
+    fn migrate(&self) -> Result<()> {
+        self.add_column_if_missing("files", "line_count", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("files", "is_test", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("files", "encoding", "TEXT")?;
+        self.add_column_if_missing("projects", "generation", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("projects", "last_parsed_at", "TEXT")?;
+        self.add_column_if_missing("nodes", "stable_id", "TEXT")?;
+        self.conn.execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_nodes_stable ON nodes(stable_id);
+            CREATE INDEX IF NOT EXISTS idx_nodes_file_type ON nodes(file_id, node_type);
+            CREATE INDEX IF NOT EXISTS idx_edges_type_target ON edges(edge_type, target_id);
+            "#,
+        )?;
+        self.backfill_stable_ids()?;
+        Ok(())
+    }
+
+    /// Give nodes stored before stable ids were recorded their id
+    ///
+    /// Re-parses skip files whose content did not change, so without this
+    /// their nodes would never get one.
+    fn backfill_stable_ids(&self) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, f.path, n.node_type, n.name, n.qualified_name, n.start_line, n.start_column
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE n.stable_id IS NULL
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let (path, node_type, name): (String, String, String) =
+                (row.get(1)?, row.get(2)?, row.get(3)?);
+            let qualified_name: Option<String> = row.get(4)?;
+            let stable_id = stable_node_id(
+                &path,
+                &node_type,
+                qualified_name.as_deref().unwrap_or(&name),
+                row.get(5)?,
+                row.get(6)?,
+            );
+            Ok((row.get::<_, i64>(0)?, stable_id))
+        })?;
+        let missing = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        self.write_transaction(|| {
+            let mut update = self
+                .conn
+                .prepare("UPDATE nodes SET stable_id = ?1 WHERE id = ?2")?;
+            for (node_id, stable_id) in &missing {
+                update.execute(params![stable_id, node_id])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Add a column to an existing table unless it is already present
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+
+        for existing in columns {
+            if existing? == column {
+                return Ok(());
+            }
+        }
+
+        self.conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Run a single write statement, retrying with backoff while another
+    /// connection holds the lock past the busy timeout
+    ///
+    /// Inside a transaction the error is returned at once: re-running one
+    /// statement cannot help there, so the whole transaction is retried by
+    /// [`write_transaction`](Self::write_transaction) instead.
+    fn retry_busy<T>(&self, mut write: impl FnMut() -> rusqlite::Result<T>) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let e = match write() {
+                Ok(value) => return Ok(value),
+                Err(e) => Error::from(e),
+            };
+            match busy_backoff(attempt) {
+                Some(wait) if e.is_busy() && self.conn.is_autocommit() => std::thread::sleep(wait),
+                _ => return Err(e),
+            }
+            attempt += 1;
+        }
+    }
+
+    // ==================== Maintenance Operations ====================
+
+    /// Rebuild the database file, reclaiming free pages left by re-parses
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Refresh the query planner statistics
+    pub fn analyze(&self) -> Result<()> {
+        self.conn.execute_batch("ANALYZE")?;
         Ok(())
     }
 
+    /// Current journal mode, e.g. `wal` or `delete`
+    pub fn journal_mode(&self) -> Result<String> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))?)
+    }
+
+    /// Run SQLite's integrity check, returning true if no problems were found
+    pub fn integrity_check(&self) -> Result<bool> {
+        let result: String = self
+            .conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        Ok(result == "ok")
+    }
+
+    /// Begin a write transaction on this connection
+    ///
+    /// Statements issued through this `Database` are part of the transaction
+    /// until the returned guard is committed; dropping it rolls them back.
+    /// The write lock is taken up front, so a busy database is reported here
+    /// rather than halfway through the writes.
+    pub fn transaction(&self) -> Result<Transaction<'_>> {
+        Ok(Transaction::new_unchecked(
+            &self.conn,
+            TransactionBehavior::Immediate,
+        )?)
+    }
+
+    /// Run `write` in one transaction, retrying the whole transaction with
+    /// backoff while another connection holds the write lock
+    ///
+    /// Nothing is locked while waiting between attempts. Called inside
+    /// another transaction, `write` just joins it and the outer caller
+    /// handles retries. Async callers should loop over
+    /// [`try_write_transaction`](Self::try_write_transaction) with a
+    /// non-blocking sleep instead, since this blocks the thread.
+    pub fn write_transaction<T>(&self, mut write: impl FnMut() -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            return write();
+        }
+        let mut attempt = 0;
+        loop {
+            match self.try_write_transaction(&mut write) {
+                Err(e) if e.is_busy() => match busy_backoff(attempt) {
+                    Some(wait) => std::thread::sleep(wait),
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Run `write` in one transaction, without retrying
+    ///
+    /// Fails with a busy error ([`Error::is_busy`]) if the write lock could
+    /// not be taken within the busy timeout; the transaction is rolled back,
+    /// so the caller can wait and try again.
+    pub fn try_write_transaction<T>(&self, write: impl FnOnce() -> Result<T>) -> Result<T> {
+        let tx = self.transaction()?;
+        let value = write()?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     // ==================== Project Operations ====================
 
     /// Insert a new project
     pub fn insert_project(&self, project: &ProjectRecord) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO projects (name, root_path, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                project.name,
-                project.root_path,
-                project.created_at.to_rfc3339(),
-                project.updated_at.to_rfc3339()
-            ],
-        )?;
+        self.retry_busy(|| {
+            self.conn.execute(
+                "INSERT INTO projects (name, root_path, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    project.name,
+                    project.root_path,
+                    project.created_at.to_rfc3339(),
+                    project.updated_at.to_rfc3339()
+                ],
+            )
+        })?;
         Ok(self.conn.last_insert_rowid())
     }
 
@@ -138,6 +552,41 @@ impl Database {
             .map_err(Into::into)
     }
 
+    /// Record an additional source root for a project; existing roots are kept
+    pub fn add_project_root(&self, project_id: i64, path: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO project_roots (project_id, path) VALUES (?1, ?2)",
+            params![project_id, path],
+        )?;
+        Ok(())
+    }
+
+    /// Get a project's root path
+    pub fn get_project_root(&self, project_id: i64) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT root_path FROM projects WHERE id = ?1",
+                params![project_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Get a project's additional source roots in the order they were added
+    pub fn get_project_roots(&self, project_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM project_roots WHERE project_id = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![project_id], |row| row.get(0))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
     /// Get a project by its name
     pub fn get_project_by_name(&self, name: &str) -> Result<Option<ProjectRecord>> {
         self.conn
@@ -165,7 +614,7 @@ impl Database {
     /// List all projects
     pub fn list_projects(&self) -> Result<Vec<ProjectRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, root_path, created_at, updated_at FROM projects ORDER BY name"
+            "SELECT id, name, root_path, created_at, updated_at FROM projects ORDER BY name",
         )?;
 
         let rows = stmt.query_map([], |row| {
@@ -199,10 +648,63 @@ impl Database {
         Ok(())
     }
 
+    /// Record that a parse of the project has just finished
+    ///
+    /// Unlike `updated_at`, this is not touched by metadata changes such as
+    /// renames.
+    pub fn mark_project_parsed(&self, project_id: i64) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn.execute(
+            "UPDATE projects SET last_parsed_at = ?1 WHERE id = ?2",
+            params![now, project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Mark a project's graph as changed, invalidating cached query results
+    pub fn bump_project_generation(&self, project_id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects SET generation = generation + 1 WHERE id = ?1",
+            params![project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get a project's graph generation; 0 for unknown projects
+    pub fn get_project_generation(&self, project_id: i64) -> Result<i64> {
+        let generation = self
+            .conn
+            .query_row(
+                "SELECT generation FROM projects WHERE id = ?1",
+                params![project_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(generation.unwrap_or(0))
+    }
+
+    /// Rename a project, refusing a name already used by another project
+    ///
+    /// Returns false if no project has the given id.
+    pub fn rename_project(&self, project_id: i64, new_name: &str) -> Result<bool> {
+        if let Some(existing) = self.get_project_by_name(new_name)? {
+            if existing.id != project_id {
+                return Err(Error::ProjectExists(new_name.to_string()));
+            }
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let updated = self.conn.execute(
+            "UPDATE projects SET name = ?1, updated_at = ?2 WHERE id = ?3",
+            params![new_name, now, project_id],
+        )?;
+        Ok(updated > 0)
+    }
+
     /// Get project status
     pub fn get_project_status(&self, project_id: i64) -> Result<Option<ProjectStatus>> {
         let project = self.conn.query_row(
-            "SELECT id, name, root_path, created_at, updated_at FROM projects WHERE id = ?1",
+            "SELECT id, name, root_path, created_at, updated_at, last_parsed_at FROM projects WHERE id = ?1",
             params![project_id],
             |row| {
                 Ok((
@@ -210,28 +712,20 @@ impl Database {
                     row.get::<_, String>(1)?,
                     row.get::<_, String>(2)?,
                     row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
                 ))
             },
         ).optional()?;
 
-        if let Some((id, name, root_path, updated_at)) = project {
+        if let Some((id, name, root_path, updated_at, last_parsed_at)) = project {
             let files_count: u32 = self.conn.query_row(
                 "SELECT COUNT(*) FROM files WHERE project_id = ?1",
                 params![project_id],
                 |row| row.get(0),
             )?;
 
-            let nodes_count: u32 = self.conn.query_row(
-                "SELECT COUNT(*) FROM nodes n JOIN files f ON n.file_id = f.id WHERE f.project_id = ?1",
-                params![project_id],
-                |row| row.get(0),
-            )?;
-
-            let edges_count: u32 = self.conn.query_row(
-                "SELECT COUNT(*) FROM edges e JOIN nodes n ON e.source_id = n.id JOIN files f ON n.file_id = f.id WHERE f.project_id = ?1",
-                params![project_id],
-                |row| row.get(0),
-            )?;
+            let nodes_count = self.count_nodes(project_id)?;
+            let edges_count = self.count_edges(project_id)?;
 
             Ok(Some(ProjectStatus {
                 project_id: id,
@@ -244,26 +738,115 @@ impl Database {
                 last_updated: chrono::DateTime::parse_from_rfc3339(&updated_at)
                     .unwrap()
                     .with_timezone(&chrono::Utc),
+                last_parsed_at: last_parsed_at.map(|t| {
+                    chrono::DateTime::parse_from_rfc3339(&t)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc)
+                }),
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Get the status of every project, ordered by name
+    ///
+    /// File, node and edge counts are aggregated per project in one query
+    /// rather than with a [`get_project_status`](Self::get_project_status)
+    /// call per project.
+    pub fn all_project_statuses(&self) -> Result<Vec<ProjectStatus>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT p.id, p.name, p.root_path, p.updated_at, p.last_parsed_at,
+                   COALESCE(fc.count, 0), COALESCE(nc.count, 0), COALESCE(ec.count, 0)
+            FROM projects p
+            LEFT JOIN (
+                SELECT project_id, COUNT(*) AS count FROM files GROUP BY project_id
+            ) fc ON fc.project_id = p.id
+            LEFT JOIN (
+                SELECT f.project_id, COUNT(*) AS count
+                FROM nodes n JOIN files f ON n.file_id = f.id
+                GROUP BY f.project_id
+            ) nc ON nc.project_id = p.id
+            LEFT JOIN (
+                SELECT f.project_id, COUNT(*) AS count
+                FROM edges e
+                JOIN nodes n ON e.source_id = n.id
+                JOIN files f ON n.file_id = f.id
+                GROUP BY f.project_id
+            ) ec ON ec.project_id = p.id
+            ORDER BY p.name
+            "#,
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(ProjectStatus {
+                project_id: row.get(0)?,
+                name: row.get(1)?,
+                root_path: row.get(2)?,
+                status: "ready".to_string(),
+                files_parsed: row.get(5)?,
+                nodes_count: row.get(6)?,
+                edges_count: row.get(7)?,
+                last_updated: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                last_parsed_at: row.get::<_, Option<String>>(4)?.map(|t| {
+                    chrono::DateTime::parse_from_rfc3339(&t)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc)
+                }),
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Number of nodes in a project
+    pub fn count_nodes(&self, project_id: i64) -> Result<u32> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM nodes n JOIN files f ON n.file_id = f.id WHERE f.project_id = ?1",
+                params![project_id],
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Number of edges leaving nodes in a project
+    pub fn count_edges(&self, project_id: i64) -> Result<u32> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM edges e JOIN nodes n ON e.source_id = n.id JOIN files f ON n.file_id = f.id WHERE f.project_id = ?1",
+                params![project_id],
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
+
     // ==================== File Operations ====================
 
     /// Insert a new file
     pub fn insert_file(&self, file: &FileRecord) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO files (project_id, path, language, content_hash, parsed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                file.project_id,
-                file.path,
-                file.language,
-                file.content_hash,
-                file.parsed_at.to_rfc3339()
-            ],
-        )?;
+        self.retry_busy(|| {
+            self.conn.execute(
+                "INSERT INTO files (project_id, path, language, content_hash, parsed_at, line_count, is_test, encoding) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    file.project_id,
+                    file.path,
+                    file.language,
+                    file.content_hash,
+                    file.parsed_at.to_rfc3339(),
+                    file.line_count,
+                    file.is_test,
+                    file.encoding
+                ],
+            )
+        })?;
         Ok(self.conn.last_insert_rowid())
     }
 
@@ -271,7 +854,7 @@ impl Database {
     pub fn get_file_by_path(&self, project_id: i64, path: &str) -> Result<Option<FileRecord>> {
         self.conn
             .query_row(
-                "SELECT id, project_id, path, language, content_hash, parsed_at FROM files WHERE project_id = ?1 AND path = ?2",
+                "SELECT id, project_id, path, language, content_hash, parsed_at, line_count, is_test, encoding FROM files WHERE project_id = ?1 AND path = ?2",
                 params![project_id, path],
                 |row| {
                     Ok(FileRecord {
@@ -283,6 +866,9 @@ impl Database {
                         parsed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
                             .unwrap()
                             .with_timezone(&chrono::Utc),
+                        line_count: row.get(6)?,
+                        is_test: row.get(7)?,
+                        encoding: row.get(8)?,
                     })
                 },
             )
@@ -294,7 +880,7 @@ impl Database {
     pub fn get_file(&self, file_id: i64) -> Result<Option<FileRecord>> {
         self.conn
             .query_row(
-                "SELECT id, project_id, path, language, content_hash, parsed_at FROM files WHERE id = ?1",
+                "SELECT id, project_id, path, language, content_hash, parsed_at, line_count, is_test, encoding FROM files WHERE id = ?1",
                 params![file_id],
                 |row| {
                     Ok(FileRecord {
@@ -306,6 +892,9 @@ impl Database {
                         parsed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
                             .unwrap()
                             .with_timezone(&chrono::Utc),
+                        line_count: row.get(6)?,
+                        is_test: row.get(7)?,
+                        encoding: row.get(8)?,
                     })
                 },
             )
@@ -313,98 +902,239 @@ impl Database {
             .map_err(Into::into)
     }
 
-    /// Delete all data for a file
-    pub fn delete_file_data(&self, file_id: i64) -> Result<()> {
-        // Edges will be deleted via CASCADE
-        self.conn.execute(
-            "DELETE FROM nodes WHERE file_id = ?1",
-            params![file_id],
+    /// Location on disk of a file, resolving relative paths against its project root
+    pub fn get_file_disk_path(&self, file_id: i64) -> Result<Option<PathBuf>> {
+        self.conn
+            .query_row(
+                "SELECT p.root_path, f.path FROM files f JOIN projects p ON f.project_id = p.id WHERE f.id = ?1",
+                params![file_id],
+                |row| Ok(resolve_stored_path(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Get every file in a project, ordered by path
+    pub fn get_project_files(&self, project_id: i64) -> Result<Vec<FileRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project_id, path, language, content_hash, parsed_at, line_count, is_test, encoding FROM files WHERE project_id = ?1 ORDER BY path",
         )?;
-        self.conn.execute(
-            "DELETE FROM files WHERE id = ?1",
-            params![file_id],
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(FileRecord {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                path: row.get(2)?,
+                language: row.get(3)?,
+                content_hash: row.get(4)?,
+                parsed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                line_count: row.get(6)?,
+                is_test: row.get(7)?,
+                encoding: row.get(8)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Get per-file metrics (language, line count, node count) for a project
+    pub fn file_metrics(&self, project_id: i64) -> Result<Vec<FileMetrics>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT f.path, f.language, f.line_count, COUNT(n.id)
+            FROM files f
+            LEFT JOIN nodes n ON n.file_id = f.id
+            WHERE f.project_id = ?1
+            GROUP BY f.id
+            ORDER BY f.path
+            "#,
         )?;
-        Ok(())
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(FileMetrics {
+                path: row.get(0)?,
+                language: row.get(1)?,
+                line_count: row.get(2)?,
+                node_count: row.get(3)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Count files, nodes and edges per language in a project
+    ///
+    /// Edges are attributed to the language of their source node's file.
+    pub fn counts_by_language(&self, project_id: i64) -> Result<Vec<LanguageCounts>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT f.language,
+                   COUNT(*),
+                   COALESCE(SUM((SELECT COUNT(*) FROM nodes n WHERE n.file_id = f.id)), 0),
+                   COALESCE(SUM((
+                       SELECT COUNT(*) FROM edges e
+                       JOIN nodes n ON e.source_id = n.id
+                       WHERE n.file_id = f.id
+                   )), 0)
+            FROM files f
+            WHERE f.project_id = ?1
+            GROUP BY f.language
+            ORDER BY f.language
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(LanguageCounts {
+                language: row.get(0)?,
+                file_count: row.get(1)?,
+                node_count: row.get(2)?,
+                edge_count: row.get(3)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Node types present in a project, sorted
+    ///
+    /// Unlike a language's declared node types, these are only the types the
+    /// project's graph actually contains.
+    pub fn distinct_node_types(&self, project_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT DISTINCT n.node_type
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+            ORDER BY n.node_type
+            "#,
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+            .map_err(Into::into)
+    }
+
+    /// Edge types present in a project, sorted
+    ///
+    /// An edge belongs to the project of its source node's file.
+    pub fn distinct_edge_types(&self, project_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT DISTINCT e.edge_type
+            FROM edges e
+            JOIN nodes n ON e.source_id = n.id
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+            ORDER BY e.edge_type
+            "#,
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+            .map_err(Into::into)
+    }
+
+    /// Delete all data for a file
+    pub fn delete_file_data(&self, file_id: i64) -> Result<()> {
+        // Edges will be deleted via CASCADE
+        self.write_transaction(|| {
+            self.conn
+                .execute("DELETE FROM nodes WHERE file_id = ?1", params![file_id])?;
+            self.conn
+                .execute("DELETE FROM files WHERE id = ?1", params![file_id])?;
+            Ok(())
+        })
     }
 
     // ==================== Node Operations ====================
 
     /// Insert a new node
+    ///
+    /// Looks up the path of the node's file; use
+    /// [`insert_file_node`](Self::insert_file_node) when it is already known.
     pub fn insert_node(&self, node: &NodeRecord) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO nodes (file_id, node_type, name, qualified_name, start_line, start_column, end_line, end_column, attributes)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![
-                node.file_id,
-                node.node_type,
-                node.name,
-                node.qualified_name,
-                node.start_line,
-                node.start_column,
-                node.end_line,
-                node.end_column,
-                node.attributes
-            ],
-        )?;
+        let file_path = self
+            .conn
+            .prepare_cached("SELECT path FROM files WHERE id = ?1")?
+            .query_row(params![node.file_id], |row| row.get::<_, String>(0))?;
+        self.insert_file_node(node, &file_path)
+    }
+
+    /// Insert a new node of the file stored under `file_path`
+    ///
+    /// The node's [`stable_id`](NodeRecord::stable_id) is stored with it.
+    pub fn insert_file_node(&self, node: &NodeRecord, file_path: &str) -> Result<i64> {
+        let stable_id = node.stable_id(file_path);
+        self.retry_busy(|| {
+            self.conn.execute(
+                "INSERT INTO nodes (file_id, node_type, name, qualified_name, start_line, start_column, end_line, end_column, attributes, stable_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    node.file_id,
+                    node.node_type,
+                    node.name,
+                    node.qualified_name,
+                    node.start_line,
+                    node.start_column,
+                    node.end_line,
+                    node.end_column,
+                    node.attributes,
+                    stable_id
+                ],
+            )
+        })?;
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// Find a node at a specific position
-    pub fn find_node_at_position(
+    /// Find a project's node by its [`stable_id`](NodeRecord::stable_id)
+    pub fn find_node_by_stable_id(
         &self,
         project_id: i64,
-        file_path: &str,
-        line: u32,
-        column: u32,
+        stable_id: &str,
     ) -> Result<Option<NodeRecord>> {
-        self.conn
+        let node_id = self
+            .conn
             .query_row(
                 r#"
-                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
-                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+                SELECT n.id
                 FROM nodes n
                 JOIN files f ON n.file_id = f.id
-                WHERE f.project_id = ?1
-                  AND f.path = ?2
-                  AND n.start_line <= ?3 AND n.end_line >= ?3
-                  AND (n.start_line < ?3 OR n.start_column <= ?4)
-                  AND (n.end_line > ?3 OR n.end_column >= ?4)
-                ORDER BY (n.end_line - n.start_line), (n.end_column - n.start_column)
-                LIMIT 1
+                WHERE f.project_id = ?1 AND n.stable_id = ?2
                 "#,
-                params![project_id, file_path, line, column],
-                |row| {
-                    Ok(NodeRecord {
-                        id: row.get(0)?,
-                        file_id: row.get(1)?,
-                        node_type: row.get(2)?,
-                        name: row.get(3)?,
-                        qualified_name: row.get(4)?,
-                        start_line: row.get(5)?,
-                        start_column: row.get(6)?,
-                        end_line: row.get(7)?,
-                        end_column: row.get(8)?,
-                        attributes: row.get(9)?,
-                    })
-                },
+                params![project_id, stable_id],
+                |row| row.get::<_, i64>(0),
             )
-            .optional()
-            .map_err(Into::into)
+            .optional()?;
+        match node_id {
+            Some(id) => self.get_node(id),
+            None => Ok(None),
+        }
     }
 
-    /// Find symbol by name
-    pub fn find_symbol_by_name(&self, project_id: i64, name: &str) -> Result<Option<NodeRecord>> {
+    /// Get a node by ID
+    pub fn get_node(&self, node_id: i64) -> Result<Option<NodeRecord>> {
         self.conn
             .query_row(
                 r#"
-                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
-                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
-                FROM nodes n
-                JOIN files f ON n.file_id = f.id
-                WHERE f.project_id = ?1 AND (n.name = ?2 OR n.qualified_name = ?2)
-                LIMIT 1
+                SELECT id, file_id, node_type, name, qualified_name,
+                       start_line, start_column, end_line, end_column, attributes
+                FROM nodes
+                WHERE id = ?1
                 "#,
-                params![project_id, name],
+                params![node_id],
                 |row| {
                     Ok(NodeRecord {
                         id: row.get(0)?,
@@ -424,16 +1154,12 @@ impl Database {
             .map_err(Into::into)
     }
 
-    /// Search symbols by name pattern
-    pub fn search_symbols(
+    /// Get the nodes of a file in source order, optionally filtered by node type
+    pub fn get_nodes_by_file(
         &self,
-        project_id: i64,
-        query: &str,
-        symbol_type: Option<&str>,
-        limit: u32,
+        file_id: i64,
+        node_type: Option<&str>,
     ) -> Result<Vec<NodeRecord>> {
-        let pattern = format!("%{}%", query);
-
         let row_mapper = |row: &rusqlite::Row| -> rusqlite::Result<NodeRecord> {
             Ok(NodeRecord {
                 id: row.get(0)?,
@@ -451,36 +1177,31 @@ impl Database {
 
         let mut result = Vec::new();
 
-        if let Some(stype) = symbol_type {
+        if let Some(ntype) = node_type {
             let mut stmt = self.conn.prepare(
                 r#"
-                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
-                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
-                FROM nodes n
-                JOIN files f ON n.file_id = f.id
-                WHERE f.project_id = ?1
-                  AND n.node_type = ?2
-                  AND (n.name LIKE ?3 OR n.qualified_name LIKE ?3)
-                LIMIT ?4
+                SELECT id, file_id, node_type, name, qualified_name,
+                       start_line, start_column, end_line, end_column, attributes
+                FROM nodes
+                WHERE file_id = ?1 AND node_type = ?2
+                ORDER BY start_line, start_column
                 "#,
             )?;
-            let rows = stmt.query_map(params![project_id, stype, pattern, limit], row_mapper)?;
+            let rows = stmt.query_map(params![file_id, ntype], row_mapper)?;
             for row in rows {
                 result.push(row?);
             }
         } else {
             let mut stmt = self.conn.prepare(
                 r#"
-                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
-                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
-                FROM nodes n
-                JOIN files f ON n.file_id = f.id
-                WHERE f.project_id = ?1
-                  AND (n.name LIKE ?2 OR n.qualified_name LIKE ?2)
-                LIMIT ?3
+                SELECT id, file_id, node_type, name, qualified_name,
+                       start_line, start_column, end_line, end_column, attributes
+                FROM nodes
+                WHERE file_id = ?1
+                ORDER BY start_line, start_column
                 "#,
             )?;
-            let rows = stmt.query_map(params![project_id, pattern, limit], row_mapper)?;
+            let rows = stmt.query_map(params![file_id], row_mapper)?;
             for row in rows {
                 result.push(row?);
             }
@@ -489,75 +1210,30 @@ impl Database {
         Ok(result)
     }
 
-    /// Get unresolved references (nodes that reference symbols not yet linked)
-    pub fn get_unresolved_references(&self, project_id: i64) -> Result<Vec<(i64, String)>> {
-        let mut stmt = self.conn.prepare(
-            r#"
-            SELECT n.id, n.name
-            FROM nodes n
-            JOIN files f ON n.file_id = f.id
-            LEFT JOIN edges e ON e.source_id = n.id AND e.edge_type = 'references'
-            WHERE f.project_id = ?1
-              AND n.node_type = 'reference'
-              AND e.id IS NULL
-            "#,
-        )?;
-
-        let rows = stmt.query_map(params![project_id], |row| {
-            Ok((row.get(0)?, row.get(1)?))
-        })?;
-
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
-        }
-        Ok(result)
-    }
-
-    /// Find definition by name
-    pub fn find_definition_by_name(&self, project_id: i64, name: &str) -> Result<Option<i64>> {
-        self.conn
-            .query_row(
-                r#"
-                SELECT n.id
-                FROM nodes n
-                JOIN files f ON n.file_id = f.id
-                WHERE f.project_id = ?1
-                  AND n.name = ?2
-                  AND n.node_type IN ('function', 'method', 'class', 'interface', 'struct', 'variable')
-                LIMIT 1
-                "#,
-                params![project_id, name],
-                |row| row.get(0),
-            )
-            .optional()
-            .map_err(Into::into)
-    }
-
-    // ==================== Edge Operations ====================
-
-    /// Insert a new edge
-    pub fn insert_edge(&self, edge: &EdgeRecord) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO edges (source_id, target_id, edge_type, attributes) VALUES (?1, ?2, ?3, ?4)",
-            params![edge.source_id, edge.target_id, edge.edge_type, edge.attributes],
-        )?;
-        Ok(self.conn.last_insert_rowid())
-    }
-
-    /// Find the target of a reference
-    pub fn find_reference_target(&self, node_id: i64) -> Result<Option<NodeRecord>> {
+    /// Find a node at a specific position
+    pub fn find_node_at_position(
+        &self,
+        project_id: i64,
+        file_path: &str,
+        line: u32,
+        column: u32,
+    ) -> Result<Option<NodeRecord>> {
         self.conn
             .query_row(
                 r#"
                 SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
                        n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
                 FROM nodes n
-                JOIN edges e ON e.target_id = n.id
-                WHERE e.source_id = ?1 AND e.edge_type = 'references'
+                JOIN files f ON n.file_id = f.id
+                WHERE f.project_id = ?1
+                  AND f.path = ?2
+                  AND n.start_line <= ?3 AND n.end_line >= ?3
+                  AND (n.start_line < ?3 OR n.start_column <= ?4)
+                  AND (n.end_line > ?3 OR n.end_column >= ?4)
+                ORDER BY (n.end_line - n.start_line), (n.end_column - n.start_column)
                 LIMIT 1
                 "#,
-                params![node_id],
+                params![project_id, file_path, line, column],
                 |row| {
                     Ok(NodeRecord {
                         id: row.get(0)?,
@@ -577,19 +1253,1176 @@ impl Database {
             .map_err(Into::into)
     }
 
-    /// Find all references to a node
-    pub fn find_all_references(&self, node_id: i64) -> Result<Vec<NodeRecord>> {
+    /// Find every declaration whose range contains a position, outermost first
+    ///
+    /// Uses the same containment test as [`Database::find_node_at_position`];
+    /// larger ranges come first, so each node encloses the ones after it.
+    /// Only [`DECLARATION_NODE_TYPES`] count, so calls, parameters and
+    /// references are left out.
+    pub fn find_enclosing_nodes(
+        &self,
+        project_id: i64,
+        file_path: &str,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND f.path = ?2
+              AND n.start_line <= ?3 AND n.end_line >= ?3
+              AND (n.start_line < ?3 OR n.start_column <= ?4)
+              AND (n.end_line > ?3 OR n.end_column >= ?4)
+              AND n.node_type IN ({})
+            ORDER BY (n.end_line - n.start_line) DESC, (n.end_column - n.start_column) DESC, n.id
+            "#,
+            declaration_types_sql()
+        ))?;
+
+        let rows = stmt.query_map(params![project_id, file_path, line, column], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find the nodes in a file whose line span overlaps `[start_line, end_line]`
+    ///
+    /// Nodes partially inside the range are included. Results are ordered by
+    /// position, so enclosing nodes come before the nodes nested in them.
+    pub fn find_nodes_in_range(
+        &self,
+        project_id: i64,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+    ) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND f.path = ?2
+              AND n.start_line <= ?4 AND n.end_line >= ?3
+            ORDER BY n.start_line, n.start_column, n.end_line DESC, n.id
+            "#,
+        )?;
+
+        let rows = stmt.query_map(
+            params![project_id, file_path, start_line, end_line],
+            |row| {
+                Ok(NodeRecord {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    node_type: row.get(2)?,
+                    name: row.get(3)?,
+                    qualified_name: row.get(4)?,
+                    start_line: row.get(5)?,
+                    start_column: row.get(6)?,
+                    end_line: row.get(7)?,
+                    end_column: row.get(8)?,
+                    attributes: row.get(9)?,
+                })
+            },
+        )?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find symbol by name
+    ///
+    /// Matches the name or qualified name exactly, falling back to a
+    /// qualified name ending in `name`, e.g. `UserService.getUser` finds
+    /// `com.example.UserService.getUser`. Exact matches are preferred.
+    pub fn find_symbol_by_name(&self, project_id: i64, name: &str) -> Result<Option<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND (n.name = ?2 OR n.qualified_name = ?2 OR n.qualified_name LIKE ?3)
+            ORDER BY (n.name = ?2 OR n.qualified_name IS ?2) DESC, n.id
+            LIMIT ?4
+            "#,
+        )?;
+
+        let params = params![project_id, name, format!("%{}", name), DEFAULT_RESULT_LIMIT];
+        let rows = stmt.query_map(params, |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut suffix_match = None;
+        for row in rows {
+            let node = row?;
+            if node.is_named(name) {
+                return Ok(Some(node));
+            }
+            // LIKE treats `_` as a wildcard, so confirm the suffix match exactly
+            if suffix_match.is_none() && node.has_qualified_suffix(name) {
+                suffix_match = Some(node);
+            }
+        }
+        Ok(suffix_match)
+    }
+
+    /// Search symbols by name pattern
+    pub fn search_symbols(
+        &self,
+        project_id: i64,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<NodeRecord>> {
+        self.search_symbols_filtered(project_id, query, symbol_type, limit, TestFilter::All)
+    }
+
+    /// Search symbols by name pattern, keeping only those in files that pass
+    /// the test filter
+    pub fn search_symbols_filtered(
+        &self,
+        project_id: i64,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+        tests: TestFilter,
+    ) -> Result<Vec<NodeRecord>> {
+        let pattern = format!("%{}%", query);
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND (?2 IS NULL OR n.node_type = ?2)
+              AND (n.name LIKE ?3 OR n.qualified_name LIKE ?3)
+              AND (?4 IS NULL OR f.is_test = ?4)
+            LIMIT ?5
+            "#,
+        )?;
+
+        let rows = stmt.query_map(
+            params![project_id, symbol_type, pattern, tests.is_test(), limit],
+            |row| {
+                Ok(NodeRecord {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    node_type: row.get(2)?,
+                    name: row.get(3)?,
+                    qualified_name: row.get(4)?,
+                    start_line: row.get(5)?,
+                    start_column: row.get(6)?,
+                    end_line: row.get(7)?,
+                    end_column: row.get(8)?,
+                    attributes: row.get(9)?,
+                })
+            },
+        )?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Package clauses named `package`, one per file
+    pub fn find_package_nodes(&self, project_id: i64, package: &str) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1 AND n.node_type = 'package' AND n.name = ?2
+            ORDER BY f.path
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id, package], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Top-level declarations (functions, methods, types, constants and
+    /// variables) in any file of `package`, ordered by file and line
+    ///
+    /// A declaration is top-level when no other declaration or closure of its
+    /// file encloses it, so struct fields and the constants and types declared
+    /// inside a function body are left out.
+    pub fn find_package_symbols(&self, project_id: i64, package: &str) -> Result<Vec<NodeRecord>> {
+        let types = declaration_types_sql();
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND n.node_type IN ({types})
+              AND f.id IN (
+                SELECT p.file_id FROM nodes p
+                WHERE p.node_type = 'package' AND p.name = ?2
+              )
+              AND NOT EXISTS (
+                SELECT 1 FROM nodes o
+                WHERE o.file_id = n.file_id
+                  AND o.id != n.id
+                  AND (o.node_type IN ({types}) OR o.node_type = 'closure')
+                  AND (o.start_line < n.start_line
+                       OR (o.start_line = n.start_line AND o.start_column <= n.start_column))
+                  AND (o.end_line > n.end_line
+                       OR (o.end_line = n.end_line AND o.end_column >= n.end_column))
+                  AND (o.start_line != n.start_line OR o.start_column != n.start_column
+                       OR o.end_line != n.end_line OR o.end_column != n.end_column)
+              )
+            ORDER BY f.path, n.start_line, n.start_column
+            "#
+        ))?;
+
+        let rows = stmt.query_map(params![project_id, package], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Search symbols whose name or qualified name is exactly `query`,
+    /// keeping only those in files that pass the test filter
+    pub fn search_symbols_exact(
+        &self,
+        project_id: i64,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+        tests: TestFilter,
+    ) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND (?2 IS NULL OR n.node_type = ?2)
+              AND (n.name = ?3 OR n.qualified_name = ?3)
+              AND (?4 IS NULL OR f.is_test = ?4)
+            ORDER BY n.id
+            LIMIT ?5
+            "#,
+        )?;
+
+        let rows = stmt.query_map(
+            params![project_id, symbol_type, query, tests.is_test(), limit],
+            |row| {
+                Ok(NodeRecord {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    node_type: row.get(2)?,
+                    name: row.get(3)?,
+                    qualified_name: row.get(4)?,
+                    start_line: row.get(5)?,
+                    start_column: row.get(6)?,
+                    end_line: row.get(7)?,
+                    end_column: row.get(8)?,
+                    attributes: row.get(9)?,
+                })
+            },
+        )?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Search symbols whose name or qualified name matches a regular
+    /// expression, keeping only those in files that pass the test filter
+    ///
+    /// Candidates are filtered in Rust, so every symbol of the requested type
+    /// is scanned. Returns [`Error::Query`] for an invalid expression.
+    pub fn search_symbols_regex(
+        &self,
+        project_id: i64,
+        pattern: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+        tests: TestFilter,
+    ) -> Result<Vec<NodeRecord>> {
+        let regex = regex::Regex::new(pattern)
+            .map_err(|e| Error::Query(format!("Invalid regex {:?}: {}", pattern, e)))?;
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND (?2 IS NULL OR n.node_type = ?2)
+              AND (?3 IS NULL OR f.is_test = ?3)
+            ORDER BY n.id
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id, symbol_type, tests.is_test()], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            if result.len() >= limit as usize {
+                break;
+            }
+            let node = row?;
+            let matches = regex.is_match(&node.name)
+                || node
+                    .qualified_name
+                    .as_deref()
+                    .is_some_and(|q| regex.is_match(q));
+            if matches {
+                result.push(node);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Search symbols by name pattern within a single file of a project
+    pub fn search_symbols_in_file(
+        &self,
+        project_id: i64,
+        path: &str,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<NodeRecord>> {
+        let pattern = format!("%{}%", query);
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND f.path = ?2
+              AND (?3 IS NULL OR n.node_type = ?3)
+              AND (n.name LIKE ?4 OR n.qualified_name LIKE ?4)
+            LIMIT ?5
+            "#,
+        )?;
+
+        let rows = stmt.query_map(
+            params![project_id, path, symbol_type, pattern, limit],
+            |row| {
+                Ok(NodeRecord {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    node_type: row.get(2)?,
+                    name: row.get(3)?,
+                    qualified_name: row.get(4)?,
+                    start_line: row.get(5)?,
+                    start_column: row.get(6)?,
+                    end_line: row.get(7)?,
+                    end_column: row.get(8)?,
+                    attributes: row.get(9)?,
+                })
+            },
+        )?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Search symbols by name pattern across all projects
+    ///
+    /// Each match is returned with the owning project's ID and name.
+    pub fn search_symbols_global(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String, NodeRecord)>> {
+        let pattern = format!("%{}%", query);
+
+        let row_mapper = |row: &rusqlite::Row| -> rusqlite::Result<(i64, String, NodeRecord)> {
+            Ok((
+                row.get(10)?,
+                row.get(11)?,
+                NodeRecord {
+                    id: row.get(0)?,
+                    file_id: row.get(1)?,
+                    node_type: row.get(2)?,
+                    name: row.get(3)?,
+                    qualified_name: row.get(4)?,
+                    start_line: row.get(5)?,
+                    start_column: row.get(6)?,
+                    end_line: row.get(7)?,
+                    end_column: row.get(8)?,
+                    attributes: row.get(9)?,
+                },
+            ))
+        };
+
+        let mut result = Vec::new();
+
+        if let Some(stype) = symbol_type {
+            let mut stmt = self.conn.prepare(
+                r#"
+                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes,
+                       p.id, p.name
+                FROM nodes n
+                JOIN files f ON n.file_id = f.id
+                JOIN projects p ON f.project_id = p.id
+                WHERE n.node_type = ?1
+                  AND (n.name LIKE ?2 OR n.qualified_name LIKE ?2)
+                LIMIT ?3
+                "#,
+            )?;
+            let rows = stmt.query_map(params![stype, pattern, limit], row_mapper)?;
+            for row in rows {
+                result.push(row?);
+            }
+        } else {
+            let mut stmt = self.conn.prepare(
+                r#"
+                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes,
+                       p.id, p.name
+                FROM nodes n
+                JOIN files f ON n.file_id = f.id
+                JOIN projects p ON f.project_id = p.id
+                WHERE n.name LIKE ?1 OR n.qualified_name LIKE ?1
+                LIMIT ?2
+                "#,
+            )?;
+            let rows = stmt.query_map(params![pattern, limit], row_mapper)?;
+            for row in rows {
+                result.push(row?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Delete a project's `references` edges that cross-reference linking can
+    /// rebuild, returning how many were deleted
+    ///
+    /// Field accesses without a `receiver_type` were linked by the parser and
+    /// are kept.
+    pub fn delete_reference_edges(&self, project_id: i64) -> Result<usize> {
+        let deleted = self.conn.execute(
+            r#"
+            DELETE FROM edges
+            WHERE edge_type = 'references'
+              AND source_id IN (
+                SELECT n.id
+                FROM nodes n
+                JOIN files f ON n.file_id = f.id
+                WHERE f.project_id = ?1
+                  AND (
+                    n.attributes IS NULL
+                    OR n.attributes NOT LIKE '%"access":%'
+                    OR n.attributes LIKE '%"receiver_type":%'
+                  )
+              )
+            "#,
+            params![project_id],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Get unresolved references (nodes that reference symbols not yet linked)
+    ///
+    /// Field accesses, tagged with an `access` attribute, are skipped: a
+    /// field is found through its receiver's type, not by name alone.
+    pub fn get_unresolved_references(&self, project_id: i64) -> Result<Vec<(i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.name
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            LEFT JOIN edges e ON e.source_id = n.id AND e.edge_type = 'references'
+            WHERE f.project_id = ?1
+              AND n.node_type = 'reference'
+              AND e.id IS NULL
+              AND (n.attributes IS NULL OR n.attributes NOT LIKE '%"access":%')
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Get unresolved references affected by a re-parse of one file
+    ///
+    /// These are references made from the file itself, plus references from
+    /// anywhere in the project to a name the file now defines. Field accesses
+    /// are skipped, as in [`Database::get_unresolved_references`].
+    pub fn get_unresolved_references_for_file(
+        &self,
+        project_id: i64,
+        file_id: i64,
+    ) -> Result<Vec<(i64, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT n.id, n.name
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            LEFT JOIN edges e ON e.source_id = n.id AND e.edge_type = 'references'
+            WHERE f.project_id = ?1
+              AND n.node_type = 'reference'
+              AND e.id IS NULL
+              AND (n.attributes IS NULL OR n.attributes NOT LIKE '%"access":%')
+              AND (
+                n.file_id = ?2
+                OR n.name IN (
+                    SELECT d.name FROM nodes d
+                    WHERE d.file_id = ?2
+                      AND d.node_type IN ({})
+                )
+              )
+            "#,
+            declaration_types_sql()
+        ))?;
+
+        let rows = stmt.query_map(params![project_id, file_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find definition by name
+    pub fn find_definition_by_name(&self, project_id: i64, name: &str) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                &format!(
+                    r#"
+                    SELECT n.id
+                    FROM nodes n
+                    JOIN files f ON n.file_id = f.id
+                    WHERE f.project_id = ?1
+                      AND n.name = ?2
+                      AND n.node_type IN ({})
+                    LIMIT 1
+                    "#,
+                    declaration_types_sql()
+                ),
+                params![project_id, name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Get import nodes that have not yet been linked with an `imports` edge
+    pub fn get_unresolved_imports(&self, project_id: i64) -> Result<Vec<(i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.name
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            LEFT JOIN edges e ON e.source_id = n.id AND e.edge_type = 'imports'
+            WHERE f.project_id = ?1
+              AND n.node_type = 'import'
+              AND e.id IS NULL
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find the node an import resolves to within the project
+    ///
+    /// A dotted import (`com.example.UserService`) resolves to a type declared in a
+    /// file of the matching package, and a package import (`com.example`) to that
+    /// package. Go import paths are not matched here: they name a directory of the
+    /// module, see [`find_package_in_directory`](Self::find_package_in_directory).
+    pub fn find_import_target(&self, project_id: i64, import_name: &str) -> Result<Option<i64>> {
+        if import_name.contains('/') || !import_name.contains('.') {
+            return Ok(None);
+        }
+
+        if let Some((package, type_name)) = import_name.rsplit_once('.') {
+            let type_id = self.find_type_in_package(project_id, package, type_name)?;
+            if type_id.is_some() {
+                return Ok(type_id);
+            }
+        }
+
+        self.conn
+            .query_row(
+                r#"
+                SELECT n.id
+                FROM nodes n
+                JOIN files f ON n.file_id = f.id
+                WHERE f.project_id = ?1
+                  AND n.node_type = 'package'
+                  AND n.name = ?2
+                LIMIT 1
+                "#,
+                params![project_id, import_name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Find the package node of a file directly inside `directory`
+    ///
+    /// `directory` is compared with the parent of each stored file path, so it
+    /// must be stored the same way, absolute or relative to the same root.
+    pub fn find_package_in_directory(
+        &self,
+        project_id: i64,
+        directory: &str,
+    ) -> Result<Option<i64>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, f.path
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND n.node_type = 'package'
+            ORDER BY f.path, n.id
+            "#,
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let directory = Path::new(directory);
+        for row in rows {
+            let (id, path) = row?;
+            if Path::new(&path).parent() == Some(directory) {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find a type declared in a file of the given package
+    pub fn find_type_in_package(
+        &self,
+        project_id: i64,
+        package: &str,
+        type_name: &str,
+    ) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT n.id
+                FROM nodes n
+                JOIN files f ON n.file_id = f.id
+                JOIN nodes p ON p.file_id = f.id AND p.node_type = 'package'
+                WHERE f.project_id = ?1
+                  AND n.name = ?2
+                  AND p.name = ?3
+                  AND n.node_type IN ('class', 'interface', 'struct')
+                LIMIT 1
+                "#,
+                params![project_id, type_name, package],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Get the packages each file imports with a (non-static) wildcard
+    /// import such as `import java.util.*`, keyed by file id
+    pub fn get_wildcard_imports(&self, project_id: i64) -> Result<HashMap<i64, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.file_id, n.name
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND n.node_type = 'import'
+              AND n.attributes LIKE '%"wildcard":true%'
+              AND n.attributes NOT LIKE '%"static":true%'
+            ORDER BY n.id
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut result: HashMap<i64, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (file_id, package) = row?;
+            result.entry(file_id).or_default().push(package);
+        }
+        Ok(result)
+    }
+
+    /// Find every definition whose name or qualified name matches a symbol
+    ///
+    /// A qualified name matches when it equals the symbol or ends with
+    /// `.<symbol>`, or `::<symbol>` for languages that separate names that way.
+    /// Exact matches are returned before suffix matches.
+    pub fn find_definitions(&self, project_id: i64, symbol: &str) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+              AND n.node_type IN ({})
+              AND (n.name = ?2 OR n.qualified_name = ?2 OR n.qualified_name LIKE ?3)
+            ORDER BY (n.name = ?2 OR n.qualified_name IS ?2) DESC, f.path, n.start_line
+            LIMIT ?4
+            "#,
+            declaration_types_sql()
+        ))?;
+
+        let params = params![
+            project_id,
+            symbol,
+            format!("%{}", symbol),
+            DEFAULT_RESULT_LIMIT
+        ];
+        let rows = stmt.query_map(params, |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let node = row?;
+            // LIKE treats `_` as a wildcard, so confirm the suffix match exactly
+            if node.is_named(symbol) || node.has_qualified_suffix(symbol) {
+                result.push(node);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Find the call nodes in a project that invoke the given name but were
+    /// not linked to any definition
+    ///
+    /// Calls that were linked are found through their `calls` edge instead,
+    /// with [`find_callers`](Self::find_callers) on the definition.
+    pub fn find_unlinked_call_sites(&self, project_id: i64, name: &str) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1 AND n.node_type = 'call' AND n.name = ?2
+              AND NOT EXISTS (
+                  SELECT 1 FROM edges e WHERE e.source_id = n.id AND e.edge_type = 'calls'
+              )
+            ORDER BY f.path, n.start_line, n.start_column
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id, name], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Get every node in a project together with its file path, in source order
+    pub fn get_project_nodes(&self, project_id: i64) -> Result<Vec<(String, NodeRecord)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT f.path, n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+            ORDER BY f.path, n.start_line, n.start_column
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((
+                row.get(0)?,
+                NodeRecord {
+                    id: row.get(1)?,
+                    file_id: row.get(2)?,
+                    node_type: row.get(3)?,
+                    name: row.get(4)?,
+                    qualified_name: row.get(5)?,
+                    start_line: row.get(6)?,
+                    start_column: row.get(7)?,
+                    end_line: row.get(8)?,
+                    end_column: row.get(9)?,
+                    attributes: row.get(10)?,
+                },
+            ))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    // ==================== Edge Operations ====================
+
+    /// Insert a new edge
+    pub fn insert_edge(&self, edge: &EdgeRecord) -> Result<i64> {
+        self.retry_busy(|| {
+            self.conn.execute(
+                "INSERT INTO edges (source_id, target_id, edge_type, attributes) VALUES (?1, ?2, ?3, ?4)",
+                params![edge.source_id, edge.target_id, edge.edge_type, edge.attributes],
+            )
+        })?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Get the edges leaving a node
+    pub fn get_outgoing_edges(&self, node_id: i64) -> Result<Vec<EdgeRecord>> {
+        self.get_edges_where("source_id", node_id)
+    }
+
+    /// Get the edges pointing at a node
+    pub fn get_incoming_edges(&self, node_id: i64) -> Result<Vec<EdgeRecord>> {
+        self.get_edges_where("target_id", node_id)
+    }
+
+    fn get_edges_where(&self, column: &str, node_id: i64) -> Result<Vec<EdgeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, source_id, target_id, edge_type, attributes FROM edges WHERE {} = ?1 ORDER BY id",
+            column
+        ))?;
+
+        let rows = stmt.query_map(params![node_id], |row| {
+            Ok(EdgeRecord {
+                id: row.get(0)?,
+                source_id: row.get(1)?,
+                target_id: row.get(2)?,
+                edge_type: row.get(3)?,
+                attributes: row.get(4)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Get every edge whose source node belongs to a project
+    pub fn get_project_edges(&self, project_id: i64) -> Result<Vec<EdgeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT e.id, e.source_id, e.target_id, e.edge_type, e.attributes
+            FROM edges e
+            JOIN nodes n ON e.source_id = n.id
+            JOIN files f ON n.file_id = f.id
+            WHERE f.project_id = ?1
+            ORDER BY e.id
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(EdgeRecord {
+                id: row.get(0)?,
+                source_id: row.get(1)?,
+                target_id: row.get(2)?,
+                edge_type: row.get(3)?,
+                attributes: row.get(4)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Export a project's whole graph in the `{nodes, links}` shape used by
+    /// d3-force
+    ///
+    /// Edges leading to another project's nodes are left out, so every link
+    /// refers to exported nodes.
+    pub fn export_d3(&self, project_id: i64) -> Result<D3Graph> {
+        self.get_project_status(project_id)?
+            .ok_or_else(|| Error::ProjectNotFound(project_id.to_string()))?;
+
+        let nodes: Vec<D3Node> = self
+            .get_project_nodes(project_id)?
+            .into_iter()
+            .map(|(_, node)| D3Node {
+                id: node.id,
+                label: node.qualified_name.unwrap_or(node.name),
+                group: node.node_type,
+            })
+            .collect();
+
+        let ids: HashSet<i64> = nodes.iter().map(|n| n.id).collect();
+        let links = self
+            .get_project_edges(project_id)?
+            .into_iter()
+            .filter(|e| ids.contains(&e.target_id))
+            .map(|e| D3Link {
+                source: e.source_id,
+                target: e.target_id,
+                link_type: e.edge_type,
+            })
+            .collect();
+
+        Ok(D3Graph { nodes, links })
+    }
+
+    /// Find the target of a reference
+    pub fn find_reference_target(&self, node_id: i64) -> Result<Option<NodeRecord>> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                       n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+                FROM nodes n
+                JOIN edges e ON e.target_id = n.id
+                WHERE e.source_id = ?1 AND e.edge_type = 'references'
+                LIMIT 1
+                "#,
+                params![node_id],
+                |row| {
+                    Ok(NodeRecord {
+                        id: row.get(0)?,
+                        file_id: row.get(1)?,
+                        node_type: row.get(2)?,
+                        name: row.get(3)?,
+                        qualified_name: row.get(4)?,
+                        start_line: row.get(5)?,
+                        start_column: row.get(6)?,
+                        end_line: row.get(7)?,
+                        end_column: row.get(8)?,
+                        attributes: row.get(9)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Find references to a node, one page at a time
+    pub fn find_all_references(
+        &self,
+        node_id: i64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN edges e ON e.source_id = n.id
+            WHERE e.target_id = ?1 AND e.edge_type = 'references'
+            ORDER BY n.id
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![node_id, limit, offset], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find callers of a function, one page at a time
+    pub fn find_callers(&self, node_id: i64, limit: u32, offset: u32) -> Result<Vec<NodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+                   n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
+            FROM nodes n
+            JOIN edges e ON e.source_id = n.id
+            WHERE e.target_id = ?1 AND e.edge_type = 'calls'
+            ORDER BY n.id
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![node_id, limit, offset], |row| {
+            Ok(NodeRecord {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                node_type: row.get(2)?,
+                name: row.get(3)?,
+                qualified_name: row.get(4)?,
+                start_line: row.get(5)?,
+                start_column: row.get(6)?,
+                end_line: row.get(7)?,
+                end_column: row.get(8)?,
+                attributes: row.get(9)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Find callees of a function, one page at a time
+    pub fn find_callees(&self, node_id: i64, limit: u32, offset: u32) -> Result<Vec<NodeRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
             SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
                    n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
             FROM nodes n
-            JOIN edges e ON e.source_id = n.id
-            WHERE e.target_id = ?1 AND e.edge_type = 'references'
+            JOIN edges e ON e.target_id = n.id
+            WHERE e.source_id = ?1 AND e.edge_type = 'calls'
+            ORDER BY n.id
+            LIMIT ?2 OFFSET ?3
             "#,
         )?;
 
-        let rows = stmt.query_map(params![node_id], |row| {
+        let rows = stmt.query_map(params![node_id, limit, offset], |row| {
             Ok(NodeRecord {
                 id: row.get(0)?,
                 file_id: row.get(1)?,
@@ -611,19 +2444,27 @@ impl Database {
         Ok(result)
     }
 
-    /// Find callers of a function
-    pub fn find_callers(&self, node_id: i64) -> Result<Vec<NodeRecord>> {
+    /// Find the nodes in a project carrying an annotation with the given name
+    pub fn find_nodes_by_annotation(
+        &self,
+        project_id: i64,
+        annotation: &str,
+    ) -> Result<Vec<NodeRecord>> {
+        let annotation = annotation.trim_start_matches('@');
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
+            SELECT DISTINCT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
                    n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
             FROM nodes n
-            JOIN edges e ON e.source_id = n.id
-            WHERE e.target_id = ?1 AND e.edge_type = 'calls'
+            JOIN files f ON n.file_id = f.id
+            JOIN edges e ON e.source_id = n.id AND e.edge_type = 'annotated_by'
+            JOIN nodes a ON e.target_id = a.id
+            WHERE f.project_id = ?1 AND a.node_type = 'annotation' AND a.name = ?2
+            ORDER BY n.id
             "#,
         )?;
 
-        let rows = stmt.query_map(params![node_id], |row| {
+        let rows = stmt.query_map(params![project_id, annotation], |row| {
             Ok(NodeRecord {
                 id: row.get(0)?,
                 file_id: row.get(1)?,
@@ -645,15 +2486,15 @@ impl Database {
         Ok(result)
     }
 
-    /// Find callees of a function
-    pub fn find_callees(&self, node_id: i64) -> Result<Vec<NodeRecord>> {
+    /// Find import nodes that resolve to the given package or type
+    pub fn find_importers(&self, node_id: i64) -> Result<Vec<NodeRecord>> {
         let mut stmt = self.conn.prepare(
             r#"
             SELECT n.id, n.file_id, n.node_type, n.name, n.qualified_name,
                    n.start_line, n.start_column, n.end_line, n.end_column, n.attributes
             FROM nodes n
-            JOIN edges e ON e.target_id = n.id
-            WHERE e.source_id = ?1 AND e.edge_type = 'calls'
+            JOIN edges e ON e.source_id = n.id
+            WHERE e.target_id = ?1 AND e.edge_type = 'imports'
             "#,
         )?;
 
@@ -709,6 +2550,9 @@ mod tests {
             language: "java".to_string(),
             content_hash: "abc123".to_string(),
             parsed_at: chrono::Utc::now(),
+            line_count: 0,
+            is_test: false,
+            encoding: None,
         };
         db.insert_file(&file).unwrap()
     }
@@ -729,12 +2573,67 @@ mod tests {
         db.insert_node(&node).unwrap()
     }
 
+    #[test]
+    fn test_concurrent_writers_wait_for_lock() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("shared.db");
+        let db = Database::open(&db_path).unwrap();
+        db.init_schema().unwrap();
+        let file_id = create_file(&db, create_project(&db));
+
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let db_path = db_path.clone();
+                std::thread::spawn(move || {
+                    let db = Database::open(&db_path).unwrap();
+                    for i in 0..100 {
+                        create_node(&db, file_id, "method", &format!("writer{}_{}", writer, i));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("writer failed with a lock error");
+        }
+
+        assert_eq!(db.get_nodes_by_file(file_id, None).unwrap().len(), 200);
+    }
+
+    #[test]
+    fn test_file_database_uses_wal() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("wal.db");
+
+        let db = Database::open(&db_path).unwrap();
+        assert_eq!(db.journal_mode().unwrap(), "wal");
+        drop(db);
+
+        let options = OpenOptions {
+            wal: false,
+            synchronous: Synchronous::Normal,
+            ..OpenOptions::default()
+        };
+        let db =
+            Database::open_with_options(&temp_dir.path().join("rollback.db"), &options).unwrap();
+        assert_eq!(db.journal_mode().unwrap(), "delete");
+
+        assert_eq!(setup_db().journal_mode().unwrap(), "memory");
+    }
+
     #[test]
     fn test_init_schema() {
         let db = Database::open_in_memory().unwrap();
         db.init_schema().unwrap();
     }
 
+    #[test]
+    fn test_has_schema() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(!db.has_schema().unwrap());
+        db.init_schema().unwrap();
+        assert!(db.has_schema().unwrap());
+    }
+
     #[test]
     fn test_init_schema_idempotent() {
         let db = Database::open_in_memory().unwrap();
@@ -742,6 +2641,82 @@ mod tests {
         db.init_schema().unwrap(); // Should not fail
     }
 
+    #[test]
+    fn test_integrity_check_fresh_db() {
+        let db = setup_db();
+        assert!(db.integrity_check().unwrap());
+    }
+
+    #[test]
+    fn test_vacuum_and_analyze() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        create_node(&db, file_id, "class", "Test");
+        db.delete_file_data(file_id).unwrap();
+
+        db.vacuum().unwrap();
+        db.analyze().unwrap();
+        assert!(db.integrity_check().unwrap());
+    }
+
+    #[test]
+    fn test_migrate_backfills_stable_ids() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let node_id = create_node(&db, file_id, "class", "Legacy");
+
+        // A node stored before stable ids were recorded
+        db.conn
+            .execute(
+                "UPDATE nodes SET stable_id = NULL WHERE id = ?1",
+                params![node_id],
+            )
+            .unwrap();
+        let stable_id = db
+            .get_node(node_id)
+            .unwrap()
+            .unwrap()
+            .stable_id("/test/path/file.java");
+        assert!(db
+            .find_node_by_stable_id(project_id, &stable_id)
+            .unwrap()
+            .is_none());
+
+        db.init_schema().unwrap();
+        assert_eq!(
+            db.find_node_by_stable_id(project_id, &stable_id)
+                .unwrap()
+                .unwrap()
+                .id,
+            node_id
+        );
+    }
+
+    #[test]
+    fn test_transaction_rollback_on_drop() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+
+        {
+            let _tx = db.transaction().unwrap();
+            create_file(&db, project_id);
+        }
+        assert!(db
+            .get_file_by_path(project_id, "/test/path/file.java")
+            .unwrap()
+            .is_none());
+
+        let tx = db.transaction().unwrap();
+        create_file(&db, project_id);
+        tx.commit().unwrap();
+        assert!(db
+            .get_file_by_path(project_id, "/test/path/file.java")
+            .unwrap()
+            .is_some());
+    }
+
     #[test]
     fn test_insert_project() {
         let db = Database::open_in_memory().unwrap();
@@ -776,6 +2751,20 @@ mod tests {
         assert!(project.is_none());
     }
 
+    #[test]
+    fn test_project_roots() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+
+        db.add_project_root(project_id, "/test/libs/b").unwrap();
+        db.add_project_root(project_id, "/test/services/a").unwrap();
+        db.add_project_root(project_id, "/test/libs/b").unwrap();
+
+        let roots = db.get_project_roots(project_id).unwrap();
+        assert_eq!(roots, ["/test/libs/b", "/test/services/a"]);
+        assert!(db.get_project_roots(project_id + 1).unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_project_by_name() {
         let db = setup_db();
@@ -830,6 +2819,38 @@ mod tests {
         assert!(project.updated_at >= project.created_at);
     }
 
+    #[test]
+    fn test_rename_project() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let before = db.get_project_by_name("test-project").unwrap().unwrap();
+
+        assert!(db.rename_project(project_id, "renamed").unwrap());
+
+        let renamed = db.get_project_by_name("renamed").unwrap().unwrap();
+        assert_eq!(renamed.id, project_id);
+        assert!(renamed.updated_at >= before.updated_at);
+        assert!(db.get_project_by_name("test-project").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rename_project_rejects_taken_name() {
+        let db = setup_db();
+        let first = create_project(&db);
+        let other = ProjectRecord {
+            id: 0,
+            name: "other".to_string(),
+            root_path: "/other/path".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        db.insert_project(&other).unwrap();
+
+        let result = db.rename_project(first, "other");
+        assert!(matches!(result, Err(Error::ProjectExists(name)) if name == "other"));
+        assert!(!db.rename_project(9999, "unused").unwrap());
+    }
+
     #[test]
     fn test_get_project_status() {
         let db = setup_db();
@@ -844,6 +2865,89 @@ mod tests {
         assert_eq!(status.files_parsed, 1);
         assert_eq!(status.nodes_count, 1);
         assert_eq!(status.status, "ready");
+        assert!(status.last_parsed_at.is_none());
+
+        db.mark_project_parsed(project_id).unwrap();
+        let status = db.get_project_status(project_id).unwrap().unwrap();
+        assert!(status.last_parsed_at.is_some());
+    }
+
+    #[test]
+    fn test_all_project_statuses_match_per_project_status() {
+        let db = setup_db();
+        let mut project_ids = Vec::new();
+        for (name, files) in [("beta", 2), ("alpha", 1), ("empty", 0)] {
+            let project_id = db
+                .insert_project(&ProjectRecord {
+                    id: 0,
+                    name: name.to_string(),
+                    root_path: format!("/{}", name),
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                })
+                .unwrap();
+            for i in 0..files {
+                let file_id = db
+                    .insert_file(&FileRecord {
+                        id: 0,
+                        project_id,
+                        path: format!("/{}/File{}.java", name, i),
+                        language: "java".to_string(),
+                        content_hash: "abc123".to_string(),
+                        parsed_at: chrono::Utc::now(),
+                        line_count: 10,
+                        is_test: false,
+                        encoding: None,
+                    })
+                    .unwrap();
+                let class_id = create_node(&db, file_id, "class", "Widget");
+                let method_id = create_node(&db, file_id, "method", "render");
+                db.insert_edge(&EdgeRecord {
+                    id: 0,
+                    source_id: class_id,
+                    target_id: method_id,
+                    edge_type: "contains".to_string(),
+                    attributes: None,
+                })
+                .unwrap();
+            }
+            project_ids.push(project_id);
+        }
+        db.mark_project_parsed(project_ids[0]).unwrap();
+
+        let summary = |s: &ProjectStatus| {
+            (
+                s.project_id,
+                s.name.clone(),
+                s.files_parsed,
+                s.nodes_count,
+                s.edges_count,
+                s.last_parsed_at,
+            )
+        };
+        let statuses = db.all_project_statuses().unwrap();
+        let names: Vec<_> = statuses.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "beta", "empty"]);
+        for status in &statuses {
+            let expected = db.get_project_status(status.project_id).unwrap().unwrap();
+            assert_eq!(summary(status), summary(&expected));
+        }
+        assert_eq!(
+            (
+                statuses[1].files_parsed,
+                statuses[1].nodes_count,
+                statuses[1].edges_count
+            ),
+            (2, 4, 2)
+        );
+        assert_eq!(
+            (
+                statuses[2].files_parsed,
+                statuses[2].nodes_count,
+                statuses[2].edges_count
+            ),
+            (0, 0, 0)
+        );
     }
 
     #[test]
@@ -858,10 +2962,15 @@ mod tests {
             language: "java".to_string(),
             content_hash: "hash123".to_string(),
             parsed_at: chrono::Utc::now(),
+            line_count: 0,
+            is_test: false,
+            encoding: Some("windows-1252".to_string()),
         };
 
         let file_id = db.insert_file(&file).unwrap();
         assert!(file_id > 0);
+        let stored = db.get_file(file_id).unwrap().unwrap();
+        assert_eq!(stored.encoding.as_deref(), Some("windows-1252"));
     }
 
     #[test]
@@ -870,7 +2979,9 @@ mod tests {
         let project_id = create_project(&db);
         let file_id = create_file(&db, project_id);
 
-        let file = db.get_file_by_path(project_id, "/test/path/file.java").unwrap();
+        let file = db
+            .get_file_by_path(project_id, "/test/path/file.java")
+            .unwrap();
         assert!(file.is_some());
         assert_eq!(file.unwrap().id, file_id);
     }
@@ -881,45 +2992,362 @@ mod tests {
         let project_id = create_project(&db);
         let file_id = create_file(&db, project_id);
 
-        let file = db.get_file(file_id).unwrap();
-        assert!(file.is_some());
-        assert_eq!(file.unwrap().path, "/test/path/file.java");
+        let file = db.get_file(file_id).unwrap();
+        assert!(file.is_some());
+        assert_eq!(file.unwrap().path, "/test/path/file.java");
+    }
+
+    #[test]
+    fn test_file_metrics() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+
+        let file = FileRecord {
+            id: 0,
+            project_id,
+            path: "/test/path/Service.java".to_string(),
+            language: "java".to_string(),
+            content_hash: "hash".to_string(),
+            parsed_at: chrono::Utc::now(),
+            line_count: 25,
+            is_test: false,
+            encoding: None,
+        };
+        let file_id = db.insert_file(&file).unwrap();
+        create_node(&db, file_id, "class", "Service");
+        create_node(&db, file_id, "method", "run");
+
+        let metrics = db.file_metrics(project_id).unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].path, "/test/path/Service.java");
+        assert_eq!(metrics[0].language, "java");
+        assert_eq!(metrics[0].line_count, 25);
+        assert_eq!(metrics[0].node_count, 2);
+    }
+
+    #[test]
+    fn test_counts_by_language() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+
+        let java_file = create_file(&db, project_id);
+        let go_file = db
+            .insert_file(&FileRecord {
+                id: 0,
+                project_id,
+                path: "/test/path/main.go".to_string(),
+                language: "go".to_string(),
+                content_hash: "def456".to_string(),
+                parsed_at: chrono::Utc::now(),
+                line_count: 0,
+                is_test: false,
+                encoding: None,
+            })
+            .unwrap();
+
+        let class_id = create_node(&db, java_file, "class", "Service");
+        let method_id = create_node(&db, java_file, "method", "run");
+        create_node(&db, go_file, "function", "main");
+        db.insert_edge(&EdgeRecord {
+            id: 0,
+            source_id: class_id,
+            target_id: method_id,
+            edge_type: "contains".to_string(),
+            attributes: None,
+        })
+        .unwrap();
+
+        let counts = db.counts_by_language(project_id).unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0].language, "go");
+        assert_eq!(
+            (
+                counts[0].file_count,
+                counts[0].node_count,
+                counts[0].edge_count
+            ),
+            (1, 1, 0)
+        );
+        assert_eq!(counts[1].language, "java");
+        assert_eq!(
+            (
+                counts[1].file_count,
+                counts[1].node_count,
+                counts[1].edge_count
+            ),
+            (1, 2, 1)
+        );
+    }
+
+    #[test]
+    fn test_distinct_node_and_edge_types() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let class_id = create_node(&db, file_id, "class", "Service");
+        let method_id = create_node(&db, file_id, "method", "run");
+        let other_method_id = create_node(&db, file_id, "method", "stop");
+        let call_id = create_node(&db, file_id, "call", "stop");
+        for (source_id, target_id, edge_type) in [
+            (class_id, method_id, "contains"),
+            (class_id, other_method_id, "contains"),
+            (call_id, other_method_id, "calls"),
+        ] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: edge_type.to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        assert_eq!(
+            db.distinct_node_types(project_id).unwrap(),
+            ["call", "class", "method"]
+        );
+        assert_eq!(
+            db.distinct_edge_types(project_id).unwrap(),
+            ["calls", "contains"]
+        );
+
+        let empty = db
+            .insert_project(&ProjectRecord {
+                id: 0,
+                name: "empty".to_string(),
+                root_path: "/empty".to_string(),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        assert!(db.distinct_node_types(empty).unwrap().is_empty());
+        assert!(db.distinct_edge_types(empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_d3_links_reference_nodes() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let class_id = create_node(&db, file_id, "class", "Service");
+        let method_id = create_node(&db, file_id, "method", "run");
+        let call_id = create_node(&db, file_id, "call", "stop");
+        for (source_id, target_id, edge_type) in [
+            (class_id, method_id, "contains"),
+            (method_id, call_id, "calls"),
+        ] {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: edge_type.to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let json = serde_json::to_value(db.export_d3(project_id).unwrap()).unwrap();
+        let nodes = json["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes
+            .iter()
+            .any(|n| n["id"] == class_id && n["group"] == "class"));
+        let ids: Vec<&serde_json::Value> = nodes.iter().map(|n| &n["id"]).collect();
+        let links = json["links"].as_array().unwrap();
+        assert_eq!(links.len(), 2);
+        for link in links {
+            assert!(ids.contains(&&link["source"]));
+            assert!(ids.contains(&&link["target"]));
+        }
+        assert!(links
+            .iter()
+            .any(|l| l["type"] == "calls" && l["target"] == call_id));
+
+        assert!(matches!(
+            db.export_d3(project_id + 100),
+            Err(Error::ProjectNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_migrate_adds_line_count() {
+        let db = Database::open_in_memory().unwrap();
+        db.conn
+            .execute_batch(
+                r#"
+                CREATE TABLE files (
+                    id INTEGER PRIMARY KEY,
+                    project_id INTEGER NOT NULL,
+                    path TEXT NOT NULL,
+                    language TEXT NOT NULL,
+                    content_hash TEXT NOT NULL,
+                    parsed_at TEXT NOT NULL
+                );
+                "#,
+            )
+            .unwrap();
+
+        db.init_schema().unwrap();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let file = db.get_file(file_id).unwrap().unwrap();
+        assert_eq!(file.line_count, 0);
+        assert!(!file.is_test);
+    }
+
+    #[test]
+    fn test_delete_file_data() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        create_node(&db, file_id, "class", "Test");
+
+        db.delete_file_data(file_id).unwrap();
+
+        let file = db.get_file(file_id).unwrap();
+        assert!(file.is_none());
+    }
+
+    #[test]
+    fn test_count_nodes_and_edges() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        assert_eq!(
+            (
+                db.count_nodes(project_id).unwrap(),
+                db.count_edges(project_id).unwrap()
+            ),
+            (0, 0)
+        );
+
+        let class_id = create_node(&db, file_id, "class", "Test");
+        let method_id = create_node(&db, file_id, "method", "run");
+        db.insert_edge(&EdgeRecord {
+            id: 0,
+            source_id: class_id,
+            target_id: method_id,
+            edge_type: "contains".to_string(),
+            attributes: None,
+        })
+        .unwrap();
+        assert_eq!(db.count_nodes(project_id).unwrap(), 2);
+        assert_eq!(db.count_edges(project_id).unwrap(), 1);
+
+        let status = db.get_project_status(project_id).unwrap().unwrap();
+        assert_eq!((status.nodes_count, status.edges_count), (2, 1));
+
+        db.delete_file_data(file_id).unwrap();
+        assert_eq!(db.count_nodes(project_id).unwrap(), 0);
+        assert_eq!(db.count_edges(project_id).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_insert_node() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let node = NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "function".to_string(),
+            name: "myFunction".to_string(),
+            qualified_name: Some("pkg.myFunction".to_string()),
+            start_line: 5,
+            start_column: 1,
+            end_line: 10,
+            end_column: 1,
+            attributes: Some(r#"{"public":true}"#.to_string()),
+        };
+
+        let node_id = db.insert_node(&node).unwrap();
+        assert!(node_id > 0);
+    }
+
+    #[test]
+    fn test_bump_project_generation() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+
+        assert_eq!(db.get_project_generation(project_id).unwrap(), 0);
+        db.bump_project_generation(project_id).unwrap();
+        db.bump_project_generation(project_id).unwrap();
+        assert_eq!(db.get_project_generation(project_id).unwrap(), 2);
+        assert_eq!(db.get_project_generation(project_id + 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_node() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let node = NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "method".to_string(),
+            name: "save".to_string(),
+            qualified_name: Some("com.example.UserService.save".to_string()),
+            start_line: 12,
+            start_column: 5,
+            end_line: 20,
+            end_column: 6,
+            attributes: Some(r#"{"abstract":true}"#.to_string()),
+        };
+        let node_id = db.insert_node(&node).unwrap();
+
+        let fetched = db.get_node(node_id).unwrap().unwrap();
+        assert_eq!(fetched.id, node_id);
+        assert_eq!(fetched.file_id, file_id);
+        assert_eq!(fetched.name, "save");
+        assert_eq!(fetched.qualified_name, node.qualified_name);
+        assert_eq!((fetched.start_line, fetched.end_line), (12, 20));
+        assert_eq!(fetched.attributes, node.attributes);
+
+        assert!(db.get_node(node_id + 1).unwrap().is_none());
     }
 
     #[test]
-    fn test_delete_file_data() {
+    fn test_get_nodes_by_file() {
         let db = setup_db();
         let project_id = create_project(&db);
         let file_id = create_file(&db, project_id);
-        create_node(&db, file_id, "class", "Test");
 
-        db.delete_file_data(file_id).unwrap();
+        create_node(&db, file_id, "class", "Service");
+        create_node(&db, file_id, "method", "start");
+        create_node(&db, file_id, "method", "stop");
 
-        let file = db.get_file(file_id).unwrap();
-        assert!(file.is_none());
+        let all = db.get_nodes_by_file(file_id, None).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let methods = db.get_nodes_by_file(file_id, Some("method")).unwrap();
+        assert_eq!(methods.len(), 2);
+        assert!(methods.iter().all(|n| n.node_type == "method"));
     }
 
     #[test]
-    fn test_insert_node() {
+    #[cfg(debug_assertions)]
+    fn test_get_nodes_by_file_uses_composite_index() {
         let db = setup_db();
-        let project_id = create_project(&db);
-        let file_id = create_file(&db, project_id);
 
-        let node = NodeRecord {
-            id: 0,
-            file_id,
-            node_type: "function".to_string(),
-            name: "myFunction".to_string(),
-            qualified_name: Some("pkg.myFunction".to_string()),
-            start_line: 5,
-            start_column: 1,
-            end_line: 10,
-            end_column: 1,
-            attributes: Some(r#"{"public":true}"#.to_string()),
-        };
-
-        let node_id = db.insert_node(&node).unwrap();
-        assert!(node_id > 0);
+        let mut stmt = db
+            .conn
+            .prepare(
+                "EXPLAIN QUERY PLAN SELECT id FROM nodes WHERE file_id = ?1 AND node_type = ?2",
+            )
+            .unwrap();
+        let details: Vec<String> = stmt
+            .query_map(params![1, "method"], |row| row.get(3))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert!(details.iter().any(|d| d.contains("idx_nodes_file_type")));
     }
 
     #[test]
@@ -942,7 +3370,9 @@ mod tests {
         };
         db.insert_node(&node).unwrap();
 
-        let found = db.find_node_at_position(project_id, "/test/path/file.java", 10, 5).unwrap();
+        let found = db
+            .find_node_at_position(project_id, "/test/path/file.java", 10, 5)
+            .unwrap();
         assert!(found.is_some());
         assert_eq!(found.unwrap().name, "TestClass");
     }
@@ -952,7 +3382,9 @@ mod tests {
         let db = setup_db();
         let project_id = create_project(&db);
 
-        let found = db.find_node_at_position(project_id, "/nonexistent.java", 10, 5).unwrap();
+        let found = db
+            .find_node_at_position(project_id, "/nonexistent.java", 10, 5)
+            .unwrap();
         assert!(found.is_none());
     }
 
@@ -975,10 +3407,113 @@ mod tests {
         let file_id = create_file(&db, project_id);
         create_node(&db, file_id, "class", "UserService");
 
-        let found = db.find_symbol_by_name(project_id, "com.example.UserService").unwrap();
+        let found = db
+            .find_symbol_by_name(project_id, "com.example.UserService")
+            .unwrap();
         assert!(found.is_some());
     }
 
+    #[test]
+    fn test_find_symbol_by_partially_qualified_name() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let method = NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "method".to_string(),
+            name: "getUser".to_string(),
+            qualified_name: Some("com.example.UserService.getUser".to_string()),
+            start_line: 3,
+            start_column: 5,
+            end_line: 5,
+            end_column: 5,
+            attributes: None,
+        };
+        db.insert_node(&method).unwrap();
+
+        let found = db
+            .find_symbol_by_name(project_id, "getUser")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.name, "getUser");
+
+        let found = db
+            .find_symbol_by_name(project_id, "UserService.getUser")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            found.qualified_name.as_deref(),
+            Some("com.example.UserService.getUser")
+        );
+
+        // Suffixes must cover whole segments
+        assert!(db
+            .find_symbol_by_name(project_id, "rService.getUser")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_symbol_by_name_prefers_exact_match() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        // Inserted first, but only matches `Service` as a suffix
+        create_node(&db, file_id, "class", "Service");
+        let exact = NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "package".to_string(),
+            name: "example.Service".to_string(),
+            qualified_name: None,
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 1,
+            attributes: None,
+        };
+        db.insert_node(&exact).unwrap();
+
+        let found = db
+            .find_symbol_by_name(project_id, "example.Service")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.node_type, "package");
+    }
+
+    #[test]
+    fn test_find_symbol_by_name_limits_suffix_matches() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let suffix_match = |i: u32| NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "class".to_string(),
+            name: "Service".to_string(),
+            qualified_name: Some(format!("pkg{}.example.Service", i)),
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 1,
+            attributes: None,
+        };
+        for i in 0..=DEFAULT_RESULT_LIMIT {
+            db.insert_node(&suffix_match(i)).unwrap();
+        }
+        // Inserted after more suffix matches than the limit
+        let exact_id = create_node(&db, file_id, "package", "example.Service");
+
+        let found = db
+            .find_symbol_by_name(project_id, "example.Service")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, exact_id);
+        let definitions = db.find_definitions(project_id, "example.Service").unwrap();
+        assert_eq!(definitions.len(), DEFAULT_RESULT_LIMIT as usize);
+    }
+
     #[test]
     fn test_search_symbols() {
         let db = setup_db();
@@ -1002,11 +3537,105 @@ mod tests {
         create_node(&db, file_id, "class", "UserService");
         create_node(&db, file_id, "method", "getUser");
 
-        let results = db.search_symbols(project_id, "User", Some("method"), 10).unwrap();
+        let results = db
+            .search_symbols(project_id, "User", Some("method"), 10)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].node_type, "method");
     }
 
+    #[test]
+    fn test_find_nodes_in_range() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let insert = |name: &str, start_line: u32, end_line: u32| {
+            db.insert_node(&NodeRecord {
+                id: 0,
+                file_id,
+                node_type: "method".to_string(),
+                name: name.to_string(),
+                qualified_name: None,
+                start_line,
+                start_column: 5,
+                end_line,
+                end_column: 6,
+                attributes: None,
+            })
+            .unwrap();
+        };
+        insert("Outer", 1, 50);
+        insert("before", 2, 9);
+        insert("straddles", 8, 14);
+        insert("inside", 11, 12);
+        insert("after", 21, 30);
+
+        let names: Vec<_> = db
+            .find_nodes_in_range(project_id, "/test/path/file.java", 10, 20)
+            .unwrap()
+            .into_iter()
+            .map(|n| n.name)
+            .collect();
+        assert_eq!(names, ["Outer", "straddles", "inside"]);
+
+        // Ranges touching a node's first or last line overlap it
+        let names: Vec<_> = db
+            .find_nodes_in_range(project_id, "/test/path/file.java", 9, 9)
+            .unwrap()
+            .into_iter()
+            .map(|n| n.name)
+            .collect();
+        assert_eq!(names, ["Outer", "before", "straddles"]);
+
+        assert!(db
+            .find_nodes_in_range(project_id, "/test/path/other.java", 1, 50)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_symbols_in_file() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let other_file_id = db
+            .insert_file(&FileRecord {
+                id: 0,
+                project_id,
+                path: "/test/path/Other.java".to_string(),
+                language: "java".to_string(),
+                content_hash: "def456".to_string(),
+                parsed_at: chrono::Utc::now(),
+                line_count: 0,
+                is_test: false,
+                encoding: None,
+            })
+            .unwrap();
+
+        create_node(&db, file_id, "class", "UserService");
+        create_node(&db, file_id, "method", "getUser");
+        create_node(&db, other_file_id, "class", "UserRepository");
+
+        let results = db
+            .search_symbols_in_file(project_id, "/test/path/Other.java", "User", None, 10)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "UserRepository");
+
+        let results = db
+            .search_symbols_in_file(
+                project_id,
+                "/test/path/file.java",
+                "User",
+                Some("method"),
+                10,
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "getUser");
+    }
+
     #[test]
     fn test_search_symbols_with_limit() {
         let db = setup_db();
@@ -1021,6 +3650,40 @@ mod tests {
         assert_eq!(results.len(), 5);
     }
 
+    #[test]
+    fn test_search_symbols_global() {
+        let db = setup_db();
+
+        let mut project_ids = Vec::new();
+        for name in ["alpha", "beta"] {
+            let project = ProjectRecord {
+                id: 0,
+                name: name.to_string(),
+                root_path: format!("/test/{}", name),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            let project_id = db.insert_project(&project).unwrap();
+            let file_id = create_file(&db, project_id);
+            create_node(&db, file_id, "class", "UserService");
+            project_ids.push(project_id);
+        }
+
+        let results = db.search_symbols_global("User", None, 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .any(|(id, name, _)| *id == project_ids[0] && name == "alpha"));
+        assert!(results
+            .iter()
+            .any(|(id, name, _)| *id == project_ids[1] && name == "beta"));
+
+        let methods = db
+            .search_symbols_global("User", Some("method"), 10)
+            .unwrap();
+        assert!(methods.is_empty());
+    }
+
     #[test]
     fn test_insert_edge() {
         let db = setup_db();
@@ -1086,10 +3749,115 @@ mod tests {
             db.insert_edge(&edge).unwrap();
         }
 
-        let refs = db.find_all_references(target_id).unwrap();
+        let refs = db
+            .find_all_references(target_id, DEFAULT_RESULT_LIMIT, 0)
+            .unwrap();
         assert_eq!(refs.len(), 2);
     }
 
+    #[test]
+    fn test_get_project_nodes_and_edges() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let caller_id = create_node(&db, file_id, "function", "main");
+        let callee_id = create_node(&db, file_id, "function", "helper");
+        let edge = EdgeRecord {
+            id: 0,
+            source_id: caller_id,
+            target_id: callee_id,
+            edge_type: "calls".to_string(),
+            attributes: None,
+        };
+        db.insert_edge(&edge).unwrap();
+
+        let nodes = db.get_project_nodes(project_id).unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.iter().all(|(path, _)| path == "/test/path/file.java"));
+
+        let edges = db.get_project_edges(project_id).unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(
+            (edges[0].source_id, edges[0].target_id),
+            (caller_id, callee_id)
+        );
+
+        assert!(db.get_project_nodes(project_id + 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_outgoing_and_incoming_edges() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let class_id = create_node(&db, file_id, "class", "UserService");
+        let method_id = create_node(&db, file_id, "method", "save");
+        let call_id = create_node(&db, file_id, "call", "persist");
+        for (source_id, target_id, edge_type) in [
+            (class_id, method_id, "contains"),
+            (method_id, call_id, "calls"),
+        ] {
+            let edge = EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: edge_type.to_string(),
+                attributes: None,
+            };
+            db.insert_edge(&edge).unwrap();
+        }
+
+        let outgoing = db.get_outgoing_edges(method_id).unwrap();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(
+            (outgoing[0].target_id, outgoing[0].edge_type.as_str()),
+            (call_id, "calls")
+        );
+
+        let incoming = db.get_incoming_edges(method_id).unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(
+            (incoming[0].source_id, incoming[0].edge_type.as_str()),
+            (class_id, "contains")
+        );
+    }
+
+    #[test]
+    fn test_find_all_references_paginated() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let target_id = create_node(&db, file_id, "function", "log");
+        let mut ref_ids = Vec::new();
+        for i in 0..100 {
+            let ref_id = create_node(&db, file_id, "reference", &format!("ref{}", i));
+            let edge = EdgeRecord {
+                id: 0,
+                source_id: ref_id,
+                target_id,
+                edge_type: "references".to_string(),
+                attributes: None,
+            };
+            db.insert_edge(&edge).unwrap();
+            ref_ids.push(ref_id);
+        }
+
+        let page = db.find_all_references(target_id, 10, 20).unwrap();
+        let page_ids: Vec<i64> = page.iter().map(|n| n.id).collect();
+        assert_eq!(page_ids, ref_ids[20..30]);
+
+        let tail = db.find_all_references(target_id, 10, 95).unwrap();
+        assert_eq!(tail.len(), 5);
+
+        let all = db
+            .find_all_references(target_id, DEFAULT_RESULT_LIMIT, 0)
+            .unwrap();
+        assert_eq!(all.len(), 100);
+    }
+
     #[test]
     fn test_find_callers() {
         let db = setup_db();
@@ -1111,10 +3879,47 @@ mod tests {
             db.insert_edge(&edge).unwrap();
         }
 
-        let callers = db.find_callers(callee_id).unwrap();
+        let callers = db.find_callers(callee_id, DEFAULT_RESULT_LIMIT, 0).unwrap();
         assert_eq!(callers.len(), 2);
     }
 
+    #[test]
+    fn test_find_nodes_by_annotation() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let service = create_node(&db, file_id, "annotation", "Service");
+        let deprecated = create_node(&db, file_id, "annotation", "Deprecated");
+        let orders = create_node(&db, file_id, "class", "OrderService");
+        let billing = create_node(&db, file_id, "class", "BillingService");
+        let legacy = create_node(&db, file_id, "class", "LegacyClient");
+        create_node(&db, file_id, "class", "Helper");
+
+        for (source_id, target_id) in [(orders, service), (billing, service), (legacy, deprecated)]
+        {
+            db.insert_edge(&EdgeRecord {
+                id: 0,
+                source_id,
+                target_id,
+                edge_type: "annotated_by".to_string(),
+                attributes: None,
+            })
+            .unwrap();
+        }
+
+        let annotated = db.find_nodes_by_annotation(project_id, "Service").unwrap();
+        let ids: Vec<_> = annotated.iter().map(|n| n.id).collect();
+        assert_eq!(ids, [orders, billing]);
+
+        let with_at = db.find_nodes_by_annotation(project_id, "@Service").unwrap();
+        assert_eq!(with_at.len(), 2);
+        assert!(db
+            .find_nodes_by_annotation(project_id, "Component")
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_find_callees() {
         let db = setup_db();
@@ -1136,10 +3941,87 @@ mod tests {
             db.insert_edge(&edge).unwrap();
         }
 
-        let callees = db.find_callees(caller_id).unwrap();
+        let callees = db.find_callees(caller_id, DEFAULT_RESULT_LIMIT, 0).unwrap();
         assert_eq!(callees.len(), 2);
     }
 
+    #[test]
+    fn test_find_unlinked_call_sites() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let helper_id = create_node(&db, file_id, "function", "helper");
+        create_node(&db, file_id, "call", "helper");
+        create_node(&db, file_id, "call", "helper");
+        create_node(&db, file_id, "call", "other");
+        let linked_id = create_node(&db, file_id, "call", "helper");
+        db.insert_edge(&EdgeRecord {
+            id: 0,
+            source_id: linked_id,
+            target_id: helper_id,
+            edge_type: "calls".to_string(),
+            attributes: None,
+        })
+        .unwrap();
+
+        let sites = db.find_unlinked_call_sites(project_id, "helper").unwrap();
+        assert_eq!(sites.len(), 2);
+        assert!(sites
+            .iter()
+            .all(|n| n.node_type == "call" && n.id != linked_id));
+    }
+
+    #[test]
+    fn test_find_import_target_package() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let pkg_id = create_node(&db, file_id, "package", "com.example");
+        create_node(&db, file_id, "package", "util");
+
+        let target = db.find_import_target(project_id, "com.example").unwrap();
+        assert_eq!(target, Some(pkg_id));
+
+        // Go import paths are never matched by package name
+        assert!(db
+            .find_import_target(project_id, "example.com/app/util")
+            .unwrap()
+            .is_none());
+        assert!(db.find_import_target(project_id, "util").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_package_in_directory() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        let pkg_id = create_node(&db, file_id, "package", "path");
+
+        let target = db
+            .find_package_in_directory(project_id, "/test/path")
+            .unwrap();
+        assert_eq!(target, Some(pkg_id));
+        assert!(db
+            .find_package_in_directory(project_id, "/test")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_import_target_type_in_package() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+        create_node(&db, file_id, "package", "com.example");
+        let class_id = create_node(&db, file_id, "class", "UserService");
+
+        let target = db
+            .find_import_target(project_id, "com.example.UserService")
+            .unwrap();
+        assert_eq!(target, Some(class_id));
+    }
+
     #[test]
     fn test_get_unresolved_references() {
         let db = setup_db();
@@ -1175,10 +4057,121 @@ mod tests {
         create_node(&db, file_id, "function", "myFunction");
         create_node(&db, file_id, "call", "myFunction"); // This should not be found
 
-        let def_id = db.find_definition_by_name(project_id, "myFunction").unwrap();
+        let def_id = db
+            .find_definition_by_name(project_id, "myFunction")
+            .unwrap();
         assert!(def_id.is_some());
     }
 
+    #[test]
+    fn test_find_definitions_skips_call_nodes_in_sql() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        // More same-named call sites than a 50-row window, all before the definition
+        for _ in 0..60 {
+            create_node(&db, file_id, "call", "save");
+        }
+        let definition_id = create_node(&db, file_id, "method", "save");
+
+        let definitions = db.find_definitions(project_id, "save").unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].id, definition_id);
+    }
+
+    #[test]
+    fn test_find_definitions_of_enums_and_constants() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let enum_id = create_node(&db, file_id, "enum", "Color");
+        let red_id = create_node(&db, file_id, "enum_constant", "RED");
+        let const_id = create_node(&db, file_id, "constant", "MaxRetries");
+        let constructor_id = create_node(&db, file_id, "constructor", "Service");
+
+        for (symbol, id) in [
+            ("Color", enum_id),
+            ("RED", red_id),
+            ("MaxRetries", const_id),
+            ("Service", constructor_id),
+        ] {
+            let definitions = db.find_definitions(project_id, symbol).unwrap();
+            assert_eq!(definitions.len(), 1, "{}", symbol);
+            assert_eq!(definitions[0].id, id);
+        }
+    }
+
+    #[test]
+    fn test_find_definitions_exact_matches_first() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let method = |qualified_name: &str, start_line: u32| NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "method".to_string(),
+            name: "save".to_string(),
+            qualified_name: Some(qualified_name.to_string()),
+            start_line,
+            start_column: 1,
+            end_line: start_line + 5,
+            end_column: 1,
+            attributes: None,
+        };
+        // Earlier in the file, but only matches `Repo.save` as a suffix
+        let suffix_id = db.insert_node(&method("com.example.Repo.save", 1)).unwrap();
+        let exact_id = db.insert_node(&method("Repo.save", 20)).unwrap();
+
+        let ids: Vec<_> = db
+            .find_definitions(project_id, "Repo.save")
+            .unwrap()
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![exact_id, suffix_id]);
+    }
+
+    #[test]
+    fn test_find_definitions_matches_double_colon_suffix() {
+        let db = setup_db();
+        let project_id = create_project(&db);
+        let file_id = create_file(&db, project_id);
+
+        let function = |qualified_name: &str| NodeRecord {
+            id: 0,
+            file_id,
+            node_type: "function".to_string(),
+            name: "new".to_string(),
+            qualified_name: Some(qualified_name.to_string()),
+            start_line: 1,
+            start_column: 1,
+            end_line: 3,
+            end_column: 2,
+            attributes: None,
+        };
+        let router_id = db
+            .insert_node(&function("crate::server::Router::new"))
+            .unwrap();
+        // Ends with the query text, but not on a segment boundary
+        db.insert_node(&function("crate::server::MyRouter::new"))
+            .unwrap();
+
+        let ids: Vec<_> = db
+            .find_definitions(project_id, "Router::new")
+            .unwrap()
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![router_id]);
+        let found = db
+            .find_symbol_by_name(project_id, "server::Router::new")
+            .unwrap();
+        assert_eq!(found.map(|n| n.id), Some(router_id));
+    }
+
     #[test]
     fn test_cascade_delete() {
         let db = setup_db();
@@ -1215,6 +4208,16 @@ mod tests {
         assert_eq!(projects.len(), 1);
     }
 
+    #[test]
+    fn test_open_error_names_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("missing/dir/test.db");
+
+        let err = Database::open(&db_path).err().unwrap();
+        assert!(matches!(&err, Error::DbOpen { path, .. } if path == &db_path));
+        assert!(err.to_string().contains("missing/dir/test.db"));
+    }
+
     #[test]
     fn test_unique_project_path_constraint() {
         let db = setup_db();
@@ -1252,6 +4255,9 @@ mod tests {
             language: "java".to_string(),
             content_hash: "hash1".to_string(),
             parsed_at: chrono::Utc::now(),
+            line_count: 0,
+            is_test: false,
+            encoding: None,
         };
 
         let file2 = FileRecord {
@@ -1261,6 +4267,9 @@ mod tests {
             language: "java".to_string(),
             content_hash: "hash2".to_string(),
             parsed_at: chrono::Utc::now(),
+            line_count: 0,
+            is_test: false,
+            encoding: None,
         };
 
         db.insert_file(&file1).unwrap();
@@ -6,7 +6,10 @@
 use std::path::PathBuf;
 use tempfile::TempDir;
 
-use codegraph::{CodeParser, GraphBuilder, LanguageRegistry, Database};
+use codegraph::core::config::ParseConfig;
+use codegraph::storage::models::ProjectRecord;
+use codegraph::storage::sqlite::OpenOptions;
+use codegraph::{CodeParser, Database, GraphBuilder, LanguageRegistry, QueryExecutor};
 
 fn setup_test_environment() -> (TempDir, Database, LanguageRegistry) {
     let temp_dir = TempDir::new().unwrap();
@@ -65,15 +68,23 @@ public class UserService {
     assert!(!graph_data.content_hash.is_empty());
 
     // Check for expected node types (required)
-    let node_types: Vec<_> = graph_data.nodes.iter().map(|n| n.node_type.as_str()).collect();
+    let node_types: Vec<_> = graph_data
+        .nodes
+        .iter()
+        .map(|n| n.node_type.as_str())
+        .collect();
     assert!(node_types.contains(&"import"));
     assert!(node_types.contains(&"class"));
     assert!(node_types.contains(&"method"));
 
     // Store the graph
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("test-project", temp_dir.path()).unwrap();
-    builder.store_file_graph(project_id, &file_path, "java", graph_data).unwrap();
+    let project_id = builder
+        .create_or_get_project("test-project", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "java", graph_data)
+        .unwrap();
 }
 
 #[test]
@@ -118,7 +129,11 @@ func main() {
     assert!(!graph_data.nodes.is_empty());
 
     // Check for expected node types
-    let node_types: Vec<_> = graph_data.nodes.iter().map(|n| n.node_type.as_str()).collect();
+    let node_types: Vec<_> = graph_data
+        .nodes
+        .iter()
+        .map(|n| n.node_type.as_str())
+        .collect();
     assert!(node_types.contains(&"package"));
     assert!(node_types.contains(&"import"));
     assert!(node_types.contains(&"struct"));
@@ -127,8 +142,67 @@ func main() {
 
     // Store the graph
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("test-project", temp_dir.path()).unwrap();
-    builder.store_file_graph(project_id, &file_path, "go", graph_data).unwrap();
+    let project_id = builder
+        .create_or_get_project("test-project", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "go", graph_data)
+        .unwrap();
+}
+
+#[test]
+fn test_go_promoted_method_call_links_to_embedded_type() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let db = Database::open(&db_path).unwrap();
+    db.init_schema().unwrap();
+
+    let go_code = r#"
+package main
+
+type Logger struct {
+    prefix string
+}
+
+func (l *Logger) Log(msg string) {
+}
+
+type Server struct {
+    Logger
+    port int
+}
+
+func Run(s *Server) {
+    s.Log("starting")
+}
+"#;
+    let file_path = create_go_file(&temp_dir, "server.go", go_code);
+
+    let parser = CodeParser::new(LanguageRegistry::new());
+    let graph_data = parser.parse_file(&file_path, "go").unwrap();
+
+    let mut builder = GraphBuilder::new(db);
+    let project_id = builder
+        .create_or_get_project("test-project", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "go", graph_data)
+        .unwrap();
+    builder.build_cross_references(project_id).unwrap();
+
+    // `Server` embeds `Logger`, so `s.Log` is `Logger.Log`
+    let db = Database::open(&db_path).unwrap();
+    let nodes = db.get_project_nodes(project_id).unwrap();
+    let call = nodes
+        .iter()
+        .map(|(_, n)| n)
+        .find(|n| n.node_type == "call" && n.name == "s.Log")
+        .unwrap();
+
+    let targets = db.find_callees(call.id, 10, 0).unwrap();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].node_type, "method");
+    assert_eq!(targets[0].qualified_name.as_deref(), Some("Logger.Log"));
 }
 
 #[test]
@@ -163,14 +237,20 @@ public interface UserRepository {
     let parser = CodeParser::new(registry);
     let mut builder = GraphBuilder::new(db);
 
-    let project_id = builder.create_or_get_project("multi-file-project", temp_dir.path()).unwrap();
+    let project_id = builder
+        .create_or_get_project("multi-file-project", temp_dir.path())
+        .unwrap();
 
     // Parse and store both files
     let service_graph = parser.parse_file(&service_path, "java").unwrap();
-    builder.store_file_graph(project_id, &service_path, "java", service_graph).unwrap();
+    builder
+        .store_file_graph(project_id, &service_path, "java", service_graph)
+        .unwrap();
 
     let repo_graph = parser.parse_file(&repo_path, "java").unwrap();
-    builder.store_file_graph(project_id, &repo_path, "java", repo_graph).unwrap();
+    builder
+        .store_file_graph(project_id, &repo_path, "java", repo_graph)
+        .unwrap();
 
     // Build cross-references
     builder.build_cross_references(project_id).unwrap();
@@ -202,19 +282,137 @@ public class Calculator {
 
     // Verify parsing produced expected nodes
     assert!(!graph_data.nodes.is_empty());
-    assert!(graph_data.nodes.iter().any(|n| n.name == "Calculator" && n.node_type == "class"));
-    assert!(graph_data.nodes.iter().any(|n| n.name == "add" && n.node_type == "method"));
-    assert!(graph_data.nodes.iter().any(|n| n.name == "subtract" && n.node_type == "method"));
+    assert!(graph_data
+        .nodes
+        .iter()
+        .any(|n| n.name == "Calculator" && n.node_type == "class"));
+    assert!(graph_data
+        .nodes
+        .iter()
+        .any(|n| n.name == "add" && n.node_type == "method"));
+    assert!(graph_data
+        .nodes
+        .iter()
+        .any(|n| n.name == "subtract" && n.node_type == "method"));
 
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("query-test", temp_dir.path()).unwrap();
-    let file_id = builder.store_file_graph(project_id, &file_path, "java", graph_data).unwrap();
+    let project_id = builder
+        .create_or_get_project("query-test", temp_dir.path())
+        .unwrap();
+    let file_id = builder
+        .store_file_graph(project_id, &file_path, "java", graph_data)
+        .unwrap();
 
     // Verify storage succeeded
     assert!(project_id > 0);
     assert!(file_id > 0);
 }
 
+#[test]
+fn test_callgraph_call_sites_match_source() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let db = Database::open(&db_path).unwrap();
+    db.init_schema().unwrap();
+
+    let java_code = r#"
+public class Worker {
+    void helper() {
+    }
+
+    void run() {
+        int x = 1;
+        helper();
+    }
+}
+"#;
+
+    let file_path = create_java_file(&temp_dir, "Worker.java", java_code);
+    let parser = CodeParser::new(LanguageRegistry::new());
+    let graph_data = parser.parse_file(&file_path, "java").unwrap();
+
+    let mut builder = GraphBuilder::new(db);
+    let project_id = builder
+        .create_or_get_project("callsite-test", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "java", graph_data)
+        .unwrap();
+
+    let invocation_line = java_code
+        .lines()
+        .position(|l| l.trim() == "helper();")
+        .unwrap() as u32
+        + 1;
+
+    let executor = QueryExecutor::new(Database::open(&db_path).unwrap());
+    let result = executor
+        .get_callgraph(project_id, "helper", 1, "both", true)
+        .unwrap();
+
+    assert_eq!(result.call_sites.len(), 1);
+    assert_eq!(result.call_sites[0].line, invocation_line);
+    assert_eq!(result.call_sites[0].context.as_deref(), Some("run"));
+}
+
+#[test]
+fn test_find_definition_picks_overload_by_arity() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let db = Database::open(&db_path).unwrap();
+    db.init_schema().unwrap();
+
+    let java_code = r#"
+public class Processor {
+    void process(String input) {
+    }
+
+    void process(String input, int retries) {
+    }
+
+    void run() {
+        process("a");
+        process("b", 3);
+    }
+}
+"#;
+
+    let file_path = create_java_file(&temp_dir, "Processor.java", java_code);
+    let parser = CodeParser::new(LanguageRegistry::new());
+    let graph_data = parser.parse_file(&file_path, "java").unwrap();
+
+    let mut builder = GraphBuilder::new(db);
+    let project_id = builder
+        .create_or_get_project("overload-test", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "java", graph_data)
+        .unwrap();
+
+    let line_of = |text: &str| java_code.lines().position(|l| l.trim() == text).unwrap() as u32 + 1;
+    let file = file_path.to_string_lossy();
+    let executor = QueryExecutor::new(Database::open(&db_path).unwrap());
+
+    let one_arg = executor
+        .find_definition(project_id, &file, line_of("process(\"a\");"), 10)
+        .unwrap()
+        .definition
+        .unwrap();
+    assert_eq!(one_arg.node_type, "method");
+    assert_eq!(one_arg.line, line_of("void process(String input) {"));
+
+    let two_args = executor
+        .find_definition(project_id, &file, line_of("process(\"b\", 3);"), 10)
+        .unwrap()
+        .definition
+        .unwrap();
+    assert_eq!(two_args.node_type, "method");
+    assert_eq!(
+        two_args.line,
+        line_of("void process(String input, int retries) {")
+    );
+}
+
 #[test]
 fn test_file_collection() {
     let (temp_dir, _db, registry) = setup_test_environment();
@@ -256,13 +454,17 @@ fn test_file_collection_with_filter() {
 
     // Filter Java only
     let filter = vec!["java".to_string()];
-    let files = parser.collect_files(temp_dir.path(), Some(&filter)).unwrap();
+    let files = parser
+        .collect_files(temp_dir.path(), Some(&filter))
+        .unwrap();
     // All returned files should be Java (if any)
     assert!(files.iter().all(|(_, lang)| lang == "java"));
 
     // Filter Go only
     let filter = vec!["go".to_string()];
-    let files = parser.collect_files(temp_dir.path(), Some(&filter)).unwrap();
+    let files = parser
+        .collect_files(temp_dir.path(), Some(&filter))
+        .unwrap();
     // All returned files should be Go (if any)
     assert!(files.iter().all(|(_, lang)| lang == "go"));
 }
@@ -277,17 +479,23 @@ fn test_incremental_parsing() {
     let parser = CodeParser::new(registry);
     let mut builder = GraphBuilder::new(db);
 
-    let project_id = builder.create_or_get_project("incremental-test", temp_dir.path()).unwrap();
+    let project_id = builder
+        .create_or_get_project("incremental-test", temp_dir.path())
+        .unwrap();
 
     // First parse
     let graph1 = parser.parse_file(&file_path, "java").unwrap();
     let hash1 = graph1.content_hash.clone();
-    let file_id1 = builder.store_file_graph(project_id, &file_path, "java", graph1).unwrap();
+    let file_id1 = builder
+        .store_file_graph(project_id, &file_path, "java", graph1)
+        .unwrap();
 
     // Parse same file again (should return same file_id due to same hash)
     let graph2 = parser.parse_file(&file_path, "java").unwrap();
     let hash2 = graph2.content_hash.clone();
-    let file_id2 = builder.store_file_graph(project_id, &file_path, "java", graph2).unwrap();
+    let file_id2 = builder
+        .store_file_graph(project_id, &file_path, "java", graph2)
+        .unwrap();
 
     assert_eq!(hash1, hash2);
     assert_eq!(file_id1, file_id2);
@@ -300,7 +508,9 @@ fn test_incremental_parsing() {
     let parser = CodeParser::new(LanguageRegistry::new());
     let graph3 = parser.parse_file(&file_path, "java").unwrap();
     let hash3 = graph3.content_hash.clone();
-    let file_id3 = builder.store_file_graph(project_id, &file_path, "java", graph3).unwrap();
+    let file_id3 = builder
+        .store_file_graph(project_id, &file_path, "java", graph3)
+        .unwrap();
 
     // Hash should be different for different content
     assert_ne!(hash1, hash3);
@@ -319,9 +529,13 @@ fn test_project_status() {
     let parser = CodeParser::new(registry);
     let mut builder = GraphBuilder::new(db);
 
-    let project_id = builder.create_or_get_project("status-test", temp_dir.path()).unwrap();
+    let project_id = builder
+        .create_or_get_project("status-test", temp_dir.path())
+        .unwrap();
     let graph = parser.parse_file(&file_path, "java").unwrap();
-    let file_id = builder.store_file_graph(project_id, &file_path, "java", graph).unwrap();
+    let file_id = builder
+        .store_file_graph(project_id, &file_path, "java", graph)
+        .unwrap();
 
     // Verify file was stored
     assert!(project_id > 0);
@@ -342,7 +556,11 @@ fn test_nested_directory_structure() {
     std::fs::create_dir_all(&example_dir).unwrap();
 
     std::fs::write(example_dir.join("Service.java"), "public class Service {}").unwrap();
-    std::fs::write(example_dir.join("Repository.java"), "public class Repository {}").unwrap();
+    std::fs::write(
+        example_dir.join("Repository.java"),
+        "public class Repository {}",
+    )
+    .unwrap();
 
     let parser = CodeParser::new(registry);
     let files = parser.collect_files(temp_dir.path(), None).unwrap();
@@ -374,8 +592,14 @@ fn test_hidden_directories_excluded() {
     // Hidden files should be excluded - no files from hidden directories
     for (path, _) in &files {
         let path_str = path.to_string_lossy();
-        assert!(!path_str.contains(".hidden"), "Hidden directory should be excluded");
-        assert!(!path_str.contains(".git"), ".git directory should be excluded");
+        assert!(
+            !path_str.contains(".hidden"),
+            "Hidden directory should be excluded"
+        );
+        assert!(
+            !path_str.contains(".git"),
+            ".git directory should be excluded"
+        );
     }
 }
 
@@ -400,8 +624,12 @@ public class Calculator {
     let graph = parser.parse_file(&file_path, "java").unwrap();
 
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("calc-test", temp_dir.path()).unwrap();
-    builder.store_file_graph(project_id, &file_path, "java", graph).unwrap();
+    let project_id = builder
+        .create_or_get_project("calc-test", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "java", graph)
+        .unwrap();
 
     // For testing, we need to reopen the database connection
     // In real usage, Database connection would be reused
@@ -451,29 +679,53 @@ public class UserService extends AbstractService implements UserOperations {
     let graph = parser.parse_file(&file_path, "java").unwrap();
 
     // Verify complex structure was parsed
-    let class_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "class").collect();
+    let class_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "class")
+        .collect();
     assert_eq!(class_nodes.len(), 1);
     assert_eq!(class_nodes[0].name, "UserService");
 
-    let method_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "method").collect();
+    let method_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "method")
+        .collect();
     assert_eq!(method_nodes.len(), 3); // findById, findAll, sendWelcomeEmail
 
-    let field_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "field").collect();
+    let field_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "field")
+        .collect();
     assert_eq!(field_nodes.len(), 2);
 
     // Verify extends relationship if present
-    let extends_edges: Vec<_> = graph.edges.iter().filter(|e| e.edge_type == "extends").collect();
+    let extends_edges: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == "extends")
+        .collect();
     // extends edge may or may not be created depending on implementation
     assert!(extends_edges.len() <= 1);
 
     // Verify implements relationships if present
-    let implements_edges: Vec<_> = graph.edges.iter().filter(|e| e.edge_type == "implements").collect();
+    let implements_edges: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == "implements")
+        .collect();
     // implements edges may or may not be created depending on implementation
     assert!(implements_edges.len() <= 2);
 
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("complex-test", temp_dir.path()).unwrap();
-    builder.store_file_graph(project_id, &file_path, "java", graph).unwrap();
+    let project_id = builder
+        .create_or_get_project("complex-test", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "java", graph)
+        .unwrap();
 }
 
 #[test]
@@ -534,22 +786,1140 @@ type Handler interface {
     let graph = parser.parse_file(&file_path, "go").unwrap();
 
     // Verify struct types
-    let struct_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "struct").collect();
+    let struct_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "struct")
+        .collect();
     assert_eq!(struct_nodes.len(), 2); // Config, Server
 
     // Verify functions
-    let func_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "function").collect();
+    let func_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "function")
+        .collect();
     assert_eq!(func_nodes.len(), 1); // NewServer
 
     // Verify methods
-    let method_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "method").collect();
+    let method_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "method")
+        .collect();
     assert!(method_nodes.len() >= 2); // Start, Stop + interface methods
 
     // Verify interface
-    let interface_nodes: Vec<_> = graph.nodes.iter().filter(|n| n.node_type == "interface").collect();
+    let interface_nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "interface")
+        .collect();
     assert_eq!(interface_nodes.len(), 1); // Handler
 
     let mut builder = GraphBuilder::new(db);
-    let project_id = builder.create_or_get_project("go-complex-test", temp_dir.path()).unwrap();
-    builder.store_file_graph(project_id, &file_path, "go", graph).unwrap();
+    let project_id = builder
+        .create_or_get_project("go-complex-test", temp_dir.path())
+        .unwrap();
+    builder
+        .store_file_graph(project_id, &file_path, "go", graph)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_parse_project_skips_oversized_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+
+    std::fs::write(src_dir.join("Small.java"), "class S {}").unwrap();
+    std::fs::write(
+        src_dir.join("Huge.java"),
+        "public class Huge { void a() {} void b() {} void c() {} }",
+    )
+    .unwrap();
+
+    let config = ParseConfig {
+        max_file_bytes: 32,
+        ..Default::default()
+    };
+    let report =
+        codegraph::core::parse_project(&db_path, "size-test", &src_dir, None, None, &config)
+            .await
+            .unwrap();
+
+    assert_eq!(report.files_parsed, 1);
+    assert_eq!(report.skipped.len(), 1);
+    assert!(report.skipped[0].path.ends_with("Huge.java"));
+}
+
+#[tokio::test]
+async fn test_parse_project_skips_files_over_parse_timeout() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+
+    std::fs::write(src_dir.join("Small.java"), "class S {}").unwrap();
+    let mut source = String::from("class Slow {\n");
+    for i in 0..20_000 {
+        source.push_str(&format!(
+            "    int f{i}(int a) {{ if (a > {i}) {{ return a - {i}; }} return a; }}\n"
+        ));
+    }
+    source.push_str("}\n");
+    std::fs::write(src_dir.join("Slow.java"), source).unwrap();
+
+    let config = ParseConfig {
+        parse_timeout_ms: 1,
+        ..Default::default()
+    };
+    let report =
+        codegraph::core::parse_project(&db_path, "timeout-test", &src_dir, None, None, &config)
+            .await
+            .unwrap();
+
+    assert_eq!(report.files_parsed, 1);
+    assert_eq!(report.files_failed, 0);
+    assert_eq!(report.skipped.len(), 1);
+    assert!(report.skipped[0].path.ends_with("Slow.java"));
+    assert!(report.skipped[0].reason.contains("1 ms limit"));
+}
+
+#[tokio::test]
+async fn test_parse_project_records_last_parsed_at() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(src_dir.join("Main.java"), "class Main {}").unwrap();
+
+    let config = ParseConfig::default();
+    let report =
+        codegraph::core::parse_project(&db_path, "parsed-at", &src_dir, None, None, &config)
+            .await
+            .unwrap();
+    let db = Database::open(&db_path).unwrap();
+    let first = db.get_project_status(report.project_id).unwrap().unwrap();
+    let first_parsed = first.last_parsed_at.expect("last_parsed_at not set");
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    codegraph::core::parse_project(&db_path, "parsed-at", &src_dir, None, None, &config)
+        .await
+        .unwrap();
+
+    let project = db
+        .get_project_by_path(&src_dir.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    let second = db.get_project_status(report.project_id).unwrap().unwrap();
+    let second_parsed = second.last_parsed_at.unwrap();
+    assert!(second_parsed > first_parsed);
+    assert!(second_parsed > project.created_at);
+}
+
+#[tokio::test]
+async fn test_parse_archive_indexes_entries_without_extracting() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let archive_path = temp_dir.path().join("project.tar");
+
+    let mut archive = tar::Builder::new(std::fs::File::create(&archive_path).unwrap());
+    let sources = [
+        (
+            "project/main.go",
+            "package main\n\nfunc main() {\n\tHelper()\n}\n",
+        ),
+        ("project/util/util.go", "package util\n\nfunc Helper() {}\n"),
+        ("project/README.md", "# not source\n"),
+    ];
+    for (path, contents) in sources {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, path, contents.as_bytes())
+            .unwrap();
+    }
+    archive.finish().unwrap();
+    drop(archive);
+
+    let report = codegraph::core::parse_archive(
+        &db_path,
+        &OpenOptions::default(),
+        "archived",
+        &archive_path,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.files_parsed, 2);
+    assert_eq!(report.files_failed, 0);
+
+    let db = Database::open(&db_path).unwrap();
+    let mut paths: Vec<String> = db
+        .get_project_files(report.project_id)
+        .unwrap()
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec!["project/main.go", "project/util/util.go"]);
+
+    // Nothing was unpacked next to the archive
+    assert!(!temp_dir.path().join("project").exists());
+}
+
+#[tokio::test]
+async fn test_field_references_distinguish_reads_from_writes() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("counter.go"),
+        "package main\n\ntype Counter struct {\n\thits int\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("incr.go"),
+        "package main\n\nfunc (c *Counter) Incr() int {\n\tc.hits = 1\n\treturn c.hits\n}\n",
+    )
+    .unwrap();
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "fields",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let result =
+        codegraph::core::query::find_references_by_symbol(&db_path, report.project_id, "hits", 10)
+            .unwrap();
+    let mut kinds: Vec<(u32, Option<String>)> = result
+        .references
+        .into_iter()
+        .map(|r| (r.line, r.kind))
+        .collect();
+    kinds.sort();
+    assert_eq!(
+        kinds,
+        vec![
+            (4, Some("write".to_string())),
+            (5, Some("read".to_string()))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_parse_project_with_multiple_roots() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let service_dir = temp_dir.path().join("services/a");
+    let lib_dir = temp_dir.path().join("libs/b");
+    std::fs::create_dir_all(&service_dir).unwrap();
+    std::fs::create_dir_all(&lib_dir).unwrap();
+    std::fs::write(service_dir.join("Service.java"), "class Service {}").unwrap();
+    std::fs::write(
+        lib_dir.join("util.go"),
+        "package util\n\nfunc Helper() {}\n",
+    )
+    .unwrap();
+
+    let extra_roots = [lib_dir.clone()];
+    let options = codegraph::core::ParseOptions {
+        extra_roots: &extra_roots,
+        ..Default::default()
+    };
+    let report = codegraph::core::parse_project_with_options(
+        &db_path,
+        &OpenOptions::default(),
+        "monorepo",
+        &service_dir,
+        &options,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.files_parsed, 2);
+
+    let db = Database::open(&db_path).unwrap();
+    let service_file = service_dir.join("Service.java");
+    let lib_file = lib_dir.join("util.go");
+    for file in [&service_file, &lib_file] {
+        assert!(db
+            .get_file_by_path(report.project_id, &file.to_string_lossy())
+            .unwrap()
+            .is_some());
+    }
+
+    let projects = db.list_projects().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].root_path, service_dir.to_string_lossy());
+
+    // The extra root is remembered, so a plain re-parse still walks it
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "monorepo",
+        &service_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.files_parsed, 2);
+}
+
+#[tokio::test]
+async fn test_test_files_flagged_and_filtered() {
+    use codegraph::storage::models::TestFilter;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(src_dir.join("Foo.go"), "package foo\n\nfunc Foo() {}\n").unwrap();
+    std::fs::write(
+        src_dir.join("Foo_test.go"),
+        "package foo\n\nfunc FooHelper() {}\n",
+    )
+    .unwrap();
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "tests",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let db = Database::open(&db_path).unwrap();
+    let test_file = db
+        .get_file_by_path(
+            report.project_id,
+            &src_dir.join("Foo_test.go").to_string_lossy(),
+        )
+        .unwrap()
+        .unwrap();
+    assert!(test_file.is_test);
+    let source_file = db
+        .get_file_by_path(report.project_id, &src_dir.join("Foo.go").to_string_lossy())
+        .unwrap()
+        .unwrap();
+    assert!(!source_file.is_test);
+
+    let search = |tests| {
+        codegraph::core::query::search_symbols_with_project(
+            &db_path,
+            report.project_id,
+            "Foo",
+            Some("function"),
+            10,
+            tests,
+        )
+        .unwrap()
+        .symbols
+        .into_iter()
+        .map(|s| s.name)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(search(TestFilter::All).len(), 2);
+    assert_eq!(search(TestFilter::Exclude), ["Foo"]);
+    assert_eq!(search(TestFilter::Only), ["FooHelper"]);
+}
+
+#[derive(Default)]
+struct CountingProgress {
+    total: std::sync::atomic::AtomicUsize,
+    found: std::sync::atomic::AtomicUsize,
+    done: std::sync::atomic::AtomicUsize,
+    finished: std::sync::atomic::AtomicBool,
+}
+
+impl codegraph::core::ParseProgress for CountingProgress {
+    fn start(&self, total: usize) {
+        self.total.store(total, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn file_found(&self, _path: &std::path::Path) {
+        self.found.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn file_done(&self, _path: &std::path::Path) {
+        self.done.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn finish(&self, _report: &codegraph::core::ParseReport) {
+        self.finished
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_parse_progress_counts_collected_files() {
+    use std::sync::atomic::Ordering;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(src_dir.join("A.java"), "class A {}").unwrap();
+    std::fs::write(src_dir.join("B.java"), "class B {".repeat(10)).unwrap();
+    std::fs::write(src_dir.join("main.go"), "package main").unwrap();
+    std::fs::write(src_dir.join("notes.txt"), "not code").unwrap();
+
+    let collected = CodeParser::new(LanguageRegistry::new())
+        .collect_files(&src_dir, None)
+        .unwrap();
+
+    let progress = CountingProgress::default();
+    let options = codegraph::core::ParseOptions {
+        progress: Some(&progress),
+        ..Default::default()
+    };
+    codegraph::core::parse_project_with_options(
+        &db_path,
+        &OpenOptions::default(),
+        "progress",
+        &src_dir,
+        &options,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(progress.total.load(Ordering::SeqCst), collected.len());
+    assert_eq!(progress.done.load(Ordering::SeqCst), collected.len());
+    assert!(progress.finished.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_parallel_walk_parses_files_as_found() {
+    use std::sync::atomic::Ordering;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir_all(src_dir.join("pkg")).unwrap();
+    std::fs::write(src_dir.join("A.java"), "class A {}").unwrap();
+    std::fs::write(
+        src_dir.join("pkg/main.go"),
+        "package main\n\nfunc main() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("pkg/util.go"),
+        "package main\n\nfunc util() {}\n",
+    )
+    .unwrap();
+    std::fs::write(src_dir.join("notes.txt"), "not code").unwrap();
+
+    let config = ParseConfig {
+        parallel_walk: true,
+        ..ParseConfig::default()
+    };
+    let progress = CountingProgress::default();
+    let options = codegraph::core::ParseOptions {
+        progress: Some(&progress),
+        ..Default::default()
+    };
+    let report = codegraph::core::parse_project_with_options(
+        &db_path,
+        &OpenOptions::default(),
+        "parallel",
+        &src_dir,
+        &options,
+        &config,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.files_parsed, 3);
+    assert_eq!(progress.total.load(Ordering::SeqCst), 0);
+    assert_eq!(progress.found.load(Ordering::SeqCst), 3);
+    assert_eq!(progress.done.load(Ordering::SeqCst), 3);
+    assert!(progress.finished.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_parse_project_since_git_ref() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&repo_dir)
+            .args(["-c", "user.email=test@example.com", "-c", "user.name=Test"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    std::fs::write(repo_dir.join("A.java"), "class A {}").unwrap();
+    std::fs::write(repo_dir.join("B.java"), "class B {}").unwrap();
+    std::fs::write(repo_dir.join("C.java"), "class C {}").unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    let config = ParseConfig::default();
+    let report =
+        codegraph::core::parse_project(&db_path, "git-test", &repo_dir, None, None, &config)
+            .await
+            .unwrap();
+    assert_eq!(report.files_parsed, 3);
+
+    std::fs::write(repo_dir.join("A.java"), "class A { void run() {} }").unwrap();
+    std::fs::remove_file(repo_dir.join("C.java")).unwrap();
+    // Untracked files are new, but ignored ones are skipped
+    std::fs::write(repo_dir.join("D.java"), "class D {}").unwrap();
+    std::fs::write(repo_dir.join(".gitignore"), "E.java\n").unwrap();
+    std::fs::write(repo_dir.join("E.java"), "class E {}").unwrap();
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "git-test",
+        &repo_dir,
+        None,
+        Some("HEAD"),
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.files_parsed, 2);
+    assert_eq!(report.files_removed, 1);
+
+    let db = Database::open(&db_path).unwrap();
+    assert!(db
+        .find_symbol_by_name(report.project_id, "C")
+        .unwrap()
+        .is_none());
+    assert!(db
+        .find_symbol_by_name(report.project_id, "D")
+        .unwrap()
+        .is_some());
+    assert!(db
+        .find_symbol_by_name(report.project_id, "E")
+        .unwrap()
+        .is_none());
+    assert!(db
+        .find_symbol_by_name(report.project_id, "B")
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_counts_by_language_mixed_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+
+    std::fs::write(
+        src_dir.join("Calculator.java"),
+        "public class Calculator {\n    int add(int a, int b) { return a + b; }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("Util.java"),
+        "public class Util {\n    void run() {}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("main.go"),
+        "package main\n\nfunc main() {\n    helper()\n}\n\nfunc helper() {}\n",
+    )
+    .unwrap();
+
+    // Expected totals come straight from the extractors
+    let parser = CodeParser::new(LanguageRegistry::new());
+    let mut expected = std::collections::HashMap::new();
+    for (file, language) in [
+        ("Calculator.java", "java"),
+        ("Util.java", "java"),
+        ("main.go", "go"),
+    ] {
+        let graph = parser.parse_file(&src_dir.join(file), language).unwrap();
+        let entry = expected.entry(language).or_insert((0u32, 0u32, 0u32));
+        entry.0 += 1;
+        entry.1 += graph.nodes.len() as u32;
+        entry.2 += graph.edges.len() as u32;
+    }
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "mixed",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let db = Database::open(&db_path).unwrap();
+    let counts = db.counts_by_language(report.project_id).unwrap();
+
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[0].language, "go");
+    assert_eq!(counts[1].language, "java");
+    for c in &counts {
+        let (files, nodes, edges) = expected[c.language.as_str()];
+        assert_eq!(c.file_count, files, "{} files", c.language);
+        assert_eq!(c.node_count, nodes, "{} nodes", c.language);
+        assert_eq!(c.edge_count, edges, "{} edges", c.language);
+    }
+    assert_eq!(counts[1].file_count, 2);
+}
+
+#[test]
+fn test_parse_dry_run_lists_files_without_writing() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(src_dir.join("A.java"), "class A {}").unwrap();
+    std::fs::write(src_dir.join("B.java"), "class B {}").unwrap();
+    std::fs::write(src_dir.join("main.go"), "package main").unwrap();
+    std::fs::write(src_dir.join("notes.txt"), "not code").unwrap();
+
+    let report = codegraph::core::dry_run(&src_dir, &[], None, &ParseConfig::default()).unwrap();
+    assert_eq!(report.total, 3);
+    assert_eq!(report.files_by_language["java"].len(), 2);
+    assert_eq!(report.files_by_language["go"].len(), 1);
+
+    let filtered = codegraph::core::dry_run(
+        &src_dir,
+        &[],
+        Some(&["go".to_string()]),
+        &ParseConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(filtered.total, 1);
+    assert!(!filtered.files_by_language.contains_key("java"));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .arg("parse")
+        .arg("--path")
+        .arg(&src_dir)
+        .arg("--database")
+        .arg(&db_path)
+        .arg("--dry-run")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would parse 3 files"));
+    assert!(stdout.contains("java (2)"));
+    assert!(stdout.contains("main.go"));
+    assert!(!stdout.contains("notes.txt"));
+    assert!(!db_path.exists());
+}
+
+#[tokio::test]
+async fn test_extra_extension_collected_and_parsed() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("Foo.jav"),
+        "public class Foo { void bar() {} }",
+    )
+    .unwrap();
+
+    // Without the mapping the file is not recognised
+    let plain = codegraph::core::dry_run(&src_dir, &[], None, &ParseConfig::default()).unwrap();
+    assert_eq!(plain.total, 0);
+
+    let mut config = ParseConfig::default();
+    config
+        .extra_extensions
+        .insert(".jav".to_string(), "java".to_string());
+
+    let plan = codegraph::core::dry_run(&src_dir, &[], None, &config).unwrap();
+    assert_eq!(plan.files_by_language["java"].len(), 1);
+
+    let report =
+        codegraph::core::parse_project(&db_path, "ext-test", &src_dir, None, None, &config)
+            .await
+            .unwrap();
+    assert_eq!(report.files_parsed, 1);
+
+    let db = Database::open(&db_path).unwrap();
+    let foo = db
+        .find_symbol_by_name(report.project_id, "Foo")
+        .unwrap()
+        .unwrap();
+    assert_eq!(foo.node_type, "class");
+}
+
+#[tokio::test]
+async fn test_diff_against_exported_snapshot() {
+    use codegraph::core::diff::{diff_against, GraphSnapshot};
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("main.go"),
+        "package main\n\nfunc main() {\n    helper()\n}\n\nfunc helper() {}\n",
+    )
+    .unwrap();
+
+    let config = ParseConfig::default();
+    let report =
+        codegraph::core::parse_project(&db_path, "diff-test", &src_dir, None, None, &config)
+            .await
+            .unwrap();
+
+    let baseline_path = temp_dir.path().join("baseline.json");
+    {
+        let db = Database::open(&db_path).unwrap();
+        let baseline = GraphSnapshot::from_database(&db, report.project_id).unwrap();
+        assert_eq!(baseline.project, "diff-test");
+        std::fs::write(&baseline_path, serde_json::to_string(&baseline).unwrap()).unwrap();
+    }
+
+    std::fs::write(
+        src_dir.join("main.go"),
+        "package main\n\nfunc main() {\n    helper()\n    audit()\n}\n\nfunc helper() {}\n\nfunc audit() {}\n",
+    )
+    .unwrap();
+    codegraph::core::parse_project(&db_path, "diff-test", &src_dir, None, None, &config)
+        .await
+        .unwrap();
+
+    let db = Database::open(&db_path).unwrap();
+    let diff = diff_against(&db, report.project_id, &baseline_path).unwrap();
+    assert_eq!(diff.summary.symbols_removed, 0);
+    assert!(diff
+        .added_symbols
+        .iter()
+        .any(|s| s.name == "audit" && s.node_type == "function"));
+    assert!(diff
+        .added_edges
+        .iter()
+        .any(|e| e.edge_type == "calls" && e.target.ends_with("audit")));
+    // main grew by one line
+    assert!(diff.changed_symbols.iter().any(|s| s.name == "main"));
+}
+
+#[tokio::test]
+async fn test_query_output_written_to_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("UserService.java"),
+        "public class UserService { void save() {} }",
+    )
+    .unwrap();
+
+    codegraph::core::parse_project(
+        &db_path,
+        "output-test",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let out_path = temp_dir.path().join("results").join("symbols.json");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["query", "--project", "output-test", "--database"])
+        .arg(&db_path)
+        .args([
+            "symbols",
+            "--query",
+            "UserService",
+            "--symbol-type",
+            "class",
+            "-o",
+        ])
+        .arg(&out_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("UserService"));
+
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+    assert_eq!(written["count"], 1);
+    assert_eq!(written["symbols"][0]["name"], "UserService");
+    assert_eq!(written["symbols"][0]["node_type"], "class");
+}
+
+#[test]
+fn test_init_creates_queryable_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("UserService.java"),
+        "public class UserService { void save() {} }",
+    )
+    .unwrap();
+
+    // Querying an empty database points at `init`
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["query", "--database"])
+        .arg(&db_path)
+        .args(["symbols", "--query", "UserService"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("codegraph init"));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["init", "--name", "init-test", "--path"])
+        .arg(&src_dir)
+        .arg("--database")
+        .arg(&db_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let db = Database::open(&db_path).unwrap();
+    let project = db.get_project_by_name("init-test").unwrap().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("(id={})", project.id)),
+        "{}",
+        stdout
+    );
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["query", "--project", "init-test", "--database"])
+        .arg(&db_path)
+        .args(["symbols", "--query", "save"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["count"], 1);
+    assert_eq!(result["symbols"][0]["name"], "save");
+
+    // Initializing the same directory again keeps the project
+    let init_again = |name: Option<&str>| {
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"));
+        command
+            .args(["init", "--path"])
+            .arg(&src_dir)
+            .arg("--database")
+            .arg(&db_path);
+        if let Some(name) = name {
+            command.args(["--name", name]);
+        }
+        command.output().unwrap()
+    };
+    let output = init_again(None);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "Initialized project 'init-test' (id={})",
+            project.id
+        )),
+        "{}",
+        stdout
+    );
+
+    let output = init_again(Some("other-name"));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("already initialized as project 'init-test'"));
+    assert!(db.get_project_by_name("other-name").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_bundle_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("main.go"),
+        "package main\n\nfunc main() {\n    helper()\n}\n\nfunc helper() {}\n",
+    )
+    .unwrap();
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "bundle-test",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let bundle_path = temp_dir.path().join("project.cg");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["export", "--project", "bundle-test", "--database"])
+        .arg(&db_path)
+        .arg("--output")
+        .arg(&bundle_path)
+        .arg("bundle")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The bundle stands alone once the source database is gone
+    std::fs::remove_file(&db_path).unwrap();
+    let db = Database::open_bundle(&bundle_path).unwrap();
+    let executor = QueryExecutor::new(db);
+
+    let result = executor
+        .search_symbols(report.project_id, "helper", Some("function"), 10)
+        .unwrap();
+    assert_eq!(result.count, 1);
+    assert_eq!(result.symbols[0].name, "helper");
+
+    // Qualified, since the package is also named `main`
+    let callgraph = executor
+        .get_callgraph(report.project_id, "main.main", 1, "callees", false)
+        .unwrap();
+    assert!(callgraph.callees.iter().any(|c| c.name == "helper"));
+}
+
+#[tokio::test]
+async fn test_projects_json_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let mut expected = Vec::new();
+    for name in ["alpha", "beta"] {
+        let src_dir = temp_dir.path().join(name);
+        std::fs::create_dir(&src_dir).unwrap();
+        std::fs::write(src_dir.join("main.go"), "package main\n\nfunc main() {}\n").unwrap();
+        let report = codegraph::core::parse_project(
+            &db_path,
+            name,
+            &src_dir,
+            None,
+            None,
+            &ParseConfig::default(),
+        )
+        .await
+        .unwrap();
+        expected.push((report.project_id, name.to_string()));
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .args(["projects", "--format", "json", "--database"])
+        .arg(&db_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let projects: Vec<ProjectRecord> = serde_json::from_slice(&output.stdout).unwrap();
+    let mut listed: Vec<_> = projects.iter().map(|p| (p.id, p.name.clone())).collect();
+    listed.sort();
+    assert_eq!(listed, expected);
+    assert!(projects.iter().all(|p| p.root_path.ends_with(&p.name)));
+}
+
+#[tokio::test]
+async fn test_parse_without_cross_references() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("main.go"),
+        "package main\n\ntype Server struct {\n\tcfg Config\n}\n\nfunc main() {\n\thelper()\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("util.go"),
+        "package main\n\ntype Config struct{}\n\nfunc helper() {}\n",
+    )
+    .unwrap();
+
+    let options = codegraph::core::ParseOptions {
+        skip_cross_references: true,
+        ..Default::default()
+    };
+    let report = codegraph::core::parse_project_with_options(
+        &db_path,
+        &OpenOptions::default(),
+        "no-xrefs",
+        &src_dir,
+        &options,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let db = Database::open(&db_path).unwrap();
+    let edges = db.get_project_edges(report.project_id).unwrap();
+    assert!(!edges.iter().any(|e| e.edge_type == "references"));
+    assert!(edges.iter().any(|e| e.edge_type == "calls"));
+    assert!(db.count_nodes(report.project_id).unwrap() > 0);
+    let unresolved = db.get_unresolved_references(report.project_id).unwrap();
+    assert!(unresolved.iter().any(|(_, name)| name == "Config"));
+
+    let status = db.get_project_status(report.project_id).unwrap().unwrap();
+    assert!(status.last_parsed_at.is_some());
+
+    // A normal parse links the same reference
+    codegraph::core::parse_project(
+        &db_path,
+        "no-xrefs",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+    let edges = db.get_project_edges(report.project_id).unwrap();
+    assert!(edges.iter().any(|e| e.edge_type == "references"));
+}
+
+#[tokio::test]
+async fn test_package_summary_spans_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir_all(src_dir.join("server")).unwrap();
+    std::fs::write(
+        src_dir.join("server/server.go"),
+        "// Package server serves HTTP.\npackage server\n\ntype Server struct {\n    addr string\n}\n\nfunc (s *Server) Start() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src_dir.join("server/routes.go"),
+        "package server\n\nconst Prefix = \"/api\"\n\nvar DefaultPort = 8080\n\nfunc Routes() {\n    const limit = 3\n    type route struct{}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(src_dir.join("main.go"), "package main\n\nfunc main() {}\n").unwrap();
+
+    let report = codegraph::core::parse_project(
+        &db_path,
+        "packages",
+        &src_dir,
+        None,
+        None,
+        &ParseConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let summary =
+        codegraph::core::query::package_summary_with_project(&db_path, report.project_id, "server")
+            .unwrap();
+    assert_eq!(summary.doc.as_deref(), Some("Package server serves HTTP."));
+    assert_eq!(summary.files.len(), 2);
+    assert!(summary.files.iter().all(|f| f.contains("server")));
+
+    let mut symbols: Vec<(&str, &str)> = summary
+        .symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.node_type.as_str()))
+        .collect();
+    symbols.sort();
+    assert_eq!(
+        symbols,
+        vec![
+            ("DefaultPort", "variable"),
+            ("Prefix", "constant"),
+            ("Routes", "function"),
+            ("Server", "struct"),
+            ("Start", "method"),
+        ]
+    );
+    assert_eq!(summary.count, 5);
+
+    let missing =
+        codegraph::core::query::package_summary_with_project(&db_path, report.project_id, "client");
+    assert!(matches!(missing, Err(codegraph::Error::SymbolNotFound(_))));
+}
+
+#[test]
+fn test_parse_stores_relative_roots_as_absolute() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("codegraph.db");
+    std::fs::create_dir_all(temp_dir.path().join("services/a")).unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("libs/b")).unwrap();
+    std::fs::write(
+        temp_dir.path().join("libs/b/util.go"),
+        "package b\n\nfunc Util() {}\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_codegraph"))
+        .current_dir(temp_dir.path())
+        .args([
+            "parse",
+            "--name",
+            "roots-test",
+            "--path",
+            "services/a",
+            "--root",
+            "libs/b",
+            "--database",
+        ])
+        .arg(&db_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let db = Database::open(&db_path).unwrap();
+    let project = db.get_project_by_name("roots-test").unwrap().unwrap();
+    let lib_root = temp_dir.path().join("libs/b").canonicalize().unwrap();
+    assert!(db
+        .get_project_roots(project.id)
+        .unwrap()
+        .contains(&lib_root.to_string_lossy().into_owned()));
 }